@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::grid::Neighborhood;
+use crate::map::{TileCoord, Zone};
+use crate::sim::ZoneGrid;
+use crate::simulation::simulation_step;
+use crate::state::{GameSet, ResetCity};
+
+/// Keeps track of what each home has next to it, which raises or lowers
+/// how many residents it can hold.
+pub struct AdjacencyPlugin;
+
+impl Plugin for AdjacencyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ResetCity, init_adjacency).add_systems(
+            Update,
+            update_adjacency
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        );
+    }
+}
+
+/// A kind of neighbor that changes how much a home is worth living in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Neighbor {
+    /// Noise and smoke from a factory.
+    Industry,
+    /// Shops around the corner.
+    Shops,
+    /// Woods to walk in; the city has no parks yet.
+    Green,
+}
+
+impl Neighbor {
+    pub const ALL: [Neighbor; 3] = [Neighbor::Industry, Neighbor::Shops, Neighbor::Green];
+
+    /// Change to a home's population cap, in percent, when at least one
+    /// tile next to it is this.
+    pub fn percent(self) -> i32 {
+        match self {
+            Neighbor::Industry => -20,
+            Neighbor::Shops => 5,
+            Neighbor::Green => 10,
+        }
+    }
+
+    /// The neighbor a zone counts as, if any.
+    fn of(zone: Zone) -> Option<Self> {
+        match zone {
+            Zone::Industrial => Some(Neighbor::Industry),
+            Zone::Commercial => Some(Neighbor::Shops),
+            Zone::Forest => Some(Neighbor::Green),
+            _ => None,
+        }
+    }
+
+    /// Locale key of the inspector line explaining the effect.
+    pub fn label(self) -> &'static str {
+        match self {
+            Neighbor::Industry => "inspector.next_to.industry",
+            Neighbor::Shops => "inspector.next_to.shops",
+            Neighbor::Green => "inspector.next_to.green",
+        }
+    }
+}
+
+/// The kinds of neighbor around one tile, one bit per `Neighbor`.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Neighbors(u8);
+
+impl Neighbors {
+    /// What is next to `coord`, by the tiles `neighborhood` counts.
+    pub fn around(zones: &ZoneGrid, coord: IVec2, neighborhood: Neighborhood) -> Self {
+        let mut neighbors = Self::default();
+        for &offset in neighborhood.offsets() {
+            if let Some(neighbor) = zones.get(coord + offset).and_then(Neighbor::of) {
+                neighbors.0 |= 1 << neighbor as u8;
+            }
+        }
+        neighbors
+    }
+
+    pub fn contains(self, neighbor: Neighbor) -> bool {
+        self.0 & (1 << neighbor as u8) != 0
+    }
+
+    /// Every kind of neighbor present, in `Neighbor::ALL` order.
+    pub fn iter(self) -> impl Iterator<Item = Neighbor> {
+        Neighbor::ALL.into_iter().filter(move |&n| self.contains(n))
+    }
+
+    /// Combined change to a home's population cap, in percent.
+    pub fn percent(self) -> i32 {
+        self.iter().map(Neighbor::percent).sum()
+    }
+}
+
+/// What is next to every tile, laid out row by row. Rebuilt only when
+/// zoning or the neighborhood rule changes.
+#[derive(Resource)]
+pub struct Adjacency {
+    width: i32,
+    height: i32,
+    neighbors: Vec<Neighbors>,
+}
+
+impl Adjacency {
+    fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            neighbors: vec![Neighbors::default(); (width * height) as usize],
+        }
+    }
+
+    /// What is next to `coord`; nothing off the map.
+    pub fn get(&self, coord: IVec2) -> Neighbors {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return Neighbors::default();
+        }
+        self.neighbors[(coord.y * self.width + coord.x) as usize]
+    }
+}
+
+fn init_adjacency(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(Adjacency::new(map.width, map.height));
+}
+
+/// Rebuild the neighbors of every tile, but only when some zone or the
+/// neighborhood rule changed.
+fn update_adjacency(
+    mut adjacency: ResMut<Adjacency>,
+    neighborhood: Res<Neighborhood>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+) {
+    if changed.is_empty() && !neighborhood.is_changed() {
+        return;
+    }
+
+    let (width, height) = (adjacency.width, adjacency.height);
+    let mut zones = ZoneGrid::new(width, height);
+    for (coord, zone) in tiles.iter() {
+        zones.set(coord.coord, *zone);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let coord = IVec2::new(x, y);
+            adjacency.neighbors[(y * width + x) as usize] =
+                Neighbors::around(&zones, coord, *neighborhood);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x3 map drawn top row first: `I` industry, `C` shops, `F` forest,
+    /// `R` a home, `#` a road; anything else is empty.
+    fn zones(rows: [&str; 3]) -> ZoneGrid {
+        let mut zones = ZoneGrid::new(3, 3);
+        for (row, line) in rows.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let zone = match c {
+                    'I' => Zone::Industrial,
+                    'C' => Zone::Commercial,
+                    'F' => Zone::Forest,
+                    'R' => Zone::Residential,
+                    '#' => Zone::Road,
+                    _ => Zone::Empty,
+                };
+                zones.set(IVec2::new(x as i32, 2 - row as i32), zone);
+            }
+        }
+        zones
+    }
+
+    fn center(rows: [&str; 3], neighborhood: Neighborhood) -> Neighbors {
+        Neighbors::around(&zones(rows), IVec2::ONE, neighborhood)
+    }
+
+    #[test]
+    fn industry_next_door_lowers_the_cap() {
+        let next_door = center(["...", ".RI", "..."], Neighborhood::Four);
+        assert_eq!(next_door.iter().collect::<Vec<_>>(), vec![Neighbor::Industry]);
+        assert_eq!(next_door.percent(), -20);
+        let twice = center([".I.", ".RI", "..."], Neighborhood::Four);
+        assert_eq!(twice.percent(), -20);
+    }
+
+    #[test]
+    fn diagonals_count_only_in_the_eight_neighborhood() {
+        let rows = ["I..", ".R.", "..C"];
+        assert_eq!(center(rows, Neighborhood::Four), Neighbors::default());
+        let eight = center(rows, Neighborhood::Eight);
+        assert!(eight.contains(Neighbor::Industry) && eight.contains(Neighbor::Shops));
+        assert_eq!(eight.percent(), -15);
+    }
+
+    #[test]
+    fn shops_and_woods_raise_the_cap() {
+        assert_eq!(center([".C.", ".R.", "..."], Neighborhood::Four).percent(), 5);
+        assert_eq!(center(["...", "FR.", "..."], Neighborhood::Four).percent(), 10);
+        let all = center([".C.", "FRI", ".#."], Neighborhood::Four);
+        assert_eq!(all.iter().collect::<Vec<_>>(), Neighbor::ALL.to_vec());
+        assert_eq!(all.percent(), -20 + 5 + 10);
+    }
+
+    #[test]
+    fn roads_homes_and_the_map_edge_are_neutral() {
+        let rows = ["R#R", "#R#", "R#R"];
+        assert_eq!(center(rows, Neighborhood::Eight), Neighbors::default());
+        let corner = Neighbors::around(&zones(rows), IVec2::ZERO, Neighborhood::Eight);
+        assert_eq!(corner, Neighbors::default());
+        assert_eq!(Adjacency::new(3, 3).get(IVec2::new(-1, 0)), Neighbors::default());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::seq::IndexedRandom;
+use rand::SeedableRng;
+
+use crate::assets::CitySprites;
+use crate::config::MapConfig;
+use crate::grid::{tile_to_world, CARDINAL_NEIGHBORS};
+use crate::map::{TileCoord, TileData, TileMap, Zone};
+use crate::oneway::{can_drive, RoadDirection};
+use crate::pathfinding::find_path;
+use crate::simulation::{SimRng, SimTimer};
+use crate::state::{AppState, GameSet, ResetCity};
+
+/// Sprite sheet cell used for commuter cars.
+pub const CAR_SPRITE_INDEX: usize = 549;
+/// Upper bound on live agents regardless of population.
+const MAX_AGENTS: usize = 300;
+/// Residents represented by each agent on screen.
+const RESIDENTS_PER_AGENT: u32 = 20;
+/// New agents spawned per simulation tick at most, so they trickle in.
+const SPAWNS_PER_TICK: usize = 10;
+/// Travel speed in tiles per second.
+const AGENT_SPEED: f32 = 3.0;
+/// Draw order of the cars: on the roads of the buildings layer, under the
+/// grid lines.
+const AGENT_Z: f32 = 1.2;
+
+/// Cosmetic commuter cars driving between homes and jobs. They read the
+/// city but never feed back into the economy.
+pub struct AgentsPlugin;
+
+impl Plugin for AgentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, seed_agent_rng).add_systems(
+            Update,
+            (despawn_stranded_agents, reroute_agents, spawn_agents, move_agents)
+                .chain()
+                .in_set(GameSet::Simulation),
+        )
+        .add_systems(ResetCity, despawn_all_agents);
+    }
+}
+
+/// A commuter following a road path from home to work.
+#[derive(Component)]
+pub struct Agent {
+    home: IVec2,
+    work: IVec2,
+    path: Vec<IVec2>,
+    /// Distance travelled along `path`, in tiles.
+    progress: f32,
+}
+
+/// Random stream for picking trips. Agents spawn and despawn with frame
+/// timing, so they must not draw from `SimRng` and perturb the simulation.
+#[derive(Resource)]
+struct AgentRng(SmallRng);
+
+fn seed_agent_rng(mut commands: Commands, mut sim_rng: ResMut<SimRng>) {
+    commands.insert_resource(AgentRng(SmallRng::from_rng(&mut sim_rng.0)));
+}
+
+/// Top up the agent count toward the population's share each tick.
+#[allow(clippy::too_many_arguments)]
+fn spawn_agents(
+    mut commands: Commands,
+    timer: Res<SimTimer>,
+    mut rng: ResMut<AgentRng>,
+    sprites: Res<CitySprites>,
+    map: Res<MapConfig>,
+    tile_map: Res<TileMap>,
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    directions: Query<&RoadDirection>,
+    agents: Query<(), With<Agent>>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    let mut homes = Vec::new();
+    let mut jobs = Vec::new();
+    let mut population = 0;
+    for (coord, zone, data) in tiles.iter() {
+        population += data.population;
+        match zone {
+            Zone::Residential if data.population > 0 => homes.push(coord.coord),
+            Zone::Commercial | Zone::Industrial if data.jobs > 0 => {
+                jobs.push(coord.coord)
+            }
+            _ => {}
+        }
+    }
+
+    let target = ((population / RESIDENTS_PER_AGENT) as usize).min(MAX_AGENTS);
+    let missing = target.saturating_sub(agents.iter().count());
+    if missing == 0 || homes.is_empty() || jobs.is_empty() {
+        return;
+    }
+
+    let is_road = |c: IVec2| {
+        tile_map
+            .get(c)
+            .and_then(|e| tiles.get(e).ok())
+            .is_some_and(|(_, zone, _)| zone.is_road())
+    };
+    let direction = |c: IVec2| {
+        tile_map
+            .get(c)
+            .and_then(|e| directions.get(e).ok())
+            .map_or(RoadDirection::TwoWay, |direction| *direction)
+    };
+    let can_step = |from: IVec2, to: IVec2| can_drive(direction(from), direction(to), to - from);
+    let road_next_to =
+        |c: IVec2| CARDINAL_NEIGHBORS.iter().map(|&n| c + n).find(|&n| is_road(n));
+
+    for _ in 0..missing.min(SPAWNS_PER_TICK) {
+        let (Some(&home), Some(&work)) =
+            (homes.choose(&mut rng.0), jobs.choose(&mut rng.0))
+        else {
+            return;
+        };
+        // No route means no trip; try another pair next time.
+        let Some(path) = road_next_to(home)
+            .zip(road_next_to(work))
+            .and_then(|(from, to)| find_path(from, to, is_road, can_step))
+        else {
+            continue;
+        };
+
+        commands.spawn((
+            Sprite {
+                image: sprites.texture.clone(),
+                custom_size: Some(Vec2::splat(map.tile_size * 0.6)),
+                texture_atlas: Some(TextureAtlas {
+                    layout: sprites.layout.clone(),
+                    index: CAR_SPRITE_INDEX,
+                }),
+                ..default()
+            },
+            Transform::from_translation(tile_to_world(&map, path[0]).extend(AGENT_Z)),
+            Agent {
+                home,
+                work,
+                path,
+                progress: 0.0,
+            },
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+/// Drive every agent along its path and remove it on arrival.
+fn move_agents(
+    mut commands: Commands,
+    time: Res<Time>,
+    map: Res<MapConfig>,
+    mut agents: Query<(Entity, &mut Agent, &mut Transform)>,
+) {
+    let step = AGENT_SPEED * time.delta_secs();
+    for (entity, mut agent, mut transform) in agents.iter_mut() {
+        agent.progress += step;
+        let last = agent.path.len() - 1;
+        if agent.progress >= last as f32 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let segment = agent.progress as usize;
+        let from = tile_to_world(&map, agent.path[segment]);
+        let to = tile_to_world(&map, agent.path[segment + 1]);
+        let position = from.lerp(to, agent.progress.fract());
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Despawn agents whose home, workplace, or remaining route was rezoned.
+fn despawn_stranded_agents(
+    mut commands: Commands,
+    changed: Query<&TileCoord, Changed<Zone>>,
+    agents: Query<(Entity, &Agent)>,
+) {
+    let changed: HashSet<IVec2> = changed.iter().map(|c| c.coord).collect();
+    if changed.is_empty() {
+        return;
+    }
+    for (entity, agent) in agents.iter() {
+        let remaining = &agent.path[agent.progress as usize..];
+        if changed.contains(&agent.home)
+            || changed.contains(&agent.work)
+            || remaining.iter().any(|c| changed.contains(c))
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Re-route agents when a road ahead of them changes direction to run
+/// against their route, from the tile they are driving to. An agent with
+/// no way left to work gives up and despawns.
+fn reroute_agents(
+    mut commands: Commands,
+    redirected: Query<&TileCoord, Changed<RoadDirection>>,
+    tile_map: Res<TileMap>,
+    tiles: Query<(&Zone, &RoadDirection)>,
+    mut agents: Query<(Entity, &mut Agent)>,
+) {
+    let redirected: HashSet<IVec2> = redirected.iter().map(|c| c.coord).collect();
+    if redirected.is_empty() {
+        return;
+    }
+    let tile = |c: IVec2| tile_map.get(c).and_then(|e| tiles.get(e).ok());
+    let is_road = |c: IVec2| tile(c).is_some_and(|(zone, _)| zone.is_road());
+    let direction = |c: IVec2| tile(c).map_or(RoadDirection::TwoWay, |(_, direction)| *direction);
+    let can_step = |from: IVec2, to: IVec2| can_drive(direction(from), direction(to), to - from);
+    for (entity, mut agent) in agents.iter_mut() {
+        // The step under way is finished either way.
+        let next = agent.progress as usize + 1;
+        let ahead = &agent.path[next..];
+        if !ahead.iter().any(|c| redirected.contains(c))
+            || ahead.windows(2).all(|step| can_step(step[0], step[1]))
+        {
+            continue;
+        }
+        let goal = agent.path[agent.path.len() - 1];
+        match find_path(agent.path[next], goal, is_road, can_step) {
+            Some(route) => {
+                agent.path.truncate(next);
+                agent.path.extend(route);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+fn despawn_all_agents(mut commands: Commands, agents: Query<Entity, With<Agent>>) {
+    for entity in agents.iter() {
+        commands.entity(entity).despawn();
+    }
+}
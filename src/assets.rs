@@ -0,0 +1,315 @@
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::download_sprites::{self, DownloadStatus};
+use crate::agents::CAR_SPRITE_INDEX;
+use crate::locale::Locale;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::locale::Phrase;
+use crate::map::{Zone, CONSTRUCTION_COLOR, CONSTRUCTION_SPRITE_INDEX};
+use crate::state::{AppState, SpawnSet};
+use crate::zone_defs::ZoneDefs;
+
+// Use the tilemap.png that's already in the repo
+const SPRITE_ASSET_PATH: &str = "kenney_roguelike-modern-city/Tilemap/tilemap.png";
+
+/// Layout of the Kenney sprite sheet, shared with the placeholder atlas.
+const SHEET_TILE_PX: u32 = 16;
+const SHEET_COLUMNS: u32 = 37;
+const SHEET_ROWS: u32 = 28;
+const SHEET_SPACING_PX: u32 = 1;
+/// Cells on the sheet, so on the placeholder atlas too.
+pub const SHEET_CELLS: usize = (SHEET_COLUMNS * SHEET_ROWS) as usize;
+
+/// Gets the sprite sheet onto disk behind a loading screen, then builds the
+/// texture atlas when play starts.
+pub struct CityAssetsPlugin;
+
+impl Plugin for CityAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_loading_screen)
+            .add_systems(OnExit(AppState::Loading), despawn_loading_screen)
+            .add_systems(OnEnter(AppState::DownloadFailed), show_download_error)
+            .add_systems(
+                Update,
+                continue_with_placeholders
+                    .run_if(in_state(AppState::DownloadFailed)),
+            )
+            .add_systems(OnExit(AppState::DownloadFailed), despawn_loading_screen)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                load_sprites.in_set(SpawnSet::Assets),
+            );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(OnEnter(AppState::Loading), start_sprite_download)
+            .add_systems(
+                Update,
+                poll_sprite_download.run_if(in_state(AppState::Loading)),
+            );
+        // On the web the AssetServer fetches the bundled sheet over HTTP, so
+        // there is nothing to download up front.
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(OnEnter(AppState::Loading), skip_sprite_download);
+    }
+}
+
+/// Resource holding sprite atlas info
+#[derive(Resource)]
+pub struct CitySprites {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+/// Present when the sprite sheet could not be obtained and tiles are drawn
+/// from a generated placeholder atlas instead.
+#[derive(Resource)]
+pub struct PlaceholderGraphics;
+
+/// Receiving end of the background sprite download.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource)]
+struct SpriteDownload(std::sync::Mutex<std::sync::mpsc::Receiver<DownloadStatus>>);
+
+/// Root of the loading screen UI.
+#[derive(Component)]
+struct LoadingScreen;
+
+/// Status line on the loading screen.
+#[derive(Component)]
+struct LoadingText;
+
+/// Fill node of the loading progress bar.
+#[derive(Component)]
+struct LoadingBar;
+
+fn setup_loading_screen(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text("loading.downloading")),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(Justify::Center),
+                LoadingText,
+            ));
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(300.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.2, 0.8, 0.2)),
+                        LoadingBar,
+                    ));
+                });
+        });
+}
+
+/// Verify (and if needed download) the sprite sheet on a background thread
+/// so the window stays responsive.
+#[cfg(not(target_arch = "wasm32"))]
+fn start_sprite_download(mut commands: Commands) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let sheet_path = std::path::Path::new("assets").join(SPRITE_ASSET_PATH);
+    std::thread::spawn(move || {
+        download_sprites::ensure_sprites_downloaded(&sheet_path, sender);
+    });
+    commands.insert_resource(SpriteDownload(std::sync::Mutex::new(receiver)));
+}
+
+/// Update the loading screen from download progress and move on once the
+/// sprite sheet is ready (or the download gave up).
+#[cfg(not(target_arch = "wasm32"))]
+fn poll_sprite_download(
+    mut commands: Commands,
+    download: Res<SpriteDownload>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut text: Query<&mut Text, With<LoadingText>>,
+    mut bar: Query<&mut Node, With<LoadingBar>>,
+    locale: Res<Locale>,
+) {
+    let Ok(receiver) = download.0.lock() else {
+        return;
+    };
+
+    while let Ok(status) = receiver.try_recv() {
+        match status {
+            DownloadStatus::Progress { received, total } => {
+                if let Ok(mut text) = text.single_mut() {
+                    **text = locale.render(
+                        &Phrase::new("loading.progress").number("kb", (received / 1024) as i64),
+                    );
+                }
+                if let (Ok(mut node), Some(total)) = (bar.single_mut(), total) {
+                    node.width = Val::Percent(
+                        received as f32 / total.max(1) as f32 * 100.0,
+                    );
+                }
+            }
+            DownloadStatus::Finished(result) => {
+                match result {
+                    Ok(()) => next_state.set(AppState::MainMenu),
+                    Err(err) => {
+                        if let Ok(mut text) = text.single_mut() {
+                            **text = err;
+                        }
+                        next_state.set(AppState::DownloadFailed);
+                    }
+                }
+                commands.remove_resource::<SpriteDownload>();
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn skip_sprite_download(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::MainMenu);
+}
+
+fn despawn_loading_screen(
+    mut commands: Commands,
+    screens: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in screens.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Keep the error message on screen and drop the progress bar.
+fn show_download_error(
+    mut commands: Commands,
+    bar: Query<&ChildOf, With<LoadingBar>>,
+    mut text: Query<(&mut Text, &mut TextColor), With<LoadingText>>,
+    locale: Res<Locale>,
+) {
+    if let Ok(child_of) = bar.single() {
+        commands.entity(child_of.parent()).despawn();
+    }
+    if let Ok((mut text, mut color)) = text.single_mut() {
+        text.push_str("\n\n");
+        text.push_str(&locale.text("loading.placeholders"));
+        color.0 = Color::srgb(0.9, 0.3, 0.3);
+    }
+}
+
+/// Go on to the main menu without the sprite sheet when the player asks
+/// to.
+fn continue_with_placeholders(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        commands.insert_resource(PlaceholderGraphics);
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn load_sprites(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    placeholder: Option<Res<PlaceholderGraphics>>,
+    defs: Res<ZoneDefs>,
+) {
+    let texture = if placeholder.is_some() {
+        images.add(placeholder_sheet(&defs))
+    } else {
+        asset_server.load(SPRITE_ASSET_PATH)
+    };
+
+    // Kenney's tilemap has 1px spacing between tiles
+    let layout = TextureAtlasLayout::from_grid(
+        UVec2::splat(SHEET_TILE_PX),       // tile size in pixels
+        SHEET_COLUMNS,
+        SHEET_ROWS,
+        Some(UVec2::splat(SHEET_SPACING_PX)), // 1px padding between tiles (CRITICAL FIX)
+        None,                                 // no offset
+    );
+
+    let layout_handle = texture_atlases.add(layout);
+
+    commands.insert_resource(CitySprites {
+        texture,
+        layout: layout_handle,
+    });
+}
+
+/// Build a stand-in for the Kenney sheet with the same layout, where every
+/// cell a zone uses is filled with that zone's placeholder color (and the
+/// car and building site cells with plain ones of their own).
+fn placeholder_sheet(defs: &ZoneDefs) -> Image {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{
+        Extent3d, TextureDimension, TextureFormat,
+    };
+
+    let stride = SHEET_TILE_PX + SHEET_SPACING_PX;
+    let width = SHEET_COLUMNS * stride - SHEET_SPACING_PX;
+    let height = SHEET_ROWS * stride - SHEET_SPACING_PX;
+    let mut data = vec![0u8; (width * height * 4) as usize];
+
+    let cells = Zone::ALL
+        .into_iter()
+        .flat_map(|zone| {
+            let color = defs.get(zone).color();
+            defs.sprite_indices(zone).map(move |index| (index, color))
+        })
+        .chain([
+            (CAR_SPRITE_INDEX, Color::srgb(0.9, 0.2, 0.2)),
+            (CONSTRUCTION_SPRITE_INDEX, CONSTRUCTION_COLOR),
+        ]);
+    for (index, color) in cells {
+        let index = index as u32;
+        let left = (index % SHEET_COLUMNS) * stride;
+        let top = (index / SHEET_COLUMNS) * stride;
+        let rgba = color.to_srgba().to_u8_array();
+        for y in top..top + SHEET_TILE_PX {
+            for x in left..left + SHEET_TILE_PX {
+                let offset = ((y * width + x) * 4) as usize;
+                data[offset..offset + 4].copy_from_slice(&rgba);
+            }
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
@@ -0,0 +1,361 @@
+use std::f32::consts::TAU;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::audio::{AddAudioSource, Source, Volume};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+
+use crate::input::TileEdit;
+use crate::locale::Phrase;
+use crate::map::Zone;
+use crate::settings::Settings;
+use crate::simulation::CityStats;
+use crate::state::{AppState, GameSet, SpawnSet};
+use crate::ui::Notification;
+
+/// Sample rate of every generated sound.
+const SAMPLE_RATE: u32 = 22_050;
+/// Length of the ambient loop, and of the crossfade that hides its seam.
+const AMBIENT_LOOP_SECONDS: f32 = 4.0;
+const AMBIENT_SEAM_SECONDS: f32 = 0.5;
+/// Ambient volume of an empty map and of a city of `AMBIENT_FULL_POPULATION`.
+const AMBIENT_QUIET: f32 = 0.05;
+const AMBIENT_LOUD: f32 = 0.4;
+const AMBIENT_FULL_POPULATION: f32 = 5_000.0;
+/// Seconds the ambient volume takes to settle after the population changes.
+const AMBIENT_SMOOTHING_SECONDS: f32 = 3.0;
+
+/// Placement clicks, the bulldozer, and the city hum, all synthesized at
+/// startup so the game ships no audio files. M mutes everything. Volume
+/// and muting live in `Settings`.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Tone>()
+            .add_systems(Startup, generate_sounds)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_ambient.in_set(SpawnSet::Ui),
+            )
+            .add_systems(Update, play_edit_sounds.in_set(GameSet::Ui))
+            .add_systems(
+                Update,
+                (toggle_mute, update_ambient_volume)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// One-shot sound effects.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Effect {
+    Road,
+    Residential,
+    Commercial,
+    Industrial,
+    Service,
+    Terrain,
+    Bulldoze,
+    /// Building a service the treasury couldn't pay for.
+    Error,
+}
+
+impl Effect {
+    const ALL: [Effect; 8] = [
+        Effect::Road,
+        Effect::Residential,
+        Effect::Commercial,
+        Effect::Industrial,
+        Effect::Service,
+        Effect::Terrain,
+        Effect::Bulldoze,
+        Effect::Error,
+    ];
+
+    /// Sound of placing `zone`.
+    fn placing(zone: Zone) -> Self {
+        match zone {
+            Zone::Empty => Effect::Bulldoze,
+            Zone::Road | Zone::DirtRoad | Zone::Avenue | Zone::Bridge => Effect::Road,
+            Zone::Residential => Effect::Residential,
+            Zone::Commercial | Zone::Airport => Effect::Commercial,
+            Zone::Industrial | Zone::PowerPlant | Zone::Seaport => Effect::Industrial,
+            Zone::FireStation
+            | Zone::PoliceStation
+            | Zone::School
+            | Zone::WaterTower
+            | Zone::PumpingStation
+            | Zone::Landfill
+            | Zone::RecyclingCenter => Effect::Service,
+            Zone::Water | Zone::Forest => Effect::Terrain,
+        }
+    }
+
+    fn render(self) -> Vec<f32> {
+        let mut noise = Noise::default();
+        match self {
+            // A short tick of noise, like a paving stone set down.
+            Effect::Road => render(0.05, |t| 0.35 * noise.next() * (-t * 90.0).exp()),
+            Effect::Residential => render(0.1, |t| 0.4 * sine(880.0, t) * (-t * 40.0).exp()),
+            // Two rising notes, a shop door bell.
+            Effect::Commercial => render(0.2, |t| {
+                let (freq, start) = if t < 0.08 { (660.0, 0.0) } else { (990.0, 0.08) };
+                0.35 * sine(freq, t) * (-(t - start) * 30.0).exp()
+            }),
+            Effect::Industrial => render(0.18, |t| {
+                (0.45 * sine(150.0, t) + 0.15 * noise.next()) * (-t * 25.0).exp()
+            }),
+            Effect::Service => render(0.5, |t| {
+                let bell = sine(523.0, t) + 0.5 * sine(1046.0, t) + 0.25 * sine(1569.0, t);
+                0.25 * bell * (-t * 9.0).exp()
+            }),
+            Effect::Terrain => render(0.25, |t| {
+                0.3 * sine(440.0, t) * (t / 0.05).min(1.0) * (-t * 14.0).exp()
+            }),
+            Effect::Bulldoze => {
+                let mut rumble = LowPass::new(0.08);
+                render(0.4, |t| {
+                    (0.9 * rumble.next(noise.next()) + 0.3 * sine(60.0, t)) * (-t * 8.0).exp()
+                })
+            }
+            Effect::Error => render(0.25, |t| 0.2 * sine(110.0, t).signum()),
+        }
+    }
+}
+
+/// Handles of every generated sound.
+#[derive(Resource)]
+struct Sounds {
+    /// Indexed by `Effect`.
+    effects: [Handle<Tone>; Effect::ALL.len()],
+    ambient: Handle<Tone>,
+}
+
+/// The looping city hum.
+#[derive(Component)]
+struct AmbientSound;
+
+/// A generated mono sound, played through `AudioPlayer<Tone>`.
+#[derive(Asset, TypePath)]
+pub struct Tone {
+    samples: Arc<[f32]>,
+}
+
+pub struct ToneDecoder {
+    samples: Arc<[f32]>,
+    next: usize,
+}
+
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.next).copied();
+        self.next += 1;
+        sample
+    }
+}
+
+impl Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.next))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+impl Decodable for Tone {
+    type DecoderItem = f32;
+    type Decoder = ToneDecoder;
+
+    fn decoder(&self) -> ToneDecoder {
+        ToneDecoder {
+            samples: self.samples.clone(),
+            next: 0,
+        }
+    }
+}
+
+/// `seconds` of samples of `f(t)`, faded in and out over a few milliseconds
+/// so the edges don't click.
+fn render(seconds: f32, mut f: impl FnMut(f32) -> f32) -> Vec<f32> {
+    let len = (seconds * SAMPLE_RATE as f32) as usize;
+    let fade = (0.004 * SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|i| {
+            let edge = i.min(len - 1 - i) as f32 / fade as f32;
+            f(i as f32 / SAMPLE_RATE as f32) * edge.min(1.0)
+        })
+        .collect()
+}
+
+fn sine(freq: f32, t: f32) -> f32 {
+    (TAU * freq * t).sin()
+}
+
+/// White noise in -1..1 from a fixed xorshift seed, so every run sounds the
+/// same and the city RNG is left alone.
+struct Noise(u32);
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self(0x9E37_79B9)
+    }
+}
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// One-pole low-pass filter; smaller `amount` keeps less of the highs.
+struct LowPass {
+    amount: f32,
+    value: f32,
+}
+
+impl LowPass {
+    fn new(amount: f32) -> Self {
+        Self { amount, value: 0.0 }
+    }
+
+    fn next(&mut self, input: f32) -> f32 {
+        self.value += (input - self.value) * self.amount;
+        self.value
+    }
+}
+
+/// Traffic rumble over a low hum. The loop is rendered a seam longer than
+/// it plays, and the extra tail is crossfaded into the start so it repeats
+/// without a click; the hum frequencies fit a whole number of cycles.
+fn render_ambient() -> Vec<f32> {
+    let mut noise = Noise::default();
+    let mut rumble = LowPass::new(0.02);
+    let mut hiss = LowPass::new(0.3);
+    let len = (AMBIENT_LOOP_SECONDS * SAMPLE_RATE as f32) as usize;
+    let seam = (AMBIENT_SEAM_SECONDS * SAMPLE_RATE as f32) as usize;
+    let raw: Vec<f32> = (0..len + seam)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let n = noise.next();
+            2.0 * rumble.next(n)
+                + 0.04 * hiss.next(n)
+                + 0.15 * sine(55.0, t)
+                + 0.05 * sine(110.0, t)
+        })
+        .collect();
+    let mut samples = raw[..len].to_vec();
+    for (i, sample) in samples.iter_mut().take(seam).enumerate() {
+        let blend = i as f32 / seam as f32;
+        *sample = raw[len + i] * (1.0 - blend) + *sample * blend;
+    }
+    samples
+}
+
+fn generate_sounds(mut commands: Commands, mut tones: ResMut<Assets<Tone>>) {
+    let mut tone = |samples: Vec<f32>| {
+        tones.add(Tone {
+            samples: samples.into(),
+        })
+    };
+    commands.insert_resource(Sounds {
+        effects: Effect::ALL.map(|effect| tone(effect.render())),
+        ambient: tone(render_ambient()),
+    });
+}
+
+fn spawn_ambient(mut commands: Commands, sounds: Res<Sounds>) {
+    commands.spawn((
+        AudioPlayer(sounds.ambient.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::Linear(0.0)),
+        AmbientSound,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// One sound per frame for the player's edits: the zone placed or the
+/// bulldozer, or a buzz when a building pushed the treasury into the red.
+/// Nothing refuses a build the city can't afford, so the buzz is the
+/// warning that it went into debt.
+fn play_edit_sounds(
+    mut commands: Commands,
+    mut edits: MessageReader<TileEdit>,
+    stats: Res<CityStats>,
+    settings: Res<Settings>,
+    sounds: Res<Sounds>,
+) {
+    let Some(effect) = edits
+        .read()
+        .filter_map(|edit| {
+            if edit.cost > 0 && stats.money < 0 {
+                return Some(Effect::Error);
+            }
+            edit.tiles.first().map(|(_, tile)| Effect::placing(tile.zone))
+        })
+        .last()
+    else {
+        return;
+    };
+    if settings.muted {
+        return;
+    }
+    commands.spawn((
+        AudioPlayer(sounds.effects[effect as usize].clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(settings.sound_level())),
+    ));
+}
+
+fn toggle_mute(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keys.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    settings.muted = !settings.muted;
+    notifications.write(Notification(Phrase::new(if settings.muted {
+        "notify.sound_off"
+    } else {
+        "notify.sound_on"
+    })));
+}
+
+/// The hum swells gently as the city grows, easing toward its new level
+/// rather than jumping each tick.
+fn update_ambient_volume(
+    time: Res<Time>,
+    stats: Res<CityStats>,
+    settings: Res<Settings>,
+    mut level: Local<f32>,
+    mut sinks: Query<&mut AudioSink, With<AmbientSound>>,
+) {
+    let growth = (stats.population as f32 / AMBIENT_FULL_POPULATION).sqrt().min(1.0);
+    let target = AMBIENT_QUIET + (AMBIENT_LOUD - AMBIENT_QUIET) * growth;
+    let ease = 1.0 - (-time.delta_secs() / AMBIENT_SMOOTHING_SECONDS).exp();
+    *level += (target - *level) * ease;
+
+    let volume = Volume::Linear(*level * settings.sound_level());
+    for mut sink in sinks.iter_mut() {
+        sink.set_volume(volume);
+    }
+}
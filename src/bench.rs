@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy::ecs::schedule::Schedule;
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::adjacency::AdjacencyPlugin;
+use crate::commute::CommutePlugin;
+use crate::config::MapConfig;
+use crate::day_night::DayNightPlugin;
+use crate::development::Development;
+use crate::diagnostics::{SimDiagnostics, SimPhase};
+use crate::districts::{DistrictStats, Districts};
+use crate::economy::Economy;
+use crate::elevation::ElevationPlugin;
+use crate::garbage::Garbage;
+use crate::grid::Neighborhood;
+use crate::loans::Loans;
+use crate::map::{TileCoord, TileData, TileMap, Zone};
+use crate::oneway::RoadDirection;
+use crate::ports::Ports;
+use crate::power::PowerPlugin;
+use crate::services::ServicesPlugin;
+use crate::sim::ZoneGrid;
+use crate::simulation::{simulation_step, SimulationPlugin};
+use crate::state::ResetCity;
+use crate::statistics::CityBreakdown;
+use crate::tourism::Tourism;
+use crate::trade::Trade;
+use crate::traffic::TrafficPlugin;
+use crate::water::{Pipes, WaterSupply};
+use crate::weather::Weather;
+use crate::zone_defs::ZoneDefsPlugin;
+
+/// Map sizes measured, each with the number of ticks to time on it.
+const SIZES: [(i32, usize); 3] = [(32, 400), (128, 60), (256, 20)];
+/// Seed of both the benchmark layout and the simulation, so every run
+/// times exactly the same work.
+const BENCH_SEED: u64 = 0x5EED;
+/// Side length of a city block between two roads.
+const BLOCK: i32 = 5;
+/// Ticks run before timing starts, so traffic and job access have settled.
+const WARMUP_TICKS: usize = 3;
+
+/// Time the simulation on generated cities of a few sizes and print a
+/// table of the results (`--bench-sim`), then one of the phases the
+/// simulation times itself in `SimDiagnostics`. Nothing is drawn; the
+/// simulation plugins run in a bare `App` and their systems are called
+/// directly.
+pub fn run() {
+    println!("Simulation benchmark, seed {BENCH_SEED:#x} (median of each run)\n");
+    println!(
+        "| {:>7} | {:>5} | {:>15} | {:>12} | {:>13} | {:>11} | {:>11} | {:>8} |",
+        "map",
+        "ticks",
+        "simulation_step",
+        "full tick",
+        "HashMap build",
+        "grid build",
+        "HashMap adj",
+        "grid adj",
+    );
+    println!(
+        "|{:-<9}|{:-<7}|{:-<17}|{:-<14}|{:-<15}|{:-<13}|{:-<13}|{:-<10}|",
+        "", "", "", "", "", "", "", ""
+    );
+    let mut phase_rows = Vec::new();
+    for (size, ticks) in SIZES {
+        let layout = bench_layout(size, size);
+        let mut app = bench_app(size, &layout);
+
+        let step = app.world_mut().register_system(simulation_step);
+        let mut update = app
+            .world_mut()
+            .remove_resource::<Schedules>()
+            .and_then(|mut schedules| schedules.remove(Update))
+            .expect("the simulation plugins add Update systems");
+        let world = app.world_mut();
+        for _ in 0..WARMUP_TICKS {
+            tick(world, &mut update);
+        }
+
+        let full_tick = median(ticks, || tick(world, &mut update));
+        let sim_step = median(ticks, || {
+            advance(world);
+            world.run_system(step).expect("simulation_step runs");
+        });
+
+        let hash_map_build = median(ticks, || {
+            black_box(zone_hash_map(&layout));
+        });
+        let grid_build = median(ticks, || {
+            black_box(zone_grid(size, &layout));
+        });
+        let grid = zone_grid(size, &layout);
+        let zone_map = zone_hash_map(&layout);
+        let hash_map_adjacency = median(ticks, || {
+            black_box(homes_next_to_roads(size, size, |c| zone_map.get(&c).copied()));
+        });
+        let grid_adjacency = median(ticks, || {
+            black_box(homes_next_to_roads(size, size, |c| grid.get(c)));
+        });
+
+        println!(
+            "| {:>7} | {:>5} | {:>15} | {:>12} | {:>13} | {:>11} | {:>11} | {:>8} |",
+            format!("{size}x{size}"),
+            ticks,
+            format_duration(sim_step),
+            format_duration(full_tick),
+            format_duration(hash_map_build),
+            format_duration(grid_build),
+            format_duration(hash_map_adjacency),
+            format_duration(grid_adjacency),
+        );
+        let phases = phase_medians(world, &mut update, ticks);
+        phase_rows.push((size, phases));
+    }
+
+    print!("\n| {:>7} |", "map");
+    for phase in SimPhase::ALL {
+        print!(" {:>12} |", format!("{phase:?}"));
+    }
+    print!("\n|{:-<9}|", "");
+    for _ in SimPhase::ALL {
+        print!("{:-<14}|", "");
+    }
+    println!();
+    for (size, phases) in phase_rows {
+        print!("| {:>7} |", format!("{size}x{size}"));
+        for phase in phases {
+            print!(" {:>12} |", format_duration(phase));
+        }
+        println!();
+    }
+}
+
+/// Median time of every simulation phase over `ticks` more ticks, timed by
+/// the systems themselves.
+fn phase_medians(
+    world: &mut World,
+    update: &mut Schedule,
+    ticks: usize,
+) -> [Duration; SimPhase::ALL.len()] {
+    world.resource_mut::<SimDiagnostics>().enabled = true;
+    let mut timings: Vec<[Duration; SimPhase::ALL.len()]> = (0..ticks)
+        .map(|_| {
+            tick(world, update);
+            let diagnostics = world.resource::<SimDiagnostics>();
+            SimPhase::ALL.map(|phase| diagnostics.last(phase))
+        })
+        .collect();
+    world.resource_mut::<SimDiagnostics>().enabled = false;
+    SimPhase::ALL.map(|phase| {
+        timings.sort_by_key(|timing| timing[phase as usize]);
+        timings[timings.len() / 2][phase as usize]
+    })
+}
+
+/// A block grid of streets with a seeded mix of zones and some residents
+/// and jobs already in place, so growth, coverage, and commuting all have
+/// work to do.
+fn bench_layout(width: i32, height: i32) -> Vec<(IVec2, Zone, TileData)> {
+    let mut rng = SmallRng::seed_from_u64(BENCH_SEED);
+    let mut tiles = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let zone = if x % BLOCK == 0 || y % BLOCK == 0 {
+                Zone::Road
+            } else {
+                match rng.random_range(0..100) {
+                    0..50 => Zone::Residential,
+                    50..70 => Zone::Commercial,
+                    70..88 => Zone::Industrial,
+                    88..90 => Zone::FireStation,
+                    90..92 => Zone::PoliceStation,
+                    92..94 => Zone::School,
+                    94..97 => Zone::Forest,
+                    _ => Zone::Empty,
+                }
+            };
+            let data = match zone {
+                Zone::Residential => TileData {
+                    population: rng.random_range(0..60),
+                    ..default()
+                },
+                Zone::Commercial | Zone::Industrial => TileData {
+                    jobs: rng.random_range(0..60),
+                    ..default()
+                },
+                _ => TileData::default(),
+            };
+            tiles.push((IVec2::new(x, y), zone, data));
+        }
+    }
+    tiles
+}
+
+/// The simulation plugins on a bare `App`, reset for a `size`x`size` map
+/// and holding `layout`, every tile of it row by row.
+pub fn bench_app(size: i32, layout: &[(IVec2, Zone, TileData)]) -> App {
+    let mut app = App::new();
+    app.init_schedule(ResetCity)
+        .insert_resource(Time::<()>::default())
+        .init_resource::<Loans>()
+        .init_resource::<Economy>()
+        .insert_resource(Pipes::new(size, size))
+        .insert_resource(Districts::new(size, size))
+        .insert_resource(Garbage::new(size, size))
+        .init_resource::<DistrictStats>()
+        .init_resource::<CityBreakdown>()
+        .init_resource::<Ports>()
+        .init_resource::<Weather>()
+        .init_resource::<Tourism>()
+        .insert_resource(Trade::new(size, size))
+        .insert_resource(WaterSupply::new(size, size))
+        .insert_resource(MapConfig {
+            width: size,
+            height: size,
+            tile_size: 1.0,
+        })
+        .add_plugins((
+            TaskPoolPlugin::default(),
+            SimulationPlugin {
+                tick_seconds: 1.0,
+                starting_money: 10_000,
+                road_upkeep: 0.5,
+                seed: BENCH_SEED,
+                neighborhood: Neighborhood::Four,
+                development: Development::Instant,
+            },
+            ServicesPlugin,
+            TrafficPlugin,
+            CommutePlugin,
+            DayNightPlugin,
+            ElevationPlugin,
+            AdjacencyPlugin,
+            PowerPlugin,
+            ZoneDefsPlugin,
+        ));
+    let world = app.world_mut();
+    world.run_schedule(ResetCity);
+    let tiles = layout
+        .iter()
+        .map(|&(coord, zone, data)| {
+            world
+                .spawn((TileCoord { coord }, zone, data, RoadDirection::default()))
+                .id()
+        })
+        .collect();
+    world.insert_resource(TileMap::new(size, size, tiles));
+    app
+}
+
+/// Move time on by exactly one simulation tick.
+pub fn advance(world: &mut World) {
+    world
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_secs(1));
+}
+
+/// One tick of every simulation system, as the game runs them.
+fn tick(world: &mut World, update: &mut Schedule) {
+    advance(world);
+    update.run(world);
+}
+
+/// Median wall time of `runs` calls of `f`.
+fn median(runs: usize, mut f: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[times.len() / 2]
+}
+
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros >= 1000.0 {
+        format!("{:.2} ms", micros / 1000.0)
+    } else {
+        format!("{micros:.1} us")
+    }
+}
+
+/// The zone lookup `simulation_step` used to rebuild every tick, kept as
+/// the baseline `ZoneGrid` replaced.
+fn zone_hash_map(layout: &[(IVec2, Zone, TileData)]) -> HashMap<IVec2, Zone> {
+    layout.iter().map(|&(coord, zone, _)| (coord, zone)).collect()
+}
+
+/// The lookup `simulation_step` builds every tick now.
+fn zone_grid(size: i32, layout: &[(IVec2, Zone, TileData)]) -> ZoneGrid {
+    let mut grid = ZoneGrid::new(size, size);
+    for &(coord, zone, _) in layout {
+        grid.set(coord, zone);
+    }
+    grid
+}
+
+/// Homes with a road on some side, the check residential growth makes,
+/// through either zone lookup.
+fn homes_next_to_roads(
+    width: i32,
+    height: i32,
+    zone_at: impl Fn(IVec2) -> Option<Zone>,
+) -> usize {
+    (0..width * height)
+        .map(|i| IVec2::new(i % width, i / width))
+        .filter(|&c| zone_at(c) == Some(Zone::Residential))
+        .filter(|&c| {
+            [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+                .iter()
+                .any(|&n| zone_at(c + n).is_some_and(Zone::is_road))
+        })
+        .count()
+}
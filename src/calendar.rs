@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+
+use crate::day_night::{advance_clock, TimeOfDay};
+use crate::history::StatsHistory;
+use crate::locale::Phrase;
+use crate::simulation::SimTimer;
+use crate::state::{GameSet, ResetCity};
+
+/// Simulation ticks in one calendar day, days in a month, and months in a
+/// year. A month lasts as long as one turn of the day/night cycle.
+pub const TICKS_PER_DAY: u64 = 4;
+pub const DAYS_PER_MONTH: u64 = 30;
+pub const TICKS_PER_MONTH: u64 = TICKS_PER_DAY * DAYS_PER_MONTH;
+pub const TICKS_PER_YEAR: u64 = TICKS_PER_MONTH * MONTH_NAMES.len() as u64;
+const MONTH_NAMES: [&str; 12] = [
+    "month.jan",
+    "month.feb",
+    "month.mar",
+    "month.apr",
+    "month.may",
+    "month.jun",
+    "month.jul",
+    "month.aug",
+    "month.sep",
+    "month.oct",
+    "month.nov",
+    "month.dec",
+];
+/// Months of history kept for long-term graphs: a hundred years.
+const MONTHLY_HISTORY_CAPACITY: usize = 1200;
+
+/// Groups simulation ticks into days, months, and years, and announces
+/// the end of each month for the systems that run monthly.
+pub struct CalendarPlugin;
+
+impl Plugin for CalendarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .init_resource::<MonthlyHistory>()
+            .add_message::<MonthEnded>()
+            .add_systems(
+                Update,
+                (update_calendar, record_monthly_stats)
+                    .chain()
+                    .in_set(GameSet::Simulation)
+                    .after(advance_clock),
+            )
+            .add_systems(ResetCity, reset_calendar);
+    }
+}
+
+/// The calendar date the clock's ticks have reached. Kept in step with
+/// `TimeOfDay`, which is what saves store, so it follows the simulation at
+/// any speed.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameClock {
+    /// Starting at 1.
+    pub year: u64,
+    /// 0 for January.
+    pub month: usize,
+    /// Day of the month, starting at 1.
+    pub day: u64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::at(0)
+    }
+}
+
+impl GameClock {
+    /// The date after `ticks` simulation ticks.
+    pub fn at(ticks: u64) -> Self {
+        let months = ticks / TICKS_PER_MONTH;
+        Self {
+            year: months / MONTH_NAMES.len() as u64 + 1,
+            month: (months % MONTH_NAMES.len() as u64) as usize,
+            day: ticks % TICKS_PER_MONTH / TICKS_PER_DAY + 1,
+        }
+    }
+
+    /// The date as the HUD shows it, like "Mar, Year 3".
+    pub fn phrase(&self) -> Phrase {
+        Phrase::new("calendar.date")
+            .key("month", MONTH_NAMES[self.month])
+            .with("year", self.year)
+    }
+}
+
+/// Whether the tick numbered `tick` (counting from 1) is the last of its
+/// month.
+pub fn ends_month(tick: u64) -> bool {
+    tick > 0 && tick.is_multiple_of(TICKS_PER_MONTH)
+}
+
+/// Written on the tick a month ends, with the month just finished.
+#[derive(Message, Clone, Copy)]
+pub struct MonthEnded {
+    // Only the native autosave reads it so far.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub month: GameClock,
+}
+
+/// One `StatsHistory` snapshot per month, for graphs over years of play.
+#[derive(Resource)]
+pub struct MonthlyHistory(pub StatsHistory);
+
+impl Default for MonthlyHistory {
+    fn default() -> Self {
+        Self(StatsHistory::with_capacity(MONTHLY_HISTORY_CAPACITY))
+    }
+}
+
+/// Follow the clock, which also catches it jumping when a city is loaded,
+/// and announce each month that a tick finishes.
+fn update_calendar(
+    timer: Res<SimTimer>,
+    clock: Res<TimeOfDay>,
+    mut calendar: ResMut<GameClock>,
+    mut month_ended: MessageWriter<MonthEnded>,
+) {
+    calendar.set_if_neq(GameClock::at(clock.ticks));
+    if timer.0.just_finished() && ends_month(clock.ticks) {
+        month_ended.write(MonthEnded {
+            month: GameClock::at(clock.ticks - 1),
+        });
+    }
+}
+
+/// Keep the city's totals at the end of each month.
+fn record_monthly_stats(
+    mut month_ended: MessageReader<MonthEnded>,
+    history: Res<StatsHistory>,
+    mut monthly: ResMut<MonthlyHistory>,
+) {
+    for _ in month_ended.read() {
+        if let Some(latest) = history.latest() {
+            monthly.0.push(*latest);
+        }
+    }
+}
+
+fn reset_calendar(mut calendar: ResMut<GameClock>, mut monthly: ResMut<MonthlyHistory>) {
+    *calendar = GameClock::default();
+    monthly.0.clear();
+}
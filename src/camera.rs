@@ -0,0 +1,387 @@
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResized};
+
+use crate::config::MapConfig;
+use crate::grid::tile_to_world;
+use crate::input::{ctrl_held, cursor_tile};
+use crate::map::MainCamera;
+use crate::state::AppState;
+use crate::touch::TouchGestures;
+
+/// Screen pixels per second the camera pans while a pan key is held.
+const PAN_SPEED: f32 = 600.0;
+/// Zoom change per line of mouse wheel, and the zoom range allowed
+/// (projection scale: below 1 is zoomed in).
+const ZOOM_STEP: f32 = 1.15;
+const MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 4.0;
+/// Scroll pixels that count as one wheel line on touchpads.
+const PIXELS_PER_LINE: f32 = 40.0;
+/// Wheel lines per second a fully pulled gamepad trigger zooms by.
+const TRIGGER_ZOOM_LINES: f32 = 6.0;
+/// How much bigger than the map the view is when framing all of it.
+const FIT_MARGIN: f32 = 1.1;
+/// Zoom a focused tile is shown at, unless already zoomed in closer.
+const FOCUS_SCALE: f32 = 0.5;
+/// How long a focus animation takes.
+const FOCUS_SECONDS: f32 = 0.3;
+/// Closer than this to the target, the camera lands on it.
+const SETTLE_DISTANCE: f32 = 0.05;
+const SETTLE_SCALE: f32 = 0.0005;
+/// Longest gap between the clicks of a double-click.
+const DOUBLE_CLICK_SECONDS: f32 = 0.35;
+
+/// Panning (WASD, arrow keys, dragging with the middle button or a finger,
+/// or a gamepad's left stick) and zoom (the mouse wheel, pinching, or the
+/// gamepad's triggers), eased toward where the player is steering rather than
+/// snapping there. A city starts framed to fit the whole map in the window,
+/// Home or 0 returns to that view, and zooming out stops there (or at the
+/// usual limit, if that is further). Focusing a tile animates the camera to
+/// it: double-click a tile or click a notification about one.
+pub struct CameraPlugin {
+    /// Seconds the camera takes to catch up with the player's steering;
+    /// zero snaps straight there.
+    pub smoothing: f32,
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CameraRig {
+            smoothing: self.smoothing,
+            ..default()
+        })
+        .add_systems(OnEnter(AppState::Playing), reset_camera)
+        .add_systems(
+            Update,
+            (
+                (
+                    steer_camera,
+                    refit_on_resize,
+                    focus_on_double_click,
+                    focus_on_links,
+                    reset_view,
+                ),
+                move_camera,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Clicking a UI node with this focuses the camera on the tile.
+#[derive(Component, Clone, Copy)]
+pub struct TileLink(pub IVec2);
+
+/// Where the camera is headed and how it gets there.
+#[derive(Resource)]
+struct CameraRig {
+    smoothing: f32,
+    /// Position and projection scale the camera eases toward.
+    target: Vec2,
+    scale: f32,
+    /// Projection scale that fits the whole map in the window.
+    fit_scale: f32,
+    /// A focus animation in flight, which overrides the easing until it
+    /// ends or the player steers.
+    focus: Option<Focus>,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.0,
+            target: Vec2::ZERO,
+            scale: 1.0,
+            fit_scale: 1.0,
+            focus: None,
+        }
+    }
+}
+
+impl CameraRig {
+    /// Keep the target over the map: the middle of the view never leaves
+    /// it, and the zoom stays in range, which always reaches far enough out
+    /// to see the whole map.
+    fn clamp(&mut self, map: &MapConfig) {
+        let half = Vec2::new(map.width as f32, map.height as f32) * map.tile_size / 2.0;
+        self.target = self.target.clamp(-half, half);
+        self.scale = self.scale.clamp(MIN_SCALE, MAX_SCALE.max(self.fit_scale));
+    }
+
+    /// Start animating from the camera's current view to `tile`.
+    fn focus(&mut self, map: &MapConfig, camera: (Vec2, f32), tile: IVec2) {
+        self.glide(map, camera, tile_to_world(map, tile), camera.1.min(FOCUS_SCALE));
+    }
+
+    /// Start animating from the camera's current view to `target` at
+    /// `scale`.
+    fn glide(&mut self, map: &MapConfig, camera: (Vec2, f32), target: Vec2, scale: f32) {
+        self.target = target;
+        self.scale = scale;
+        self.clamp(map);
+        self.focus = Some(Focus {
+            from: camera.0,
+            from_scale: camera.1,
+            elapsed: 0.0,
+        });
+    }
+}
+
+/// Progress of a focus animation toward `CameraRig::target`.
+struct Focus {
+    from: Vec2,
+    from_scale: f32,
+    elapsed: f32,
+}
+
+/// Projection scale at which the whole map fits in a `window` the given
+/// size in logical pixels, with a margin around it.
+fn fit_scale(map: &MapConfig, window: Vec2) -> f32 {
+    let size = Vec2::new(map.width as f32, map.height as f32) * map.tile_size * FIT_MARGIN;
+    (size / window.max(Vec2::ONE)).max_element().max(MIN_SCALE)
+}
+
+/// Size of the primary window in logical pixels.
+fn window_size(windows: &Query<&Window, With<PrimaryWindow>>) -> Option<Vec2> {
+    windows.single().ok().map(Window::size)
+}
+
+/// A new city is looked at from the middle, framed to fit the window.
+fn reset_camera(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    rig.fit_scale = window_size(&windows).map_or(1.0, |size| fit_scale(&map, size));
+    rig.target = Vec2::ZERO;
+    rig.scale = rig.fit_scale;
+    rig.focus = None;
+    if let Ok((mut transform, mut projection)) = camera.single_mut() {
+        transform.translation = Vec3::ZERO;
+        if let Projection::Orthographic(projection) = &mut *projection {
+            projection.scale = rig.scale;
+        }
+    }
+}
+
+/// Follow the window's size: a view framing the whole map keeps doing so,
+/// and any other view is kept within the new zoom range.
+fn refit_on_resize(
+    mut resized: MessageReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+) {
+    if resized.read().count() == 0 {
+        return;
+    }
+    let Some(size) = window_size(&windows) else {
+        return;
+    };
+    let fit = fit_scale(&map, size);
+    if (rig.scale - rig.fit_scale).abs() < SETTLE_SCALE {
+        rig.scale = fit;
+    }
+    rig.fit_scale = fit;
+    rig.clamp(&map);
+}
+
+/// Move the target with the pan keys, middle-drag, the mouse wheel, touch
+/// gestures, and the gamepad's left stick and triggers. Any of them cancels a focus
+/// animation from wherever it has got to, so the player takes over without
+/// the camera pulling back.
+#[allow(clippy::too_many_arguments)]
+fn steer_camera(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    gamepads: Query<&Gamepad>,
+    touch: Res<TouchGestures>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+    camera: Query<(&Transform, &Projection), With<MainCamera>>,
+) {
+    let mut pan = Vec2::ZERO;
+    if !ctrl_held(&keys) {
+        for (key, direction) in [
+            (KeyCode::KeyW, Vec2::Y),
+            (KeyCode::ArrowUp, Vec2::Y),
+            (KeyCode::KeyS, Vec2::NEG_Y),
+            (KeyCode::ArrowDown, Vec2::NEG_Y),
+            (KeyCode::KeyA, Vec2::NEG_X),
+            (KeyCode::ArrowLeft, Vec2::NEG_X),
+            (KeyCode::KeyD, Vec2::X),
+            (KeyCode::ArrowRight, Vec2::X),
+        ] {
+            if keys.pressed(key) {
+                pan += direction;
+            }
+        }
+    }
+    // The right trigger zooms in and the left one out, as far as they are
+    // pulled.
+    let mut stick = Vec2::ZERO;
+    let mut triggers = 0.0;
+    for gamepad in gamepads.iter() {
+        stick += gamepad.left_stick();
+        triggers += gamepad.get(GamepadButton::RightTrigger2).unwrap_or(0.0)
+            - gamepad.get(GamepadButton::LeftTrigger2).unwrap_or(0.0);
+    }
+    let pan = (pan.normalize_or_zero() + stick).clamp_length_max(1.0)
+        * PAN_SPEED
+        * time.delta_secs();
+    let drag = if buttons.pressed(MouseButton::Middle) {
+        Vec2::new(-motion.delta.x, motion.delta.y)
+    } else {
+        Vec2::ZERO
+    } + touch.pan;
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_LINE,
+    } + triggers * TRIGGER_ZOOM_LINES * time.delta_secs();
+    if pan == Vec2::ZERO && drag == Vec2::ZERO && lines == 0.0 && touch.pinch == 1.0 {
+        return;
+    }
+
+    if rig.focus.take().is_some() {
+        if let Ok((transform, Projection::Orthographic(projection))) = camera.single() {
+            rig.target = transform.translation.truncate();
+            rig.scale = projection.scale;
+        }
+    }
+    // Pans cover the same screen distance at any zoom.
+    let scale = rig.scale;
+    rig.target += (pan + drag) * scale;
+    rig.scale = scale * ZOOM_STEP.powf(-lines) * touch.pinch;
+    rig.clamp(&map);
+}
+
+/// Double-clicking a map tile focuses it.
+#[allow(clippy::too_many_arguments)]
+fn focus_on_double_click(
+    time: Res<Time>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    camera: Query<(&Transform, &Projection), With<MainCamera>>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+    mut last_click: Local<Option<(IVec2, f32)>>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(tile) = cursor_tile(&windows, &camera_q, &map) else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    let double = last_click
+        .is_some_and(|(last, at)| last == tile && now - at <= DOUBLE_CLICK_SECONDS);
+    *last_click = if double { None } else { Some((tile, now)) };
+    if double {
+        if let Some(view) = current_view(&camera) {
+            rig.focus(&map, view, tile);
+        }
+    }
+}
+
+/// Clicking a notification about a tile focuses it.
+fn focus_on_links(
+    links: Query<(&Interaction, &TileLink), Changed<Interaction>>,
+    camera: Query<(&Transform, &Projection), With<MainCamera>>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+) {
+    for (interaction, link) in links.iter() {
+        if *interaction == Interaction::Pressed {
+            if let Some(view) = current_view(&camera) {
+                rig.focus(&map, view, link.0);
+            }
+        }
+    }
+}
+
+/// Home or 0 glides back to the view of the whole map.
+fn reset_view(
+    keys: Res<ButtonInput<KeyCode>>,
+    camera: Query<(&Transform, &Projection), With<MainCamera>>,
+    map: Res<MapConfig>,
+    mut rig: ResMut<CameraRig>,
+) {
+    if !keys.any_just_pressed([KeyCode::Home, KeyCode::Digit0, KeyCode::Numpad0]) {
+        return;
+    }
+    if let Some(view) = current_view(&camera) {
+        let fit = rig.fit_scale;
+        rig.glide(&map, view, Vec2::ZERO, fit);
+    }
+}
+
+/// Position and projection scale of the main camera.
+fn current_view(
+    camera: &Query<(&Transform, &Projection), With<MainCamera>>,
+) -> Option<(Vec2, f32)> {
+    match camera.single().ok()? {
+        (transform, Projection::Orthographic(projection)) => {
+            Some((transform.translation.truncate(), projection.scale))
+        }
+        _ => None,
+    }
+}
+
+/// Bring the camera to the target: along a smoothstep curve while focusing,
+/// otherwise easing exponentially so it settles at the same rate whatever
+/// the frame rate.
+fn move_camera(
+    time: Res<Time>,
+    mut rig: ResMut<CameraRig>,
+    mut camera: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    let Ok((mut transform, mut projection)) = camera.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(projection) = &mut *projection else {
+        return;
+    };
+    let (target, scale) = (rig.target, rig.scale);
+
+    let (position, zoom) = if let Some(focus) = &mut rig.focus {
+        focus.elapsed += time.delta_secs();
+        let t = (focus.elapsed / FOCUS_SECONDS).min(1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        let moved = (
+            focus.from.lerp(target, eased),
+            focus.from_scale + (scale - focus.from_scale) * eased,
+        );
+        if t >= 1.0 {
+            rig.focus = None;
+        }
+        moved
+    } else {
+        let current = transform.translation.truncate();
+        let ease = if rig.smoothing > 0.0 {
+            1.0 - (-time.delta_secs() / rig.smoothing).exp()
+        } else {
+            1.0
+        };
+        // Land exactly once close enough, rather than creeping forever.
+        let position = current.lerp(target, ease);
+        let zoom = projection.scale + (scale - projection.scale) * ease;
+        (
+            if position.distance(target) < SETTLE_DISTANCE { target } else { position },
+            if (zoom - scale).abs() < SETTLE_SCALE { scale } else { zoom },
+        )
+    };
+
+    if transform.translation.truncate() != position {
+        transform.translation = position.extend(transform.translation.z);
+    }
+    if projection.scale != zoom {
+        projection.scale = zoom;
+    }
+}
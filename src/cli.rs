@@ -0,0 +1,243 @@
+use crate::config::{Config, MAX_MAP_SIZE};
+use crate::settings::Settings;
+
+pub const USAGE: &str = "\
+Usage: bevy_city_sim [OPTIONS]
+
+Options override values from city_sim.toml and the saved settings.
+
+  --map-size <W>x<H>   Map size in tiles, e.g. 64x64
+  --bench-sim          Time the simulation on large maps and exit
+  --dev                Allow the cheats in the ~ console (see README)
+  --import-map <PATH>  Start a city laid out from a PNG image, one pixel
+                       per tile (see README)
+  --lang <CODE>        Language of the interface, e.g. de (see README)
+  --random             Start new cities on a random map from the seed
+  --remote             Serve the city over HTTP on 127.0.0.1:15702 and
+                       skip the main menu (see README)
+  --replay <PATH>      Play back a recorded session, e.g. replay.ron
+  --seed <N>           Seed for the simulation RNG
+  --speed <X>          Simulation speed multiplier, e.g. 2
+  --windowed <W>x<H>   Run in a window of the given size
+  -h, --help           Print this help
+";
+
+/// Options given on the command line. Anything left `None` falls through
+/// to the config file and then the defaults.
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub help: bool,
+    pub bench_sim: bool,
+    /// Allow console cheats; not a config file setting.
+    pub dev: bool,
+    /// Layout image to start a city from; not a config file setting.
+    pub import_map: Option<String>,
+    pub lang: Option<String>,
+    pub map_size: Option<(i32, i32)>,
+    pub random: bool,
+    /// Serve the city to external tools; not a config file setting.
+    pub remote: bool,
+    /// Recording to play back; not a config file setting.
+    pub replay: Option<String>,
+    pub seed: Option<u64>,
+    pub speed: Option<f32>,
+    pub windowed: Option<(u32, u32)>,
+}
+
+impl CliArgs {
+    /// Parse arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next().ok_or_else(|| format!("{name} needs a value"))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--bench-sim" => parsed.bench_sim = true,
+                "--dev" => parsed.dev = true,
+                "--import-map" => parsed.import_map = Some(value("--import-map")?),
+                "--lang" => parsed.lang = Some(value("--lang")?),
+                "--map-size" => {
+                    let (w, h) = dimensions("--map-size", &value("--map-size")?)?;
+                    if !(1..=MAX_MAP_SIZE as u32).contains(&w)
+                        || !(1..=MAX_MAP_SIZE as u32).contains(&h)
+                    {
+                        return Err(format!(
+                            "--map-size must be between 1x1 and \
+                             {MAX_MAP_SIZE}x{MAX_MAP_SIZE}, got {w}x{h}"
+                        ));
+                    }
+                    parsed.map_size = Some((w as i32, h as i32));
+                }
+                "--random" => parsed.random = true,
+                "--remote" => parsed.remote = true,
+                "--replay" => parsed.replay = Some(value("--replay")?),
+                "--seed" => {
+                    let raw = value("--seed")?;
+                    parsed.seed = Some(raw.parse().map_err(|_| {
+                        format!("--seed must be a non-negative integer, got {raw}")
+                    })?);
+                }
+                "--speed" => {
+                    let raw = value("--speed")?;
+                    let speed: f32 = raw
+                        .parse()
+                        .map_err(|_| format!("--speed must be a number, got {raw}"))?;
+                    if !speed.is_finite() || speed <= 0.0 {
+                        return Err(format!(
+                            "--speed must be greater than zero, got {raw}"
+                        ));
+                    }
+                    parsed.speed = Some(speed);
+                }
+                "--windowed" => {
+                    parsed.windowed =
+                        Some(dimensions("--windowed", &value("--windowed")?)?);
+                }
+                other => return Err(format!("unknown argument {other}")),
+            }
+        }
+
+        if parsed.import_map.is_some() && parsed.replay.is_some() {
+            return Err("--import-map and --replay can't be used together".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// Layer these options over `config`, which already holds the config
+    /// file values on top of the defaults.
+    pub fn apply(&self, config: &mut Config, settings: &mut Settings) {
+        if let Some(lang) = &self.lang {
+            settings.language = lang.clone();
+        }
+        if let Some((width, height)) = self.map_size {
+            config.map.width = width;
+            config.map.height = height;
+        }
+        if self.random {
+            config.random_map = true;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(speed) = self.speed {
+            config.tick_seconds /= speed;
+        }
+        if let Some((width, height)) = self.windowed {
+            settings.window_width = width;
+            settings.window_height = height;
+        }
+    }
+}
+
+/// Parse `<W>x<H>` into two non-zero sizes.
+fn dimensions(name: &str, raw: &str) -> Result<(u32, u32), String> {
+    let parse = |part: Option<&str>| {
+        part.and_then(|p| p.trim().parse::<u32>().ok())
+            .filter(|&n| n > 0)
+    };
+    let mut parts = raw.split('x');
+    match (parse(parts.next()), parse(parts.next()), parts.next()) {
+        (Some(w), Some(h), None) => Ok((w, h)),
+        _ => Err(format!("{name} expects <W>x<H> with non-zero sizes, got {raw}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    /// The config file and the options over it, in the order `main` layers
+    /// them.
+    fn layered(file: &str, args: &[&str]) -> (Config, Settings) {
+        let mut config = Config::parse(file).unwrap();
+        let mut settings = Settings::from_config(&config);
+        parse(args).unwrap().apply(&mut config, &mut settings);
+        (config, settings)
+    }
+
+    #[test]
+    fn parses_every_option() {
+        let args = parse(&[
+            "--map-size", "64x48", "--seed", "7", "--speed", "2", "--windowed", "800x600",
+            "--lang", "de", "--random", "--dev", "--remote",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            CliArgs {
+                map_size: Some((64, 48)),
+                seed: Some(7),
+                speed: Some(2.0),
+                windowed: Some((800, 600)),
+                lang: Some("de".to_string()),
+                random: true,
+                dev: true,
+                remote: true,
+                ..CliArgs::default()
+            }
+        );
+        assert_eq!(parse(&[]).unwrap(), CliArgs::default());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        for args in [
+            &["--map-size", "64"][..],
+            &["--map-size", "0x10"],
+            &["--map-size", "2000x10"],
+            &["--map-size", "8x8x8"],
+            &["--seed", "-1"],
+            &["--speed", "0"],
+            &["--speed", "fast"],
+            &["--windowed", "x600"],
+            &["--seed"],
+            &["--unknown"],
+            &["--import-map", "city.png", "--replay", "replay.ron"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn defaults_without_a_file_or_options() {
+        let (config, settings) = layered("", &[]);
+        let defaults = Config::default();
+        assert_eq!((config.map.width, config.map.height), (32, 32));
+        assert_eq!(config.seed, defaults.seed);
+        assert_eq!(config.tick_seconds, defaults.tick_seconds);
+        assert_eq!(settings.window_width, defaults.window_width);
+    }
+
+    #[test]
+    fn the_file_overrides_the_defaults() {
+        let file = "[map]\nwidth = 50\n[simulation]\nseed = 3\ntick_seconds = 1.0\n";
+        let (config, _) = layered(file, &[]);
+        assert_eq!((config.map.width, config.map.height), (50, 32));
+        assert_eq!(config.seed, Some(3));
+        assert_eq!(config.tick_seconds, 1.0);
+    }
+
+    #[test]
+    fn options_override_the_file() {
+        let file = "[map]\nwidth = 50\n[simulation]\nseed = 3\ntick_seconds = 1.0\n\
+                    [window]\nwidth = 1024\nheight = 768\n[ui]\nlanguage = \"en\"\n";
+        let (config, settings) = layered(
+            file,
+            &["--map-size", "10x20", "--seed", "9", "--speed", "4", "--lang", "de"],
+        );
+        assert_eq!((config.map.width, config.map.height), (10, 20));
+        assert_eq!(config.seed, Some(9));
+        assert_eq!(config.tick_seconds, 0.25);
+        assert_eq!(settings.language, "de");
+        // Left alone by the options, so still the file's.
+        assert_eq!((settings.window_width, settings.window_height), (1024, 768));
+    }
+}
@@ -0,0 +1,370 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::assets::CitySprites;
+use crate::config::MapConfig;
+use crate::development::Development;
+use crate::elevation::Elevation;
+use crate::grid::tile_to_world;
+use crate::input::{ctrl_held, cursor_tile, rezone, TileEdit, UndoStack, ZoneAction};
+use crate::layers::{MapLayer, MapLayers};
+use crate::locale::Phrase;
+use crate::map::{MainCamera, TileData, TileMap, Zone};
+use crate::milestones::Milestones;
+use crate::replay::Playback;
+use crate::simulation::{CityStats, NewCity};
+use crate::state::{AppState, GameSet, ResetCity};
+use crate::ui::Notification;
+use crate::zone_defs::ZoneDefs;
+
+/// Color of the selection rectangle drawn over the map.
+const SELECTION_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.25);
+/// Tints of ghost tiles that will and won't be stamped.
+const GHOST_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
+const GHOST_BLOCKED_COLOR: Color = Color::srgba(1.0, 0.3, 0.3, 0.6);
+/// Draw order of the selection and ghosts within the markers layer, under
+/// the problem icons.
+const OVERLAY_Z: f32 = 0.0;
+
+/// Copy a rectangle of zoning and stamp it elsewhere: Ctrl+drag selects,
+/// Ctrl+C copies, Ctrl+V shows a preview that a click stamps (R rotates it,
+/// right click stops pasting).
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clipboard>()
+            .init_resource::<Selection>()
+            .add_message::<Notification>()
+            .add_message::<TileEdit>()
+            .add_systems(
+                Update,
+                (
+                    select_region,
+                    draw_selection,
+                    copy_selection,
+                    toggle_paste,
+                    rotate_clipboard,
+                    update_paste_preview,
+                    stamp_clipboard,
+                )
+                    .chain()
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(ResetCity, clear_clipboard);
+    }
+}
+
+/// Copied zoning, relative to the bottom-left corner of its block.
+#[derive(Resource, Default)]
+pub struct Clipboard {
+    tiles: Vec<(IVec2, Zone)>,
+    size: IVec2,
+    /// Set while the paste preview follows the cursor; plain clicks don't
+    /// zone tiles in the meantime.
+    pub pasting: bool,
+}
+
+impl Clipboard {
+    /// Turn the block a quarter turn clockwise.
+    fn rotate(&mut self) {
+        let width = self.size.x;
+        for (offset, _) in &mut self.tiles {
+            *offset = IVec2::new(offset.y, width - 1 - offset.x);
+        }
+        self.size = IVec2::new(self.size.y, self.size.x);
+    }
+
+    /// Bottom-left tile of the block when centered on `cursor`.
+    fn origin(&self, cursor: IVec2) -> IVec2 {
+        cursor - self.size / 2
+    }
+}
+
+/// The selected rectangle, and where the current Ctrl+drag started.
+#[derive(Resource, Default)]
+pub struct Selection {
+    anchor: Option<IVec2>,
+    rect: Option<IRect>,
+}
+
+/// Sprite covering the selected rectangle.
+#[derive(Component)]
+struct SelectionHighlight;
+
+/// Parent of the ghost tiles previewing a paste.
+#[derive(Component)]
+struct PastePreview;
+
+/// One ghost tile, at this offset into the clipboard block.
+#[derive(Component)]
+struct GhostTile(IVec2);
+
+/// Ctrl+drag selects a rectangle of tiles.
+fn select_region(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    mut selection: ResMut<Selection>,
+) {
+    if buttons.just_released(MouseButton::Left) {
+        selection.anchor = None;
+    }
+    if buttons.just_pressed(MouseButton::Right) {
+        *selection = Selection::default();
+        return;
+    }
+
+    let Some(cursor) = cursor_tile(&windows, &camera_q, &map) else {
+        return;
+    };
+    if buttons.just_pressed(MouseButton::Left) && ctrl_held(&keys) {
+        selection.anchor = Some(cursor);
+    }
+    if let Some(anchor) = selection.anchor {
+        let rect = Some(IRect::from_corners(anchor, cursor));
+        if selection.rect != rect {
+            selection.rect = rect;
+        }
+    }
+}
+
+/// Keep the selection highlight in step with the selection.
+fn draw_selection(
+    mut commands: Commands,
+    selection: Res<Selection>,
+    map: Res<MapConfig>,
+    layers: Res<MapLayers>,
+    mut highlight: Query<(Entity, &mut Transform, &mut Sprite), With<SelectionHighlight>>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+
+    let Some(rect) = selection.rect else {
+        for (entity, ..) in highlight.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let center =
+        (tile_to_world(&map, rect.min) + tile_to_world(&map, rect.max)) / 2.0;
+    let size = (rect.size() + IVec2::ONE).as_vec2() * map.tile_size;
+    if let Ok((_, mut transform, mut sprite)) = highlight.single_mut() {
+        transform.translation = center.extend(OVERLAY_Z);
+        sprite.custom_size = Some(size);
+    } else {
+        commands.spawn((
+            Sprite::from_color(SELECTION_COLOR, size),
+            Transform::from_translation(center.extend(OVERLAY_Z)),
+            SelectionHighlight,
+            ChildOf(layers.root(MapLayer::Markers)),
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+/// Ctrl+C copies the zones inside the selection. Multi-tile buildings are
+/// left out, since part of one can't be stamped on its own.
+fn copy_selection(
+    keys: Res<ButtonInput<KeyCode>>,
+    selection: Res<Selection>,
+    tile_map: Res<TileMap>,
+    zones: Query<&Zone>,
+    mut clipboard: ResMut<Clipboard>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !ctrl_held(&keys) || !keys.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+    let Some(rect) = selection.rect else {
+        return;
+    };
+
+    clipboard.tiles.clear();
+    for y in rect.min.y..=rect.max.y {
+        for x in rect.min.x..=rect.max.x {
+            let coord = IVec2::new(x, y);
+            let zone = tile_map.get(coord).and_then(|e| zones.get(e).ok());
+            if let Some(zone) = zone.filter(|zone| !zone.is_multi_tile()) {
+                clipboard.tiles.push((coord - rect.min, *zone));
+            }
+        }
+    }
+    clipboard.size = rect.size() + IVec2::ONE;
+    notifications.write(Notification(
+        Phrase::new("notify.copied")
+            .with("width", clipboard.size.x)
+            .with("height", clipboard.size.y),
+    ));
+}
+
+/// Ctrl+V starts pasting the clipboard; a right click stops.
+pub fn toggle_paste(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut clipboard: ResMut<Clipboard>,
+    mut selection: ResMut<Selection>,
+) {
+    if buttons.just_pressed(MouseButton::Right) && clipboard.pasting {
+        clipboard.pasting = false;
+    } else if ctrl_held(&keys)
+        && keys.just_pressed(KeyCode::KeyV)
+        && !clipboard.tiles.is_empty()
+    {
+        clipboard.pasting = true;
+        *selection = Selection::default();
+    }
+}
+
+/// R turns the block being pasted.
+fn rotate_clipboard(keys: Res<ButtonInput<KeyCode>>, mut clipboard: ResMut<Clipboard>) {
+    if clipboard.pasting && !ctrl_held(&keys) && keys.just_pressed(KeyCode::KeyR) {
+        clipboard.rotate();
+    }
+}
+
+/// Show the clipboard as ghost tiles centered on the cursor, marking the
+/// ones a click would skip.
+#[allow(clippy::too_many_arguments)]
+fn update_paste_preview(
+    mut commands: Commands,
+    clipboard: Res<Clipboard>,
+    sprites: Res<CitySprites>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    tile_map: Res<TileMap>,
+    elevation: Res<Elevation>,
+    defs: Res<ZoneDefs>,
+    layers: Res<MapLayers>,
+    zones: Query<&Zone>,
+    preview: Query<Entity, With<PastePreview>>,
+    mut ghosts: Query<(&GhostTile, &mut Sprite, &mut Transform)>,
+) {
+    let cursor = cursor_tile(&windows, &camera_q, &map);
+    if !clipboard.pasting || cursor.is_none() || clipboard.is_changed() {
+        for entity in preview.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+    let (true, Some(cursor)) = (clipboard.pasting, cursor) else {
+        return;
+    };
+
+    let origin = clipboard.origin(cursor);
+    if preview.is_empty() || clipboard.is_changed() {
+        commands
+            .spawn((
+                PastePreview,
+                Transform::default(),
+                Visibility::default(),
+                ChildOf(layers.root(MapLayer::Markers)),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .with_children(|parent| {
+                for &(offset, zone) in &clipboard.tiles {
+                    parent.spawn((
+                        Sprite {
+                            image: sprites.texture.clone(),
+                            custom_size: Some(Vec2::splat(map.tile_size)),
+                            color: GHOST_COLOR,
+                            texture_atlas: Some(TextureAtlas {
+                                layout: sprites.layout.clone(),
+                                index: defs.get(zone).sprite,
+                            }),
+                            ..default()
+                        },
+                        Transform::from_translation(
+                            tile_to_world(&map, origin + offset).extend(OVERLAY_Z),
+                        ),
+                        GhostTile(offset),
+                    ));
+                }
+            });
+        return;
+    }
+
+    for (ghost, mut sprite, mut transform) in ghosts.iter_mut() {
+        let coord = origin + ghost.0;
+        transform.translation = tile_to_world(&map, coord).extend(OVERLAY_Z);
+        let open = tile_map
+            .get(coord)
+            .and_then(|e| zones.get(e).ok())
+            .is_some_and(|zone| zone.buildable() && !elevation.too_steep(coord));
+        sprite.color = if open { GHOST_COLOR } else { GHOST_BLOCKED_COLOR };
+    }
+}
+
+/// A click while pasting stamps the block as one undoable action, zoning
+/// each tile as a paint stroke would: tiles off the map, on water, or too
+/// steep are skipped, as are buildings not unlocked yet, and every
+/// building placed is paid for.
+#[allow(clippy::too_many_arguments)]
+fn stamp_clipboard(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    clipboard: Res<Clipboard>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut undo: ResMut<UndoStack>,
+    mut stats: ResMut<CityStats>,
+    milestones: Res<Milestones>,
+    elevation: Res<Elevation>,
+    (defs, development): (Res<ZoneDefs>, Res<Development>),
+    new_city: Res<NewCity>,
+    mut edits: MessageWriter<TileEdit>,
+) {
+    if !clipboard.pasting || ctrl_held(&keys) || !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = cursor_tile(&windows, &camera_q, &map) else {
+        return;
+    };
+
+    let origin = clipboard.origin(cursor);
+    let mut action = ZoneAction::default();
+    for &(offset, zone) in &clipboard.tiles {
+        if !zone.buildable() {
+            continue;
+        }
+        let stamped = rezone(
+            origin + offset,
+            zone,
+            &tile_map,
+            &mut tiles,
+            &mut stats,
+            &milestones,
+            &elevation,
+            &defs,
+            *development,
+            new_city.seed,
+        );
+        if let Ok(stamped) = stamped {
+            action.cost += stamped.cost;
+            action.changes.extend(stamped.changes);
+        }
+    }
+    if !action.changes.is_empty() {
+        edits.write(TileEdit {
+            tiles: action.changes.iter().map(|c| (c.coord, c.after)).collect(),
+            cost: action.cost,
+        });
+    }
+    undo.push(action);
+}
+
+fn clear_clipboard(
+    mut clipboard: ResMut<Clipboard>,
+    mut selection: ResMut<Selection>,
+) {
+    *clipboard = Clipboard::default();
+    *selection = Selection::default();
+}
@@ -0,0 +1,468 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::diagnostics::{SimDiagnostics, SimPhase};
+use crate::grid::CARDINAL_NEIGHBORS;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::oneway::{can_drive, RoadDirection};
+use crate::simulation::{simulation_step, SimTimer};
+use crate::state::{GameSet, ResetCity};
+
+/// Road steps a resident will travel to get to work.
+pub const COMMUTE_RADIUS: u32 = 20;
+/// Homes whose job access is recomputed per tick; every home is revisited
+/// once the slices have gone all the way round the map.
+const HOMES_PER_TICK: usize = 256;
+
+/// Tracks how many spare jobs each home can reach by road, which gates
+/// residential growth.
+pub struct CommutePlugin;
+
+impl Plugin for CommutePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ResetCity, init_job_access).add_systems(
+            Update,
+            update_job_access
+                .in_set(GameSet::Simulation)
+                .after(simulation_step),
+        );
+    }
+}
+
+/// Jobs and competing workers within commuting range of one home.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Reach {
+    pub jobs: u32,
+    /// Residents of every home in range, including the starting one.
+    pub workers: u32,
+}
+
+impl Reach {
+    /// Jobs in range that nobody in range is taking yet.
+    pub fn unfilled(self) -> u32 {
+        self.jobs.saturating_sub(self.workers)
+    }
+}
+
+/// Spare jobs reachable from each home, laid out row by row. Only
+/// residential entries are meaningful.
+#[derive(Resource)]
+pub struct JobAccess {
+    width: i32,
+    height: i32,
+    unfilled: Vec<u32>,
+    /// Tile index the next slice of homes starts from.
+    next_home: usize,
+    /// Homes in range of a road that changed direction since they were
+    /// last visited, recomputed ahead of the next slice.
+    stale: HashSet<usize>,
+}
+
+impl JobAccess {
+    fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            unfilled: vec![0; (width * height) as usize],
+            next_home: 0,
+            stale: HashSet::new(),
+        }
+    }
+
+    /// Mark every tile a commute through the road at `road` could start
+    /// from: those within `COMMUTE_RADIUS` road steps of a road next to it.
+    fn mark_stale(&mut self, road: IVec2) {
+        let reach = COMMUTE_RADIUS as i32 + 1;
+        for y in (road.y - reach).max(0)..=(road.y + reach).min(self.height - 1) {
+            let spare = reach - (y - road.y).abs();
+            for x in (road.x - spare).max(0)..=(road.x + spare).min(self.width - 1) {
+                self.stale.insert((y * self.width + x) as usize);
+            }
+        }
+    }
+
+    /// Spare jobs within commuting range of the home at `coord`.
+    pub fn unfilled(&self, coord: IVec2) -> u32 {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return 0;
+        }
+        self.unfilled[(coord.y * self.width + coord.x) as usize]
+    }
+}
+
+/// Walk the road network up to `radius` steps out from the roads touching
+/// `home`, adding up the jobs and residents of every tile along the way.
+/// Each road tile entered costs its tier's steps, so slow roads shrink the
+/// range, and one-way roads are only driven the way they run.
+#[allow(clippy::too_many_arguments)]
+pub fn reach_from(
+    width: i32,
+    height: i32,
+    zones: &[Zone],
+    directions: &[RoadDirection],
+    population: &[u32],
+    jobs: &[u32],
+    home: IVec2,
+    radius: u32,
+) -> Reach {
+    let index = |c: IVec2| {
+        (c.x >= 0 && c.y >= 0 && c.x < width && c.y < height)
+            .then(|| (c.y * width + c.x) as usize)
+    };
+    let coord = |i: usize| IVec2::new(i as i32 % width, i as i32 / width);
+
+    let mut reach = Reach::default();
+    let mut counted = HashSet::new();
+    let mut count = |i: usize, reach: &mut Reach| {
+        if counted.insert(i) {
+            match zones[i] {
+                Zone::Residential => reach.workers += population[i],
+                Zone::Commercial | Zone::Industrial => reach.jobs += jobs[i],
+                _ => {}
+            }
+        }
+    };
+    let Some(start) = index(home) else {
+        return reach;
+    };
+    count(start, &mut reach);
+
+    // Cheapest known steps to each road tile, explored cheapest first.
+    let mut best = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    for &n in &CARDINAL_NEIGHBORS {
+        if let Some(i) = index(home + n).filter(|&i| zones[i].is_road()) {
+            best.insert(i, 0);
+            queue.push(Reverse((0, i)));
+        }
+    }
+    while let Some(Reverse((steps, road))) = queue.pop() {
+        if best.get(&road).is_some_and(|&known| known < steps) {
+            continue;
+        }
+        for &n in &CARDINAL_NEIGHBORS {
+            let Some(j) = index(coord(road) + n) else {
+                continue;
+            };
+            let Some(tier) = zones[j].road() else {
+                count(j, &mut reach);
+                continue;
+            };
+            if !can_drive(directions[road], directions[j], coord(j) - coord(road)) {
+                continue;
+            }
+            let next = steps + tier.steps;
+            if next <= radius && best.get(&j).is_none_or(|&known| next < known) {
+                best.insert(j, next);
+                queue.push(Reverse((next, j)));
+            }
+        }
+    }
+    reach
+}
+
+/// A road route from a home to a tile with jobs, as the commute check walks
+/// it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CommuteRoute {
+    /// The tile with jobs the route ends next to.
+    pub job: IVec2,
+    /// Road steps taken, counted the way `radius` limits them.
+    pub steps: u32,
+    /// Road tiles from the one touching the home to the one touching the
+    /// job.
+    pub roads: Vec<IVec2>,
+}
+
+/// Routes from `home` to the `limit` nearest tiles with jobs within
+/// `radius` road steps, nearest first, along with every road tile in that
+/// range. Steps are counted and one-way roads followed as in `reach_from`.
+#[allow(clippy::too_many_arguments)]
+pub fn routes_from(
+    width: i32,
+    height: i32,
+    zones: &[Zone],
+    directions: &[RoadDirection],
+    jobs: &[u32],
+    home: IVec2,
+    radius: u32,
+    limit: usize,
+) -> (Vec<CommuteRoute>, Vec<IVec2>) {
+    let index = |c: IVec2| {
+        (c.x >= 0 && c.y >= 0 && c.x < width && c.y < height)
+            .then(|| (c.y * width + c.x) as usize)
+    };
+    let coord = |i: usize| IVec2::new(i as i32 % width, i as i32 / width);
+
+    let mut best = HashMap::new();
+    let mut came_from = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    for &n in &CARDINAL_NEIGHBORS {
+        if let Some(i) = index(home + n).filter(|&i| zones[i].is_road()) {
+            best.insert(i, 0);
+            queue.push(Reverse((0, i)));
+        }
+    }
+    let mut routes: Vec<CommuteRoute> = Vec::new();
+    while let Some(Reverse((steps, road))) = queue.pop() {
+        if best.get(&road).is_some_and(|&known| known < steps) {
+            continue;
+        }
+        for &n in &CARDINAL_NEIGHBORS {
+            let Some(j) = index(coord(road) + n) else {
+                continue;
+            };
+            if let Some(tier) = zones[j].road() {
+                if !can_drive(directions[road], directions[j], coord(j) - coord(road)) {
+                    continue;
+                }
+                let next = steps + tier.steps;
+                if next <= radius && best.get(&j).is_none_or(|&known| next < known) {
+                    best.insert(j, next);
+                    came_from.insert(j, road);
+                    queue.push(Reverse((next, j)));
+                }
+                continue;
+            }
+            let employs = matches!(zones[j], Zone::Commercial | Zone::Industrial) && jobs[j] > 0;
+            if employs && routes.len() < limit && routes.iter().all(|r| r.job != coord(j)) {
+                let mut roads = vec![coord(road)];
+                let mut at = road;
+                while let Some(&prev) = came_from.get(&at) {
+                    roads.push(coord(prev));
+                    at = prev;
+                }
+                roads.reverse();
+                routes.push(CommuteRoute {
+                    job: coord(j),
+                    steps,
+                    roads,
+                });
+            }
+        }
+    }
+    (routes, best.into_keys().map(coord).collect())
+}
+
+/// Population a home gains this tick out of a possible `base`, given the
+/// spare jobs in reach: none without any, at least one with some, and the
+/// full amount once they could employ everyone already living there.
+pub fn commute_growth(base: u32, unfilled: u32, population: u32) -> u32 {
+    if unfilled == 0 {
+        return 0;
+    }
+    (base * unfilled / population.max(1)).clamp(1, base)
+}
+
+fn init_job_access(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(JobAccess::new(map.width, map.height));
+}
+
+/// Recompute job access for the homes near roads that changed direction,
+/// then for the next slice of homes, in row order.
+fn update_job_access(
+    timer: Res<SimTimer>,
+    mut access: ResMut<JobAccess>,
+    diagnostics: Res<SimDiagnostics>,
+    redirected: Query<(&TileCoord, Ref<RoadDirection>), Changed<RoadDirection>>,
+    tiles: Query<(&TileCoord, &Zone, &TileData, &RoadDirection)>,
+) {
+    // Directions change between ticks too, so look for them every frame.
+    // A new map's tiles all count as changed, but start out two-way.
+    for (coord, direction) in redirected.iter() {
+        if !direction.is_added() {
+            access.mark_stale(coord.coord);
+        }
+    }
+    if !timer.0.just_finished() {
+        return;
+    }
+    let span = diagnostics.start();
+
+    let (width, height) = (access.width, access.height);
+    let size = (width * height) as usize;
+    let mut zones = vec![Zone::Empty; size];
+    let mut directions = vec![RoadDirection::TwoWay; size];
+    let mut population = vec![0; size];
+    let mut jobs = vec![0; size];
+    for (coord, zone, data, direction) in tiles.iter() {
+        let i = (coord.coord.y * width + coord.coord.x) as usize;
+        zones[i] = *zone;
+        directions[i] = *direction;
+        population[i] = data.population;
+        jobs[i] = data.jobs;
+    }
+    let reach = |home: usize| {
+        let home = IVec2::new(home as i32 % width, home as i32 / width);
+        reach_from(width, height, &zones, &directions, &population, &jobs, home, COMMUTE_RADIUS)
+            .unfilled()
+    };
+
+    let stale = std::mem::take(&mut access.stale);
+    for i in stale {
+        if zones[i] == Zone::Residential {
+            access.unfilled[i] = reach(i);
+        }
+    }
+
+    let mut visited = 0;
+    let mut updated = 0;
+    let mut i = access.next_home;
+    while updated < HOMES_PER_TICK && visited < size {
+        if zones[i] == Zone::Residential {
+            access.unfilled[i] = reach(i);
+            updated += 1;
+        }
+        visited += 1;
+        i = (i + 1) % size;
+    }
+    access.next_home = i;
+    diagnostics.finish(SimPhase::Connectivity, span);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A map drawn row by row: `R` a home of 5, `C` and `I` 10 jobs each,
+    /// `#` a road, `:` a dirt road, `<` a road one-way west; anything else
+    /// is empty.
+    struct Grid {
+        width: i32,
+        height: i32,
+        zones: Vec<Zone>,
+        directions: Vec<RoadDirection>,
+        population: Vec<u32>,
+        jobs: Vec<u32>,
+    }
+
+    fn grid(rows: &[&str]) -> Grid {
+        let mut grid = Grid {
+            width: rows[0].len() as i32,
+            height: rows.len() as i32,
+            zones: Vec::new(),
+            directions: Vec::new(),
+            population: Vec::new(),
+            jobs: Vec::new(),
+        };
+        for c in rows.concat().chars() {
+            let (zone, direction) = match c {
+                'R' => (Zone::Residential, RoadDirection::TwoWay),
+                'C' => (Zone::Commercial, RoadDirection::TwoWay),
+                'I' => (Zone::Industrial, RoadDirection::TwoWay),
+                '#' => (Zone::Road, RoadDirection::TwoWay),
+                ':' => (Zone::DirtRoad, RoadDirection::TwoWay),
+                '<' => (Zone::Road, RoadDirection::West),
+                _ => (Zone::Empty, RoadDirection::TwoWay),
+            };
+            grid.zones.push(zone);
+            grid.directions.push(direction);
+            grid.population.push(if zone == Zone::Residential { 5 } else { 0 });
+            grid.jobs.push(if c == 'C' || c == 'I' { 10 } else { 0 });
+        }
+        grid
+    }
+
+    fn reach(rows: &[&str], home: IVec2, radius: u32) -> Reach {
+        let g = grid(rows);
+        reach_from(
+            g.width,
+            g.height,
+            &g.zones,
+            &g.directions,
+            &g.population,
+            &g.jobs,
+            home,
+            radius,
+        )
+    }
+
+    #[test]
+    fn jobs_down_the_road_are_in_reach() {
+        let found = reach(&["R###C"], IVec2::ZERO, COMMUTE_RADIUS);
+        assert_eq!(found, Reach { jobs: 10, workers: 5 });
+        assert_eq!(found.unfilled(), 5);
+
+        let both = reach(&["C###R###I", "#########"], IVec2::new(4, 0), COMMUTE_RADIUS);
+        assert_eq!(both, Reach { jobs: 20, workers: 5 });
+    }
+
+    #[test]
+    fn jobs_without_a_road_between_are_out_of_reach() {
+        assert_eq!(reach(&["RC"], IVec2::ZERO, COMMUTE_RADIUS), Reach { jobs: 0, workers: 5 });
+        assert_eq!(reach(&["R#.#C"], IVec2::ZERO, COMMUTE_RADIUS).jobs, 0);
+    }
+
+    #[test]
+    fn homes_among_homes_share_no_jobs() {
+        let rows = ["RRR", "###", "RRR"];
+        let found = reach(&rows, IVec2::new(1, 0), COMMUTE_RADIUS);
+        assert_eq!(found, Reach { jobs: 0, workers: 30 });
+        assert_eq!(found.unfilled(), 0);
+
+        let crowded = reach(&["RRRC", "####"], IVec2::ZERO, COMMUTE_RADIUS);
+        assert_eq!(crowded, Reach { jobs: 10, workers: 15 });
+        assert_eq!(crowded.unfilled(), 0);
+    }
+
+    #[test]
+    fn the_radius_caps_the_road_steps() {
+        // The first road is free, so the shop's road is four steps away.
+        let rows = ["R#####C"];
+        assert_eq!(reach(&rows, IVec2::ZERO, 3).jobs, 0);
+        assert_eq!(reach(&rows, IVec2::ZERO, 4).jobs, 10);
+        // Dirt roads count double.
+        let dirt = ["R:::::C"];
+        assert_eq!(reach(&dirt, IVec2::ZERO, 7).jobs, 0);
+        assert_eq!(reach(&dirt, IVec2::ZERO, 8).jobs, 10);
+    }
+
+    #[test]
+    fn one_way_roads_are_only_driven_their_way() {
+        assert_eq!(reach(&["R#<#C"], IVec2::ZERO, COMMUTE_RADIUS).jobs, 0);
+        assert_eq!(reach(&["C#<#R"], IVec2::new(4, 0), COMMUTE_RADIUS).jobs, 10);
+    }
+
+    #[test]
+    fn routes_lead_to_the_nearest_jobs_first() {
+        let g = grid(&["C#R###I", ".#....."]);
+        let (routes, roads) = routes_from(
+            g.width,
+            g.height,
+            &g.zones,
+            &g.directions,
+            &g.jobs,
+            IVec2::new(2, 0),
+            COMMUTE_RADIUS,
+            2,
+        );
+        assert_eq!(
+            routes,
+            vec![
+                CommuteRoute {
+                    job: IVec2::ZERO,
+                    steps: 0,
+                    roads: vec![IVec2::new(1, 0)],
+                },
+                CommuteRoute {
+                    job: IVec2::new(6, 0),
+                    steps: 2,
+                    roads: vec![IVec2::new(3, 0), IVec2::new(4, 0), IVec2::new(5, 0)],
+                },
+            ]
+        );
+        assert_eq!(roads.len(), 5);
+    }
+
+    #[test]
+    fn growth_follows_the_spare_jobs() {
+        assert_eq!(commute_growth(4, 0, 10), 0);
+        assert_eq!(commute_growth(4, 1, 100), 1);
+        assert_eq!(commute_growth(4, 5, 10), 2);
+        assert_eq!(commute_growth(4, 50, 10), 4);
+        assert_eq!(commute_growth(4, 3, 0), 4);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::commute::{routes_from, CommuteRoute, COMMUTE_RADIUS};
+use crate::config::MapConfig;
+use crate::grid::tile_to_world;
+use crate::inspector::Inspected;
+use crate::locale::Phrase;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::oneway::RoadDirection;
+use crate::state::GameSet;
+use crate::tint::{apply_tile_tints, TileTint};
+
+/// Routes traced to the nearest tiles with jobs.
+const ROUTES_SHOWN: usize = 3;
+/// Line colors of the routes, nearest first.
+const ROUTE_COLORS: [Color; ROUTES_SHOWN] = [
+    Color::srgb(0.1, 0.95, 1.0),
+    Color::srgb(1.0, 0.3, 0.9),
+    Color::srgb(1.0, 0.9, 0.2),
+];
+/// Tint of the roads a home can reach when none of them lead to jobs.
+const REACHABLE_TINT: Color = Color::srgb(0.4, 0.7, 1.0);
+
+/// F10 turns on a debug view of the commute check: inspecting a home then
+/// draws its routes to the nearest jobs, or tints the roads it can reach
+/// when no route exists. Routes are traced once per inspected tile, not
+/// kept up to date.
+pub struct CommuteDebugPlugin;
+
+impl Plugin for CommuteDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommuteDebug>()
+            .init_resource::<TracedCommute>()
+            .add_systems(Update, toggle_commute_debug.in_set(GameSet::Input))
+            .add_systems(
+                Update,
+                (
+                    trace_commute,
+                    tint_reachable_roads.before(apply_tile_tints),
+                    draw_commute_routes,
+                )
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// Whether the debug view is on.
+#[derive(Resource, Default)]
+struct CommuteDebug(bool);
+
+/// What the commute check sees from one home.
+pub struct CommuteTrace {
+    pub home: IVec2,
+    /// Routes to the nearest tiles with jobs, nearest first.
+    pub routes: Vec<CommuteRoute>,
+    /// Every road tile within commuting range.
+    pub reachable: Vec<IVec2>,
+}
+
+impl CommuteTrace {
+    /// Inspector line with the distance to the nearest job, or why there
+    /// is none.
+    pub fn summary(&self) -> Phrase {
+        match self.routes.first() {
+            Some(route) => Phrase::new("inspector.commute").number("steps", route.steps),
+            None if self.reachable.is_empty() => Phrase::new("inspector.commute.no_road"),
+            None => Phrase::new("inspector.commute.none").number("radius", COMMUTE_RADIUS),
+        }
+    }
+}
+
+/// The trace of the inspected home while the debug view is on.
+#[derive(Resource, Default)]
+pub struct TracedCommute(pub Option<CommuteTrace>);
+
+fn toggle_commute_debug(keys: Res<ButtonInput<KeyCode>>, mut debug: ResMut<CommuteDebug>) {
+    if keys.just_pressed(KeyCode::F10) {
+        debug.0 = !debug.0;
+    }
+}
+
+/// Trace the inspected home when it or the debug view changes; nothing is
+/// searched on other frames.
+fn trace_commute(
+    debug: Res<CommuteDebug>,
+    inspected: Res<Inspected>,
+    map: Res<MapConfig>,
+    tiles: Query<(&TileCoord, &Zone, &TileData, &RoadDirection)>,
+    mut traced: ResMut<TracedCommute>,
+) {
+    if !debug.is_changed() && !inspected.is_changed() {
+        return;
+    }
+    traced.0 = None;
+    let Some(home) = inspected.0.filter(|_| debug.0) else {
+        return;
+    };
+
+    let size = (map.width * map.height) as usize;
+    let mut zones = vec![Zone::Empty; size];
+    let mut directions = vec![RoadDirection::TwoWay; size];
+    let mut jobs = vec![0; size];
+    for (coord, zone, data, direction) in tiles.iter() {
+        let i = (coord.coord.y * map.width + coord.coord.x) as usize;
+        zones[i] = *zone;
+        directions[i] = *direction;
+        jobs[i] = data.jobs;
+    }
+    if zones[(home.y * map.width + home.x) as usize] != Zone::Residential {
+        return;
+    }
+    let (routes, reachable) = routes_from(
+        map.width,
+        map.height,
+        &zones,
+        &directions,
+        &jobs,
+        home,
+        COMMUTE_RADIUS,
+        ROUTES_SHOWN,
+    );
+    traced.0 = Some(CommuteTrace {
+        home,
+        routes,
+        reachable,
+    });
+}
+
+/// Tint the roads a traced home can reach when none of them lead to jobs,
+/// and clear the tint everywhere else.
+fn tint_reachable_roads(traced: Res<TracedCommute>, mut tiles: Query<(&TileCoord, &mut TileTint)>) {
+    if !traced.is_changed() {
+        return;
+    }
+    let reachable: HashSet<IVec2> = traced
+        .0
+        .iter()
+        .filter(|trace| trace.routes.is_empty())
+        .flat_map(|trace| trace.reachable.iter().copied())
+        .collect();
+    for (coord, mut tint) in tiles.iter_mut() {
+        let route = if reachable.contains(&coord.coord) {
+            REACHABLE_TINT
+        } else {
+            Color::WHITE
+        };
+        if tint.route != route {
+            tint.route = route;
+        }
+    }
+}
+
+/// Draw the traced routes from the home's center along the roads to the
+/// job tile's center.
+fn draw_commute_routes(traced: Res<TracedCommute>, map: Res<MapConfig>, mut gizmos: Gizmos) {
+    let Some(trace) = &traced.0 else {
+        return;
+    };
+    for (route, color) in trace.routes.iter().zip(ROUTE_COLORS) {
+        let tiles = std::iter::once(trace.home)
+            .chain(route.roads.iter().copied())
+            .chain(std::iter::once(route.job));
+        gizmos.linestrip_2d(tiles.map(|coord| tile_to_world(&map, coord)), color);
+    }
+}
@@ -0,0 +1,334 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::development::Development;
+use crate::grid::Neighborhood;
+use crate::locale::DEFAULT_LANGUAGE;
+
+/// Optional config file read from the working directory at startup.
+pub const CONFIG_PATH: &str = "city_sim.toml";
+
+/// Largest map edge we accept, from the config file, the command line, or
+/// an imported image; bigger maps are better served by a save.
+pub const MAX_MAP_SIZE: i32 = 1024;
+
+/// Written to `CONFIG_PATH` when it doesn't exist so the options are easy
+/// to discover. Keep in sync with the defaults below.
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_CONFIG: &str = "\
+# Bevy City Sim configuration. Delete this file to restore the defaults.
+
+[map]
+# Map size in tiles.
+width = 32
+height = 32
+# On-screen size of one tile in pixels.
+tile_size = 32.0
+# Start new cities on a random map generated from the seed instead of an
+# empty grid.
+random = false
+
+[simulation]
+# Seconds between simulation ticks.
+tick_seconds = 0.5
+# Money in the treasury when a new city starts.
+starting_money = 0
+# Maintenance charged per road tile each tick (rounded over all roads).
+road_upkeep = 0.25
+# Tiles around a tile that count as next to it when checking for a road:
+# 4 for the sides only, 8 to include the diagonals. Also switchable from the
+# pause menu.
+neighborhood = 4
+# How homes, shops, and industry get built: instant puts a building down
+# as soon as a zone is painted; gradual only zones the lots, and buildings
+# go up on them over time as demand calls for them. Also switchable from
+# the main menu before starting a city.
+development = \"instant\"
+# Seed for the simulation RNG; leave unset for a different city every run.
+# seed = 42
+
+[window]
+width = 1280
+height = 720
+
+[camera]
+# Seconds the camera takes to catch up when panning and zooming; 0 snaps.
+smoothing = 0.1
+
+[audio]
+# Volume of all sound, from 0.0 (silent) to 1.0.
+volume = 0.8
+# Start with the sound off; M toggles it while playing.
+muted = false
+
+[ui]
+# Show tips for new players as the city takes shape.
+hints = true
+# Language of the interface: en or de, or any file in assets/locales.
+language = \"en\"
+";
+
+/// Dimensions of the tile grid, read by everything that converts between
+/// tiles and world space.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct MapConfig {
+    pub width: i32,
+    pub height: i32,
+    pub tile_size: f32,
+}
+
+/// Everything `city_sim.toml` can override.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub map: MapConfig,
+    pub random_map: bool,
+    pub tick_seconds: f32,
+    pub starting_money: i64,
+    pub road_upkeep: f32,
+    pub neighborhood: Neighborhood,
+    pub development: Development,
+    pub seed: Option<u64>,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub camera_smoothing: f32,
+    pub volume: f32,
+    pub muted: bool,
+    pub hints: bool,
+    pub language: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            map: MapConfig {
+                width: 32,
+                height: 32,
+                tile_size: 32.0,
+            },
+            random_map: false,
+            tick_seconds: 0.5,
+            starting_money: 0,
+            road_upkeep: 0.25,
+            neighborhood: Neighborhood::Four,
+            development: Development::Instant,
+            seed: None,
+            window_width: 1280,
+            window_height: 720,
+            camera_smoothing: 0.1,
+            volume: 0.8,
+            muted: false,
+            hints: true,
+            language: DEFAULT_LANGUAGE.to_string(),
+        }
+    }
+}
+
+/// On-disk layout of the config file; every field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    map: MapSection,
+    simulation: SimulationSection,
+    window: WindowSection,
+    camera: CameraSection,
+    audio: AudioSection,
+    ui: UiSection,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct MapSection {
+    width: Option<i64>,
+    height: Option<i64>,
+    tile_size: Option<f32>,
+    random: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct SimulationSection {
+    tick_seconds: Option<f32>,
+    starting_money: Option<i64>,
+    road_upkeep: Option<f32>,
+    neighborhood: Option<i64>,
+    development: Option<String>,
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct WindowSection {
+    width: Option<i64>,
+    height: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct CameraSection {
+    smoothing: Option<f32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct AudioSection {
+    volume: Option<f32>,
+    muted: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct UiSection {
+    hints: Option<bool>,
+    language: Option<String>,
+}
+
+impl Config {
+    /// Load `CONFIG_PATH`, writing a commented default file if there is
+    /// none. A broken file is reported and the defaults are used instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_or_create() -> Self {
+        let path = Path::new(CONFIG_PATH);
+        if !path.exists() {
+            if let Err(err) = fs::write(path, DEFAULT_CONFIG) {
+                eprintln!("Could not write default {CONFIG_PATH}: {err}");
+            }
+            return Self::default();
+        }
+
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Self::parse(&text))
+        {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Ignoring {CONFIG_PATH}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// There is no filesystem on the web, so always use the defaults.
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_or_create() -> Self {
+        Self::default()
+    }
+
+    /// Parse and validate config file contents on top of the defaults.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        let mut config = Self::default();
+
+        if let Some(width) = file.map.width {
+            config.map.width = map_size("map.width", width)?;
+        }
+        if let Some(height) = file.map.height {
+            config.map.height = map_size("map.height", height)?;
+        }
+        if let Some(tile_size) = file.map.tile_size {
+            config.map.tile_size = positive("map.tile_size", tile_size)?;
+        }
+        if let Some(random) = file.map.random {
+            config.random_map = random;
+        }
+        if let Some(tick) = file.simulation.tick_seconds {
+            config.tick_seconds = positive("simulation.tick_seconds", tick)?;
+        }
+        if let Some(money) = file.simulation.starting_money {
+            config.starting_money = money;
+        }
+        if let Some(upkeep) = file.simulation.road_upkeep {
+            config.road_upkeep = non_negative("simulation.road_upkeep", upkeep)?;
+        }
+        if let Some(neighborhood) = file.simulation.neighborhood {
+            config.neighborhood = match neighborhood {
+                4 => Neighborhood::Four,
+                8 => Neighborhood::Eight,
+                _ => {
+                    return Err(format!(
+                        "simulation.neighborhood must be 4 or 8, got {neighborhood}"
+                    ))
+                }
+            };
+        }
+        if let Some(development) = file.simulation.development {
+            config.development = match development.as_str() {
+                "instant" => Development::Instant,
+                "gradual" => Development::Gradual,
+                _ => {
+                    return Err(format!(
+                        "simulation.development must be instant or gradual, got {development}"
+                    ))
+                }
+            };
+        }
+        if let Some(seed) = file.simulation.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(width) = file.window.width {
+            config.window_width = window_size("window.width", width)?;
+        }
+        if let Some(height) = file.window.height {
+            config.window_height = window_size("window.height", height)?;
+        }
+        if let Some(smoothing) = file.camera.smoothing {
+            config.camera_smoothing = non_negative("camera.smoothing", smoothing)?;
+        }
+
+        if let Some(volume) = file.audio.volume {
+            config.volume = fraction("audio.volume", volume)?;
+        }
+        if let Some(muted) = file.audio.muted {
+            config.muted = muted;
+        }
+        if let Some(hints) = file.ui.hints {
+            config.hints = hints;
+        }
+        if let Some(language) = file.ui.language {
+            config.language = language;
+        }
+
+        Ok(config)
+    }
+}
+
+fn map_size(key: &str, value: i64) -> Result<i32, String> {
+    if !(1..=MAX_MAP_SIZE as i64).contains(&value) {
+        return Err(format!(
+            "{key} must be between 1 and {MAX_MAP_SIZE}, got {value}"
+        ));
+    }
+    Ok(value as i32)
+}
+
+fn window_size(key: &str, value: i64) -> Result<u32, String> {
+    if !(1..=u16::MAX as i64).contains(&value) {
+        return Err(format!("{key} must be a positive pixel size, got {value}"));
+    }
+    Ok(value as u32)
+}
+
+fn non_negative(key: &str, value: f32) -> Result<f32, String> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("{key} must be zero or more, got {value}"));
+    }
+    Ok(value)
+}
+
+fn positive(key: &str, value: f32) -> Result<f32, String> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("{key} must be greater than zero, got {value}"));
+    }
+    Ok(value)
+}
+
+fn fraction(key: &str, value: f32) -> Result<f32, String> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("{key} must be between 0.0 and 1.0, got {value}"));
+    }
+    Ok(value)
+}
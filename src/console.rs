@@ -0,0 +1,626 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::{ButtonState, InputSystems};
+use bevy::prelude::*;
+
+use crate::day_night::TimeOfDay;
+use crate::development::Development;
+use crate::elevation::Elevation;
+use crate::input::{rezone, TileEdit, UndoStack, ZoneAction};
+use crate::locale::Locale;
+use crate::map::{TileData, TileMap, Zone};
+use crate::milestones::Milestones;
+use crate::replay::Playback;
+use crate::save::{write_save, CityState};
+use crate::simulation::{simulation_step, CityStats, NewCity, SimTimer};
+use crate::state::{AppState, GameSet, GameState, ResetCity, SpawnSet};
+use crate::zone_defs::ZoneDefs;
+
+/// Most ticks one `tick` command runs.
+const MAX_FAST_FORWARD: u32 = 10_000;
+/// Most tiles one `fill` command zones.
+const MAX_FILL_TILES: i64 = 64 * 64;
+/// Lines of output kept, and how many of the latest are shown.
+const MAX_SCROLLBACK: usize = 100;
+const SHOWN_LINES: usize = 12;
+/// Commands kept for recalling with the arrow keys.
+const MAX_HISTORY: usize = 50;
+
+const HELP: [&str; 8] = [
+    "set <x> <y> <zone>: zone one tile, e.g. set 12 7 road",
+    "fill <x0> <y0> <x1> <y1> <zone>: zone every tile between two corners",
+    "money <amount>: add money, or take it away if negative (--dev)",
+    "tick <n>: run the simulation ahead n ticks, one a frame (--dev)",
+    "seed: print the seed of the city",
+    "stats: print population, jobs, money, and the tick count",
+    "save <path>: save the city to a file, e.g. save foo.ron",
+    "help: list these commands",
+];
+
+/// A drop-down console (the ~ key) for poking at the city while testing:
+/// zoning goes through the same path as the brush, paying for it and
+/// going into undo and the replay recording, while the cheats (money and
+/// fast-forwarding) only work with `--dev`.
+pub struct ConsolePlugin {
+    /// Whether cheats are allowed.
+    pub dev: bool,
+}
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Console {
+            dev: self.dev,
+            ..default()
+        })
+        .init_resource::<FastForward>()
+        .add_message::<ConsoleLine>()
+        .add_message::<SaveCommand>()
+        .add_message::<TileEdit>()
+        .add_systems(ResetCity, reset_console)
+        .add_systems(
+            OnEnter(AppState::Playing),
+            spawn_console.in_set(SpawnSet::Ui),
+        )
+        .add_systems(
+            PreUpdate,
+            type_console
+                .after(InputSystems)
+                .run_if(in_state(GameState::Running)),
+        )
+        .add_systems(
+            Update,
+            (run_console_commands, save_from_console)
+                .chain()
+                .in_set(GameSet::Input),
+        )
+        .add_systems(
+            Update,
+            fast_forward.in_set(GameSet::Simulation).before(simulation_step),
+        )
+        .add_systems(Update, update_console.run_if(in_state(AppState::Playing)));
+    }
+}
+
+/// A command typed into the console.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ConsoleCommand {
+    Help,
+    Money(i64),
+    Set { coord: IVec2, zone: Zone },
+    /// Both corners are included, in either order.
+    Fill { from: IVec2, to: IVec2, zone: Zone },
+    Tick(u32),
+    Seed,
+    Stats,
+    Save(String),
+}
+
+/// Parse one line typed into the console. Zones are named as in saves,
+/// in any case and with or without underscores, e.g. `police_station`.
+pub fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("type a command, or help to list them".to_string());
+    };
+    let args: Vec<&str> = words.collect();
+    let expect = |count: usize, usage: &str| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(format!("usage: {usage}"))
+        }
+    };
+    let command = match name.to_ascii_lowercase().as_str() {
+        "help" => {
+            expect(0, "help")?;
+            ConsoleCommand::Help
+        }
+        "money" => {
+            expect(1, "money <amount>")?;
+            ConsoleCommand::Money(number(args[0])?)
+        }
+        "set" => {
+            expect(3, "set <x> <y> <zone>")?;
+            ConsoleCommand::Set {
+                coord: IVec2::new(number(args[0])?, number(args[1])?),
+                zone: parse_zone(args[2])?,
+            }
+        }
+        "fill" => {
+            expect(5, "fill <x0> <y0> <x1> <y1> <zone>")?;
+            let from = IVec2::new(number(args[0])?, number(args[1])?);
+            let to = IVec2::new(number(args[2])?, number(args[3])?);
+            let span = |a: i32, b: i32| (i64::from(a) - i64::from(b)).abs() + 1;
+            if span(from.x, to.x).saturating_mul(span(from.y, to.y)) > MAX_FILL_TILES {
+                return Err(format!("fill at most {MAX_FILL_TILES} tiles at once"));
+            }
+            ConsoleCommand::Fill {
+                from,
+                to,
+                zone: parse_zone(args[4])?,
+            }
+        }
+        "tick" => {
+            expect(1, "tick <n>")?;
+            let ticks = number(args[0])?;
+            if !(1..=MAX_FAST_FORWARD).contains(&ticks) {
+                return Err(format!("tick takes 1 to {MAX_FAST_FORWARD} ticks"));
+            }
+            ConsoleCommand::Tick(ticks)
+        }
+        "seed" => {
+            expect(0, "seed")?;
+            ConsoleCommand::Seed
+        }
+        "stats" => {
+            expect(0, "stats")?;
+            ConsoleCommand::Stats
+        }
+        "save" => {
+            expect(1, "save <path>")?;
+            ConsoleCommand::Save(args[0].to_string())
+        }
+        other => return Err(format!("unknown command {other}, type help to list them")),
+    };
+    Ok(command)
+}
+
+fn number<T: std::str::FromStr>(raw: &str) -> Result<T, String> {
+    raw.parse().map_err(|_| format!("{raw} is not a whole number"))
+}
+
+/// A zone the player can build, by name. Water, forest, and bridges are
+/// left to the terrain.
+fn parse_zone(raw: &str) -> Result<Zone, String> {
+    let name = raw.replace('_', "");
+    let zone = Zone::ALL
+        .into_iter()
+        .find(|zone| format!("{zone:?}").eq_ignore_ascii_case(&name))
+        .ok_or_else(|| format!("no zone called {raw}"))?;
+    if matches!(zone, Zone::Water | Zone::Forest | Zone::Bridge) {
+        return Err(format!("{raw} can't be built"));
+    }
+    Ok(zone)
+}
+
+/// What the console shows and remembers.
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    dev: bool,
+    /// The line being typed.
+    input: String,
+    scrollback: Vec<String>,
+    history: Vec<String>,
+    /// Which line of `history` the arrow keys have brought back, if any.
+    recalled: Option<usize>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        if self.scrollback.len() == MAX_SCROLLBACK {
+            self.scrollback.remove(0);
+        }
+        self.scrollback.push(line.into());
+    }
+
+    /// Bring back the previous (`back`) or next command typed.
+    fn recall(&mut self, back: bool) {
+        let index = match (self.recalled, back) {
+            (None, true) if self.history.is_empty() => return,
+            (None, true) => Some(self.history.len() - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) => Some(i + 1).filter(|&i| i < self.history.len()),
+            (None, false) => return,
+        };
+        self.recalled = index;
+        self.input = index.map(|i| self.history[i].clone()).unwrap_or_default();
+    }
+}
+
+/// A line entered in the console, to run.
+#[derive(Message)]
+struct ConsoleLine(String);
+
+/// Save the city to a path, asked for from the console.
+#[derive(Message)]
+struct SaveCommand(String);
+
+/// Ticks left to run straight away, one a frame.
+#[derive(Resource, Default)]
+struct FastForward(u32);
+
+#[derive(Component)]
+struct ConsolePanel;
+
+#[derive(Component)]
+struct ScrollbackText;
+
+#[derive(Component)]
+struct InputText;
+
+/// A new or cleared city starts with the console closed and doesn't carry
+/// on running ahead.
+fn reset_console(mut console: ResMut<Console>, mut fast_forward: ResMut<FastForward>) {
+    console.open = false;
+    fast_forward.0 = 0;
+}
+
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            GlobalZIndex(10),
+            ConsolePanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                ScrollbackText,
+            ));
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                InputText,
+            ));
+        });
+}
+
+/// Open and close the console with the key under Escape (~ or `), and
+/// while it is open take every key for typing, so nothing else reacts to
+/// them. Enter runs the line, the arrow keys step through earlier lines,
+/// and Escape closes the console.
+fn type_console(
+    mut typed: MessageReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut lines: MessageWriter<ConsoleLine>,
+) {
+    let was_open = console.open;
+    for input in typed.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        if input.key_code == KeyCode::Backquote {
+            console.open = !console.open;
+            continue;
+        }
+        if !console.open {
+            continue;
+        }
+        match input.logical_key {
+            Key::Escape => console.open = false,
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.recalled = None;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if console.history.last() != Some(&line) {
+                    if console.history.len() == MAX_HISTORY {
+                        console.history.remove(0);
+                    }
+                    console.history.push(line.clone());
+                }
+                lines.write(ConsoleLine(line));
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::ArrowUp => console.recall(true),
+            Key::ArrowDown => console.recall(false),
+            _ => {
+                let text = input.text.as_deref().unwrap_or_default();
+                console.input.extend(text.chars().filter(|c| !c.is_control()));
+            }
+        }
+    }
+    if was_open || console.open {
+        keys.reset_all();
+    }
+}
+
+/// Run every line entered since the last frame and print what came of it.
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    mut lines: MessageReader<ConsoleLine>,
+    mut console: ResMut<Console>,
+    playback: Option<Res<Playback>>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut stats: ResMut<CityStats>,
+    (milestones, elevation): (Res<Milestones>, Res<Elevation>),
+    (defs, development): (Res<ZoneDefs>, Res<Development>),
+    (new_city, clock): (Res<NewCity>, Res<TimeOfDay>),
+    mut undo: ResMut<UndoStack>,
+    mut edits: MessageWriter<TileEdit>,
+    mut fast_forward: ResMut<FastForward>,
+    mut saves: MessageWriter<SaveCommand>,
+    locale: Res<Locale>,
+) {
+    for ConsoleLine(line) in lines.read() {
+        console.print(format!("> {line}"));
+        let command = match parse_command(line) {
+            Ok(command) => command,
+            Err(err) => {
+                console.print(err);
+                continue;
+            }
+        };
+        let cheat = matches!(command, ConsoleCommand::Money(_) | ConsoleCommand::Tick(_));
+        let edits_city = cheat
+            || matches!(command, ConsoleCommand::Set { .. } | ConsoleCommand::Fill { .. });
+        if cheat && !console.dev {
+            console.print("that command needs --dev");
+            continue;
+        }
+        if edits_city && playback.is_some() {
+            console.print("a replay is playing back");
+            continue;
+        }
+        match command {
+            ConsoleCommand::Help => {
+                for line in HELP {
+                    console.print(line);
+                }
+            }
+            ConsoleCommand::Money(amount) => {
+                stats.money += amount;
+                console.print(format!("money is now ${}", stats.money));
+            }
+            ConsoleCommand::Set { coord, zone } => {
+                let rezoned = rezone(
+                    coord,
+                    zone,
+                    &tile_map,
+                    &mut tiles,
+                    &mut stats,
+                    &milestones,
+                    &elevation,
+                    &defs,
+                    *development,
+                    new_city.seed,
+                );
+                match rezoned {
+                    Ok(action) => {
+                        let (x, y, cost) = (coord.x, coord.y, action.cost);
+                        console.print(format!("zoned ({x}, {y}) for ${cost}"));
+                        edits.write(TileEdit {
+                            tiles: action.changes.iter().map(|c| (c.coord, c.after)).collect(),
+                            cost: action.cost,
+                        });
+                        undo.push(action);
+                    }
+                    Err(reason) => console.print(locale.english(reason)),
+                }
+            }
+            ConsoleCommand::Fill { from, to, zone } => {
+                // The whole fill is undone as one action.
+                let mut filled = ZoneAction::default();
+                let mut tried = 0;
+                let mut zoned = 0;
+                let mut failure = None;
+                let (min, max) = (from.min(to), from.max(to));
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        tried += 1;
+                        let rezoned = rezone(
+                            IVec2::new(x, y),
+                            zone,
+                            &tile_map,
+                            &mut tiles,
+                            &mut stats,
+                            &milestones,
+                            &elevation,
+                            &defs,
+                            *development,
+                            new_city.seed,
+                        );
+                        match rezoned {
+                            Ok(action) => {
+                                zoned += 1;
+                                filled.cost += action.cost;
+                                filled.changes.extend(action.changes);
+                            }
+                            Err(reason) => {
+                                failure.get_or_insert(reason);
+                            }
+                        }
+                    }
+                }
+                console.print(format!("zoned {zoned} of {tried} tiles for ${}", filled.cost));
+                if let Some(reason) = failure {
+                    console.print(format!("the rest: {}", locale.english(reason)));
+                }
+                if !filled.changes.is_empty() {
+                    edits.write(TileEdit {
+                        tiles: filled.changes.iter().map(|c| (c.coord, c.after)).collect(),
+                        cost: filled.cost,
+                    });
+                    undo.push(filled);
+                }
+            }
+            ConsoleCommand::Tick(ticks) => {
+                fast_forward.0 += ticks;
+                console.print(format!("running ahead {} ticks", fast_forward.0));
+            }
+            ConsoleCommand::Seed => console.print(format!("seed {}", new_city.seed)),
+            ConsoleCommand::Stats => console.print(format!(
+                "tick {}: population {}, jobs {}, money ${}",
+                clock.ticks, stats.population, stats.jobs, stats.money
+            )),
+            ConsoleCommand::Save(path) => {
+                saves.write(SaveCommand(path));
+            }
+        }
+    }
+}
+
+/// Saving needs to read the whole city, which `run_console_commands`
+/// holds mutably, so it is done here just after.
+fn save_from_console(
+    mut saves: MessageReader<SaveCommand>,
+    city: CityState,
+    mut console: ResMut<Console>,
+) {
+    for SaveCommand(path) in saves.read() {
+        match write_save(path, &city.save()) {
+            Ok(()) => console.print(format!("saved the city to {path}")),
+            Err(err) => console.print(format!("could not save to {path}: {err}")),
+        }
+    }
+}
+
+/// Finish the next tick this frame, however long it still had to go, until
+/// the ticks asked for have run.
+fn fast_forward(mut fast_forward: ResMut<FastForward>, mut timer: ResMut<SimTimer>) {
+    if fast_forward.0 == 0 {
+        return;
+    }
+    fast_forward.0 -= 1;
+    let duration = timer.0.duration();
+    timer.0.set_elapsed(duration);
+}
+
+fn update_console(
+    console: Res<Console>,
+    mut panel: Query<&mut Node, With<ConsolePanel>>,
+    mut scrollback: Query<&mut Text, (With<ScrollbackText>, Without<InputText>)>,
+    mut input: Query<&mut Text, (With<InputText>, Without<ScrollbackText>)>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = if console.open { Display::Flex } else { Display::None };
+    }
+    if let Ok(mut text) = scrollback.single_mut() {
+        let shown = console.scrollback.len().saturating_sub(SHOWN_LINES);
+        text.0 = console.scrollback[shown..].join("\n");
+    }
+    if let Ok(mut text) = input.single_mut() {
+        text.0 = format!("> {}_", console.input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_command() {
+        assert_eq!(parse_command("help"), Ok(ConsoleCommand::Help));
+        assert_eq!(parse_command("  MONEY   -50 "), Ok(ConsoleCommand::Money(-50)));
+        assert_eq!(
+            parse_command("set 12 7 road"),
+            Ok(ConsoleCommand::Set {
+                coord: IVec2::new(12, 7),
+                zone: Zone::Road,
+            })
+        );
+        assert_eq!(
+            parse_command("fill 10 10 0 0 Police_Station"),
+            Ok(ConsoleCommand::Fill {
+                from: IVec2::new(10, 10),
+                to: IVec2::ZERO,
+                zone: Zone::PoliceStation,
+            })
+        );
+        assert_eq!(parse_command("tick 100"), Ok(ConsoleCommand::Tick(100)));
+        assert_eq!(parse_command("seed"), Ok(ConsoleCommand::Seed));
+        assert_eq!(parse_command("stats"), Ok(ConsoleCommand::Stats));
+        assert_eq!(parse_command("save foo.ron"), Ok(ConsoleCommand::Save("foo.ron".into())));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for line in [
+            "",
+            "   ",
+            "teleport",
+            "help me",
+            "money",
+            "money lots",
+            "money 1.5",
+            "money 1 2",
+            "set 1 2",
+            "set x 2 road",
+            "set 1 2 road extra",
+            "set 1 2 castle",
+            "set 1 2 water",
+            "set 1 2 forest",
+            "set 1 2 bridge",
+            "fill 0 0 10 residential",
+            "tick",
+            "tick 0",
+            "tick -3",
+            "seed 4",
+            "save",
+            "save a b",
+        ] {
+            assert!(parse_command(line).is_err(), "{line:?}");
+        }
+        let huge = format!("fill {} 0 0 0 road", MAX_FILL_TILES);
+        assert!(parse_command(&huge).is_err());
+        let too_long = format!("tick {}", MAX_FAST_FORWARD + 1);
+        assert!(parse_command(&too_long).is_err());
+        let overflow = format!("fill {} {} {} {} road", i32::MIN, i32::MIN, i32::MAX, i32::MAX);
+        assert!(parse_command(&overflow).is_err());
+    }
+
+    #[test]
+    fn errors_say_what_went_wrong() {
+        assert_eq!(parse_command("money"), Err("usage: money <amount>".to_string()));
+        assert_eq!(parse_command("money lots"), Err("lots is not a whole number".to_string()));
+        assert_eq!(parse_command("set 1 2 castle"), Err("no zone called castle".to_string()));
+        assert_eq!(parse_command("set 1 2 water"), Err("water can't be built".to_string()));
+        assert!(parse_command("teleport").unwrap_err().contains("unknown command teleport"));
+    }
+
+    #[test]
+    fn history_is_recalled_with_the_arrow_keys() {
+        let mut console = Console::default();
+        console.recall(true);
+        assert_eq!((console.input.as_str(), console.recalled), ("", None));
+        console.history = vec!["seed".to_string(), "stats".to_string()];
+        console.recall(true);
+        assert_eq!(console.input, "stats");
+        console.recall(true);
+        console.recall(true);
+        assert_eq!(console.input, "seed");
+        console.recall(false);
+        assert_eq!(console.input, "stats");
+        console.recall(false);
+        assert_eq!((console.input.as_str(), console.recalled), ("", None));
+    }
+
+    #[test]
+    fn scrollback_keeps_the_latest_lines() {
+        let mut console = Console::default();
+        for i in 0..MAX_SCROLLBACK + 3 {
+            console.print(i.to_string());
+        }
+        assert_eq!(console.scrollback.len(), MAX_SCROLLBACK);
+        assert_eq!(console.scrollback[0], "3");
+    }
+}
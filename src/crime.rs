@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::economy::update_economy;
+use crate::locale::Phrase;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::simulation::{simulation_step, CityStats, SimRng, SimTimer};
+use crate::state::GameSet;
+use crate::ui::TileNotification;
+
+/// Crime at which a home or shop can be robbed.
+const ROBBERY_CRIME: u32 = 90;
+/// Chance per tick of a robbery, per tile with that much crime; there is at
+/// most one a tick.
+const ROBBERY_CHANCE_PER_TILE: f64 = 0.01;
+/// Money a robbery takes from the treasury.
+const ROBBERY_LOSS: i64 = 250;
+
+/// Robberies in the city's most lawless homes and shops, which take money
+/// from the treasury. Crime itself grows with the tiles, in
+/// `sim::next_crime`.
+pub struct CrimePlugin;
+
+impl Plugin for CrimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            commit_robberies
+                .in_set(GameSet::Simulation)
+                .after(simulation_step)
+                .after(update_economy),
+        );
+    }
+}
+
+/// Once a tick, maybe rob one of the tiles with extreme crime. Runs after
+/// `update_economy`, and picks from the tiles in row order, so `SimRng` is
+/// always drawn from in the same order.
+fn commit_robberies(
+    timer: Res<SimTimer>,
+    mut rng: ResMut<SimRng>,
+    mut stats: ResMut<CityStats>,
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    mut notifications: MessageWriter<TileNotification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    let mut lawless: Vec<IVec2> = tiles
+        .iter()
+        .filter(|(_, zone, data)| {
+            matches!(zone, Zone::Residential | Zone::Commercial) && data.crime >= ROBBERY_CRIME
+        })
+        .map(|(coord, _, _)| coord.coord)
+        .collect();
+    if lawless.is_empty() {
+        return;
+    }
+    lawless.sort_by_key(|coord| (coord.y, coord.x));
+    let chance = (lawless.len() as f64 * ROBBERY_CHANCE_PER_TILE).min(1.0);
+    if !rng.0.random_bool(chance) {
+        return;
+    }
+
+    let tile = lawless[rng.0.random_range(0..lawless.len())];
+    stats.money -= ROBBERY_LOSS;
+    notifications.write(TileNotification {
+        tile,
+        text: Phrase::new("notify.robbery")
+            .number("amount", ROBBERY_LOSS)
+            .with("x", tile.x)
+            .with("y", tile.y),
+    });
+}
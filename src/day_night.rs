@@ -0,0 +1,90 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::simulation::{simulation_step, SimTimer};
+use crate::state::{GameSet, ResetCity};
+
+/// Simulation ticks in one in-game day.
+pub const TICKS_PER_DAY: u64 = 120;
+/// The first day starts at 08:00.
+const START_TICK: u64 = TICKS_PER_DAY / 3;
+/// Background color at noon and at midnight.
+const DAY_SKY: Color = Color::srgb(0.05, 0.05, 0.08);
+const NIGHT_SKY: Color = Color::srgb(0.01, 0.01, 0.03);
+
+/// Advances the clock with the simulation and darkens the world at night.
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(DAY_SKY))
+            .init_resource::<TimeOfDay>()
+            .init_resource::<Daylight>()
+            .add_systems(
+                Update,
+                advance_clock
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            )
+            .add_systems(ResetCity, reset_clock);
+    }
+}
+
+/// Simulation ticks since the city was founded.
+#[derive(Resource, Default)]
+pub struct TimeOfDay {
+    pub ticks: u64,
+}
+
+impl TimeOfDay {
+    fn day_ticks(&self) -> u64 {
+        self.ticks + START_TICK
+    }
+
+    /// Fraction of the current day elapsed, 0 at midnight.
+    pub fn fraction(&self) -> f32 {
+        (self.day_ticks() % TICKS_PER_DAY) as f32 / TICKS_PER_DAY as f32
+    }
+}
+
+/// How dark it is, from 0 (full day) to 1 (deep night). Only changes
+/// around dawn and dusk, so tint updates are skipped the rest of the time.
+#[derive(Resource, Default, PartialEq)]
+pub struct Daylight {
+    pub darkness: f32,
+}
+
+/// Darkness for a point in the day: a smoothed band around the sun being
+/// below the horizon, so daytime and nighttime both hold steady.
+fn darkness_at(fraction: f32) -> f32 {
+    let sun = -(fraction * TAU).cos();
+    let t = ((0.25 - sun) / 0.5).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn reset_clock(
+    mut commands: Commands,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    commands.insert_resource(TimeOfDay::default());
+    commands.insert_resource(Daylight::default());
+    clear_color.0 = DAY_SKY;
+}
+
+pub fn advance_clock(
+    timer: Res<SimTimer>,
+    mut clock: ResMut<TimeOfDay>,
+    mut daylight: ResMut<Daylight>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    clock.ticks += 1;
+    let darkness = darkness_at(clock.fraction());
+    if daylight.set_if_neq(Daylight { darkness }) {
+        clear_color.0 = DAY_SKY.mix(&NIGHT_SKY, darkness);
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+use crate::grid::Neighborhood;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::sim::ZoneGrid;
+use crate::simulation::{simulation_step, Demand, SimTimer};
+use crate::state::GameSet;
+use crate::tint::{apply_tile_tints, TileTint};
+use crate::zone_defs::ZoneDefs;
+
+/// Most zoned tiles of each zone that start building in one tick.
+const DEVELOPED_PER_TICK: usize = 3;
+/// How strongly a zoned lot is tinted with its zone's color.
+const ZONED_TINT_STRENGTH: f32 = 0.35;
+
+/// Gradual development: with `Development::Gradual`, painting homes,
+/// shops, or industry only zones the lots, and buildings go up on them
+/// over time wherever the city has demand for them.
+pub struct DevelopmentPlugin;
+
+impl Plugin for DevelopmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Development>()
+            .add_systems(
+                Update,
+                develop_zoned_tiles
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            )
+            .add_systems(
+                Update,
+                tint_zoned_tiles.before(apply_tile_tints).in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// How zoned homes, shops, and industry come to be built. Chosen per new
+/// city and kept in its saves.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Development {
+    /// Painting a zone puts a building there straight away.
+    #[default]
+    Instant,
+    /// Painting a zone only zones the lot; demand builds on it later, and
+    /// bulldozing a building leaves the lot zoned.
+    Gradual,
+}
+
+impl Development {
+    pub fn toggled(self) -> Self {
+        match self {
+            Development::Instant => Development::Gradual,
+            Development::Gradual => Development::Instant,
+        }
+    }
+}
+
+/// Whether buildings of a zone develop on their own under
+/// `Development::Gradual`.
+pub fn develops(zone: Zone) -> bool {
+    matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial)
+}
+
+/// Once a tick, start building on a few zoned lots of every zone the city
+/// has demand for, by the road first and then the most valuable land.
+/// Development costs nothing beyond what zoning the lot did.
+fn develop_zoned_tiles(
+    timer: Res<SimTimer>,
+    development: Res<Development>,
+    demand: Res<Demand>,
+    map: Res<MapConfig>,
+    neighborhood: Res<Neighborhood>,
+    defs: Res<ZoneDefs>,
+    mut tiles: Query<(&TileCoord, &Zone, &mut TileData)>,
+) {
+    if !timer.0.just_finished() || *development != Development::Gradual {
+        return;
+    }
+    let mut zones = ZoneGrid::new(map.width, map.height);
+    let mut lots = Vec::new();
+    for (coord, zone, data) in tiles.iter() {
+        zones.set(coord.coord, *zone);
+        if data.zoned && demand.get(*zone) > 0 {
+            lots.push((*zone, coord.coord, data.land_value));
+        }
+    }
+    if lots.is_empty() {
+        return;
+    }
+    lots.sort_by_key(|&(_, coord, land_value)| {
+        let by_road = zones.next_to_road(coord, *neighborhood);
+        (std::cmp::Reverse((by_road, land_value)), coord.y, coord.x)
+    });
+
+    let mut chosen = Vec::new();
+    for zone in [Zone::Residential, Zone::Commercial, Zone::Industrial] {
+        let of_zone = lots.iter().filter(|(lot_zone, ..)| *lot_zone == zone);
+        chosen.extend(of_zone.take(DEVELOPED_PER_TICK).map(|&(_, coord, _)| coord));
+    }
+    for (coord, zone, mut data) in tiles.iter_mut() {
+        if chosen.contains(&coord.coord) {
+            data.zoned = false;
+            data.construction = defs.get(*zone).construction;
+        }
+    }
+}
+
+/// Give zoned lots a faint wash of their zone's color, so they stand out
+/// from plain grass until something is built on them.
+fn tint_zoned_tiles(
+    defs: Res<ZoneDefs>,
+    mut tiles: Query<(&Zone, Ref<TileData>, &mut TileTint)>,
+) {
+    let all = defs.is_changed();
+    for (zone, data, mut tint) in tiles.iter_mut() {
+        if !all && !data.is_changed() {
+            continue;
+        }
+        let color = if data.zoned {
+            let (r, g, b) = defs.get(*zone).color;
+            Color::WHITE.mix(&Color::srgb(r, g, b), ZONED_TINT_STRENGTH)
+        } else {
+            Color::WHITE
+        };
+        if tint.zoning != color {
+            tint.zoning = color;
+        }
+    }
+}
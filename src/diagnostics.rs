@@ -0,0 +1,202 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+
+use crate::agents::Agent;
+use crate::locale::{Locale, Phrase};
+use crate::map::TileCoord;
+use crate::state::{AppState, GameSet, SpawnSet};
+
+/// The diagnostics panel, toggled with F9: frame rate and frame time from
+/// Bevy's frame time diagnostics, how many tiles and cars there are, and
+/// how long the last simulation tick and its heaviest phases took. The
+/// simulation only times itself while the panel is open.
+pub struct DiagnosticsPlugin;
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin::default())
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_diagnostics_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                Update,
+                (toggle_diagnostics_panel, update_diagnostics_panel)
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// The parts of a simulation tick that are timed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimPhase {
+    /// All of `simulation_step`.
+    Tick,
+    /// Growing every tile, within the tick.
+    Growth,
+    /// Working out the jobs each home can reach by road.
+    Connectivity,
+    /// Routing commuters onto the roads.
+    Traffic,
+}
+
+impl SimPhase {
+    pub const ALL: [SimPhase; 4] = [
+        SimPhase::Tick,
+        SimPhase::Growth,
+        SimPhase::Connectivity,
+        SimPhase::Traffic,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SimPhase::Tick => "diag.phase.tick",
+            SimPhase::Growth => "diag.phase.growth",
+            SimPhase::Connectivity => "diag.phase.connectivity",
+            SimPhase::Traffic => "diag.phase.traffic",
+        }
+    }
+}
+
+/// How long each simulation phase took the last time it ran, timed by the
+/// systems themselves. While `enabled` is off nothing is timed and only a
+/// flag is checked. The timings are atomics so that the systems recording
+/// them only need to read the resource and can still run side by side.
+#[derive(Resource, Default)]
+pub struct SimDiagnostics {
+    pub enabled: bool,
+    nanos: [AtomicU64; SimPhase::ALL.len()],
+}
+
+impl SimDiagnostics {
+    /// Start timing a phase, or `None` while disabled.
+    pub fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Record the time since `started` as the phase's latest.
+    pub fn finish(&self, phase: SimPhase, started: Option<Instant>) {
+        if let Some(started) = started {
+            let nanos = started.elapsed().as_nanos() as u64;
+            self.nanos[phase as usize].store(nanos, Ordering::Relaxed);
+        }
+    }
+
+    /// The phase's latest timing; zero if it hasn't been timed yet.
+    pub fn last(&self, phase: SimPhase) -> Duration {
+        Duration::from_nanos(self.nanos[phase as usize].load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Component)]
+struct DiagnosticsPanel;
+
+#[derive(Component)]
+struct DiagnosticsText;
+
+fn spawn_diagnostics_panel(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            DiagnosticsPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(locale.text("diag.title")),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                DiagnosticsText,
+            ));
+        });
+}
+
+/// Show or hide the panel with F9, timing the simulation only while it is
+/// shown.
+fn toggle_diagnostics_panel(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut diagnostics: ResMut<SimDiagnostics>,
+    mut panel: Query<&mut Node, With<DiagnosticsPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+        diagnostics.enabled = node.display != Display::None;
+    }
+}
+
+/// Rewrite the panel every frame it is open.
+fn update_diagnostics_panel(
+    store: Res<DiagnosticsStore>,
+    diagnostics: Res<SimDiagnostics>,
+    locale: Res<Locale>,
+    tiles: Query<(), With<TileCoord>>,
+    agents: Query<(), With<Agent>>,
+    panel: Query<&Node, With<DiagnosticsPanel>>,
+    mut text: Query<&mut Text, With<DiagnosticsText>>,
+) {
+    if panel.single().is_ok_and(|node| node.display == Display::None) {
+        return;
+    }
+
+    let smoothed = |path| store.get(path).and_then(|d| d.smoothed()).unwrap_or(0.0);
+    let mut lines = vec![
+        locale.render(
+            &Phrase::new("diag.frames")
+                .number("fps", smoothed(&FrameTimeDiagnosticsPlugin::FPS).round() as i64)
+                .with(
+                    "ms",
+                    format!("{:.1}", smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME)),
+                ),
+        ),
+        locale.render(
+            &Phrase::new("diag.entities")
+                .number("tiles", tiles.iter().len() as i64)
+                .number("agents", agents.iter().len() as i64),
+        ),
+    ];
+    for phase in SimPhase::ALL {
+        let micros = diagnostics.last(phase).as_micros() as i64;
+        let line = locale.render(&Phrase::new(phase.label()).number("us", micros));
+        lines.push(line);
+    }
+
+    let body = lines.join("\n");
+    if let Ok(mut text) = text.single_mut() {
+        if **text != body {
+            **text = body;
+        }
+    }
+}
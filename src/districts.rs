@@ -0,0 +1,608 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::{ButtonState, InputSystems};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+use crate::input::{BuildTool, PaintCommand};
+use crate::locale::{Locale, Phrase};
+use crate::map::{TileCoord, TileData, Zone};
+use crate::replay::Playback;
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+use crate::tint::{apply_tile_tints, TileTint};
+use crate::ui::{disabled_menu_button, menu_button};
+
+/// Most districts a city can have.
+pub const MAX_DISTRICTS: usize = 12;
+/// Longest name a district can be given, in characters.
+const MAX_NAME_CHARS: usize = 24;
+/// Colors districts are shown in while the panel is open, by id.
+const DISTRICT_COLORS: [Color; 6] = [
+    Color::srgb(0.95, 0.45, 0.4),
+    Color::srgb(0.45, 0.65, 1.0),
+    Color::srgb(0.55, 0.95, 0.45),
+    Color::srgb(1.0, 0.85, 0.35),
+    Color::srgb(0.8, 0.5, 1.0),
+    Color::srgb(0.4, 0.95, 0.9),
+];
+/// Tint of tiles in no district while the panel is open.
+const UNASSIGNED_TINT: Color = Color::srgb(0.45, 0.45, 0.45);
+/// Color of the row of the district being painted.
+const PAINTING_TEXT_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// Named districts painted onto the map, with a panel (L) listing what
+/// each one holds and pays. While the panel is open the map shows every
+/// district in its color.
+pub struct DistrictsPlugin;
+
+impl Plugin for DistrictsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Districts::new(0, 0))
+            .init_resource::<DistrictStats>()
+            .init_resource::<DistrictPanelOpen>()
+            .init_resource::<Renaming>()
+            .add_message::<PaintCommand>()
+            .add_systems(ResetCity, clear_districts)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_district_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                PreUpdate,
+                type_district_name.after(InputSystems).run_if(renaming),
+            )
+            .add_systems(
+                Update,
+                paint_districts
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_district_panel,
+                    handle_district_buttons.run_if(not(resource_exists::<Playback>)),
+                    rebuild_district_list,
+                    update_district_rows,
+                    tint_district_view.before(apply_tile_tints),
+                )
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// A named part of the city. Ids start at 1; 0 marks tiles in no district.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct District {
+    pub id: u16,
+    pub name: String,
+}
+
+/// The districts, and which one every tile is in, laid out row by row.
+/// Kept in saves.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct Districts {
+    width: i32,
+    height: i32,
+    /// District id of every tile, 0 for none.
+    tile_ids: Vec<u16>,
+    /// In order of id.
+    list: Vec<District>,
+}
+
+impl Districts {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self::from_saved(width, height, vec![0; (width * height).max(0) as usize], Vec::new())
+    }
+
+    /// `tile_ids` must hold one entry per tile, row by row, naming only
+    /// districts in `list`.
+    pub fn from_saved(
+        width: i32,
+        height: i32,
+        tile_ids: Vec<u16>,
+        mut list: Vec<District>,
+    ) -> Self {
+        list.sort_by_key(|district| district.id);
+        Self {
+            width,
+            height,
+            tile_ids,
+            list,
+        }
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        on_map.then(|| (coord.y * self.width + coord.x) as usize)
+    }
+
+    /// District id of the tile at `coord`, or `None` off the map.
+    pub fn get(&self, coord: IVec2) -> Option<u16> {
+        self.index(coord).map(|i| self.tile_ids[i])
+    }
+
+    /// Put the tile at `coord` in district `id`, or in none for 0.
+    pub fn set(&mut self, coord: IVec2, id: u16) {
+        if let Some(i) = self.index(coord) {
+            self.tile_ids[i] = id;
+        }
+    }
+
+    /// Where in `list` the district of the tile at `coord` is, if it is in
+    /// one.
+    pub fn slot(&self, coord: IVec2) -> Option<usize> {
+        let id = self.get(coord).filter(|&id| id != 0)?;
+        self.list.binary_search_by_key(&id, |district| district.id).ok()
+    }
+
+    /// Every district, in order of id.
+    pub fn list(&self) -> &[District] {
+        &self.list
+    }
+
+    /// District id of every tile, row by row, for saving.
+    pub fn tile_ids(&self) -> &[u16] {
+        &self.tile_ids
+    }
+
+    pub fn name(&self, id: u16) -> Option<&str> {
+        self.list.iter().find(|d| d.id == id).map(|d| d.name.as_str())
+    }
+
+    /// The lowest id no district has, unless the city has as many as it
+    /// can.
+    pub fn unused_id(&self) -> Option<u16> {
+        if self.list.len() >= MAX_DISTRICTS {
+            return None;
+        }
+        (1..).find(|&id| self.list.iter().all(|d| d.id != id))
+    }
+
+    pub fn insert(&mut self, district: District) {
+        let at = self.list.partition_point(|d| d.id < district.id);
+        self.list.insert(at, district);
+    }
+
+    pub fn rename(&mut self, id: u16, name: String) {
+        if let Some(district) = self.list.iter_mut().find(|d| d.id == id) {
+            district.name = name;
+        }
+    }
+
+    /// Remove a district, leaving its tiles in none.
+    pub fn remove(&mut self, id: u16) {
+        self.list.retain(|d| d.id != id);
+        for tile in self.tile_ids.iter_mut().filter(|tile| **tile == id) {
+            *tile = 0;
+        }
+    }
+}
+
+/// What name a new district gets, and a renamed one left blank.
+fn default_name(locale: &Locale, id: u16) -> String {
+    locale.render(&Phrase::new("districts.default_name").number("number", id))
+}
+
+/// Running totals of one district during the stats pass of
+/// `simulation_step`.
+#[derive(Clone, Copy, Default)]
+pub struct DistrictTally {
+    population: u32,
+    jobs: u32,
+    taxed_jobs: u32,
+    upkeep: i64,
+    land_value: u64,
+    valued_tiles: u32,
+}
+
+impl DistrictTally {
+    /// Count one tile: who lives and works there, the jobs on it paying
+    /// tax, and the upkeep charged on it.
+    pub fn add(&mut self, zone: Zone, data: &TileData, taxed_jobs: u32, upkeep: i64) {
+        self.population += data.population;
+        self.jobs += data.jobs;
+        self.taxed_jobs += taxed_jobs;
+        self.upkeep += upkeep;
+        if matches!(zone, Zone::Residential | Zone::Commercial) {
+            self.land_value += u64::from(data.land_value);
+            self.valued_tiles += 1;
+        }
+    }
+
+    /// Residents and taxed jobs counted, to work out the taxes they pay.
+    pub fn taxpayers(&self) -> (u32, u32) {
+        (self.population, self.taxed_jobs)
+    }
+
+    /// The district's summary, given the `taxes` its taxpayers paid.
+    pub fn summary(&self, id: u16, taxes: i64) -> DistrictSummary {
+        DistrictSummary {
+            id,
+            population: self.population,
+            jobs: self.jobs,
+            land_value: self.land_value.checked_div(self.valued_tiles.into()).unwrap_or(0) as u32,
+            net_tax: taxes - self.upkeep,
+        }
+    }
+}
+
+/// What one district held and paid in the last tick.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct DistrictSummary {
+    pub id: u16,
+    pub population: u32,
+    pub jobs: u32,
+    /// Average land value of its homes and shops, 0 without any.
+    pub land_value: u32,
+    /// Taxes paid there less the upkeep of the buildings there, per tick.
+    pub net_tax: i64,
+}
+
+/// Every district's summary, in district order, added up by
+/// `simulation_step` alongside the city totals.
+#[derive(Resource, Default, Clone, PartialEq, Eq)]
+pub struct DistrictStats(pub Vec<DistrictSummary>);
+
+/// Whether the district panel, and with it the district colors, is shown.
+#[derive(Resource, Default)]
+struct DistrictPanelOpen(bool);
+
+/// The district whose name is being typed, if any. Every key goes to the
+/// name until Enter or Escape.
+#[derive(Resource, Default)]
+struct Renaming(Option<u16>);
+
+fn renaming(renaming: Res<Renaming>) -> bool {
+    renaming.0.is_some()
+}
+
+#[derive(Component)]
+struct DistrictPanel;
+
+/// Container the district rows and the new district button are rebuilt in.
+#[derive(Component)]
+struct DistrictList;
+
+/// Text of the row listing one district.
+#[derive(Component)]
+struct DistrictRow(u16);
+
+#[derive(Component, Clone, Copy)]
+enum DistrictButton {
+    New,
+    /// Start or stop painting the district.
+    Paint(u16),
+    Rename(u16),
+    Delete(u16),
+}
+
+/// New and cleared cities have no districts; a loaded one brings its own.
+fn clear_districts(
+    map: Res<MapConfig>,
+    mut districts: ResMut<Districts>,
+    mut stats: ResMut<DistrictStats>,
+    mut open: ResMut<DistrictPanelOpen>,
+    mut renaming: ResMut<Renaming>,
+) {
+    *districts = Districts::new(map.width, map.height);
+    stats.0.clear();
+    open.0 = false;
+    renaming.0 = None;
+}
+
+fn spawn_district_panel(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(30.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            DistrictPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(locale.text("districts.title")),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            panel.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                DistrictList,
+            ));
+        });
+}
+
+/// Show or hide the panel with L. Closing it stops painting districts and
+/// finishes any name being typed.
+fn toggle_district_panel(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut open: ResMut<DistrictPanelOpen>,
+    mut tool: ResMut<BuildTool>,
+    mut renaming: ResMut<Renaming>,
+    mut panel: Query<&mut Node, With<DistrictPanel>>,
+) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        open.0 = !open.0;
+        if !open.0 {
+            tool.district = None;
+            renaming.0 = None;
+        }
+    }
+    if !open.is_changed() {
+        return;
+    }
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = if open.0 { Display::Flex } else { Display::None };
+    }
+}
+
+/// Give a district whose name was left blank its default one back.
+fn finish_renaming(id: u16, districts: &mut Districts, locale: &Locale) {
+    if districts.name(id).is_some_and(|name| name.trim().is_empty()) {
+        districts.rename(id, default_name(locale, id));
+    }
+}
+
+/// New creates a district and starts painting it; Paint starts or stops
+/// painting one, Rename starts typing its name, and Delete removes it.
+/// Any press finishes the name being typed.
+fn handle_district_buttons(
+    buttons: Query<(&Interaction, &DistrictButton), Changed<Interaction>>,
+    locale: Res<Locale>,
+    mut districts: ResMut<Districts>,
+    mut tool: ResMut<BuildTool>,
+    mut renaming: ResMut<Renaming>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let was_renaming = renaming.0.take();
+        if let Some(id) = was_renaming {
+            finish_renaming(id, &mut districts, &locale);
+        }
+        match *button {
+            DistrictButton::New => {
+                if let Some(id) = districts.unused_id() {
+                    let name = default_name(&locale, id);
+                    districts.insert(District { id, name });
+                    tool.district = Some(id);
+                    tool.pipes = false;
+                    tool.directions = false;
+                }
+            }
+            DistrictButton::Paint(id) => {
+                tool.district = (tool.district != Some(id)).then_some(id);
+                tool.pipes = false;
+                tool.directions = false;
+            }
+            DistrictButton::Rename(id) => {
+                if was_renaming != Some(id) {
+                    renaming.0 = Some(id);
+                }
+            }
+            DistrictButton::Delete(id) => {
+                districts.remove(id);
+                if tool.district == Some(id) {
+                    tool.district = None;
+                }
+            }
+        }
+    }
+}
+
+/// Type into the name of the district being renamed: Backspace deletes,
+/// and Enter or Escape finishes. The keys are taken from the rest of the
+/// game meanwhile, so typing doesn't pan the camera or switch tools.
+fn type_district_name(
+    mut typed: MessageReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    locale: Res<Locale>,
+    mut renaming: ResMut<Renaming>,
+    mut districts: ResMut<Districts>,
+) {
+    let Some(id) = renaming.0 else {
+        return;
+    };
+    let mut name = districts.name(id).unwrap_or_default().to_string();
+    for input in typed.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+        match input.logical_key {
+            Key::Enter | Key::Escape => {
+                renaming.0 = None;
+                break;
+            }
+            Key::Backspace => {
+                name.pop();
+            }
+            _ => {
+                let text = input.text.as_deref().unwrap_or_default();
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    if name.chars().count() < MAX_NAME_CHARS {
+                        name.push(c);
+                    }
+                }
+            }
+        }
+    }
+    if districts.name(id) != Some(name.as_str()) {
+        districts.rename(id, name);
+    }
+    if renaming.0.is_none() {
+        finish_renaming(id, &mut districts, &locale);
+    }
+    keys.reset_all();
+}
+
+/// While a district is selected for painting, paint strokes put the tiles
+/// under the brush in it, or in none with the bulldozer.
+fn paint_districts(
+    mut commands: MessageReader<PaintCommand>,
+    tool: Res<BuildTool>,
+    mut districts: ResMut<Districts>,
+) {
+    let Some(painted) = tool.district else {
+        commands.clear();
+        return;
+    };
+    for command in commands.read() {
+        let PaintCommand::Paint { center, zone, .. } = *command else {
+            continue;
+        };
+        let id = if zone == Zone::Empty { 0 } else { painted };
+        for coord in tool.brush.footprint(center) {
+            if districts.get(coord).is_some_and(|old| old != id) {
+                districts.set(coord, id);
+            }
+        }
+    }
+}
+
+/// Respawn the rows, one per district, whenever a district is created or
+/// deleted, or the panel is new.
+fn rebuild_district_list(
+    mut commands: Commands,
+    districts: Res<Districts>,
+    locale: Res<Locale>,
+    list: Query<(Entity, Ref<DistrictList>)>,
+    mut shown: Local<Vec<u16>>,
+) {
+    let Ok((list, marker)) = list.single() else {
+        return;
+    };
+    let ids: Vec<u16> = districts.list().iter().map(|d| d.id).collect();
+    if ids == *shown && !marker.is_added() {
+        return;
+    }
+    *shown = ids;
+
+    commands.entity(list).despawn_related::<Children>();
+    commands.entity(list).with_children(|list| {
+        for district in districts.list() {
+            let id = district.id;
+            list.spawn(Node {
+                column_gap: Val::Px(8.0),
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        min_width: Val::Px(360.0),
+                        ..default()
+                    },
+                    DistrictRow(id),
+                ));
+                let buttons = [
+                    ("districts.paint", DistrictButton::Paint(id)),
+                    ("districts.rename", DistrictButton::Rename(id)),
+                    ("districts.delete", DistrictButton::Delete(id)),
+                ];
+                for (label, button) in buttons {
+                    row.spawn(menu_button(&locale.text(label), button));
+                }
+            });
+        }
+        let new = locale.text("districts.new");
+        if districts.unused_id().is_some() {
+            list.spawn(menu_button(&new, DistrictButton::New));
+        } else {
+            list.spawn(disabled_menu_button(&new));
+        }
+    });
+}
+
+/// Rewrite each row with the district's name and last tick's totals,
+/// showing a cursor while its name is typed and picking out the district
+/// being painted.
+fn update_district_rows(
+    districts: Res<Districts>,
+    stats: Res<DistrictStats>,
+    renaming: Res<Renaming>,
+    tool: Res<BuildTool>,
+    locale: Res<Locale>,
+    mut rows: Query<(Ref<DistrictRow>, &mut Text, &mut TextColor)>,
+) {
+    let changed = districts.is_changed()
+        || stats.is_changed()
+        || renaming.is_changed()
+        || tool.is_changed();
+    for (row, mut text, mut color) in rows.iter_mut() {
+        if !changed && !row.is_added() {
+            continue;
+        }
+        let id = row.0;
+        let mut name = districts.name(id).unwrap_or_default().to_string();
+        if renaming.0 == Some(id) {
+            name.push('_');
+        }
+        let summary = stats.0.iter().find(|s| s.id == id).copied().unwrap_or_default();
+        let content = locale.render(
+            &Phrase::new("districts.row")
+                .with("name", name)
+                .number("population", summary.population)
+                .number("jobs", summary.jobs)
+                .number("land_value", summary.land_value)
+                .signed("net", summary.net_tax),
+        );
+        if **text != content {
+            **text = content;
+        }
+        let tint = if tool.district == Some(id) {
+            PAINTING_TEXT_COLOR
+        } else {
+            Color::WHITE
+        };
+        if color.0 != tint {
+            color.0 = tint;
+        }
+    }
+}
+
+/// While the panel is open, tint every tile in its district's color and
+/// dim the ones in none; otherwise touch nothing.
+fn tint_district_view(
+    open: Res<DistrictPanelOpen>,
+    districts: Res<Districts>,
+    mut tiles: Query<(&TileCoord, &mut TileTint)>,
+) {
+    let refresh = open.is_changed() || (open.0 && districts.is_changed());
+    if !refresh {
+        return;
+    }
+    for (coord, mut tint) in tiles.iter_mut() {
+        let color = match districts.get(coord.coord) {
+            _ if !open.0 => Color::WHITE,
+            Some(0) | None => UNASSIGNED_TINT,
+            Some(id) => DISTRICT_COLORS[(id as usize - 1) % DISTRICT_COLORS.len()],
+        };
+        if tint.districts != color {
+            tint.districts = color;
+        }
+    }
+}
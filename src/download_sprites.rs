@@ -1,33 +1,127 @@
 use std::fs;
+use std::io::Read;
 use std::path::Path;
-use std::io::Write;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
 
-/// On startup, downloads the Kenney city sprites if missing.
-async fn download_city_sprites() {
-    let url = "https://kenney.nl/assets/roguelike-modern-city/download";
-    let out_path = "assets/roguelike_city.zip";
-    let assets_folder = Path::new("assets");
+use bevy::log::{info, warn};
+use sha2::{Digest, Sha256};
 
-    if !assets_folder.exists() {
-        let _ = fs::create_dir_all(assets_folder);
+/// Kenney asset pack, downloaded if the sprite sheet is missing or corrupt.
+const SPRITE_PACK_URL: &str =
+    "https://kenney.nl/assets/roguelike-modern-city/download";
+/// Location of the sprite sheet inside the pack zip.
+const SPRITE_PACK_SHEET: &str = "Tilemap/tilemap.png";
+/// SHA-256 of the known-good sprite sheet.
+const SPRITE_SHEET_SHA256: &str =
+    "e7791156bfe5fd238a698e900ca5c749dec49c8f58b0c570af2f288ced34500c";
+const SPRITE_DOWNLOAD_ATTEMPTS: u32 = 3;
+const SPRITE_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Progress reports sent from the download thread to the loading screen.
+pub enum DownloadStatus {
+    /// Bytes received so far, and the total if the server told us.
+    Progress { received: u64, total: Option<u64> },
+    /// The sprite sheet is verified on disk, or the download gave up.
+    Finished(Result<(), String>),
+}
+
+/// Make sure a verified copy of the sprite sheet at `sheet_path` is on
+/// disk, downloading the Kenney pack (with retries) if it is missing or
+/// doesn't match the expected checksum. A bad file is deleted rather than
+/// left in place. Meant to run off the main thread; progress and the final
+/// result are reported through `status`.
+pub fn ensure_sprites_downloaded(sheet_path: &Path, status: Sender<DownloadStatus>) {
+    let result = fetch_if_needed(sheet_path, &status);
+    let _ = status.send(DownloadStatus::Finished(result));
+}
+
+fn fetch_if_needed(
+    sheet_path: &Path,
+    status: &Sender<DownloadStatus>,
+) -> Result<(), String> {
+    if let Ok(bytes) = fs::read(sheet_path) {
+        if sprite_sheet_is_valid(&bytes) {
+            return Ok(());
+        }
+        warn!(
+            "{} is corrupt, downloading a fresh copy...",
+            sheet_path.display()
+        );
     }
 
-    if !Path::new(out_path).exists() {
-        println!("Downloading city sprite asset pack from {url} (Kenney Roguelike Modern City CC0)...");
-        if let Ok(mut resp) = reqwest::get(url).await {
-            if let Ok(bytes) = resp.bytes().await {
-                if let Ok(mut file) = fs::File::create(out_path) {
-                    let _ = file.write_all(&bytes);
-                    println!("Downloaded {out_path} - unzip to /assets");
+    let mut last_error = String::new();
+    for attempt in 1..=SPRITE_DOWNLOAD_ATTEMPTS {
+        info!(
+            "Downloading city sprites from {SPRITE_PACK_URL} \
+             (attempt {attempt}/{SPRITE_DOWNLOAD_ATTEMPTS})..."
+        );
+        match download_sprite_sheet(status) {
+            Ok(bytes) => {
+                if let Some(parent) = sheet_path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
                 }
+                fs::write(sheet_path, &bytes).map_err(|e| e.to_string())?;
+                return Ok(());
             }
-        } else {
-            println!("Sprite download failed - check your connection or download manually.");
+            Err(err) => last_error = err,
         }
-    } else {
-        println!("Asset pack already present: {out_path}.");
     }
+
+    let _ = fs::remove_file(sheet_path);
+    Err(format!(
+        "Could not download the city sprites ({last_error}).\n\
+         Download the pack manually from {SPRITE_PACK_URL}\n\
+         and unzip it to assets/kenney_roguelike-modern-city/."
+    ))
 }
 
-// USAGE EXAMPLE (put at the top of main() function in an async block or tokio::main):
-// tokio::spawn(download_city_sprites());
+/// Fetch the asset pack and pull the verified sprite sheet out of it.
+fn download_sprite_sheet(
+    status: &Sender<DownloadStatus>,
+) -> Result<Vec<u8>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(SPRITE_DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut response =
+        client.get(SPRITE_PACK_URL).send().map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("server returned {}", response.status()));
+    }
+
+    let total = response.content_length();
+    let mut pack = Vec::new();
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        let read = response.read(&mut chunk).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        pack.extend_from_slice(&chunk[..read]);
+        let _ = status.send(DownloadStatus::Progress {
+            received: pack.len() as u64,
+            total,
+        });
+    }
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(pack))
+        .map_err(|e| format!("download is not a zip archive: {e}"))?;
+    let mut entry = archive
+        .by_name(SPRITE_PACK_SHEET)
+        .map_err(|e| format!("{SPRITE_PACK_SHEET} missing from pack: {e}"))?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+
+    if !sprite_sheet_is_valid(&bytes) {
+        return Err("sprite sheet checksum mismatch".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Whether `bytes` are exactly the sprite sheet we expect.
+fn sprite_sheet_is_valid(bytes: &[u8]) -> bool {
+    let digest = Sha256::digest(bytes);
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    hex == SPRITE_SHEET_SHA256
+}
@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::TICKS_PER_MONTH;
+use crate::fire::update_fires;
+use crate::locale::Phrase;
+use crate::simulation::{simulation_step, CityStats, Demand, SimRng, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::ui::Notification;
+
+/// Chance per tick that an event starts: about one a year.
+const EVENT_CHANCE: f64 = 1.0 / (12 * TICKS_PER_MONTH) as f64;
+/// What a federal grant pays into the treasury.
+const GRANT_AMOUNT: i64 = 5_000;
+
+/// Booms, recessions, factory orders, and federal grants, which come along
+/// at random and shift demand, taxes, or industry for a while.
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Economy>()
+            .add_message::<Notification>()
+            .add_systems(
+                Update,
+                update_economy
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step)
+                    .after(update_fires),
+            )
+            .add_systems(ResetCity, clear_economy);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EconomicEvent {
+    Boom,
+    Recession,
+    FactoryOrders,
+    Grant,
+}
+
+impl EconomicEvent {
+    const ALL: [Self; 4] = [Self::Boom, Self::Recession, Self::FactoryOrders, Self::Grant];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Boom => "economy.boom",
+            Self::Recession => "economy.recession",
+            Self::FactoryOrders => "economy.factory_orders",
+            Self::Grant => "economy.grant",
+        }
+    }
+
+    /// Ticks the event lasts; a grant is paid out at once.
+    fn duration(self) -> u64 {
+        match self {
+            Self::Boom | Self::FactoryOrders => 2 * TICKS_PER_MONTH,
+            Self::Recession => 3 * TICKS_PER_MONTH,
+            Self::Grant => 0,
+        }
+    }
+
+    fn modifiers(self) -> Modifiers {
+        match self {
+            Self::Boom => Modifiers {
+                demand: Demand {
+                    residential: 20,
+                    commercial: 20,
+                    industrial: 10,
+                },
+                tax_percent: 125,
+                ..default()
+            },
+            Self::Recession => Modifiers {
+                demand: Demand {
+                    residential: -20,
+                    commercial: -20,
+                    industrial: -20,
+                },
+                tax_percent: 75,
+                ..default()
+            },
+            Self::FactoryOrders => Modifiers {
+                demand: Demand {
+                    industrial: 30,
+                    ..default()
+                },
+                industrial_jobs: 50,
+                ..default()
+            },
+            Self::Grant => Modifiers::default(),
+        }
+    }
+
+    fn started(self) -> Phrase {
+        match self {
+            Self::Boom => Phrase::new("notify.boom_started"),
+            Self::Recession => Phrase::new("notify.recession_started"),
+            Self::FactoryOrders => Phrase::new("notify.factory_orders_started"),
+            Self::Grant => Phrase::new("notify.grant_paid").number("amount", GRANT_AMOUNT),
+        }
+    }
+
+    fn ended(self) -> &'static str {
+        match self {
+            Self::Boom => "notify.boom_ended",
+            Self::Recession => "notify.recession_ended",
+            Self::FactoryOrders => "notify.factory_orders_ended",
+            Self::Grant => "notify.grant_ended",
+        }
+    }
+}
+
+/// How the events under way change the simulation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    /// Added to each zone's demand.
+    pub demand: Demand,
+    /// Share of the usual tax income collected.
+    pub tax_percent: i64,
+    /// Jobs an industrial tile can hold above the usual maximum.
+    pub industrial_jobs: u32,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            demand: Demand::default(),
+            tax_percent: 100,
+            industrial_jobs: 0,
+        }
+    }
+}
+
+impl Modifiers {
+    /// Both sets of modifiers at once: demand shifts and extra jobs add up,
+    /// and tax changes compound.
+    fn and(self, other: Self) -> Self {
+        Self {
+            demand: self.demand + other.demand,
+            tax_percent: self.tax_percent * other.tax_percent / 100,
+            industrial_jobs: self.industrial_jobs + other.industrial_jobs,
+        }
+    }
+
+    /// `tax` as collected under these modifiers.
+    pub fn tax(&self, tax: i64) -> i64 {
+        tax * self.tax_percent / 100
+    }
+}
+
+/// An event under way.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ActiveEvent {
+    pub event: EconomicEvent,
+    pub ticks_left: u64,
+}
+
+/// Every event under way, oldest first. Any number can overlap, even of
+/// the same kind.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Economy {
+    pub active: Vec<ActiveEvent>,
+}
+
+impl Economy {
+    /// The combined modifiers of every event under way.
+    pub fn modifiers(&self) -> Modifiers {
+        self.active
+            .iter()
+            .fold(Modifiers::default(), |all, active| all.and(active.event.modifiers()))
+    }
+}
+
+/// Count down the events under way, then maybe start a new one. Runs after
+/// `update_fires` so the two always draw from `SimRng` in the same order.
+pub fn update_economy(
+    timer: Res<SimTimer>,
+    mut rng: ResMut<SimRng>,
+    mut economy: ResMut<Economy>,
+    mut stats: ResMut<CityStats>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    if !economy.active.is_empty() {
+        economy.active.retain_mut(|active| {
+            active.ticks_left = active.ticks_left.saturating_sub(1);
+            if active.ticks_left > 0 {
+                return true;
+            }
+            notifications.write(Notification(Phrase::new(active.event.ended())));
+            false
+        });
+    }
+
+    if !rng.0.random_bool(EVENT_CHANCE) {
+        return;
+    }
+    let event = EconomicEvent::ALL[rng.0.random_range(0..EconomicEvent::ALL.len())];
+    if event == EconomicEvent::Grant {
+        stats.money += GRANT_AMOUNT;
+    } else {
+        economy.active.push(ActiveEvent {
+            event,
+            ticks_left: event.duration(),
+        });
+    }
+    notifications.write(Notification(event.started()));
+}
+
+fn clear_economy(mut economy: ResMut<Economy>) {
+    economy.active.clear();
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::state::ResetCity;
+
+/// Highest height level; 0 is the level water lies at.
+pub const MAX_LEVEL: u8 = 3;
+/// Steepest slope, in levels climbed across a tile, that can be built on.
+pub const MAX_BUILDABLE_SLOPE: u8 = 1;
+/// Extra a road tile costs to grade, per level of slope.
+pub const ROAD_GRADE_COST: i64 = 15;
+/// Level from which homes look out over the city.
+pub const VIEW_LEVEL: u8 = 2;
+
+/// The lay of the land: generated with random maps, flat everywhere else,
+/// and kept in saves. Steep tiles can't be built on, and roads cost more
+/// on slopes.
+pub struct ElevationPlugin;
+
+impl Plugin for ElevationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Elevation::flat(0, 0))
+            .add_systems(ResetCity, flatten_elevation);
+    }
+}
+
+/// Height level of every tile, laid out row by row.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct Elevation {
+    width: i32,
+    height: i32,
+    levels: Vec<u8>,
+}
+
+impl Elevation {
+    /// A map at water level everywhere.
+    pub fn flat(width: i32, height: i32) -> Self {
+        Self::new(width, height, vec![0; (width * height).max(0) as usize])
+    }
+
+    /// `levels` must hold one entry per tile, row by row.
+    pub fn new(width: i32, height: i32, levels: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            levels,
+        }
+    }
+
+    /// Level of the tile at `coord`, or `None` off the map.
+    pub fn get(&self, coord: IVec2) -> Option<u8> {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return None;
+        }
+        self.levels.get((coord.y * self.width + coord.x) as usize).copied()
+    }
+
+    /// How many levels the land climbs across the tile at `coord`: the gap
+    /// between the highest and lowest of it and the tiles on its four
+    /// sides.
+    pub fn slope(&self, coord: IVec2) -> u8 {
+        let levels = [IVec2::ZERO, IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .map(|offset| self.get(coord + offset));
+        let levels = levels.iter().flatten();
+        levels.clone().max().zip(levels.min()).map_or(0, |(high, low)| high - low)
+    }
+
+    /// Whether the tile at `coord` is too steep to build on.
+    pub fn too_steep(&self, coord: IVec2) -> bool {
+        self.slope(coord) > MAX_BUILDABLE_SLOPE
+    }
+
+    /// What grading a road onto the tile at `coord` adds to its price.
+    pub fn road_grade_cost(&self, coord: IVec2) -> i64 {
+        i64::from(self.slope(coord)) * ROAD_GRADE_COST
+    }
+
+    /// Every level, row by row, for saving.
+    pub fn levels(&self) -> &[u8] {
+        &self.levels
+    }
+}
+
+/// New and cleared cities start flat; a loaded or generated one brings
+/// its own elevation.
+fn flatten_elevation(map: Res<MapConfig>, mut elevation: ResMut<Elevation>) {
+    *elevation = Elevation::flat(map.width, map.height);
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::config::MapConfig;
+use crate::grid::CARDINAL_NEIGHBORS;
+use crate::locale::Phrase;
+use crate::map::{TileData, TileMap, Zone};
+use crate::services::{Service, ServiceCoverage};
+use crate::simulation::{simulation_step, SimRng, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::tint::TileTint;
+use crate::ui::TileNotification;
+use crate::weather::Weather;
+
+/// Chance per developed tile per tick that a fire starts somewhere; the
+/// city-wide chance scales with the number of developed tiles.
+const IGNITION_CHANCE_PER_TILE: f64 = 0.0005;
+/// How much more likely fires are in an all-industrial city.
+const INDUSTRIAL_FIRE_FACTOR: f64 = 3.0;
+/// Chance per tick that a fire spreads to each developed neighbor.
+const SPREAD_CHANCE: f64 = 0.1;
+/// Population and jobs a fire destroys per tick.
+const FIRE_DAMAGE: u32 = 20;
+/// Ticks until a covered fire is put out.
+const COVERED_BURN_TICKS: u8 = 2;
+/// Ticks until an uncovered fire destroys its tile.
+const UNCOVERED_BURN_TICKS: u8 = 8;
+/// Tint of a burning tile.
+const FIRE_TINT: Color = Color::srgb(1.0, 0.4, 0.15);
+
+/// Random fires on developed tiles, and fire stations that put them out.
+pub struct FirePlugin;
+
+impl Plugin for FirePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<TileNotification>()
+            .add_systems(ResetCity, init_fire_grid)
+            .add_systems(
+                Update,
+                update_fires
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            );
+    }
+}
+
+/// Fire state per tile, laid out row by row.
+#[derive(Resource)]
+pub struct FireGrid {
+    width: i32,
+    height: i32,
+    /// Ticks each tile has been burning, 0 when not on fire.
+    burning: Vec<u8>,
+}
+
+impl FireGrid {
+    fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            burning: vec![0; (width * height) as usize],
+        }
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return None;
+        }
+        Some((coord.y * self.width + coord.x) as usize)
+    }
+
+    fn coord(&self, index: usize) -> IVec2 {
+        IVec2::new(index as i32 % self.width, index as i32 / self.width)
+    }
+
+    /// Whether the tile at `coord` is on fire.
+    pub fn burning(&self, coord: IVec2) -> bool {
+        self.index(coord).is_some_and(|i| self.burning[i] > 0)
+    }
+}
+
+/// Whether a tile has anything on it to burn.
+fn is_developed(zone: Zone, data: TileData) -> bool {
+    matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial)
+        && data.population + data.jobs > 0
+}
+
+fn init_fire_grid(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(FireGrid::new(map.width, map.height));
+}
+
+/// Start, spread, damage, and end fires once per tick.
+///
+/// Tiles are always visited in row order and every roll comes from
+/// `SimRng`, so the same seed and inputs burn the same tiles.
+#[allow(clippy::too_many_arguments)]
+pub fn update_fires(
+    timer: Res<SimTimer>,
+    mut rng: ResMut<SimRng>,
+    mut grid: ResMut<FireGrid>,
+    coverage: Res<ServiceCoverage>,
+    weather: Res<Weather>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData, &mut TileTint)>,
+    mut notifications: MessageWriter<TileNotification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    let size = grid.burning.len();
+    let mut zones = vec![Zone::Empty; size];
+    let mut data = vec![TileData::default(); size];
+    for (i, (zone, tile)) in zones.iter_mut().zip(data.iter_mut()).enumerate() {
+        if let Some(entity) = tile_map.get(grid.coord(i)) {
+            if let Ok((z, d, _)) = tiles.get(entity) {
+                *zone = *z;
+                *tile = *d;
+            }
+        }
+    }
+
+    // Fires go out on their own when the player clears or rezones the tile
+    // to something that cannot burn.
+    for (burning, zone) in grid.burning.iter_mut().zip(&zones) {
+        if !matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial) {
+            *burning = 0;
+        }
+    }
+
+    // Ignition, more likely the more (and the more industrial) the city is,
+    // and in a heatwave.
+    let developed: Vec<usize> =
+        (0..size).filter(|&i| is_developed(zones[i], data[i])).collect();
+    if !developed.is_empty() {
+        let industrial = developed
+            .iter()
+            .filter(|&&i| zones[i] == Zone::Industrial)
+            .count();
+        let industrial_share = industrial as f64 / developed.len() as f64;
+        let chance = (developed.len() as f64
+            * IGNITION_CHANCE_PER_TILE
+            * (1.0 + INDUSTRIAL_FIRE_FACTOR * industrial_share)
+            * weather.fire_factor())
+            .min(1.0);
+        if rng.0.random_bool(chance) {
+            let i = developed[rng.0.random_range(0..developed.len())];
+            if grid.burning[i] == 0 {
+                grid.burning[i] = 1;
+                let c = grid.coord(i);
+                notifications.write(TileNotification {
+                    tile: c,
+                    text: Phrase::new("notify.fire_started").with("x", c.x).with("y", c.y),
+                });
+            }
+        }
+    }
+
+    let fires: Vec<usize> = (0..size).filter(|&i| grid.burning[i] > 0).collect();
+    for &i in &fires {
+        let coord = grid.coord(i);
+        data[i].population = data[i].population.saturating_sub(FIRE_DAMAGE);
+        data[i].jobs = data[i].jobs.saturating_sub(FIRE_DAMAGE);
+
+        for n in CARDINAL_NEIGHBORS {
+            let Some(j) = grid.index(coord + n) else {
+                continue;
+            };
+            if grid.burning[j] == 0
+                && is_developed(zones[j], data[j])
+                && rng.0.random_bool(SPREAD_CHANCE)
+            {
+                grid.burning[j] = 1;
+            }
+        }
+
+        grid.burning[i] += 1;
+        let covered = coverage.covers(Service::Fire, coord);
+        if covered && grid.burning[i] > COVERED_BURN_TICKS {
+            grid.burning[i] = 0;
+            notifications.write(TileNotification {
+                tile: coord,
+                text: Phrase::new("notify.fire_put_out")
+                    .with("x", coord.x)
+                    .with("y", coord.y),
+            });
+        } else if !covered && grid.burning[i] > UNCOVERED_BURN_TICKS {
+            grid.burning[i] = 0;
+            zones[i] = Zone::Empty;
+            data[i] = TileData::default();
+            notifications.write(TileNotification {
+                tile: coord,
+                text: Phrase::new("notify.burned_down")
+                    .with("x", coord.x)
+                    .with("y", coord.y),
+            });
+        }
+    }
+
+    // Write back damage, destroyed tiles, and the fire tint.
+    for i in 0..size {
+        let Some(entity) = tile_map.get(grid.coord(i)) else {
+            continue;
+        };
+        let Ok((mut zone, mut tile, mut tint)) = tiles.get_mut(entity) else {
+            continue;
+        };
+        zone.set_if_neq(zones[i]);
+        tile.set_if_neq(data[i]);
+        let fire = if grid.burning[i] > 0 { FIRE_TINT } else { Color::WHITE };
+        if tint.fire != fire {
+            tint.fire = fire;
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::calendar::{GameClock, TICKS_PER_MONTH};
+use crate::locale::{Locale, Phrase};
+use crate::scenario::ActiveScenario;
+use crate::simulation::{simulation_step, CityStats, SimTimer};
+use crate::state::{GameSet, GameState, ResetCity};
+use crate::ui::{menu_button, Notification};
+
+/// Consecutive ticks the treasury may stay negative before bankruptcy:
+/// three months, since the budget only reaches it once a month.
+const BANKRUPTCY_GRACE_TICKS: u32 = 3 * TICKS_PER_MONTH as u32;
+
+/// Ends the game when the city stays broke for too long, and offers a
+/// fresh start or a sandbox where money no longer matters. Scenarios end
+/// the game through here too when they are won or lost.
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bankruptcy>()
+            .init_resource::<Ending>()
+            .add_message::<Notification>()
+            .add_systems(
+                Update,
+                track_bankruptcy
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            )
+            .add_systems(OnEnter(GameState::GameOver), spawn_game_over_screen)
+            .add_systems(
+                Update,
+                handle_game_over_buttons.run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(ResetCity, reset_bankruptcy);
+    }
+}
+
+/// How long the treasury has been in the red.
+#[derive(Resource, Default)]
+pub struct Bankruptcy {
+    ticks_in_red: u32,
+    /// Set after "Continue in sandbox": the city can no longer go bankrupt.
+    sandbox: bool,
+}
+
+impl Bankruptcy {
+    /// A defaulted loan uses up half the grace period at once.
+    pub fn hasten(&mut self) {
+        self.ticks_in_red += BANKRUPTCY_GRACE_TICKS / 2;
+    }
+}
+
+/// Why the game ended, which the game-over screen is worded for.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ending {
+    #[default]
+    Bankrupt,
+    ScenarioWon,
+    ScenarioLost,
+}
+
+#[derive(Component, Clone, Copy)]
+enum GameOverButton {
+    Restart,
+    Sandbox,
+}
+
+/// Count ticks spent below zero, warn as the grace period runs out, and
+/// end the game when it has.
+pub fn track_bankruptcy(
+    timer: Res<SimTimer>,
+    stats: Res<CityStats>,
+    mut bankruptcy: ResMut<Bankruptcy>,
+    mut ending: ResMut<Ending>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !timer.0.just_finished() || bankruptcy.sandbox {
+        return;
+    }
+    if stats.money >= 0 {
+        bankruptcy.ticks_in_red = 0;
+        return;
+    }
+
+    bankruptcy.ticks_in_red += 1;
+    let ticks = bankruptcy.ticks_in_red;
+    if ticks == BANKRUPTCY_GRACE_TICKS / 4 || ticks == BANKRUPTCY_GRACE_TICKS * 3 / 4 {
+        notifications.write(Notification(
+            Phrase::new("notify.bankruptcy_warning").with("ticks", BANKRUPTCY_GRACE_TICKS - ticks),
+        ));
+    }
+    if ticks >= BANKRUPTCY_GRACE_TICKS {
+        *ending = Ending::Bankrupt;
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// The summary screen, headed by how the game ended. A won scenario can
+/// be played on as a sandbox rather than continued in one.
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    stats: Res<CityStats>,
+    calendar: Res<GameClock>,
+    ending: Res<Ending>,
+    locale: Res<Locale>,
+) {
+    let (title, summary, color, sandbox) = match *ending {
+        Ending::Bankrupt => (
+            "game_over.title",
+            "game_over.summary",
+            Color::srgb(0.9, 0.3, 0.3),
+            "game_over.sandbox",
+        ),
+        Ending::ScenarioWon => (
+            "game_over.won",
+            "game_over.won_summary",
+            Color::srgb(0.3, 0.9, 0.4),
+            "game_over.keep_playing",
+        ),
+        Ending::ScenarioLost => (
+            "game_over.lost",
+            "game_over.lost_summary",
+            Color::srgb(0.9, 0.3, 0.3),
+            "game_over.sandbox",
+        ),
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            GlobalZIndex(10),
+            DespawnOnExit(GameState::GameOver),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text(title)),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(color),
+            ));
+            parent.spawn((
+                Text::new(
+                    locale.render(
+                        &Phrase::new(summary)
+                            .number("population", stats.population)
+                            .with("date", locale.render(&calendar.phrase())),
+                    ),
+                ),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(Justify::Center),
+            ));
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(16.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (button, label) in [
+                        (GameOverButton::Restart, "game_over.restart"),
+                        (GameOverButton::Sandbox, sandbox),
+                    ] {
+                        row.spawn(menu_button(&locale.text(label), button));
+                    }
+                });
+        });
+}
+
+/// Restarting a scenario starts it over. Going on in a sandbox leaves
+/// the scenario behind, and after bankruptcy the treasury with it.
+fn handle_game_over_buttons(
+    buttons: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    ending: Res<Ending>,
+    mut bankruptcy: ResMut<Bankruptcy>,
+    mut scenario: ResMut<ActiveScenario>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            GameOverButton::Restart => next_state.set(GameState::Restarting),
+            GameOverButton::Sandbox => {
+                if *ending == Ending::Bankrupt {
+                    bankruptcy.sandbox = true;
+                }
+                scenario.0 = None;
+                next_state.set(GameState::Running);
+            }
+        }
+    }
+}
+
+fn reset_bankruptcy(mut bankruptcy: ResMut<Bankruptcy>, mut ending: ResMut<Ending>) {
+    *bankruptcy = Bankruptcy::default();
+    *ending = Ending::default();
+}
@@ -0,0 +1,414 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::input::{paint_zones, ActivePointer, BuildTool, PaintCommand};
+use crate::locale::{Locale, Phrase};
+use crate::map::Zone;
+use crate::milestones::{Lock, Milestones};
+use crate::replay::Playback;
+use crate::state::{AppState, GameSet, SpawnSet};
+use crate::ui::{menu_button, Notification};
+
+/// Tiles per second the tile cursor moves with the right stick at full tilt.
+const CURSOR_SPEED: f32 = 12.0;
+/// A d-pad direction held this long starts repeating, one tile each
+/// `DPAD_REPEAT_SECONDS`.
+const DPAD_REPEAT_DELAY: f32 = 0.3;
+const DPAD_REPEAT_SECONDS: f32 = 0.08;
+/// Every build tool, in the order the bumpers and the build menu go
+/// through them: the same order Tab steps through.
+const TOOLS: [Zone; 17] = [
+    Zone::Road,
+    Zone::Residential,
+    Zone::Commercial,
+    Zone::Industrial,
+    Zone::FireStation,
+    Zone::PoliceStation,
+    Zone::School,
+    Zone::PowerPlant,
+    Zone::DirtRoad,
+    Zone::Avenue,
+    Zone::WaterTower,
+    Zone::PumpingStation,
+    Zone::Landfill,
+    Zone::RecyclingCenter,
+    Zone::Seaport,
+    Zone::Airport,
+    Zone::Empty,
+];
+/// Text color of the build menu entry for the selected tool, and of the
+/// entries for tools no milestone has unlocked yet.
+const SELECTED_TOOL_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+const LOCKED_TOOL_COLOR: Color = Color::srgb(0.45, 0.45, 0.45);
+
+/// Building with a gamepad, alongside the mouse. The right stick or d-pad
+/// moves a tile cursor, independent of the mouse cursor, that the brush
+/// outline follows once the gamepad is in use; A paints the selected zone
+/// and B bulldozes under it, held to paint a stroke. The bumpers step
+/// through the unlocked tools and Y opens the build menu listing them all,
+/// with the locked ones greyed out. The camera
+/// side (left stick and triggers) lives with the rest of the camera
+/// controls. Gamepads can come and go at any time.
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Notification>()
+            .add_message::<PaintCommand>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (spawn_tile_cursor, spawn_build_menu.in_set(SpawnSet::Ui)),
+            )
+            .add_systems(
+                Update,
+                // Painting looks at the build menu before A can close it.
+                (
+                    cycle_tool,
+                    move_tile_cursor,
+                    paint_with_gamepad,
+                    toggle_build_menu,
+                    pick_from_build_menu,
+                )
+                    .chain()
+                    .before(paint_zones)
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(
+                Update,
+                (update_build_menu, update_build_menu_tooltip).in_set(GameSet::Ui),
+            )
+            .add_systems(Update, announce_gamepads);
+    }
+}
+
+/// The gamepad's pointer on the map, one tile at a time.
+#[derive(Component)]
+pub struct TileCursor {
+    /// In tiles, kept fractional so a gently tilted stick still creeps.
+    position: Vec2,
+}
+
+impl TileCursor {
+    /// The tile the cursor is on.
+    pub fn coord(&self) -> IVec2 {
+        self.position.round().as_ivec2()
+    }
+}
+
+/// The build menu, hidden until Y opens it.
+#[derive(Component)]
+struct BuildMenu;
+
+/// Build menu entry selecting a tool.
+#[derive(Component, Clone, Copy)]
+struct BuildMenuButton(Zone);
+
+/// Line at the foot of the build menu saying what unlocks the locked tool
+/// under the mouse.
+#[derive(Component)]
+struct BuildMenuTooltip;
+
+/// Whether any connected gamepad has `button` held.
+fn any_pressed(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool {
+    gamepads.iter().any(|gamepad| gamepad.pressed(button))
+}
+
+/// Whether any connected gamepad pressed `button` this frame.
+fn any_just_pressed(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool {
+    gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+}
+
+/// Every city starts with the tile cursor in the middle of the map, where
+/// the camera starts.
+fn spawn_tile_cursor(mut commands: Commands, map: Res<MapConfig>) {
+    commands.spawn((
+        TileCursor {
+            position: Vec2::new((map.width / 2) as f32, (map.height / 2) as f32),
+        },
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn spawn_build_menu(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(80.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            BuildMenu,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|menu| {
+            menu.spawn((
+                Text::new(locale.text("build_menu.title")),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for zone in TOOLS {
+                menu.spawn(menu_button(&locale.text(zone.label()), BuildMenuButton(zone)));
+            }
+            menu.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(LOCKED_TOOL_COLOR),
+                Node {
+                    display: Display::None,
+                    ..default()
+                },
+                BuildMenuTooltip,
+            ));
+        });
+}
+
+/// The right bumper selects the next tool, the left one the previous,
+/// passing over those still locked.
+fn cycle_tool(
+    gamepads: Query<&Gamepad>,
+    milestones: Res<Milestones>,
+    mut tool: ResMut<BuildTool>,
+) {
+    let step = if any_just_pressed(&gamepads, GamepadButton::RightTrigger) {
+        1
+    } else if any_just_pressed(&gamepads, GamepadButton::LeftTrigger) {
+        TOOLS.len() - 1
+    } else {
+        return;
+    };
+    // A tool picked some other way but not listed, like water, starts from
+    // the first.
+    let mut i = match TOOLS.iter().position(|&zone| zone == tool.zone) {
+        Some(i) => (i + step) % TOOLS.len(),
+        None => 0,
+    };
+    // Bulldozing is never locked, so this stops.
+    while milestones.locked(TOOLS[i]).is_some() {
+        i = (i + step) % TOOLS.len();
+    }
+    tool.zone = TOOLS[i];
+}
+
+/// Y opens and closes the build menu; A closes it too, keeping the tool
+/// the bumpers moved to.
+fn toggle_build_menu(
+    gamepads: Query<&Gamepad>,
+    mut menu: Query<&mut Node, With<BuildMenu>>,
+) {
+    let Ok(mut node) = menu.single_mut() else {
+        return;
+    };
+    let open = node.display != Display::None;
+    if any_just_pressed(&gamepads, GamepadButton::North)
+        || (open && any_just_pressed(&gamepads, GamepadButton::South))
+    {
+        node.display = if open { Display::None } else { Display::Flex };
+    }
+}
+
+/// Clicking a build menu entry picks its tool and closes the menu, unless
+/// the tool is locked.
+fn pick_from_build_menu(
+    buttons: Query<(&Interaction, &BuildMenuButton), Changed<Interaction>>,
+    milestones: Res<Milestones>,
+    mut tool: ResMut<BuildTool>,
+    mut menu: Query<&mut Node, With<BuildMenu>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed && milestones.locked(button.0).is_none() {
+            tool.zone = button.0;
+            if let Ok(mut node) = menu.single_mut() {
+                node.display = Display::None;
+            }
+        }
+    }
+}
+
+/// Highlight the selected tool in the build menu, and grey out the
+/// locked ones.
+fn update_build_menu(
+    tool: Res<BuildTool>,
+    milestones: Res<Milestones>,
+    buttons: Query<(&BuildMenuButton, &Children)>,
+    mut labels: Query<&mut TextColor>,
+) {
+    if !tool.is_changed() && !milestones.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        let color = if milestones.locked(button.0).is_some() {
+            LOCKED_TOOL_COLOR
+        } else if button.0 == tool.zone {
+            SELECTED_TOOL_COLOR
+        } else {
+            Color::WHITE
+        };
+        for child in children.iter() {
+            if let Ok(mut label) = labels.get_mut(child) {
+                label.0 = color;
+            }
+        }
+    }
+}
+
+/// Say what unlocks the locked tool under the mouse, if there is one.
+fn update_build_menu_tooltip(
+    buttons: Query<(&Interaction, &BuildMenuButton)>,
+    milestones: Res<Milestones>,
+    locale: Res<Locale>,
+    mut tooltip: Query<(&mut Text, &mut Node), With<BuildMenuTooltip>>,
+) {
+    let Ok((mut text, mut node)) = tooltip.single_mut() else {
+        return;
+    };
+    let locked = buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .find_map(|(_, button)| milestones.locked(button.0));
+    let display = match locked {
+        Some(lock) => {
+            let line = locale.render(&match lock {
+                Lock::Population(population) => {
+                    Phrase::new("build_menu.unlocks_at").number("population", population)
+                }
+                Lock::Scenario => Phrase::new("build_menu.banned"),
+            });
+            if **text != line {
+                **text = line;
+            }
+            Display::Flex
+        }
+        None => Display::None,
+    };
+    if node.display != display {
+        node.display = display;
+    }
+}
+
+/// Move the tile cursor with the right stick, or a tile per d-pad press,
+/// repeating while held, and keep it on the map.
+fn move_tile_cursor(
+    time: Res<Time>,
+    gamepads: Query<&Gamepad>,
+    map: Res<MapConfig>,
+    mut cursor: Query<&mut TileCursor>,
+    mut pointer: ResMut<ActivePointer>,
+    mut held: Local<(IVec2, f32)>,
+) {
+    let Ok(mut cursor) = cursor.single_mut() else {
+        return;
+    };
+    let stick: Vec2 = gamepads.iter().map(Gamepad::right_stick).sum();
+    let dpad = gamepads
+        .iter()
+        .map(Gamepad::dpad)
+        .sum::<Vec2>()
+        .clamp(Vec2::NEG_ONE, Vec2::ONE)
+        .as_ivec2();
+
+    // A d-pad direction steps once when pressed, then repeats after a pause.
+    let mut step = IVec2::ZERO;
+    if dpad != held.0 {
+        *held = (dpad, 0.0);
+        step = dpad;
+    } else if dpad != IVec2::ZERO {
+        held.1 += time.delta_secs();
+        if held.1 >= DPAD_REPEAT_DELAY {
+            held.1 -= DPAD_REPEAT_SECONDS;
+            step = dpad;
+        }
+    }
+    if stick == Vec2::ZERO && step == IVec2::ZERO {
+        return;
+    }
+
+    let mut position = if step == IVec2::ZERO {
+        cursor.position
+    } else {
+        (cursor.coord() + step).as_vec2()
+    };
+    position += stick.clamp_length_max(1.0) * CURSOR_SPEED * time.delta_secs();
+    let max = Vec2::new((map.width - 1) as f32, (map.height - 1) as f32);
+    cursor.position = position.clamp(Vec2::ZERO, max.max(Vec2::ZERO));
+    pointer.set_if_neq(ActivePointer::Gamepad);
+}
+
+/// A paints the selected zone under the tile cursor and B bulldozes,
+/// either held to paint a stroke as the cursor moves. Neither paints
+/// while the build menu is open.
+fn paint_with_gamepad(
+    gamepads: Query<&Gamepad>,
+    tool: Res<BuildTool>,
+    cursor: Query<&TileCursor>,
+    menu: Query<&Node, With<BuildMenu>>,
+    mut pointer: ResMut<ActivePointer>,
+    mut stroke: Local<Option<(GamepadButton, Zone)>>,
+    mut commands: MessageWriter<PaintCommand>,
+) {
+    let Ok(cursor) = cursor.single() else {
+        return;
+    };
+    let mut start = false;
+    if stroke.is_none() && menu.single().is_ok_and(|node| node.display == Display::None) {
+        for (button, zone) in [
+            (GamepadButton::South, tool.zone),
+            (GamepadButton::East, Zone::Empty),
+        ] {
+            if any_just_pressed(&gamepads, button) {
+                *stroke = Some((button, zone));
+                start = true;
+                break;
+            }
+        }
+    }
+    let Some((button, zone)) = *stroke else {
+        return;
+    };
+    // Also ends the stroke if the gamepad holding it is unplugged.
+    if !any_pressed(&gamepads, button) {
+        *stroke = None;
+        commands.write(PaintCommand::Finish);
+        return;
+    }
+    pointer.set_if_neq(ActivePointer::Gamepad);
+    commands.write(PaintCommand::Paint {
+        center: cursor.coord(),
+        zone,
+        start,
+    });
+}
+
+/// Say when a gamepad is plugged in or taken away.
+fn announce_gamepads(
+    mut connections: MessageReader<GamepadConnectionEvent>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for event in connections.read() {
+        match &event.connection {
+            GamepadConnection::Connected { name, .. } => {
+                info!("Gamepad connected: {name}");
+                notifications.write(Notification(
+                    Phrase::new("notify.gamepad_connected").with("name", name),
+                ));
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad disconnected");
+                notifications.write(Notification(Phrase::new("notify.gamepad_disconnected")));
+            }
+        }
+    }
+}
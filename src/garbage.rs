@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::locale::Phrase;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::services::coverage;
+use crate::simulation::{simulation_step, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::ui::{Notification, TileNotification};
+use crate::weather::Weather;
+
+/// Garbage a landfill holds before it is full.
+pub const LANDFILL_CAPACITY: u32 = 50_000;
+/// Most garbage one landfill takes in per tick.
+pub const LANDFILL_INTAKE: u32 = 150;
+/// Garbage one recycling center takes away per tick, for good.
+pub const RECYCLING_RATE: u32 = 100;
+/// Residents or jobs of a home or shop that make one unit of garbage a
+/// tick; industry makes twice as much.
+const OCCUPANTS_PER_GARBAGE: u32 = 10;
+/// Uncollected garbage the city lets pile up; what is made beyond it is
+/// never picked up.
+const MAX_BACKLOG: u32 = 5_000;
+/// Worst pollution a tile can have.
+pub const MAX_POLLUTION: u32 = 100;
+/// Pollution every tile loses per tick, more in the rain.
+const POLLUTION_DECAY: u8 = 1;
+/// Uncollected garbage for each point of pollution a tick spills, and the
+/// most a tick spills.
+const GARBAGE_PER_POLLUTION: u32 = 50;
+const MAX_SPILL: u32 = 10;
+/// How many of the fullest sites garbage spills around, and how far.
+const SPILL_SITES: usize = 3;
+const SPILL_RADIUS: i32 = 3;
+
+/// Garbage collection: homes, shops, and industry make garbage every
+/// tick, which recycling centers take away and landfills bury until they
+/// are full. Whatever neither can take piles up and spills as pollution
+/// around the fullest sites, which lowers land value and drives residents
+/// out of homes.
+pub struct GarbagePlugin;
+
+impl Plugin for GarbagePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Garbage::new(0, 0))
+            .add_message::<Notification>()
+            .add_message::<TileNotification>()
+            .add_systems(ResetCity, clear_garbage)
+            .add_systems(
+                Update,
+                collect_garbage
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            );
+    }
+}
+
+/// Whether a zone disposes of garbage.
+pub fn is_garbage_site(zone: Zone) -> bool {
+    matches!(zone, Zone::Landfill | Zone::RecyclingCenter)
+}
+
+/// Garbage a tile makes per tick.
+pub fn garbage_made(zone: Zone, data: &TileData) -> u32 {
+    match zone {
+        Zone::Residential => data.population.div_ceil(OCCUPANTS_PER_GARBAGE),
+        Zone::Commercial => data.jobs.div_ceil(OCCUPANTS_PER_GARBAGE),
+        Zone::Industrial => data.jobs.div_ceil(OCCUPANTS_PER_GARBAGE) * 2,
+        _ => 0,
+    }
+}
+
+/// What is buried in every landfill and how polluted every tile is, laid
+/// out row by row, with the garbage waiting city-wide. Kept in saves.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct Garbage {
+    width: i32,
+    height: i32,
+    /// Garbage buried under each tile; only landfills hold any.
+    fill: Vec<u32>,
+    pollution: Vec<u8>,
+    /// Garbage made that nothing could take yet.
+    pub backlog: u32,
+    /// Garbage made in the last tick.
+    pub produced: u32,
+    /// What recycling centers and landfills could take in the last tick.
+    pub capacity: u32,
+    /// Room left in every landfill together.
+    pub landfill_space: u32,
+}
+
+impl Garbage {
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height).max(0) as usize;
+        Self::from_saved(width, height, &[], vec![0; size], 0)
+    }
+
+    /// `pollution` must hold one entry per tile, row by row.
+    pub fn from_saved(
+        width: i32,
+        height: i32,
+        landfills: &[(IVec2, u32)],
+        pollution: Vec<u8>,
+        backlog: u32,
+    ) -> Self {
+        let mut garbage = Self {
+            width,
+            height,
+            fill: vec![0; pollution.len()],
+            pollution,
+            backlog,
+            produced: 0,
+            capacity: 0,
+            landfill_space: 0,
+        };
+        for &(coord, fill) in landfills {
+            if let Some(i) = garbage.index(coord) {
+                garbage.fill[i] = fill;
+            }
+        }
+        garbage
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        on_map.then(|| (coord.y * self.width + coord.x) as usize)
+    }
+
+    /// Garbage buried in the landfill at `coord`; none elsewhere.
+    pub fn fill(&self, coord: IVec2) -> u32 {
+        self.index(coord).map_or(0, |i| self.fill[i])
+    }
+
+    /// Pollution of the tile at `coord`, 0 to `MAX_POLLUTION`.
+    pub fn pollution(&self, coord: IVec2) -> u32 {
+        self.index(coord).map_or(0, |i| u32::from(self.pollution[i]))
+    }
+
+    /// Whether garbage is piling up for lack of somewhere to take it.
+    pub fn overflowing(&self) -> bool {
+        self.backlog > 0
+    }
+
+    /// Every landfill with garbage in it and how much, row by row, for
+    /// saving.
+    pub fn landfills(&self) -> Vec<(IVec2, u32)> {
+        (0..self.fill.len())
+            .filter(|&i| self.fill[i] > 0)
+            .map(|i| (IVec2::new(i as i32 % self.width, i as i32 / self.width), self.fill[i]))
+            .collect()
+    }
+
+    /// Pollution of every tile, row by row, for saving.
+    pub fn pollution_levels(&self) -> &[u8] {
+        &self.pollution
+    }
+}
+
+/// New and cleared cities start clean; a loaded one brings its own
+/// landfills, pollution, and backlog.
+fn clear_garbage(map: Res<MapConfig>, mut garbage: ResMut<Garbage>) {
+    *garbage = Garbage::new(map.width, map.height);
+}
+
+/// Once a tick, collect the garbage made and waiting: recycling centers
+/// take their share first, then the emptiest landfills, each up to its
+/// intake and its room. What is left waits, and spills as pollution around
+/// the fullest sites (or the tiles making the most, without any) while
+/// pollution everywhere slowly clears, faster in the rain. Warns when garbage starts piling
+/// up and when a landfill fills.
+fn collect_garbage(
+    timer: Res<SimTimer>,
+    mut garbage: ResMut<Garbage>,
+    weather: Res<Weather>,
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    mut notifications: MessageWriter<Notification>,
+    mut tile_notifications: MessageWriter<TileNotification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    let garbage = &mut *garbage;
+    let width = garbage.width;
+    let mut produced = 0;
+    let mut producers = Vec::new();
+    let mut landfills = Vec::new();
+    let mut recyclers = Vec::new();
+    for (coord, zone, data) in tiles.iter() {
+        let i = (coord.coord.y * width + coord.coord.x) as usize;
+        let made = garbage_made(*zone, data);
+        produced += made;
+        if made > 0 {
+            producers.push((made, i));
+        }
+        match zone {
+            Zone::Landfill => landfills.push(i),
+            Zone::RecyclingCenter => recyclers.push(i),
+            // A bulldozed landfill takes what was buried in it along.
+            _ => garbage.fill[i] = 0,
+        }
+    }
+
+    let mut waiting = garbage.backlog + produced;
+    let recycling = recyclers.len() as u32 * RECYCLING_RATE;
+    waiting -= recycling.min(waiting);
+    let mut capacity = recycling;
+    landfills.sort_by_key(|&i| (garbage.fill[i], i));
+    for &i in &landfills {
+        let intake = LANDFILL_INTAKE.min(LANDFILL_CAPACITY - garbage.fill[i]);
+        capacity += intake;
+        let taken = intake.min(waiting);
+        garbage.fill[i] += taken;
+        waiting -= taken;
+        if taken > 0 && garbage.fill[i] == LANDFILL_CAPACITY {
+            tile_notifications.write(TileNotification {
+                tile: IVec2::new(i as i32 % width, i as i32 / width),
+                text: Phrase::new("notify.landfill_full"),
+            });
+        }
+    }
+
+    let decay = POLLUTION_DECAY + weather.pollution_decay();
+    for level in &mut garbage.pollution {
+        *level = level.saturating_sub(decay);
+    }
+    if waiting > 0 {
+        // Recycling centers only overflow once they are working flat out.
+        let mut sites: Vec<(u32, usize)> = landfills
+            .iter()
+            .map(|&i| (garbage.fill[i] * 100 / LANDFILL_CAPACITY, i))
+            .chain(recyclers.iter().map(|&i| (100, i)))
+            .collect();
+        if sites.is_empty() {
+            sites = producers;
+        }
+        sites.sort_by_key(|&(fullness, i)| (std::cmp::Reverse(fullness), i));
+        let centers: Vec<IVec2> = sites
+            .iter()
+            .take(SPILL_SITES)
+            .map(|&(_, i)| IVec2::new(i as i32 % width, i as i32 / width))
+            .collect();
+        let spill = waiting.div_ceil(GARBAGE_PER_POLLUTION).min(MAX_SPILL);
+        let spilled = coverage(width, garbage.height, &centers, SPILL_RADIUS);
+        for (level, _) in garbage.pollution.iter_mut().zip(spilled).filter(|(_, hit)| *hit) {
+            *level = (u32::from(*level) + spill).min(MAX_POLLUTION) as u8;
+        }
+    }
+
+    if waiting > 0 && garbage.backlog == 0 {
+        notifications.write(Notification(
+            Phrase::new("notify.garbage_overflow")
+                .number("produced", produced)
+                .number("capacity", capacity),
+        ));
+    }
+    garbage.backlog = waiting.min(MAX_BACKLOG);
+    garbage.produced = produced;
+    garbage.capacity = capacity;
+    garbage.landfill_space = landfills.iter().map(|&i| LANDFILL_CAPACITY - garbage.fill[i]).sum();
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+
+/// World position of the bottom-left corner of the map. The map is centered
+/// on the world origin.
+fn map_corner(map: &MapConfig) -> Vec2 {
+    -Vec2::new(map.width as f32, map.height as f32) * map.tile_size / 2.0
+}
+
+/// World-space center of a tile.
+pub fn tile_to_world(map: &MapConfig, coord: IVec2) -> Vec2 {
+    map_corner(map) + (coord.as_vec2() + 0.5) * map.tile_size
+}
+
+/// Grid coordinate of the tile containing a world position, which may be
+/// off the map. A point on the edge between two tiles belongs to the one
+/// above or to the right of it.
+pub fn world_to_coord(map: &MapConfig, world: Vec2) -> IVec2 {
+    ((world - map_corner(map)) / map.tile_size).floor().as_ivec2()
+}
+
+/// The map tile containing a world position, if there is one.
+pub fn world_to_tile(map: &MapConfig, world: Vec2) -> Option<IVec2> {
+    Some(world_to_coord(map, world)).filter(|&coord| in_bounds(map, coord))
+}
+
+pub fn in_bounds(map: &MapConfig, coord: IVec2) -> bool {
+    coord.x >= 0 && coord.y >= 0 && coord.x < map.width && coord.y < map.height
+}
+
+/// The four tiles sharing an edge with a tile. Movement along roads, and
+/// anything spreading from tile to tile, only ever takes these steps.
+pub const CARDINAL_NEIGHBORS: [IVec2; 4] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+];
+
+/// The cardinal neighbors followed by the four diagonal ones.
+const ALL_NEIGHBORS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(-1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, -1),
+];
+
+/// Which tiles count as next to a tile for growth checks: whether a road
+/// is in reach, and whether a congested road is close enough to hurt.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Neighborhood {
+    /// Only tiles sharing an edge.
+    #[default]
+    Four,
+    /// Diagonal tiles too.
+    Eight,
+}
+
+impl Neighborhood {
+    pub fn offsets(self) -> &'static [IVec2] {
+        match self {
+            Neighborhood::Four => &CARDINAL_NEIGHBORS,
+            Neighborhood::Eight => &ALL_NEIGHBORS,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Neighborhood::Four => Neighborhood::Eight,
+            Neighborhood::Eight => Neighborhood::Four,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four tiles across and two up, ten units each: the map runs from
+    /// (-20, -10) to (20, 10).
+    const MAP: MapConfig = MapConfig {
+        width: 4,
+        height: 2,
+        tile_size: 10.0,
+    };
+
+    #[test]
+    fn tiles_are_centered_on_the_map() {
+        assert_eq!(tile_to_world(&MAP, IVec2::ZERO), Vec2::new(-15.0, -5.0));
+        assert_eq!(tile_to_world(&MAP, IVec2::new(3, 1)), Vec2::new(15.0, 5.0));
+        assert_eq!(tile_to_world(&MAP, IVec2::new(-1, 0)), Vec2::new(-25.0, -5.0));
+        for y in 0..MAP.height {
+            for x in 0..MAP.width {
+                let coord = IVec2::new(x, y);
+                assert_eq!(world_to_tile(&MAP, tile_to_world(&MAP, coord)), Some(coord));
+            }
+        }
+    }
+
+    #[test]
+    fn edges_belong_to_the_tile_above_and_right() {
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-20.0, -10.0)), IVec2::ZERO);
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-10.0, -10.0)), IVec2::new(1, 0));
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-10.001, -0.001)), IVec2::ZERO);
+        assert_eq!(world_to_coord(&MAP, Vec2::ZERO), IVec2::new(2, 1));
+        assert_eq!(world_to_coord(&MAP, Vec2::new(19.999, 9.999)), IVec2::new(3, 1));
+    }
+
+    #[test]
+    fn negative_positions_round_down() {
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-0.5, -0.5)), IVec2::new(1, 0));
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-20.5, -10.5)), IVec2::new(-1, -1));
+        assert_eq!(world_to_coord(&MAP, Vec2::new(-35.0, -25.0)), IVec2::new(-2, -2));
+    }
+
+    #[test]
+    fn the_eight_neighborhood_adds_the_diagonals() {
+        let four = Neighborhood::Four.offsets();
+        let eight = Neighborhood::Eight.offsets();
+        assert_eq!(four, CARDINAL_NEIGHBORS);
+        assert_eq!(&eight[..4], four);
+        assert_eq!(eight.len(), 8);
+        assert!(eight[4..].iter().all(|n| n.x.abs() == 1 && n.y.abs() == 1));
+        assert_eq!(Neighborhood::default(), Neighborhood::Four);
+        assert_eq!(Neighborhood::Four.toggled(), Neighborhood::Eight);
+        assert_eq!(Neighborhood::Eight.toggled(), Neighborhood::Four);
+    }
+
+    #[test]
+    fn positions_off_the_map_have_no_tile() {
+        assert_eq!(world_to_tile(&MAP, Vec2::new(-20.5, 0.0)), None);
+        assert_eq!(world_to_tile(&MAP, Vec2::new(0.0, -10.5)), None);
+        assert_eq!(world_to_tile(&MAP, Vec2::new(20.0, 0.0)), None);
+        assert_eq!(world_to_tile(&MAP, Vec2::new(0.0, 10.0)), None);
+        assert_eq!(world_to_tile(&MAP, Vec2::new(1000.0, -1000.0)), None);
+        assert!(!in_bounds(&MAP, IVec2::new(4, 0)));
+        assert!(!in_bounds(&MAP, IVec2::new(0, -1)));
+        assert!(in_bounds(&MAP, IVec2::new(3, 1)));
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::map::MainCamera;
+use crate::settings::Settings;
+use crate::state::{AppState, GameSet, SpawnSet};
+
+/// Draw order of the grid lines: above buildings and cars, below the data
+/// overlay.
+const GRID_Z: f32 = 1.5;
+/// Opacity of the lines when zoomed in close enough to see every tile.
+const GRID_ALPHA: f32 = 0.35;
+/// On-screen tile size, in pixels, below which the lines start fading out,
+/// and at which they are gone.
+const FADE_START_PX: f32 = 12.0;
+const FADE_END_PX: f32 = 4.0;
+
+/// Lines along the tile boundaries, toggled with G and remembered in
+/// `Settings`.
+pub struct GridOverlayPlugin;
+
+impl Plugin for GridOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            spawn_grid_lines.in_set(SpawnSet::Map),
+        )
+        .add_systems(
+            Update,
+            (toggle_grid_lines, show_grid_lines, fade_grid_lines)
+                .chain()
+                .in_set(GameSet::Ui),
+        );
+    }
+}
+
+/// The grid line mesh, shown while `grid_lines` is on.
+#[derive(Component)]
+struct GridLines;
+
+/// One hairline mesh for the whole map, so the overlay is a single draw
+/// and costs nothing while hidden.
+fn spawn_grid_lines(
+    mut commands: Commands,
+    map: Res<MapConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let size = Vec2::new(map.width as f32, map.height as f32) * map.tile_size;
+    let corner = -size / 2.0;
+    let mut points = Vec::with_capacity(((map.width + map.height + 2) * 2) as usize);
+    for x in 0..=map.width {
+        let x = corner.x + x as f32 * map.tile_size;
+        points.push([x, corner.y, 0.0]);
+        points.push([x, corner.y + size.y, 0.0]);
+    }
+    for y in 0..=map.height {
+        let y = corner.y + y as f32 * map.tile_size;
+        points.push([corner.x, y, 0.0]);
+        points.push([corner.x + size.x, y, 0.0]);
+    }
+    let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, points);
+
+    commands.spawn((
+        Mesh2d(meshes.add(mesh)),
+        MeshMaterial2d(materials.add(Color::WHITE.with_alpha(GRID_ALPHA))),
+        Transform::from_xyz(0.0, 0.0, GRID_Z),
+        Visibility::Hidden,
+        GridLines,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn toggle_grid_lines(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keys.just_pressed(KeyCode::KeyG) {
+        settings.grid_lines = !settings.grid_lines;
+    }
+}
+
+fn show_grid_lines(
+    settings: Res<Settings>,
+    mut lines: Query<&mut Visibility, With<GridLines>>,
+) {
+    let shown = if settings.grid_lines {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in lines.iter_mut() {
+        visibility.set_if_neq(shown);
+    }
+}
+
+/// Fade the lines out as tiles shrink on screen, before they would merge
+/// into a solid sheet.
+fn fade_grid_lines(
+    map: Res<MapConfig>,
+    camera: Query<&Projection, With<MainCamera>>,
+    lines: Query<(&Visibility, &MeshMaterial2d<ColorMaterial>), With<GridLines>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok(Projection::Orthographic(projection)) = camera.single() else {
+        return;
+    };
+    let tile_px = map.tile_size / projection.scale;
+    let alpha = GRID_ALPHA
+        * ((tile_px - FADE_END_PX) / (FADE_START_PX - FADE_END_PX)).clamp(0.0, 1.0);
+    for (visibility, material) in lines.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        if let Some(material) = materials.get(&material.0) {
+            if material.color.alpha() == alpha {
+                continue;
+            }
+        }
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color.set_alpha(alpha);
+        }
+    }
+}
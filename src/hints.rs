@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::grid::Neighborhood;
+use crate::history::StatsHistory;
+use crate::locale::Locale;
+use crate::map::{TileCoord, Zone};
+use crate::replay::Playback;
+use crate::settings::Settings;
+use crate::sim::ZoneGrid;
+use crate::simulation::{CityStats, SimTimer};
+use crate::state::{AppState, GameSet, SpawnSet};
+use crate::ui::menu_button;
+
+/// Every tip, in the order they take turns: the first one whose condition
+/// holds and that hasn't been shown yet this session is the one shown.
+const HINTS: [Hint; 6] = [
+    Hint {
+        applies: |city| city.roads == 0,
+        text: "hint.road_first",
+    },
+    Hint {
+        applies: |city| city.homes > 0 && city.homes_by_road == 0,
+        text: "hint.homes_need_road",
+    },
+    Hint {
+        applies: |city| city.homes_by_road > 0 && city.workplaces == 0,
+        text: "hint.jobs",
+    },
+    Hint {
+        applies: |city| city.money < 0,
+        text: "hint.money",
+    },
+    Hint {
+        applies: |city| city.population >= 200 && city.police_stations == 0,
+        text: "hint.police",
+    },
+    Hint {
+        applies: |city| city.population >= 500,
+        text: "hint.overlays",
+    },
+];
+
+/// A dismissible panel of tips for new players, each shown at most once a
+/// session, when the state of the city makes it useful. Turned off and on
+/// with `hints` in `Settings`.
+pub struct HintsPlugin;
+
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hints>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_hint_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                Update,
+                (
+                    (pick_hint, dismiss_hint).run_if(|settings: Res<Settings>| settings.hints),
+                    update_hint_panel,
+                )
+                    .chain()
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<Playback>)),
+            );
+    }
+}
+
+/// A tip, and when it is worth showing.
+struct Hint {
+    applies: fn(&CityFacts) -> bool,
+    /// Locale key of the tip.
+    text: &'static str,
+}
+
+/// What the hints' conditions look at, gathered once per tick.
+struct CityFacts {
+    roads: u32,
+    homes: u32,
+    /// Residential tiles with a road next to them.
+    homes_by_road: u32,
+    /// Commercial and industrial tiles.
+    workplaces: u32,
+    police_stations: u32,
+    population: u32,
+    money: i64,
+}
+
+/// Which hints have been shown this session, and the one on screen.
+#[derive(Resource, Default)]
+struct Hints {
+    shown: [bool; HINTS.len()],
+    current: Option<usize>,
+}
+
+/// The hint panel, hidden while there is no hint to show.
+#[derive(Component)]
+struct HintPanel;
+
+#[derive(Component)]
+struct HintText;
+
+/// Button putting the current hint away.
+#[derive(Component)]
+struct DismissHintButton;
+
+fn spawn_hint_panel(mut commands: Commands, mut hints: ResMut<Hints>, locale: Res<Locale>) {
+    hints.current = None;
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                display: Display::None,
+                ..default()
+            },
+            HintPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    max_width: Val::Px(480.0),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(12.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.05, 0.1, 0.2, 0.85)),
+            ))
+            .with_children(|panel| {
+                panel.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    HintText,
+                ));
+                panel.spawn(menu_button(&locale.text("hint.dismiss"), DismissHintButton));
+            });
+        });
+}
+
+/// Once a tick, take the current hint down if it no longer applies, and
+/// bring up the next one that does if none is showing. Nothing is looked
+/// at once every hint has been shown.
+fn pick_hint(
+    timer: Res<SimTimer>,
+    mut hints: ResMut<Hints>,
+    tiles: Query<(&TileCoord, &Zone)>,
+    map: Res<MapConfig>,
+    neighborhood: Res<Neighborhood>,
+    history: Res<StatsHistory>,
+    stats: Res<CityStats>,
+) {
+    if !timer.0.just_finished() || (hints.current.is_none() && hints.shown.iter().all(|&s| s)) {
+        return;
+    }
+    let Some(latest) = history.latest() else {
+        return;
+    };
+
+    let homes = latest.zone_count(Zone::Residential);
+    let homes_by_road = if homes > 0 {
+        let mut zones = ZoneGrid::new(map.width, map.height);
+        for (coord, zone) in tiles.iter() {
+            zones.set(coord.coord, *zone);
+        }
+        tiles
+            .iter()
+            .filter(|(coord, zone)| {
+                **zone == Zone::Residential && zones.next_to_road(coord.coord, *neighborhood)
+            })
+            .count() as u32
+    } else {
+        0
+    };
+    let city = CityFacts {
+        roads: Zone::ALL
+            .iter()
+            .filter(|zone| zone.is_road())
+            .map(|&zone| latest.zone_count(zone))
+            .sum(),
+        homes,
+        homes_by_road,
+        workplaces: latest.zone_count(Zone::Commercial) + latest.zone_count(Zone::Industrial),
+        police_stations: latest.zone_count(Zone::PoliceStation),
+        population: stats.population,
+        money: stats.money,
+    };
+
+    if let Some(current) = hints.current {
+        if (HINTS[current].applies)(&city) {
+            return;
+        }
+        hints.current = None;
+    }
+    let next = (0..HINTS.len()).find(|&i| !hints.shown[i] && (HINTS[i].applies)(&city));
+    if let Some(next) = next {
+        hints.shown[next] = true;
+        hints.current = Some(next);
+    }
+}
+
+fn dismiss_hint(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<DismissHintButton>)>,
+    mut hints: ResMut<Hints>,
+) {
+    if buttons.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        hints.current = None;
+    }
+}
+
+/// Show the current hint, or nothing while hints are turned off.
+fn update_hint_panel(
+    hints: Res<Hints>,
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    mut panel: Query<&mut Node, With<HintPanel>>,
+    mut text: Query<&mut Text, With<HintText>>,
+) {
+    if !hints.is_changed() && !settings.is_changed() {
+        return;
+    }
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = if settings.hints && hints.current.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    if let (Some(current), Ok(mut text)) = (hints.current, text.single_mut()) {
+        **text = locale.text(HINTS[current].text);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+use crate::map::Zone;
+
+/// Snapshots kept before the oldest start being dropped; at the default
+/// tick rate this is well over an hour of play.
+pub const HISTORY_CAPACITY: usize = 4096;
+
+/// City metrics at the end of one simulation tick. New metrics get a field
+/// here and a `StatsField` variant to read them by.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct StatsSnapshot {
+    /// Simulation ticks since the city was founded, counting this one.
+    pub tick: u64,
+    pub population: u32,
+    pub jobs: u32,
+    pub money: i64,
+    /// Tiles of each zone, in `Zone::ALL` order.
+    pub zone_counts: [u32; Zone::ALL.len()],
+}
+
+impl StatsSnapshot {
+    pub fn zone_count(&self, zone: Zone) -> u32 {
+        self.zone_counts[zone_index(zone)]
+    }
+
+    pub fn count_zone(&mut self, zone: Zone) {
+        self.zone_counts[zone_index(zone)] += 1;
+    }
+
+    pub fn get(&self, field: StatsField) -> i64 {
+        match field {
+            StatsField::Population => self.population as i64,
+            StatsField::Jobs => self.jobs as i64,
+            StatsField::Money => self.money,
+        }
+    }
+}
+
+fn zone_index(zone: Zone) -> usize {
+    Zone::ALL
+        .iter()
+        .position(|z| *z == zone)
+        .expect("Zone::ALL lists every zone")
+}
+
+/// A metric of `StatsSnapshot` that can be charted or summarized.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatsField {
+    Population,
+    Jobs,
+    Money,
+}
+
+impl StatsField {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatsField::Population => "stats.field.population",
+            StatsField::Jobs => "stats.field.jobs",
+            StatsField::Money => "stats.field.money",
+        }
+    }
+}
+
+/// The most recent `HISTORY_CAPACITY` snapshots, oldest first. Indices
+/// passed to the accessors count from the oldest snapshot still kept.
+#[derive(Resource)]
+pub struct StatsHistory {
+    snapshots: VecDeque<StatsSnapshot>,
+    capacity: usize,
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self::with_capacity(HISTORY_CAPACITY)
+    }
+}
+
+impl StatsHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a snapshot, dropping the oldest once full.
+    pub fn push(&mut self, snapshot: StatsSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    pub fn latest(&self) -> Option<&StatsSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// The newest `n` snapshots (or all of them, if fewer), oldest first.
+    pub fn last_n(&self, n: usize) -> impl DoubleEndedIterator<Item = &StatsSnapshot> {
+        self.snapshots
+            .range(self.snapshots.len().saturating_sub(n)..)
+    }
+
+    /// Smallest and largest value of `field` over the snapshots in `range`,
+    /// clipped to what is kept; `None` if that leaves nothing.
+    pub fn min_max(&self, field: StatsField, range: Range<usize>) -> Option<(i64, i64)> {
+        let end = range.end.min(self.snapshots.len());
+        let start = range.start.min(end);
+        self.snapshots.range(start..end).fold(None, |bounds, snapshot| {
+            let value = snapshot.get(field);
+            Some(match bounds {
+                None => (value, value),
+                Some((min, max)) => (value.min(min), value.max(max)),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(tick: u64, population: u32) -> StatsSnapshot {
+        StatsSnapshot {
+            tick,
+            population,
+            money: -(tick as i64),
+            ..default()
+        }
+    }
+
+    fn filled(capacity: usize, ticks: u64) -> StatsHistory {
+        let mut history = StatsHistory::with_capacity(capacity);
+        for tick in 1..=ticks {
+            history.push(snapshot(tick, (tick * 10 % 70) as u32));
+        }
+        history
+    }
+
+    #[test]
+    fn drops_the_oldest_once_full() {
+        let history = filled(4, 10);
+        assert_eq!(history.len(), 4);
+        let ticks: Vec<u64> = history.last_n(usize::MAX).map(|s| s.tick).collect();
+        assert_eq!(ticks, vec![7, 8, 9, 10]);
+        assert_eq!(history.latest().map(|s| s.tick), Some(10));
+    }
+
+    #[test]
+    fn last_n_is_oldest_first_and_clipped() {
+        let history = filled(8, 5);
+        let ticks: Vec<u64> = history.last_n(2).map(|s| s.tick).collect();
+        assert_eq!(ticks, vec![4, 5]);
+        assert_eq!(history.last_n(20).count(), 5);
+        assert_eq!(history.last_n(0).count(), 0);
+    }
+
+    #[test]
+    fn min_max_covers_the_range_only() {
+        // Populations 10, 20, ..., 60, 0, 10, 20 for ticks 1 to 9.
+        let history = filled(16, 9);
+        assert_eq!(history.min_max(StatsField::Population, 0..9), Some((0, 60)));
+        assert_eq!(history.min_max(StatsField::Population, 1..4), Some((20, 40)));
+        assert_eq!(history.min_max(StatsField::Money, 7..100), Some((-9, -8)));
+        assert_eq!(history.min_max(StatsField::Jobs, 3..3), None);
+        assert_eq!(history.min_max(StatsField::Jobs, 20..30), None);
+    }
+
+    #[test]
+    fn min_max_counts_from_the_oldest_kept() {
+        let history = filled(3, 9);
+        // Ticks 7, 8, and 9 are left, with populations 0, 10, and 20.
+        assert_eq!(history.min_max(StatsField::Population, 0..2), Some((0, 10)));
+        assert_eq!(history.min_max(StatsField::Money, 0..3), Some((-9, -7)));
+    }
+
+    #[test]
+    fn counts_zones() {
+        let mut snapshot = StatsSnapshot::default();
+        snapshot.count_zone(Zone::Road);
+        snapshot.count_zone(Zone::Road);
+        snapshot.count_zone(Zone::Forest);
+        assert_eq!(snapshot.zone_count(Zone::Road), 2);
+        assert_eq!(snapshot.zone_count(Zone::Forest), 1);
+        assert_eq!(snapshot.zone_count(Zone::Residential), 0);
+    }
+}
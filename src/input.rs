@@ -0,0 +1,878 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::Clipboard;
+use crate::config::MapConfig;
+use crate::development::{develops, Development};
+use crate::elevation::Elevation;
+use crate::gamepad::TileCursor;
+use crate::grid::{self, tile_to_world};
+use crate::layers::{MapLayer, MapLayers};
+use crate::locale::Phrase;
+use crate::map::{variant_for, MainCamera, TileData, TileMap, Zone};
+use crate::milestones::{Lock, Milestones};
+use crate::ports::{is_port, Ports};
+use crate::replay::Playback;
+use crate::simulation::{CityStats, NewCity};
+use crate::state::{AppState, GameSet, ResetCity};
+use crate::ui::Notification;
+use crate::zone_defs::ZoneDefs;
+
+/// Maximum number of zoning actions kept for undo.
+const UNDO_DEPTH: usize = 100;
+/// Tint of brush cells that will and won't be painted.
+const BRUSH_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+const BRUSH_BLOCKED_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, 0.35);
+/// Draw order of the brush outline within the markers layer, under the
+/// problem icons.
+const BRUSH_Z: f32 = 0.0;
+/// Share of its cost given back for zoning over a building that is still
+/// under construction; a finished one gives nothing back.
+const CONSTRUCTION_REFUND_PERCENT: i64 = 75;
+
+/// Turns mouse clicks and shortcuts into zoning actions, and paints what
+/// the gamepad asks for too.
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UndoStack>()
+            .init_resource::<BuildTool>()
+            .init_resource::<ActivePointer>()
+            .add_message::<PaintCommand>()
+            .add_message::<TileEdit>()
+            .add_message::<Notification>()
+            .add_systems(
+                Update,
+                (
+                    (select_tool, paint_with_mouse, paint_zones, draw_brush).chain(),
+                    handle_undo_redo,
+                )
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(ResetCity, (clear_undo_history, reset_build_tool));
+    }
+}
+
+/// Square footprint of the zoning brush.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum BrushSize {
+    #[default]
+    One,
+    Three,
+    Five,
+}
+
+impl BrushSize {
+    /// Tiles the brush reaches from its center.
+    fn radius(self) -> i32 {
+        match self {
+            BrushSize::One => 0,
+            BrushSize::Three => 1,
+            BrushSize::Five => 2,
+        }
+    }
+
+    fn smaller(self) -> Self {
+        match self {
+            BrushSize::One | BrushSize::Three => BrushSize::One,
+            BrushSize::Five => BrushSize::Three,
+        }
+    }
+
+    fn larger(self) -> Self {
+        match self {
+            BrushSize::One => BrushSize::Three,
+            BrushSize::Three | BrushSize::Five => BrushSize::Five,
+        }
+    }
+
+    /// Every tile covered by the brush centered on `center`.
+    pub fn footprint(self, center: IVec2) -> impl Iterator<Item = IVec2> {
+        let r = self.radius();
+        (-r..=r).flat_map(move |y| (-r..=r).map(move |x| center + IVec2::new(x, y)))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BrushSize::One => "1x1",
+            BrushSize::Three => "3x3",
+            BrushSize::Five => "5x5",
+        }
+    }
+}
+
+/// What left-clicking the map paints, and how much of it at once.
+#[derive(Resource)]
+pub struct BuildTool {
+    pub zone: Zone,
+    pub brush: BrushSize,
+    /// Whether strokes lay pipe (or dig it up, with the bulldozer) instead
+    /// of zoning.
+    pub pipes: bool,
+    /// Whether a click on a road turns it to the next one-way direction
+    /// instead of zoning.
+    pub directions: bool,
+    /// District that strokes put tiles in (or take them out of, with the
+    /// bulldozer) instead of zoning, if one is being painted.
+    pub district: Option<u16>,
+}
+
+impl BuildTool {
+    /// Whether strokes zone, rather than lay pipe, set road directions, or
+    /// paint a district.
+    pub fn zoning(&self) -> bool {
+        !self.pipes && !self.directions && self.district.is_none()
+    }
+
+    /// Tiles a click centered on `center` zones: the brush, the one road
+    /// under it when setting directions, or the whole building for
+    /// multi-tile zones.
+    pub fn footprint(&self, center: IVec2) -> Vec<IVec2> {
+        if self.directions {
+            vec![center]
+        } else if self.zone.is_multi_tile() && self.zoning() {
+            self.zone.covered_tiles(center).collect()
+        } else {
+            self.brush.footprint(center).collect()
+        }
+    }
+}
+
+impl Default for BuildTool {
+    fn default() -> Self {
+        Self {
+            zone: Zone::Road,
+            brush: BrushSize::default(),
+            pipes: false,
+            directions: false,
+            district: None,
+        }
+    }
+}
+
+/// Which pointer the brush outline follows: the mouse cursor, or the
+/// gamepad's tile cursor. Whichever was used last wins.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActivePointer {
+    #[default]
+    Mouse,
+    Gamepad,
+}
+
+/// A pointer painting with the build tool, sent every frame its button is
+/// held, whether the mouse or a gamepad is doing the painting.
+#[derive(Message, Clone, Copy, Debug)]
+pub enum PaintCommand {
+    /// Paint `zone` under the brush centered on `center`; `start` is set on
+    /// the frame the button went down.
+    Paint { center: IVec2, zone: Zone, start: bool },
+    /// The button was let go: the stroke is finished and undone as one.
+    Finish,
+}
+
+/// The paint stroke in progress: tiles already painted (so dragging back
+/// over them is free) and everything it changed so far.
+#[derive(Default)]
+pub struct Stroke {
+    painted: HashSet<IVec2>,
+    action: ZoneAction,
+}
+
+/// One cell of the brush outline drawn under the cursor.
+#[derive(Component)]
+struct BrushCell;
+
+/// State of a single tile before or after a zoning action.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct TileSnapshot {
+    pub zone: Zone,
+    pub data: TileData,
+}
+
+/// One tile touched by a zoning action.
+#[derive(Clone, Copy)]
+pub struct TileChange {
+    pub coord: IVec2,
+    pub before: TileSnapshot,
+    pub after: TileSnapshot,
+}
+
+/// A player-initiated zoning action: every tile it changed plus the money
+/// it cost (negative for a refund), so undo can put both back.
+#[derive(Clone, Default)]
+pub struct ZoneAction {
+    pub changes: Vec<TileChange>,
+    pub cost: i64,
+}
+
+/// Tiles a player action set this frame and what it cost, sent by every
+/// system that edits the map on the player's behalf.
+#[derive(Message, Clone)]
+pub struct TileEdit {
+    pub tiles: Vec<(IVec2, TileSnapshot)>,
+    pub cost: i64,
+}
+
+/// Undo/redo history of player zoning actions. Simulation-driven changes
+/// never go in here.
+#[derive(Resource, Default)]
+pub struct UndoStack {
+    undo: VecDeque<ZoneAction>,
+    redo: Vec<ZoneAction>,
+}
+
+impl UndoStack {
+    /// Record a new action, dropping the oldest past `UNDO_DEPTH` and
+    /// invalidating anything that could have been redone.
+    pub fn push(&mut self, action: ZoneAction) {
+        if action.changes.is_empty() {
+            return;
+        }
+        if self.undo.len() == UNDO_DEPTH {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(action);
+        self.redo.clear();
+    }
+}
+
+fn clear_undo_history(mut undo: ResMut<UndoStack>) {
+    *undo = UndoStack::default();
+}
+
+fn reset_build_tool(mut tool: ResMut<BuildTool>) {
+    *tool = BuildTool::default();
+}
+
+/// World position under the mouse cursor.
+fn cursor_world(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    camera_q: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let window = windows.single().ok()?;
+    screen_world(camera_q, window.cursor_position()?)
+}
+
+/// World position under a point in the window, like a touch.
+fn screen_world(
+    camera_q: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    position: Vec2,
+) -> Option<Vec2> {
+    let (camera, cam_transform) = camera_q.single().ok()?;
+    camera.viewport_to_world_2d(cam_transform, position).ok()
+}
+
+/// Grid coordinate under a point in the window, which may be off the map.
+pub fn screen_coord(
+    camera_q: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: &MapConfig,
+    position: Vec2,
+) -> Option<IVec2> {
+    screen_world(camera_q, position).map(|world| grid::world_to_coord(map, world))
+}
+
+/// Grid coordinate under the mouse cursor, which may be off the map.
+pub fn cursor_coord(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    camera_q: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: &MapConfig,
+) -> Option<IVec2> {
+    cursor_world(windows, camera_q).map(|world| grid::world_to_coord(map, world))
+}
+
+/// The map tile under the mouse cursor, if there is one.
+pub fn cursor_tile(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    camera_q: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: &MapConfig,
+) -> Option<IVec2> {
+    cursor_world(windows, camera_q).and_then(|world| grid::world_to_tile(map, world))
+}
+
+/// Whether either Ctrl key is held, which turns clicks into selections.
+pub fn ctrl_held(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// Whether either Alt key is held, which turns number keys into map layer
+/// toggles.
+pub fn alt_held(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+/// Number keys without Alt pick the zone to paint (Tab steps through
+/// them), pressing the road key again steps through the road tiers, the
+/// bracket keys shrink or grow the brush, P switches between zoning and
+/// laying pipe, and O between zoning and setting road directions.
+fn select_tool(keys: Res<ButtonInput<KeyCode>>, mut tool: ResMut<BuildTool>) {
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let digit = DIGITS.iter().position(|key| keys.just_pressed(*key));
+    if let Some(i) = digit.filter(|_| !alt_held(&keys)) {
+        tool.zone = match (tool.zone, Zone::ALL[i]) {
+            (Zone::Road, Zone::Road) => Zone::Avenue,
+            (Zone::Avenue, Zone::Road) => Zone::DirtRoad,
+            (Zone::DirtRoad, Zone::Road) => Zone::Road,
+            (_, zone) => zone,
+        };
+    } else if keys.just_pressed(KeyCode::Tab) {
+        tool.zone = tool.zone.next();
+    }
+
+    if keys.just_pressed(KeyCode::KeyP) {
+        tool.pipes = !tool.pipes;
+        tool.directions = false;
+        tool.district = None;
+    }
+    if keys.just_pressed(KeyCode::KeyO) {
+        tool.directions = !tool.directions;
+        tool.pipes = false;
+        tool.district = None;
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        tool.brush = tool.brush.smaller();
+    } else if keys.just_pressed(KeyCode::BracketRight) {
+        tool.brush = tool.brush.larger();
+    }
+}
+
+/// Paint the selected zone under the mouse cursor while the left button
+/// is held. A stroke that starts on a HUD button, or while selecting or
+/// pasting, paints nothing.
+#[allow(clippy::too_many_arguments)]
+fn paint_with_mouse(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    motion: Res<AccumulatedMouseMotion>,
+    clipboard: Res<Clipboard>,
+    tool: Res<BuildTool>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    ui_buttons: Query<&Interaction, With<Button>>,
+    mut pointer: ResMut<ActivePointer>,
+    mut painting: Local<bool>,
+    mut commands: MessageWriter<PaintCommand>,
+) {
+    if motion.delta != Vec2::ZERO || buttons.just_pressed(MouseButton::Left) {
+        pointer.set_if_neq(ActivePointer::Mouse);
+    }
+    let start = buttons.just_pressed(MouseButton::Left);
+    if start {
+        // A click on a HUD button is not a paint stroke.
+        let on_button = ui_buttons.iter().any(|i| *i != Interaction::None);
+        *painting = !ctrl_held(&keys) && !clipboard.pasting && !on_button;
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        if *painting {
+            *painting = false;
+            commands.write(PaintCommand::Finish);
+        }
+        return;
+    }
+    if !*painting {
+        return;
+    }
+    if let Some(center) = cursor_coord(&windows, &camera_q, &map) {
+        commands.write(PaintCommand::Paint {
+            center,
+            zone: tool.zone,
+            start,
+        });
+    }
+}
+
+/// Carry out paint commands from every pointer: paint across the brush,
+/// paying per tile, or place one building per press for multi-tile zones.
+/// Each tile is painted at most once per stroke, and the whole stroke is
+/// undone as one action.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_zones(
+    mut commands: MessageReader<PaintCommand>,
+    tool: Res<BuildTool>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut stroke: Local<Stroke>,
+    mut undo: ResMut<UndoStack>,
+    mut stats: ResMut<CityStats>,
+    milestones: Res<Milestones>,
+    elevation: Res<Elevation>,
+    (defs, development): (Res<ZoneDefs>, Res<Development>),
+    new_city: Res<NewCity>,
+    mut edits: MessageWriter<TileEdit>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    // Laying pipe, setting directions, and painting districts are left to
+    // their own layers.
+    if !tool.zoning() {
+        commands.clear();
+        return;
+    }
+    let mut edit = TileEdit {
+        tiles: Vec::new(),
+        cost: 0,
+    };
+    for command in commands.read() {
+        let PaintCommand::Paint {
+            center,
+            zone,
+            start,
+        } = *command
+        else {
+            let finished = std::mem::take(&mut *stroke);
+            undo.push(finished.action);
+            continue;
+        };
+
+        if let Some(lock) = milestones.locked(zone) {
+            if start {
+                notifications.write(Notification(match lock {
+                    Lock::Population(population) => Phrase::new("notify.locked")
+                        .key("zone", zone.label())
+                        .number("population", population),
+                    Lock::Scenario => Phrase::new("notify.banned").key("zone", zone.label()),
+                }));
+            }
+            continue;
+        }
+
+        // Buildings bigger than a tile go down one per press, anchored
+        // under the pointer; everything else is painted across the brush.
+        let targets: Vec<IVec2> = if zone.is_multi_tile() {
+            if !start {
+                continue;
+            }
+            vec![center]
+        } else {
+            tool.brush.footprint(center).collect()
+        };
+        for coord in targets {
+            if !stroke.painted.insert(coord) {
+                continue;
+            }
+            let rezoned = rezone(
+                coord,
+                zone,
+                &tile_map,
+                &mut tiles,
+                &mut stats,
+                &milestones,
+                &elevation,
+                &defs,
+                *development,
+                new_city.seed,
+            );
+            let action = match rezoned {
+                Ok(action) => action,
+                Err(reason) if zone.is_multi_tile() => {
+                    notifications.write(Notification(
+                        Phrase::new("notify.cant_build")
+                            .key("zone", zone.label())
+                            .key("reason", reason),
+                    ));
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            stroke.action.cost += action.cost;
+            edit.cost += action.cost;
+            edit.tiles.extend(action.changes.iter().map(|c| (c.coord, c.after)));
+            stroke.action.changes.extend(action.changes);
+        }
+    }
+    if !edit.tiles.is_empty() {
+        edits.write(edit);
+    }
+}
+
+/// Zone a tile on the player's behalf and charge for it, as a paint stroke
+/// does for every tile under the brush. A multi-tile zone builds the whole
+/// building with its anchor at `coord`, and only if the city can pay for
+/// it; zoning over part of an existing building demolishes all of it.
+/// Changing a road's tier in place only charges the difference in price.
+/// New homes, shops, and industry start out under construction, and
+/// zoning over one that is still being built refunds most of its cost.
+/// Under gradual `development` they are only zoned, for demand to build
+/// on later at no further cost, and bulldozing one leaves its lot zoned.
+/// A road over water becomes a bridge, if it continues a road in a straight
+/// line, and bulldozing a bridge leaves water. New roads on a slope cost
+/// extra to grade. Each new building's look is picked from its coordinate
+/// and the city `seed`.
+/// Fails, leaving the map as it was, when `zone` isn't unlocked yet, a
+/// tile is off the map, can't be built on, is too steep, or is `zone`
+/// already, another building is in the way, a pumping station or seaport
+/// would have no water next to it, an airport wouldn't be on flat, empty
+/// land, or the city already has the port being built.
+#[allow(clippy::too_many_arguments)]
+pub fn rezone(
+    coord: IVec2,
+    zone: Zone,
+    tile_map: &TileMap,
+    tiles: &mut Query<(&mut Zone, &mut TileData)>,
+    stats: &mut CityStats,
+    milestones: &Milestones,
+    elevation: &Elevation,
+    defs: &ZoneDefs,
+    development: Development,
+    seed: u64,
+) -> Result<ZoneAction, &'static str> {
+    match milestones.locked(zone) {
+        Some(Lock::Population(_)) => return Err("build.locked"),
+        Some(Lock::Scenario) => return Err("build.banned"),
+        None => {}
+    }
+    if is_port(zone) && tiles.iter().any(|(current, _)| *current == zone) {
+        return Err("build.only_one");
+    }
+    let covered: Vec<IVec2> = zone.covered_tiles(coord).collect();
+    // Anchors of the buildings this one replaces.
+    let mut demolished = Vec::new();
+    let mut credit = 0;
+    let mut refund = 0;
+    let mut grading = 0;
+    let gradual = development == Development::Gradual;
+    // What a single-tile zone actually leaves on the tile.
+    let mut placed = zone;
+    let mut reverted = false;
+    for &tile in &covered {
+        let (current, data) = tile_map
+            .get(tile)
+            .and_then(|entity| tiles.get(entity).ok())
+            .ok_or("build.off_map")?;
+        if current.is_water() && !zone.is_multi_tile() {
+            placed = match (*current, zone) {
+                (Zone::Bridge, Zone::Empty) => Zone::Water,
+                (Zone::Water, zone) if zone.is_road() => Zone::Bridge,
+                (Zone::Bridge, zone) if zone.is_road() => {
+                    return Err("build.already_zoned");
+                }
+                _ => return Err("build.unbuildable"),
+            };
+            let zone_at = |c: IVec2| Some(*tiles.get(tile_map.get(c)?).ok()?.0);
+            if placed == Zone::Bridge && !bridge_fits(tile, zone_at) {
+                return Err("build.bridge_not_straight");
+            }
+        } else if !current.buildable() {
+            return Err("build.unbuildable");
+        } else if zone == Zone::Bridge {
+            return Err("build.bridge_on_land");
+        } else if gradual && zone == Zone::Empty && develops(*current) && !data.zoned {
+            placed = *current;
+            reverted = true;
+        }
+        if zone != Zone::Empty && elevation.too_steep(tile) {
+            return Err("build.too_steep");
+        }
+        if zone == Zone::PumpingStation && !next_to_water(tile, tile_map, tiles) {
+            return Err("build.pump_needs_water");
+        }
+        let flat = elevation.get(tile) == elevation.get(coord);
+        if zone == Zone::Airport && (*current != Zone::Empty || !flat) {
+            return Err("build.needs_flat_empty_land");
+        }
+        if let Some(anchor) = data.anchor {
+            if zone.is_multi_tile() {
+                return Err("build.building_in_way");
+            }
+            demolished.push((*current, anchor));
+        }
+        if !zone.is_multi_tile() && *current == zone {
+            return Err("build.already_zoned");
+        }
+        if current.is_road() && placed.is_road() {
+            credit = defs.get(*current).cost;
+        }
+        // Under gradual development, building was free to begin with.
+        if data.construction > 0 && !gradual {
+            refund += defs.get(*current).cost * CONSTRUCTION_REFUND_PERCENT / 100;
+        }
+        if placed.is_road() && placed != Zone::Bridge && !current.is_road() {
+            grading += elevation.road_grade_cost(tile);
+        }
+    }
+    if zone == Zone::Seaport && !covered.iter().any(|&tile| next_to_water(tile, tile_map, tiles)) {
+        return Err("build.port_needs_water");
+    }
+    let price = if reverted { 0 } else { defs.get(placed).cost };
+    let cost = (price - credit).max(0) + grading - refund;
+    if zone.is_multi_tile() && stats.money < cost {
+        return Err("build.cant_afford");
+    }
+
+    let mut action = ZoneAction {
+        changes: Vec::new(),
+        cost,
+    };
+    let cleared = demolished
+        .into_iter()
+        .flat_map(|(building, anchor)| building.covered_tiles(anchor))
+        .filter(|tile| !covered.contains(tile))
+        .map(|tile| (tile, Zone::Empty));
+    for (tile, new_zone) in cleared.chain(covered.iter().map(|&tile| (tile, placed))) {
+        let Some(entity) = tile_map.get(tile) else {
+            continue;
+        };
+        let Ok((mut current, mut data)) = tiles.get_mut(entity) else {
+            continue;
+        };
+        let before = TileSnapshot { zone: *current, data: *data };
+        *current = new_zone;
+        if new_zone.is_multi_tile() {
+            *data = TileData {
+                anchor: Some(coord),
+                ..TileData::default()
+            };
+        } else if gradual && develops(new_zone) {
+            *data = TileData {
+                zoned: true,
+                ..TileData::default()
+            };
+        } else if defs.get(new_zone).construction > 0 {
+            *data = TileData {
+                construction: defs.get(new_zone).construction,
+                ..TileData::default()
+            };
+        } else if data.anchor.is_some() || data.construction > 0 || data.zoned {
+            *data = TileData::default();
+        }
+        if !new_zone.is_multi_tile() {
+            data.variant = variant_for(tile, seed);
+        }
+        let after = TileSnapshot { zone: *current, data: *data };
+        action.changes.push(TileChange { coord: tile, before, after });
+    }
+    stats.money -= cost;
+    Ok(action)
+}
+
+/// Whether water, bridged or not, is on a side of `coord`.
+fn next_to_water(
+    coord: IVec2,
+    tile_map: &TileMap,
+    tiles: &Query<(&mut Zone, &mut TileData)>,
+) -> bool {
+    grid::CARDINAL_NEIGHBORS.iter().any(|&n| {
+        tile_map
+            .get(coord + n)
+            .and_then(|entity| tiles.get(entity).ok())
+            .is_some_and(|(zone, _)| zone.is_water())
+    })
+}
+
+/// Whether a bridge at `coord` would join a road or bridge next to it and
+/// keep every bridge straight: neither it nor a bridge beside it may
+/// connect to roads along both axes.
+fn bridge_fits(coord: IVec2, zone_at: impl Fn(IVec2) -> Option<Zone>) -> bool {
+    let is_road = |c: IVec2| c == coord || zone_at(c).is_some_and(Zone::is_road);
+    let straight = |c: IVec2| {
+        let across = is_road(c + IVec2::X) || is_road(c - IVec2::X);
+        let along = is_road(c + IVec2::Y) || is_road(c - IVec2::Y);
+        !(across && along)
+    };
+    let neighbors = grid::CARDINAL_NEIGHBORS.map(|n| coord + n);
+    neighbors.iter().any(|&c| is_road(c))
+        && straight(coord)
+        && neighbors
+            .iter()
+            .filter(|&&c| zone_at(c) == Some(Zone::Bridge))
+            .all(|&c| straight(c))
+}
+
+/// Outline the brush footprint under the active pointer, red where the
+/// tile is off the map, can't be built on, or is too steep, or another
+/// building is in the way of a multi-tile one, a pumping station or
+/// seaport would be away from water, an airport off flat, empty land, or
+/// the city already has the port. Water shows open to a road that can bridge it, and
+/// bridges to the bulldozer; pipe goes anywhere but under water. Hidden
+/// while selecting or pasting.
+#[allow(clippy::too_many_arguments)]
+fn draw_brush(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    clipboard: Res<Clipboard>,
+    tool: Res<BuildTool>,
+    pointer: Res<ActivePointer>,
+    tile_cursor: Query<&TileCursor>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    tile_map: Res<TileMap>,
+    elevation: Res<Elevation>,
+    zones: Query<(&Zone, &TileData)>,
+    ports: Res<Ports>,
+    layers: Res<MapLayers>,
+    mut cells: Query<(Entity, &mut Transform, &mut Sprite), With<BrushCell>>,
+) {
+    let center = match *pointer {
+        ActivePointer::Mouse => cursor_coord(&windows, &camera_q, &map),
+        ActivePointer::Gamepad => tile_cursor.single().ok().map(TileCursor::coord),
+    }
+    .filter(|_| !ctrl_held(&keys) && !clipboard.pasting);
+    let footprint = center
+        .map(|center| tool.footprint(center))
+        .unwrap_or_default();
+
+    // Respawn the outline whenever its cell count changes.
+    if cells.iter().len() != footprint.len() {
+        for (entity, ..) in cells.iter() {
+            commands.entity(entity).despawn();
+        }
+        for _ in &footprint {
+            commands.spawn((
+                Sprite::from_color(BRUSH_COLOR, Vec2::splat(map.tile_size)),
+                Transform::default(),
+                BrushCell,
+                ChildOf(layers.root(MapLayer::Markers)),
+                DespawnOnExit(AppState::Playing),
+            ));
+        }
+        return;
+    }
+
+    let zone_at = |c: IVec2| Some(*zones.get(tile_map.get(c)?).ok()?.0);
+    let by_water = tool.zone == Zone::Seaport
+        && footprint.iter().any(|&c| {
+            grid::CARDINAL_NEIGHBORS
+                .iter()
+                .any(|&n| zone_at(c + n).is_some_and(Zone::is_water))
+        });
+    let anchor_level = center.and_then(|center| elevation.get(center));
+    for ((_, mut transform, mut sprite), coord) in cells.iter_mut().zip(footprint) {
+        transform.translation = tile_to_world(&map, coord).extend(BRUSH_Z);
+        let steep = tool.zone != Zone::Empty && elevation.too_steep(coord);
+        let open = tile_map
+            .get(coord)
+            .and_then(|e| zones.get(e).ok())
+            .is_some_and(|(zone, data)| match zone {
+                _ if tool.district.is_some() => true,
+                _ if tool.pipes => !zone.is_water(),
+                _ if tool.directions => zone.is_road(),
+                _ if steep => false,
+                Zone::Water => tool.zone.is_road() && bridge_fits(coord, zone_at),
+                Zone::Bridge => tool.zone == Zone::Empty,
+                _ if is_port(tool.zone) && ports.has(tool.zone) => false,
+                _ if tool.zone == Zone::Airport => {
+                    *zone == Zone::Empty && elevation.get(coord) == anchor_level
+                }
+                _ if tool.zone == Zone::Seaport => by_water && data.anchor.is_none(),
+                _ if tool.zone == Zone::PumpingStation => {
+                    zone.buildable()
+                        && grid::CARDINAL_NEIGHBORS
+                            .iter()
+                            .any(|&n| zone_at(coord + n).is_some_and(Zone::is_water))
+                }
+                _ => zone.buildable() && !(tool.zone.is_multi_tile() && data.anchor.is_some()),
+            });
+        sprite.color = if open { BRUSH_COLOR } else { BRUSH_BLOCKED_COLOR };
+    }
+}
+
+/// Whether every tile `changes` touch still holds what the action left
+/// there (to undo it) or found there (to redo it), so putting them back
+/// can't wipe out what happened to them since, such as a fire burning a
+/// building down. Only what stands on a tile counts, not the residents
+/// and jobs the simulation moves in and out. `changes` are in the order
+/// they are about to be applied.
+fn still_applies(
+    changes: &[&TileChange],
+    is_undo: bool,
+    tile_map: &TileMap,
+    tiles: &Query<(&mut Zone, &mut TileData)>,
+) -> bool {
+    let mut checked = HashSet::new();
+    changes.iter().all(|change| {
+        if !checked.insert(change.coord) {
+            return true;
+        }
+        let expected = if is_undo { change.after } else { change.before };
+        tile_map
+            .get(change.coord)
+            .and_then(|entity| tiles.get(entity).ok())
+            .is_some_and(|(zone, data)| {
+                *zone == expected.zone && data.anchor == expected.data.anchor
+            })
+    })
+}
+
+/// Ctrl+Z reverts the last zoning action; Ctrl+Y or Ctrl+Shift+Z redoes it.
+/// An action whose tiles have changed since is dropped instead.
+fn handle_undo_redo(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut undo: ResMut<UndoStack>,
+    mut stats: ResMut<CityStats>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut edits: MessageWriter<TileEdit>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !ctrl_held(&keys) {
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let redo = keys.just_pressed(KeyCode::KeyY)
+        || (shift && keys.just_pressed(KeyCode::KeyZ));
+    let is_undo = !shift && keys.just_pressed(KeyCode::KeyZ);
+
+    let action = if is_undo {
+        undo.undo.pop_back()
+    } else if redo {
+        undo.redo.pop()
+    } else {
+        return;
+    };
+    let Some(action) = action else {
+        return;
+    };
+
+    let mut edit = TileEdit {
+        tiles: Vec::new(),
+        cost: if is_undo { -action.cost } else { action.cost },
+    };
+    // A tile can change more than once in an action, e.g. a building
+    // demolished and then painted over in one stroke, so undo walks the
+    // changes backwards.
+    let changes: Vec<&TileChange> = if is_undo {
+        action.changes.iter().rev().collect()
+    } else {
+        action.changes.iter().collect()
+    };
+    if !still_applies(&changes, is_undo, &tile_map, &tiles) {
+        notifications.write(Notification(Phrase::new("notify.undo_stale")));
+        return;
+    }
+    for change in changes {
+        let target = if is_undo { change.before } else { change.after };
+        edit.tiles.push((change.coord, target));
+        let Some(entity) = tile_map.get(change.coord) else {
+            continue;
+        };
+        if let Ok((mut zone, mut data)) = tiles.get_mut(entity) {
+            *zone = target.zone;
+            *data = target.data;
+        }
+    }
+
+    if is_undo {
+        stats.money += action.cost;
+        undo.redo.push(action);
+    } else {
+        stats.money -= action.cost;
+        undo.undo.push_back(action);
+    }
+    edits.write(edit);
+}
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::adjacency::Adjacency;
+use crate::clipboard::{toggle_paste, Clipboard};
+use crate::commute_debug::{CommuteTrace, TracedCommute};
+use crate::config::MapConfig;
+use crate::elevation::Elevation;
+use crate::garbage::{garbage_made, Garbage, LANDFILL_CAPACITY, RECYCLING_RATE};
+use crate::input::cursor_tile;
+use crate::locale::{Locale, Phrase};
+use crate::map::{MainCamera, TileData, TileMap, Zone};
+use crate::power::PowerGrid;
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+use crate::water::WaterSupply;
+
+/// Right click a tile to see what's on it; right click it again, or off the
+/// map, to put the panel away.
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspected>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_inspector_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                Update,
+                (
+                    pick_inspected_tile.before(toggle_paste).in_set(GameSet::Input),
+                    update_inspector_panel.in_set(GameSet::Ui),
+                ),
+            )
+            .add_systems(ResetCity, clear_inspected);
+    }
+}
+
+/// The tile the inspector panel shows, if it's open.
+#[derive(Resource, Default)]
+pub struct Inspected(pub Option<IVec2>);
+
+#[derive(Component)]
+struct InspectorText;
+
+fn spawn_inspector_panel(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        InspectorText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// A right click opens the tile under the cursor, or closes the panel if
+/// that tile is already open or the cursor is off the map. Right clicks
+/// that stop a paste are left to the clipboard.
+fn pick_inspected_tile(
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    clipboard: Res<Clipboard>,
+    mut inspected: ResMut<Inspected>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) || clipboard.pasting {
+        return;
+    }
+    let tile = cursor_tile(&windows, &camera_q, &map);
+    inspected.0 = if tile == inspected.0 { None } else { tile };
+}
+
+/// Everything the panel lists about a tile, one line each.
+#[allow(clippy::too_many_arguments)]
+fn inspector_lines(
+    coord: IVec2,
+    zone: Zone,
+    data: &TileData,
+    elevation: &Elevation,
+    adjacency: &Adjacency,
+    power: &PowerGrid,
+    water: &WaterSupply,
+    garbage: &Garbage,
+    commute: Option<&CommuteTrace>,
+) -> Vec<Phrase> {
+    let mut lines = vec![Phrase::new("inspector.tile")
+        .number("x", coord.x)
+        .number("y", coord.y)
+        .key("zone", zone.tile_label())];
+    if let Some(level) = elevation.get(coord) {
+        let key = if elevation.too_steep(coord) {
+            "inspector.elevation.steep"
+        } else {
+            "inspector.elevation"
+        };
+        lines.push(Phrase::new(key).number("level", level));
+    }
+    if data.construction > 0 {
+        lines.push(Phrase::new("inspector.construction").number("ticks", data.construction));
+    }
+    if data.zoned {
+        lines.push(Phrase::new("inspector.zoned"));
+    }
+    if matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial) {
+        lines.push(
+            Phrase::new("inspector.occupants")
+                .number("population", data.population)
+                .number("jobs", data.jobs),
+        );
+        if !power.powered(coord) {
+            lines.push(Phrase::new("inspector.unpowered"));
+        }
+        if !water.watered(coord) {
+            lines.push(Phrase::new("inspector.no_water"));
+        }
+        lines.push(Phrase::new("inspector.garbage").number("garbage", garbage_made(zone, data)));
+    }
+    match zone {
+        Zone::Landfill => {
+            let fill = garbage.fill(coord);
+            lines.push(
+                Phrase::new("inspector.landfill")
+                    .number("fill", fill)
+                    .number("capacity", LANDFILL_CAPACITY)
+                    .number("percent", fill * 100 / LANDFILL_CAPACITY),
+            );
+        }
+        Zone::RecyclingCenter => {
+            lines.push(Phrase::new("inspector.recycling").number("rate", RECYCLING_RATE));
+        }
+        _ => {}
+    }
+    let pollution = garbage.pollution(coord);
+    if pollution > 0 {
+        lines.push(Phrase::new("inspector.pollution").number("pollution", pollution));
+    }
+    if matches!(zone, Zone::Residential | Zone::Commercial) {
+        lines.push(
+            Phrase::new("inspector.crime")
+                .number("crime", data.crime)
+                .number("land_value", data.land_value),
+        );
+    }
+    if zone == Zone::Residential {
+        for neighbor in adjacency.get(coord).iter() {
+            let percent = format!("{:+}%", neighbor.percent());
+            lines.push(Phrase::new(neighbor.label()).with("percent", percent));
+        }
+        if let Some(trace) = commute.filter(|trace| trace.home == coord) {
+            lines.push(trace.summary());
+        }
+    }
+    lines
+}
+
+/// Show the inspected tile as it is now, rewriting the text only when a
+/// line changed.
+#[allow(clippy::too_many_arguments)]
+fn update_inspector_panel(
+    inspected: Res<Inspected>,
+    locale: Res<Locale>,
+    tile_map: Option<Res<TileMap>>,
+    elevation: Res<Elevation>,
+    adjacency: Res<Adjacency>,
+    power: Res<PowerGrid>,
+    water: Res<WaterSupply>,
+    garbage: Res<Garbage>,
+    commute: Res<TracedCommute>,
+    tiles: Query<(&Zone, &TileData)>,
+    mut panel: Query<(&mut Text, &mut Node), With<InspectorText>>,
+) {
+    let Ok((mut text, mut node)) = panel.single_mut() else {
+        return;
+    };
+    let tile = inspected
+        .0
+        .zip(tile_map)
+        .and_then(|(coord, tile_map)| Some((coord, tile_map.get(coord)?)))
+        .and_then(|(coord, entity)| Some((coord, tiles.get(entity).ok()?)));
+    let Some((coord, (zone, data))) = tile else {
+        if node.display != Display::None {
+            node.display = Display::None;
+        }
+        return;
+    };
+
+    let trace = commute.0.as_ref();
+    let lines = inspector_lines(
+        coord, *zone, data, &elevation, &adjacency, &power, &water, &garbage, trace,
+    );
+    let content = lines
+        .iter()
+        .map(|line| locale.render(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if **text != content {
+        **text = content;
+    }
+    if node.display != Display::Flex {
+        node.display = Display::Flex;
+    }
+}
+
+fn clear_inspected(mut inspected: ResMut<Inspected>) {
+    inspected.0 = None;
+}
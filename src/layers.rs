@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use crate::input::alt_held;
+use crate::state::GameSet;
+
+/// The map is drawn in layers, bottom to top: terrain, the buildings on
+/// it, the data overlay, and markers such as the brush and problem icons.
+/// Every layer has a root entity its drawing is parented to, so Alt+1-4
+/// can hide each one whole.
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_layer_roots).add_systems(
+            Update,
+            (toggle_layers, show_layers).chain().in_set(GameSet::Ui),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapLayer {
+    /// Grass, water, and forest under every tile.
+    Terrain,
+    /// Roads and buildings, drawn only on tiles that have one.
+    Buildings,
+    /// Heatmap of the data overlay being shown.
+    Overlay,
+    /// The brush, paste previews, problem icons, and trade arrows.
+    Markers,
+}
+
+impl MapLayer {
+    /// Every layer, bottom to top.
+    pub const ALL: [MapLayer; 4] = [
+        MapLayer::Terrain,
+        MapLayer::Buildings,
+        MapLayer::Overlay,
+        MapLayer::Markers,
+    ];
+
+    /// Draw order of the layer's root; whatever is drawn in the layer sits
+    /// at or just above it. Cars and grid lines go between buildings and
+    /// the overlay.
+    pub fn z(self) -> f32 {
+        match self {
+            MapLayer::Terrain => 0.0,
+            MapLayer::Buildings => 1.0,
+            MapLayer::Overlay => 2.0,
+            MapLayer::Markers => 3.0,
+        }
+    }
+
+    /// Key that shows or hides the layer while Alt is held.
+    fn key(self) -> KeyCode {
+        match self {
+            MapLayer::Terrain => KeyCode::Digit1,
+            MapLayer::Buildings => KeyCode::Digit2,
+            MapLayer::Overlay => KeyCode::Digit3,
+            MapLayer::Markers => KeyCode::Digit4,
+        }
+    }
+}
+
+/// The root of every layer, and which are hidden. The roots live for the
+/// whole session; what is drawn in them despawns with the city.
+#[derive(Resource)]
+pub struct MapLayers {
+    roots: [Entity; 4],
+    hidden: [bool; 4],
+}
+
+impl MapLayers {
+    /// Entity to parent anything drawn in `layer` to.
+    pub fn root(&self, layer: MapLayer) -> Entity {
+        self.roots[layer as usize]
+    }
+
+    pub fn shown(&self, layer: MapLayer) -> bool {
+        !self.hidden[layer as usize]
+    }
+}
+
+fn spawn_layer_roots(mut commands: Commands) {
+    let roots = MapLayer::ALL.map(|layer| {
+        commands
+            .spawn((
+                Transform::from_xyz(0.0, 0.0, layer.z()),
+                Visibility::Inherited,
+            ))
+            .id()
+    });
+    commands.insert_resource(MapLayers {
+        roots,
+        hidden: [false; 4],
+    });
+}
+
+fn toggle_layers(keys: Res<ButtonInput<KeyCode>>, mut layers: ResMut<MapLayers>) {
+    if !alt_held(&keys) {
+        return;
+    }
+    for layer in MapLayer::ALL {
+        if keys.just_pressed(layer.key()) {
+            layers.hidden[layer as usize] ^= true;
+        }
+    }
+}
+
+fn show_layers(layers: Res<MapLayers>, mut roots: Query<&mut Visibility>) {
+    if !layers.is_changed() {
+        return;
+    }
+    for layer in MapLayer::ALL {
+        let Ok(mut visibility) = roots.get_mut(layers.root(layer)) else {
+            continue;
+        };
+        visibility.set_if_neq(if layers.shown(layer) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -0,0 +1,307 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::{MapConfig, MAX_MAP_SIZE};
+use crate::grid::Neighborhood;
+use crate::input::ctrl_held;
+use crate::locale::Phrase;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::map::TileMap;
+use crate::map::{TileData, Zone};
+use crate::menu::PendingCity;
+use crate::save::SaveFile;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::screenshot::timestamped_path;
+use crate::state::GameSet;
+use crate::ui::Notification;
+use crate::zone_defs::ZoneDefs;
+
+/// Image the main menu imports, relative to the working directory.
+pub const IMPORT_PATH: &str = "city.png";
+/// Directory Ctrl+E writes layout images to.
+#[cfg(not(target_arch = "wasm32"))]
+const EXPORT_DIR: &str = "exports";
+
+/// Colors the importer reads as each zone, and the exporter writes: black
+/// road, green residential, blue commercial, yellow industrial, cyan
+/// water, and white empty.
+const LAYOUT_COLORS: [(Zone, [u8; 4]); 6] = [
+    (Zone::Road, [0, 0, 0, 255]),
+    (Zone::Residential, [0, 255, 0, 255]),
+    (Zone::Commercial, [0, 0, 255, 255]),
+    (Zone::Industrial, [255, 255, 0, 255]),
+    (Zone::Water, [0, 255, 255, 255]),
+    (Zone::Empty, [255, 255, 255, 255]),
+];
+
+/// Ctrl+E exports the zoning as a PNG that `--import-map` reads back.
+pub struct LayoutImagePlugin;
+
+impl Plugin for LayoutImagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Notification>();
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, export_layout.in_set(GameSet::Input));
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, export_unavailable.in_set(GameSet::Input));
+    }
+}
+
+/// The zone a pixel of an imported layout stands for in `LAYOUT_COLORS`,
+/// or `None` for a color outside it. Each channel counts as off below 64
+/// and on above 191, so slightly off colors from an image editor still
+/// match, and mostly transparent pixels are empty.
+pub fn zone_for(rgba: [u8; 4]) -> Option<Zone> {
+    if rgba[3] < 128 {
+        return Some(Zone::Empty);
+    }
+    let level = |channel: u8| match channel {
+        0..64 => Some(false),
+        192.. => Some(true),
+        _ => None,
+    };
+    let levels = [level(rgba[0])?, level(rgba[1])?, level(rgba[2])?];
+    LAYOUT_COLORS
+        .iter()
+        .find(|(_, color)| levels == [color[0] > 127, color[1] > 127, color[2] > 127])
+        .map(|&(zone, _)| zone)
+}
+
+/// The color a tile is exported as. Every road tier is a road; zones the
+/// importer has no color for keep their map color, and import as empty.
+pub fn color_for(zone: Zone, defs: &ZoneDefs) -> [u8; 4] {
+    let zone = if zone.is_road() && zone != Zone::Bridge {
+        Zone::Road
+    } else {
+        zone
+    };
+    LAYOUT_COLORS
+        .iter()
+        .find(|(mapped, _)| *mapped == zone)
+        .map_or_else(|| defs.get(zone).color().to_srgba().to_u8_array(), |&(_, rgba)| rgba)
+}
+
+/// Lay out a new city from the pixels of a `width` x `height` image, top
+/// row first, so the map looks the way the image does. Returns the layout
+/// and how many pixels had no zone, which are left empty.
+pub fn layout_from_pixels(
+    width: i32,
+    height: i32,
+    pixels: impl Iterator<Item = [u8; 4]>,
+    money: i64,
+    neighborhood: Neighborhood,
+) -> (SaveFile, u32) {
+    let mut layout = SaveFile::empty(width, height, money, 0);
+    layout.neighborhood = neighborhood;
+    let mut unrecognized = 0;
+    for (i, rgba) in pixels.enumerate() {
+        let i = i as i32;
+        let coord = IVec2::new(i % width, height - 1 - i / width);
+        let zone = zone_for(rgba).unwrap_or_else(|| {
+            unrecognized += 1;
+            Zone::Empty
+        });
+        layout.set(coord, zone, TileData::default());
+    }
+    (layout, unrecognized)
+}
+
+/// Whether there is an image to import from the main menu.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import_exists() -> bool {
+    Path::new(IMPORT_PATH).exists()
+}
+
+/// Read a PNG layout into a new city waiting to be built, with a notice
+/// saying where it came from and how much of it didn't match a zone.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_layout(
+    path: &str,
+    money: i64,
+    neighborhood: Neighborhood,
+) -> Result<PendingCity, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?.into_rgba8();
+    let (width, height) = image.dimensions();
+    if !(1..=MAX_MAP_SIZE as u32).contains(&width) || !(1..=MAX_MAP_SIZE as u32).contains(&height)
+    {
+        return Err(format!(
+            "the image is {width}x{height}, but maps can be at most \
+             {MAX_MAP_SIZE}x{MAX_MAP_SIZE}"
+        ));
+    }
+    let (layout, unrecognized) = layout_from_pixels(
+        width as i32,
+        height as i32,
+        image.pixels().map(|pixel| pixel.0),
+        money,
+        neighborhood,
+    );
+
+    let notice = Phrase::new("notify.imported")
+        .with("width", width)
+        .with("height", height)
+        .with("path", path);
+    let notice = if unrecognized > 0 {
+        warn!("{unrecognized} pixels of {path} have colors that aren't zones, left empty");
+        Phrase::new("notify.imported_with_unknown")
+            .with("width", width)
+            .with("height", height)
+            .with("path", path)
+            .number("unrecognized", unrecognized)
+    } else {
+        notice
+    };
+    Ok(PendingCity {
+        layout,
+        notice: Some(notice),
+    })
+}
+
+/// The zoning of a `width` x `height` map as an image, one pixel per tile
+/// with the top row of the map first, as the importer expects.
+#[cfg(not(target_arch = "wasm32"))]
+fn layout_image(
+    width: i32,
+    height: i32,
+    defs: &ZoneDefs,
+    zone_at: impl Fn(IVec2) -> Zone,
+) -> image::RgbaImage {
+    image::RgbaImage::from_fn(width as u32, height as u32, |x, row| {
+        let coord = IVec2::new(x as i32, height - 1 - row as i32);
+        image::Rgba(color_for(zone_at(coord), defs))
+    })
+}
+
+/// Ctrl+E writes the zoning to `exports/city_<timestamp>.png`.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_layout(
+    keys: Res<ButtonInput<KeyCode>>,
+    map: Res<MapConfig>,
+    tile_map: Option<Res<TileMap>>,
+    zones: Query<&Zone>,
+    defs: Res<ZoneDefs>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !(ctrl_held(&keys) && keys.just_pressed(KeyCode::KeyE)) {
+        return;
+    }
+    let Some(tile_map) = tile_map else {
+        return;
+    };
+    let image = layout_image(map.width, map.height, &defs, |coord| {
+        let zone = tile_map.get(coord).and_then(|e| zones.get(e).ok());
+        zone.copied().unwrap_or(Zone::Empty)
+    });
+
+    let path = timestamped_path(EXPORT_DIR);
+    let result = std::fs::create_dir_all(EXPORT_DIR)
+        .map_err(|e| e.to_string())
+        .and_then(|()| image.save(&path).map_err(|e| e.to_string()));
+    let message = match result {
+        Ok(()) => {
+            info!("Map exported to {}", path.display());
+            Phrase::new("notify.exported").with("path", path.display())
+        }
+        Err(err) => {
+            error!("Could not export the map: {err}");
+            Phrase::new("notify.export_failed").with("error", err)
+        }
+    };
+    notifications.write(Notification(message));
+}
+
+/// Exports are written to disk, which the web build can't do.
+#[cfg(target_arch = "wasm32")]
+fn export_unavailable(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if ctrl_held(&keys) && keys.just_pressed(KeyCode::KeyE) {
+        notifications.write(Notification(
+            Phrase::new("notify.export_unavailable"),
+        ));
+    }
+}
+
+/// There is no filesystem on the web, so there is never an image.
+#[cfg(target_arch = "wasm32")]
+pub fn import_exists() -> bool {
+    false
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_layout(
+    _path: &str,
+    _money: i64,
+    _neighborhood: Neighborhood,
+) -> Result<PendingCity, String> {
+    Err("importing is not available in the web build".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every zone the importer knows, the road tiers, and one it doesn't,
+    /// in stripes across a map taller than it is wide.
+    fn zone_at(coord: IVec2) -> Zone {
+        const STRIPES: [Zone; 9] = [
+            Zone::Road,
+            Zone::Residential,
+            Zone::Commercial,
+            Zone::Industrial,
+            Zone::Water,
+            Zone::Empty,
+            Zone::DirtRoad,
+            Zone::Avenue,
+            Zone::School,
+        ];
+        STRIPES[((coord.x + 2 * coord.y) % STRIPES.len() as i32) as usize]
+    }
+
+    /// What a tile zoned `zone` comes back as.
+    fn imported(zone: Zone) -> Zone {
+        match zone {
+            Zone::DirtRoad | Zone::Avenue => Zone::Road,
+            Zone::School => Zone::Empty,
+            zone => zone,
+        }
+    }
+
+    #[test]
+    fn an_exported_layout_imports_as_the_same_zones() {
+        let (width, height) = (7, 11);
+        let defs = ZoneDefs::bundled();
+        let image = layout_image(width, height, &defs, zone_at);
+        let path = std::env::temp_dir().join(format!("city_sim_layout_{}.png", std::process::id()));
+        image.save(&path).unwrap();
+        let city = read_layout(path.to_str().unwrap(), 500, Neighborhood::Eight);
+        std::fs::remove_file(&path).unwrap();
+
+        let layout = city.unwrap().layout;
+        assert_eq!((layout.width, layout.height), (width, height));
+        assert_eq!((layout.money, layout.neighborhood), (500, Neighborhood::Eight));
+        for y in 0..height {
+            for x in 0..width {
+                let coord = IVec2::new(x, y);
+                let (zone, _) = layout.tiles[(y * width + x) as usize];
+                assert_eq!(zone, imported(zone_at(coord)), "at {coord}");
+            }
+        }
+    }
+
+    #[test]
+    fn colors_close_to_a_zone_still_match() {
+        for (zone, rgba) in LAYOUT_COLORS {
+            assert_eq!(zone_for(rgba), Some(zone));
+            let faded = rgba.map(|channel| if channel > 127 { 200 } else { 40 });
+            assert_eq!(zone_for(faded), Some(zone));
+        }
+        assert_eq!(zone_for([128, 0, 0, 255]), None);
+        assert_eq!(zone_for([255, 0, 0, 255]), None);
+        assert_eq!(zone_for([0, 0, 0, 0]), Some(Zone::Empty));
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game_over::{track_bankruptcy, Bankruptcy};
+use crate::locale::Phrase;
+use crate::simulation::{simulation_step, CityStats, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::ui::Notification;
+
+/// Amounts the city can borrow, offered in the budget panel.
+pub const LOAN_SIZES: [i64; 3] = [5_000, 10_000, 20_000];
+/// Most loans the city may have outstanding at once.
+pub const MAX_LOANS: usize = 3;
+/// Ticks over which a loan is repaid: two in-game days.
+const LOAN_TERM_TICKS: u32 = 240;
+/// Flat interest on the principal, charged over the term.
+const LOAN_INTEREST_PERCENT: i64 = 10;
+
+/// Borrowing from the budget panel, repaid with interest as a budget
+/// expense every tick.
+pub struct LoansPlugin;
+
+impl Plugin for LoansPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Loans>()
+            .add_message::<TakeLoan>()
+            .add_message::<Notification>()
+            .add_systems(
+                Update,
+                (
+                    take_loans.before(simulation_step),
+                    repay_loans
+                        .after(simulation_step)
+                        .before(track_bankruptcy),
+                )
+                    .in_set(GameSet::Simulation),
+            )
+            .add_systems(ResetCity, clear_loans);
+    }
+}
+
+/// A request to borrow `principal`, from the budget panel or a replay.
+#[derive(Message, Clone, Copy)]
+pub struct TakeLoan {
+    pub principal: i64,
+}
+
+/// One outstanding loan.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Loan {
+    pub principal: i64,
+    /// Principal and interest still to repay.
+    pub balance: i64,
+    pub ticks_left: u32,
+}
+
+impl Loan {
+    fn new(principal: i64) -> Self {
+        Self {
+            principal,
+            balance: principal * (100 + LOAN_INTEREST_PERCENT) / 100,
+            ticks_left: LOAN_TERM_TICKS,
+        }
+    }
+
+    /// This tick's repayment: an even share of the balance over the ticks
+    /// left, rounded up so the last one clears it.
+    pub fn payment(&self) -> i64 {
+        let ticks = self.ticks_left.max(1) as i64;
+        (self.balance + ticks - 1) / ticks
+    }
+}
+
+/// Every loan the city has yet to pay off, oldest first.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Loans {
+    pub active: Vec<Loan>,
+}
+
+impl Loans {
+    /// Total repayment due this tick, charged in the budget.
+    pub fn payment_due(&self) -> i64 {
+        self.active.iter().map(Loan::payment).sum()
+    }
+}
+
+/// Pay out requested loans, while the city is under `MAX_LOANS`.
+fn take_loans(
+    mut requests: MessageReader<TakeLoan>,
+    mut loans: ResMut<Loans>,
+    mut stats: ResMut<CityStats>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for request in requests.read() {
+        if loans.active.len() >= MAX_LOANS {
+            notifications.write(Notification(
+                Phrase::new("notify.loan_refused").with("max", MAX_LOANS),
+            ));
+            continue;
+        }
+        let loan = Loan::new(request.principal);
+        loans.active.push(loan);
+        stats.money += loan.principal;
+        notifications.write(Notification(
+            Phrase::new("notify.borrowed")
+                .number("principal", loan.principal)
+                .number("balance", loan.balance)
+                .with("ticks", loan.ticks_left),
+        ));
+    }
+}
+
+/// Book the repayments `simulation_step` charged this tick. A loan that
+/// comes to term while the treasury is still in the red has defaulted,
+/// and brings bankruptcy closer.
+fn repay_loans(
+    timer: Res<SimTimer>,
+    stats: Res<CityStats>,
+    mut loans: ResMut<Loans>,
+    mut bankruptcy: ResMut<Bankruptcy>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !timer.0.just_finished() || loans.active.is_empty() {
+        return;
+    }
+
+    let mut defaulted = false;
+    loans.active.retain_mut(|loan| {
+        loan.balance -= loan.payment();
+        loan.ticks_left = loan.ticks_left.saturating_sub(1);
+        if loan.ticks_left > 0 {
+            return true;
+        }
+        if stats.money < 0 {
+            defaulted = true;
+            notifications.write(Notification(
+                Phrase::new("notify.loan_defaulted").number("principal", loan.principal),
+            ));
+        } else {
+            notifications.write(Notification(
+                Phrase::new("notify.loan_paid_off").number("principal", loan.principal),
+            ));
+        }
+        false
+    });
+    if defaulted {
+        bankruptcy.hasten();
+    }
+}
+
+fn clear_loans(mut loans: ResMut<Loans>) {
+    loans.active.clear();
+}
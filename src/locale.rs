@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+
+/// The language every string is written in first, and falls back to.
+pub const DEFAULT_LANGUAGE: &str = "en";
+/// Where translations are read from, relative to the working directory,
+/// so one can be edited or added without rebuilding.
+#[cfg(not(target_arch = "wasm32"))]
+const LOCALES_DIR: &str = "assets/locales";
+/// The translations shipped with the game, built in so they are there on
+/// the web and when the game runs away from its assets.
+const BUNDLED: [(&str, &str); 2] = [
+    ("en", include_str!("../assets/locales/en.ron")),
+    ("de", include_str!("../assets/locales/de.ron")),
+];
+/// Key of the digit group separator in every locale file.
+const THOUSANDS_SEPARATOR: &str = "number.thousands_separator";
+
+/// Loads the player's language for everything that puts text on screen.
+pub struct LocalePlugin {
+    pub language: String,
+}
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Locale::load(&self.language));
+    }
+}
+
+/// Every string shown to the player, in the language picked with `--lang`
+/// or `language` under `[ui]`, with English for anything it lacks.
+#[derive(Resource)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+    english: HashMap<String, String>,
+    /// Keys already warned about, so a missing one is logged once rather
+    /// than every frame it is shown.
+    missing: Mutex<HashSet<String>>,
+}
+
+impl Locale {
+    /// Load `language`, or English alone if it can't be read. English always
+    /// comes from the built-in copy, which has every key.
+    pub fn load(language: &str) -> Self {
+        let english = parse(DEFAULT_LANGUAGE, bundled(DEFAULT_LANGUAGE).unwrap_or_default())
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                HashMap::new()
+            });
+        let strings = if language == DEFAULT_LANGUAGE {
+            english.clone()
+        } else {
+            match read(language).and_then(|text| parse(language, &text)) {
+                Ok(strings) => strings,
+                Err(err) => {
+                    warn!("{err}; using English");
+                    english.clone()
+                }
+            }
+        };
+        Self {
+            strings,
+            english,
+            missing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The string for `key`, with any placeholders left as they are.
+    pub fn text(&self, key: &str) -> String {
+        self.template(key).to_string()
+    }
+
+    /// Fill in `phrase`'s template with its arguments.
+    pub fn render(&self, phrase: &Phrase) -> String {
+        let mut rendered = self.template(phrase.key).to_string();
+        for (name, arg) in &phrase.args {
+            let value = match arg {
+                Arg::Text(text) => text.clone(),
+                Arg::Number(n) => self.number(*n),
+                Arg::Signed(n) if *n >= 0 => format!("+{}", self.number(*n)),
+                Arg::Signed(n) => self.number(*n),
+                Arg::Key(key) => self.template(key).to_string(),
+            };
+            rendered = rendered.replace(&format!("{{{name}}}"), &value);
+        }
+        rendered
+    }
+
+    /// `n` with its digits grouped in threes, as this language writes them.
+    pub fn number(&self, n: i64) -> String {
+        let digits = n.unsigned_abs().to_string();
+        let separator = self.template(THOUSANDS_SEPARATOR);
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(separator);
+            }
+            grouped.push(digit);
+        }
+        if n < 0 {
+            format!("-{grouped}")
+        } else {
+            grouped
+        }
+    }
+
+    /// The English string for `key`, for messages that leave the game.
+    // Only the native remote API sends any.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn english<'a>(&'a self, key: &'a str) -> &'a str {
+        self.english.get(key).map_or(key, String::as_str)
+    }
+
+    /// The template for `key`, falling back to English and then to the key
+    /// itself.
+    fn template<'a>(&'a self, key: &'a str) -> &'a str {
+        if let Some(template) = self.strings.get(key) {
+            return template;
+        }
+        if self.missing.lock().is_ok_and(|mut missing| missing.insert(key.to_string())) {
+            warn!("No translation for \"{key}\"");
+        }
+        self.english.get(key).map_or(key, String::as_str)
+    }
+}
+
+/// A string to show the player, by key, with the values of its named
+/// placeholders. Messages carry these rather than finished text, so they
+/// are put into words only where they are shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phrase {
+    key: &'static str,
+    args: Vec<(&'static str, Arg)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Arg {
+    Text(String),
+    /// Grouped into thousands.
+    Number(i64),
+    /// Grouped into thousands, with a sign either way.
+    Signed(i64),
+    /// Another string, looked up in the same language.
+    Key(&'static str),
+}
+
+impl Phrase {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    /// Fill `{name}` with `value` as it prints.
+    pub fn with(mut self, name: &'static str, value: impl Display) -> Self {
+        self.args.push((name, Arg::Text(value.to_string())));
+        self
+    }
+
+    /// Fill `{name}` with an amount of money or people.
+    pub fn number(mut self, name: &'static str, value: impl Into<i64>) -> Self {
+        self.args.push((name, Arg::Number(value.into())));
+        self
+    }
+
+    /// Fill `{name}` with a change in money, signed either way.
+    pub fn signed(mut self, name: &'static str, value: i64) -> Self {
+        self.args.push((name, Arg::Signed(value)));
+        self
+    }
+
+    /// Fill `{name}` with the string for `key`.
+    pub fn key(mut self, name: &'static str, key: &'static str) -> Self {
+        self.args.push((name, Arg::Key(key)));
+        self
+    }
+}
+
+/// Codes of the languages shipped with the game.
+pub fn languages() -> Vec<String> {
+    BUNDLED.iter().map(|(code, _)| code.to_string()).collect()
+}
+
+fn bundled(language: &str) -> Option<&'static str> {
+    BUNDLED
+        .iter()
+        .find(|(bundled, _)| *bundled == language)
+        .map(|(_, text)| *text)
+}
+
+/// `language`'s locale file: the one on disk if there is one, otherwise
+/// the copy built in.
+#[cfg(not(target_arch = "wasm32"))]
+fn read(language: &str) -> Result<String, String> {
+    match fs::read_to_string(format!("{LOCALES_DIR}/{language}.ron")) {
+        Ok(text) => Ok(text),
+        Err(err) => bundled(language)
+            .map(str::to_string)
+            .ok_or_else(|| format!("could not read the \"{language}\" locale: {err}")),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(language: &str) -> Result<String, String> {
+    bundled(language)
+        .map(str::to_string)
+        .ok_or_else(|| format!("there is no \"{language}\" locale"))
+}
+
+fn parse(language: &str, text: &str) -> Result<HashMap<String, String>, String> {
+    ron::from_str(text).map_err(|e| format!("the \"{language}\" locale is broken: {e}"))
+}
+
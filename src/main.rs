@@ -1,353 +1,311 @@
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 
-const MAP_WIDTH: i32 = 32;
-const MAP_HEIGHT: i32 = 32;
-const TILE_SIZE: f32 = 32.0;
-
-// Use the tilemap.png that's already in the repo
-const SPRITE_ASSET_PATH: &str = "kenney_roguelike-modern-city/Tilemap/tilemap.png";
+mod adjacency;
+mod agents;
+mod assets;
+mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+mod bench;
+mod calendar;
+mod camera;
+mod cli;
+mod clipboard;
+mod commute;
+mod commute_debug;
+// The web build has no config file, so only the defaults are used there.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+mod config;
+mod console;
+mod crime;
+mod day_night;
+mod development;
+mod diagnostics;
+mod districts;
+mod economy;
+mod elevation;
+#[cfg(not(target_arch = "wasm32"))]
+mod download_sprites;
+mod fire;
+mod gamepad;
+mod game_over;
+mod garbage;
+mod grid;
+mod grid_overlay;
+mod hints;
+mod history;
+mod input;
+mod inspector;
+mod layers;
+// Images can't be read or written on the web, so the color mapping goes
+// unused there.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+mod layout_image;
+mod loans;
+mod locale;
+mod map;
+mod mapgen;
+mod menu;
+mod milestones;
+mod oneway;
+mod overlay;
+mod pathfinding;
+mod ports;
+mod power;
+mod problems;
+#[cfg(not(target_arch = "wasm32"))]
+mod remote;
+// Recordings can't be read or written on the web, so parsing goes unused there.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+mod replay;
+// Saves can't be read or written on the web, so parsing goes unused there.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+mod save;
+mod scenario;
+mod screenshot;
+mod services;
+mod settings;
+mod sim;
+mod simulation;
+mod state;
+mod statistics;
+mod tile_render;
+mod tint;
+mod touch;
+mod tourism;
+mod trade;
+mod traffic;
+mod ui;
+mod water;
+mod weather;
+mod zone_defs;
+
+use adjacency::AdjacencyPlugin;
+use agents::AgentsPlugin;
+use assets::CityAssetsPlugin;
+use audio::SoundPlugin;
+use calendar::CalendarPlugin;
+use camera::CameraPlugin;
+use cli::CliArgs;
+use clipboard::ClipboardPlugin;
+use commute::CommutePlugin;
+use commute_debug::CommuteDebugPlugin;
+use config::Config;
+use console::ConsolePlugin;
+use crime::CrimePlugin;
+use day_night::DayNightPlugin;
+use development::DevelopmentPlugin;
+use diagnostics::DiagnosticsPlugin;
+use districts::DistrictsPlugin;
+use economy::EconomyPlugin;
+use elevation::ElevationPlugin;
+use fire::FirePlugin;
+use game_over::GameOverPlugin;
+use garbage::GarbagePlugin;
+use gamepad::GamepadPlugin;
+use grid_overlay::GridOverlayPlugin;
+use hints::HintsPlugin;
+use inspector::InspectorPlugin;
+use input::InputPlugin;
+use layers::LayersPlugin;
+use layout_image::LayoutImagePlugin;
+use loans::LoansPlugin;
+use locale::LocalePlugin;
+use map::MapPlugin;
+use menu::MenuPlugin;
+use milestones::MilestonesPlugin;
+use oneway::OneWayPlugin;
+use overlay::OverlayPlugin;
+use ports::PortsPlugin;
+use power::PowerPlugin;
+use problems::ProblemsPlugin;
+use replay::ReplayPlugin;
+use scenario::ScenarioPlugin;
+use screenshot::ScreenshotPlugin;
+use services::ServicesPlugin;
+use settings::{Settings, SettingsPlugin};
+use simulation::SimulationPlugin;
+use state::StatePlugin;
+use statistics::StatisticsPlugin;
+use tile_render::TileRenderPlugin;
+use tint::TintPlugin;
+use touch::TouchPlugin;
+use tourism::TourismPlugin;
+use trade::TradePlugin;
+use traffic::TrafficPlugin;
+use ui::UiPlugin;
+use water::WaterPlugin;
+use weather::WeatherPlugin;
+use zone_defs::ZoneDefsPlugin;
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::srgb(0.05, 0.05, 0.08)))
-        .init_resource::<CityStats>()
-        .insert_resource(SimTimer(Timer::from_seconds(
-            0.5,
-            TimerMode::Repeating,
-        )))
-        .add_plugins(
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Bevy City Sim".to_string(),
-                    resolution: (1280, 720).into(),
-                    ..default()
-                }),
-                ..default()
-            }),
-        )
-        .add_systems(Startup, (load_sprites, setup_camera, spawn_map, setup_ui).chain())
-        .add_systems(
-            Update,
-            (
-                handle_mouse_input,
-                simulation_step,
-                update_stats_ui,
-            ),
-        )
-        .run();
-}
-
-/// Resource holding sprite atlas info
-#[derive(Resource)]
-struct CitySprites {
-    texture: Handle<Image>,
-    layout: Handle<TextureAtlasLayout>,
-}
-
-impl Zone {
-    /// Get the sprite index for this zone type from the Kenney tileset (37 columns)
-    fn sprite_index(self) -> usize {
-        use Zone::*;
-        match self {
-            Empty => 23,       // grass tile
-            Road => 0,         // road tile
-            Residential => 65, // small house
-            Commercial => 143, // shop/store
-            Industrial => 220, // factory/warehouse
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
         }
-    }
-}
-
-fn load_sprites(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let texture = asset_server.load(SPRITE_ASSET_PATH);
-    
-    // Kenney's tilemap has 1px spacing between tiles
-    let layout = TextureAtlasLayout::from_grid(
-        UVec2::new(16, 16),      // tile size in pixels
-        37,                      // columns
-        28,                      // rows
-        Some(UVec2::new(1, 1)),  // 1px padding between tiles (CRITICAL FIX)
-        None,                    // no offset
-    );
-    
-    let layout_handle = texture_atlases.add(layout);
-    
-    commands.insert_resource(CitySprites {
-        texture,
-        layout: layout_handle,
-    });
-}
-
-/// Grid coordinate for each tile.
-#[derive(Component)]
-struct TileCoord {
-    coord: IVec2,
-}
-
-/// Zone type of a tile (what the player builds).
-#[derive(Component, Clone, Copy, PartialEq, Eq)]
-enum Zone {
-    Empty,
-    Road,
-    Residential,
-    Commercial,
-    Industrial,
-}
-
-/// Per-tile simulation data (simple for now).
-#[derive(Component)]
-struct TileData {
-    population: u32,
-    jobs: u32,
-}
-
-impl Zone {
-    fn next(self) -> Self {
-        use Zone::*;
-        match self {
-            Empty => Road,
-            Road => Residential,
-            Residential => Commercial,
-            Commercial => Industrial,
-            Industrial => Empty,
-        }
-    }
-}
-
-/// Aggregate city statistics.
-#[derive(Resource, Default)]
-struct CityStats {
-    population: u32,
-    jobs: u32,
-    money: i64,
-}
-
-/// Timer that ticks the simulation.
-#[derive(Resource)]
-struct SimTimer(Timer);
-
-/// Marker on the UI text that shows stats.
-#[derive(Component)]
-struct StatsText;
-
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
-}
-
-fn spawn_map(mut commands: Commands, sprites: Res<CitySprites>) {
-    // Center map around (0, 0)
-    let origin_x =
-        -(MAP_WIDTH as f32 * TILE_SIZE) / 2.0 + TILE_SIZE / 2.0;
-    let origin_y =
-        -(MAP_HEIGHT as f32 * TILE_SIZE) / 2.0 + TILE_SIZE / 2.0;
-
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
-            let world_x = origin_x + x as f32 * TILE_SIZE;
-            let world_y = origin_y + y as f32 * TILE_SIZE;
-
-            let zone = Zone::Empty;
-
-            commands.spawn((
-                Sprite {
-                    image: sprites.texture.clone(),
-                    custom_size: Some(Vec2::splat(TILE_SIZE)),
-                    texture_atlas: Some(TextureAtlas {
-                        layout: sprites.layout.clone(),
-                        index: zone.sprite_index(),
-                    }),
-                    ..default()
-                },
-                Transform::from_xyz(
-                    world_x,
-                    world_y,
-                    0.0,
-                ),
-                TileCoord {
-                    coord: IVec2::new(x, y),
-                },
-                zone,
-                TileData {
-                    population: 0,
-                    jobs: 0,
-                },
-            ));
-        }
-    }
-}
-
-fn setup_ui(mut commands: Commands) {
-    commands.spawn((
-        Text::new("Pop: 0  Jobs: 0  Money: 0"),
-        TextFont {
-            font_size: 24.0,
-            ..default()
-        },
-        TextColor(Color::WHITE),
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Px(10.0),
-            left: Val::Px(10.0),
-            ..default()
-        },
-        StatsText,
-    ));
-}
-
-/// Handle left mouse clicks: change the zone of the clicked tile.
-fn handle_mouse_input(
-    buttons: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window, With<PrimaryWindow>>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    mut tiles: Query<(
-        &TileCoord,
-        &mut Zone,
-        &mut Sprite,
-    )>,
-) {
-    if !buttons.just_pressed(MouseButton::Left) {
-        return;
-    }
-
-    let window = if let Ok(w) = windows.single() {
-        w
-    } else {
-        return;
-    };
-
-    let Some(cursor_pos) = window.cursor_position() else {
-        return;
-    };
-
-    let (camera, cam_transform) = if let Ok(v) = camera_q.single() {
-        v
-    } else {
-        return;
     };
-
-    let Ok(world_pos) =
-        camera.viewport_to_world_2d(cam_transform, cursor_pos)
-    else {
-        return;
-    };
-
-    // Convert world position back to tile coordinates.
-    let origin_x =
-        -(MAP_WIDTH as f32 * TILE_SIZE) / 2.0 + TILE_SIZE / 2.0;
-    let origin_y =
-        -(MAP_HEIGHT as f32 * TILE_SIZE) / 2.0 + TILE_SIZE / 2.0;
-
-    let tx = ((world_pos.x - origin_x) / TILE_SIZE).floor() as i32;
-    let ty = ((world_pos.y - origin_y) / TILE_SIZE).floor() as i32;
-
-    if tx < 0 || ty < 0 || tx >= MAP_WIDTH || ty >= MAP_HEIGHT {
+    if args.help {
+        print!("{}", cli::USAGE);
         return;
     }
-
-    // Find the tile at this coordinate.
-    for (coord, mut zone, mut sprite) in tiles.iter_mut() {
-        if coord.coord.x == tx && coord.coord.y == ty {
-            *zone = zone.next();
-            // Update the sprite texture atlas index
-            if let Some(ref mut atlas) = sprite.texture_atlas {
-                atlas.index = zone.sprite_index();
-            }
-            break;
-        }
-    }
-}
-
-/// Simple, very toy simulation step.
-/// Every tick:
-/// - Residential tiles gain population if next to a road
-/// - Commercial/Industrial tiles gain jobs
-/// - Money increases based on jobs and population
-fn simulation_step(
-    time: Res<Time>,
-    mut timer: ResMut<SimTimer>,
-    mut tiles: Query<(
-        &TileCoord,
-        &Zone,
-        &mut TileData,
-    )>,
-    mut stats: ResMut<CityStats>,
-) {
-    if !timer.0.tick(time.delta()).just_finished() {
+    if args.bench_sim {
+        #[cfg(not(target_arch = "wasm32"))]
+        bench::run();
         return;
     }
 
-    // Reset stats and re-compute from tiles.
-    stats.population = 0;
-    stats.jobs = 0;
+    let mut config = Config::load_or_create();
+    let mut settings = Settings::load(&config);
+    args.apply(&mut config, &mut settings);
 
-    // Build a quick lookup for zone by coord.
-    use std::collections::HashMap;
-    let mut zone_map: HashMap<IVec2, Zone> = HashMap::new();
-    for (coord, zone, _) in tiles.iter() {
-        zone_map.insert(coord.coord, *zone);
+    // A replay rebuilds the recorded city, so it brings its own map and seed.
+    let playback = args.replay.as_ref().map(|path| match replay::read_replay(path) {
+        Ok(replay) => (path.clone(), replay),
+        Err(err) => {
+            eprintln!("error: could not read replay {path}: {err}");
+            std::process::exit(2);
+        }
+    });
+    if let Some((_, replay)) = &playback {
+        config.seed = Some(replay.seed);
+        config.map.width = replay.start.width;
+        config.map.height = replay.start.height;
     }
 
-    for (coord, zone, mut data) in tiles.iter_mut() {
-        match zone {
-            Zone::Residential => {
-                let neighbors = [
-                    IVec2::new(1, 0),
-                    IVec2::new(-1, 0),
-                    IVec2::new(0, 1),
-                    IVec2::new(0, -1),
-                ];
-                let mut adjacent_road = false;
-                for n in neighbors {
-                    if let Some(n_zone) =
-                        zone_map.get(&(coord.coord + n))
-                    {
-                        if *n_zone == Zone::Road {
-                            adjacent_road = true;
-                            break;
-                        }
-                    }
-                }
-
-                if adjacent_road {
-                    data.population =
-                        (data.population + 1).min(100);
-                }
-            }
-            Zone::Commercial | Zone::Industrial => {
-                data.jobs = (data.jobs + 1).min(100);
-            }
-            Zone::Road | Zone::Empty => {
-                data.population = 0;
-                data.jobs = 0;
+    // An imported layout sets the map size from the image.
+    let import = args.import_map.as_ref().map(|path| {
+        match layout_image::read_layout(path, config.starting_money, config.neighborhood) {
+            Ok(city) => city,
+            Err(err) => {
+                eprintln!("error: could not import {path}: {err}");
+                std::process::exit(2);
             }
         }
-
-        stats.population += data.population;
-        stats.jobs += data.jobs;
+    });
+    if let Some(city) = &import {
+        config.map.width = city.layout.width;
+        config.map.height = city.layout.height;
     }
 
-    // Money: simple formula for now.
-    stats.money += (stats.jobs as i64 / 5)
-        - (stats.population as i64 / 10);
-}
+    let seed = config.seed.unwrap_or_else(default_seed);
+    println!("City seed: {seed}");
 
-fn update_stats_ui(
-    stats: Res<CityStats>,
-    mut query: Query<&mut Text, With<StatsText>>,
-) {
-    if !stats.is_changed() {
-        return;
+    let mut app = App::new();
+    app.add_plugins(
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    mode: bevy::window::WindowMode::Windowed,
+                    canvas: Some("#bevy".to_string()),
+                    fit_canvas_to_parent: true,
+                    title: "Bevy City Sim".to_string(),
+                    resolution: (settings.window_width, settings.window_height)
+                        .into(),
+                    ..default()
+                }),
+                ..default()
+            }),
+        )
+        .add_plugins((
+            StatePlugin,
+            CityAssetsPlugin,
+            MapPlugin { map: config.map },
+            TileRenderPlugin,
+            SimulationPlugin {
+                tick_seconds: config.tick_seconds,
+                starting_money: config.starting_money,
+                road_upkeep: config.road_upkeep,
+                seed,
+                neighborhood: config.neighborhood,
+                development: config.development,
+            },
+            ServicesPlugin,
+            TrafficPlugin,
+            FirePlugin,
+            AgentsPlugin,
+            DayNightPlugin,
+            TintPlugin,
+            InputPlugin,
+            UiPlugin,
+            ScreenshotPlugin,
+            GameOverPlugin,
+        ))
+        .add_plugins((
+            MenuPlugin {
+                map: config.map,
+                random_map: config.random_map,
+                skip_menu: args.remote && playback.is_none() && import.is_none(),
+                import,
+            },
+            ClipboardPlugin,
+            CommutePlugin,
+            GridOverlayPlugin,
+            OverlayPlugin,
+            LoansPlugin,
+            EconomyPlugin,
+            LayoutImagePlugin,
+            CalendarPlugin,
+            LocalePlugin {
+                language: settings.language.clone(),
+            },
+            HintsPlugin,
+            SoundPlugin,
+            CameraPlugin {
+                smoothing: config.camera_smoothing,
+            },
+            ReplayPlugin {
+                record: cfg!(not(target_arch = "wasm32")) && playback.is_none(),
+                playback,
+            },
+        ))
+        .add_plugins((
+            GamepadPlugin,
+            TouchPlugin,
+            SettingsPlugin { settings },
+            MilestonesPlugin,
+            ProblemsPlugin,
+            InspectorPlugin,
+            ElevationPlugin,
+            AdjacencyPlugin,
+            CommuteDebugPlugin,
+            PowerPlugin,
+            ZoneDefsPlugin,
+            CrimePlugin,
+            WaterPlugin,
+            DistrictsPlugin,
+            GarbagePlugin,
+        ))
+        .add_plugins((
+            DevelopmentPlugin,
+            PortsPlugin,
+            ConsolePlugin { dev: args.dev },
+            WeatherPlugin,
+            TourismPlugin,
+            ScenarioPlugin,
+            TradePlugin,
+            LayersPlugin,
+            StatisticsPlugin,
+            OneWayPlugin,
+            DiagnosticsPlugin,
+        ));
+    if args.remote {
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(remote::RemotePlugin);
     }
+    app.run();
+}
 
-    if let Ok(mut text) = query.single_mut() {
-        **text = format!(
-            "Pop: {}  Jobs: {}  Money: {}",
-            stats.population, stats.jobs, stats.money
-        );
-    }
+/// Seed for runs that don't specify one.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// `SystemTime` is unavailable on wasm32, so web builds use a fixed seed.
+#[cfg(target_arch = "wasm32")]
+fn default_seed() -> u64 {
+    0
 }
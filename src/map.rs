@@ -0,0 +1,655 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::CitySprites;
+use crate::config::MapConfig;
+use crate::grid::tile_to_world;
+use crate::layers::{MapLayer, MapLayers};
+use crate::locale::{Locale, Phrase};
+use crate::oneway::RoadDirection;
+use crate::state::{AppState, GameSet, GameState, ResetCity, SpawnSet};
+use crate::tile_render::{tile_sprite, TileRenderer, TileVisual};
+use crate::tint::apply_tile_tints;
+use crate::tint::TileTint;
+use crate::zone_defs::ZoneDefs;
+
+/// Side length, in tiles, of the chunks the map is spawned in.
+pub const CHUNK_SIZE: i32 = 16;
+/// Tiles spawned per frame while building the map, to avoid long frames.
+const TILES_PER_FRAME: usize = 4096;
+/// Atlas cell of a building site (a striped barrier), and its color in the
+/// placeholder atlas.
+pub const CONSTRUCTION_SPRITE_INDEX: usize = 246;
+pub const CONSTRUCTION_COLOR: Color = Color::srgb(0.85, 0.65, 0.2);
+
+/// Spawns the camera and the tile grid, and owns the `TileMap` lookup.
+pub struct MapPlugin {
+    pub map: MapConfig,
+}
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.map)
+            .add_systems(Startup, setup_camera)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                start_map_spawn.in_set(SpawnSet::Map),
+            )
+            .add_systems(
+                Update,
+                spawn_map_chunks.run_if(in_state(GameState::Building)),
+            )
+            .add_systems(
+                Update,
+                (sync_terrain_visuals, sync_building_sprites)
+                    .before(apply_tile_tints)
+                    .in_set(GameSet::Ui),
+            )
+            .add_systems(ResetCity, clear_tiles)
+            .add_systems(OnExit(AppState::Playing), forget_tile_map);
+    }
+}
+
+/// Grid coordinate for each tile.
+#[derive(Component)]
+pub struct TileCoord {
+    pub coord: IVec2,
+}
+
+/// Zone type of a tile (what the player builds).
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Zone {
+    Empty,
+    /// Paved road, the middle tier; see `Zone::road`.
+    Road,
+    Residential,
+    Commercial,
+    Industrial,
+    FireStation,
+    PoliceStation,
+    School,
+    /// A 2x2 building, see `Zone::footprint`.
+    PowerPlant,
+    /// The cheap road tier.
+    DirtRoad,
+    /// The high-capacity road tier.
+    Avenue,
+    /// Waters the tiles around it and feeds pipes, anywhere.
+    WaterTower,
+    /// Waters the tiles around it and feeds pipes; only next to water.
+    PumpingStation,
+    /// Buries garbage until it is full.
+    Landfill,
+    /// Takes garbage away for good at a steady rate.
+    RecyclingCenter,
+    /// A 2x2 port next to water that raises industrial demand; one per
+    /// city.
+    Seaport,
+    /// A 3x2 port on flat land that raises commercial demand; one per city.
+    Airport,
+    /// Terrain that can never be built on.
+    Water,
+    /// Terrain that is cleared by building over it.
+    Forest,
+    /// A road over water: a road to traffic and commuters, but water to
+    /// anything else built there, and water again once bulldozed.
+    Bridge,
+}
+
+/// What sets one tier of road apart from the others.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RoadTier {
+    /// Commuters a tile carries before it counts as congested.
+    pub capacity: u32,
+    /// Multiple of the configured per-tile road upkeep.
+    pub maintenance: f32,
+    /// Commuting steps a tile counts as, so slow roads shrink how far
+    /// residents will travel to work.
+    pub steps: u32,
+}
+
+/// Per-tile simulation data (simple for now).
+#[derive(Component, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TileData {
+    pub population: u32,
+    pub jobs: u32,
+    /// Crime level of a home or shop, 0-100.
+    pub crime: u32,
+    /// The anchor tile of the multi-tile building covering this tile, if
+    /// any. Every tile of the building, the anchor included, points at it.
+    #[serde(default)]
+    pub anchor: Option<IVec2>,
+    /// Ticks until a newly zoned building is finished, 0 once it is. It
+    /// holds no residents or jobs until then.
+    #[serde(default)]
+    pub construction: u32,
+    /// Zoned for a building that hasn't started yet, under gradual
+    /// development. The tile shows grass until demand builds on it.
+    #[serde(default)]
+    pub zoned: bool,
+    /// What the land under a home or shop is worth, 0-100, as of the last
+    /// tick.
+    #[serde(default)]
+    pub land_value: u32,
+    /// Which sprite of its zone's pools a building is drawn with, picked
+    /// when it is zoned.
+    #[serde(default)]
+    pub variant: u32,
+}
+
+/// A building's variant, hashed from where it stands and the city seed so
+/// that neighbors rarely match.
+pub fn variant_for(coord: IVec2, seed: u64) -> u32 {
+    // SplitMix64's finalizer, over the seed with the coordinate mixed in.
+    let mut hash = seed ^ (u64::from(coord.x as u32) << 32 | u64::from(coord.y as u32));
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (hash ^ (hash >> 31)) as u32
+}
+
+/// Tile entities indexed by grid coordinate, inserted by `spawn_map_chunks`
+/// once every tile exists.
+#[derive(Resource)]
+pub struct TileMap {
+    width: i32,
+    height: i32,
+    tiles: Vec<Entity>,
+}
+
+impl TileMap {
+    /// Lookup of the tiles of a `width` by `height` map, laid out row by
+    /// row.
+    pub fn new(width: i32, height: i32, tiles: Vec<Entity>) -> Self {
+        Self {
+            width,
+            height,
+            tiles,
+        }
+    }
+
+    /// Entity of the tile at `coord`, or `None` off the map.
+    pub fn get(&self, coord: IVec2) -> Option<Entity> {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return None;
+        }
+        self.tiles.get((coord.y * self.width + coord.x) as usize).copied()
+    }
+}
+
+/// The sprite drawing a tile's building in the buildings layer, on tiles
+/// that have one. It despawns with the tile, so clearing or unloading the
+/// map leaves none behind.
+#[derive(Component)]
+#[relationship_target(relationship = BuildingOf, linked_spawn)]
+pub struct BuildingSprite(Entity);
+
+impl BuildingSprite {
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// The tile a building sprite draws the building of.
+#[derive(Component)]
+#[relationship(relationship_target = BuildingSprite)]
+pub struct BuildingOf(pub Entity);
+
+/// One `CHUNK_SIZE` square of the map, drawn (or parenting the tiles
+/// drawn) as a unit so whole chunks can be hidden or culled at once.
+#[derive(Component)]
+pub struct MapChunk;
+
+/// Map spawning progress while in `GameState::Building`.
+#[derive(Resource)]
+struct MapSpawner {
+    next_chunk: usize,
+    tiles: Vec<Entity>,
+}
+
+/// Progress text shown while the map is being built.
+#[derive(Component)]
+struct MapSpawnProgress;
+
+/// The camera the player looks through, as opposed to any offscreen
+/// capture cameras.
+#[derive(Component)]
+pub struct MainCamera;
+
+impl Zone {
+    /// Every zone, in tool-cycling order followed by the terrain types and
+    /// bridges, which roads become over water.
+    pub const ALL: [Zone; 20] = [
+        Zone::Empty,
+        Zone::Road,
+        Zone::Residential,
+        Zone::Commercial,
+        Zone::Industrial,
+        Zone::FireStation,
+        Zone::PoliceStation,
+        Zone::School,
+        Zone::PowerPlant,
+        Zone::DirtRoad,
+        Zone::Avenue,
+        Zone::WaterTower,
+        Zone::PumpingStation,
+        Zone::Landfill,
+        Zone::RecyclingCenter,
+        Zone::Seaport,
+        Zone::Airport,
+        Zone::Water,
+        Zone::Forest,
+        Zone::Bridge,
+    ];
+
+    /// Locale key of the name shown to the player.
+    pub fn label(self) -> &'static str {
+        use Zone::*;
+        match self {
+            Empty => "zone.empty",
+            Road => "zone.road",
+            Residential => "zone.residential",
+            Commercial => "zone.commercial",
+            Industrial => "zone.industrial",
+            FireStation => "zone.fire_station",
+            PoliceStation => "zone.police_station",
+            School => "zone.school",
+            PowerPlant => "zone.power_plant",
+            DirtRoad => "zone.dirt_road",
+            Avenue => "zone.avenue",
+            WaterTower => "zone.water_tower",
+            PumpingStation => "zone.pumping_station",
+            Landfill => "zone.landfill",
+            RecyclingCenter => "zone.recycling_center",
+            Seaport => "zone.seaport",
+            Airport => "zone.airport",
+            Water => "zone.water",
+            Forest => "zone.forest",
+            Bridge => "zone.bridge",
+        }
+    }
+
+    /// Locale key of the name of a tile with this zone on it, which for an
+    /// empty tile isn't the bulldozer's.
+    pub fn tile_label(self) -> &'static str {
+        match self {
+            Zone::Empty => "zone.empty_land",
+            zone => zone.label(),
+        }
+    }
+
+    /// Whether the player can zone this tile at all. Water only takes
+    /// bridges, and bridges only roads.
+    pub fn buildable(self) -> bool {
+        !self.is_water()
+    }
+
+    /// Whether the tile's terrain is water, bridged or not.
+    pub fn is_water(self) -> bool {
+        matches!(self, Zone::Water | Zone::Bridge)
+    }
+
+    /// The zone a click turns this one into. Forest is cleared to a road
+    /// like an empty lot; water and bridges stay as they are.
+    pub fn next(self) -> Self {
+        use Zone::*;
+        match self {
+            Empty | Forest => Road,
+            Road => Residential,
+            Residential => Commercial,
+            Commercial => Industrial,
+            Industrial => FireStation,
+            FireStation => PoliceStation,
+            PoliceStation => School,
+            School => PowerPlant,
+            PowerPlant => DirtRoad,
+            DirtRoad => Avenue,
+            Avenue => WaterTower,
+            WaterTower => PumpingStation,
+            PumpingStation => Landfill,
+            Landfill => RecyclingCenter,
+            RecyclingCenter => Seaport,
+            Seaport => Airport,
+            Airport => Empty,
+            Water => Water,
+            Bridge => Bridge,
+        }
+    }
+
+    /// How this zone performs as a road, or `None` if it isn't one. Road
+    /// maintenance is charged per tile, scaled by the tier.
+    pub fn road(self) -> Option<RoadTier> {
+        match self {
+            Zone::DirtRoad => Some(RoadTier {
+                capacity: 100,
+                maintenance: 0.5,
+                steps: 2,
+            }),
+            Zone::Road | Zone::Bridge => Some(RoadTier {
+                capacity: 200,
+                maintenance: 1.0,
+                steps: 1,
+            }),
+            Zone::Avenue => Some(RoadTier {
+                capacity: 500,
+                maintenance: 2.0,
+                steps: 1,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn is_road(self) -> bool {
+        self.road().is_some()
+    }
+
+    /// Size in tiles of one building in this zone.
+    pub fn footprint(self) -> IVec2 {
+        match self {
+            Zone::PowerPlant | Zone::Seaport => IVec2::splat(2),
+            Zone::Airport => IVec2::new(3, 2),
+            _ => IVec2::ONE,
+        }
+    }
+
+    /// Whether a building in this zone covers more than one tile. Such
+    /// buildings are placed whole, one per click, and grow up and to the
+    /// right of their anchor tile.
+    pub fn is_multi_tile(self) -> bool {
+        self.footprint() != IVec2::ONE
+    }
+
+    /// Every tile covered by a building in this zone anchored at `anchor`.
+    pub fn covered_tiles(self, anchor: IVec2) -> impl Iterator<Item = IVec2> {
+        let size = self.footprint();
+        (0..size.y).flat_map(move |y| (0..size.x).map(move |x| anchor + IVec2::new(x, y)))
+    }
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn((Camera2d, IsDefaultUiCamera, MainCamera));
+}
+
+/// Start spawning the map; `spawn_map_chunks` does the actual work over
+/// the following frames.
+fn start_map_spawn(mut commands: Commands, map: Res<MapConfig>, locale: Res<Locale>) {
+    commands.insert_resource(MapSpawner {
+        next_chunk: 0,
+        tiles: vec![Entity::PLACEHOLDER; (map.width * map.height) as usize],
+    });
+    commands.spawn((
+        Text::new(locale.render(&Phrase::new("map.building").with("percent", 0))),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(50.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(Justify::Center),
+        MapSpawnProgress,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Spawn whole chunks until this frame's tile budget is used up, then hand
+/// over to the game once every tile exists.
+#[allow(clippy::too_many_arguments)]
+fn spawn_map_chunks(
+    mut commands: Commands,
+    mut renderer: TileRenderer,
+    map: Res<MapConfig>,
+    mut spawner: ResMut<MapSpawner>,
+    mut progress: Query<(Entity, &mut Text), With<MapSpawnProgress>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    locale: Res<Locale>,
+    defs: Res<ZoneDefs>,
+) {
+    let chunks_x = (map.width + CHUNK_SIZE - 1) / CHUNK_SIZE;
+    let chunks_y = (map.height + CHUNK_SIZE - 1) / CHUNK_SIZE;
+    let chunk_count = (chunks_x * chunks_y) as usize;
+
+    let mut spawned = 0;
+    while spawned < TILES_PER_FRAME && spawner.next_chunk < chunk_count {
+        let chunk = IVec2::new(
+            spawner.next_chunk as i32 % chunks_x,
+            spawner.next_chunk as i32 / chunks_x,
+        );
+        spawner.next_chunk += 1;
+
+        let parent = renderer.spawn_chunk(&mut commands, chunk);
+        for y in chunk.y * CHUNK_SIZE..((chunk.y + 1) * CHUNK_SIZE).min(map.height) {
+            for x in chunk.x * CHUNK_SIZE..((chunk.x + 1) * CHUNK_SIZE).min(map.width) {
+                let coord = IVec2::new(x, y);
+                let zone = Zone::Empty;
+                let visual = TileVisual {
+                    index: terrain_sprite_index(&defs, zone, &TileData::default()),
+                    color: Color::WHITE,
+                };
+
+                let tile = commands.spawn((
+                    renderer.tile(parent, coord, visual),
+                    TileCoord { coord },
+                    zone,
+                    TileData::default(),
+                    TileTint::default(),
+                    RoadDirection::default(),
+                    visual,
+                ));
+                spawner.tiles[(y * map.width + x) as usize] = tile.id();
+                spawned += 1;
+            }
+        }
+    }
+
+    let Ok((text_entity, mut text)) = progress.single_mut() else {
+        return;
+    };
+    if spawner.next_chunk < chunk_count {
+        **text = locale.render(
+            &Phrase::new("map.building").with("percent", spawner.next_chunk * 100 / chunk_count),
+        );
+        return;
+    }
+
+    commands.insert_resource(TileMap::new(
+        map.width,
+        map.height,
+        std::mem::take(&mut spawner.tiles),
+    ));
+    commands.remove_resource::<MapSpawner>();
+    commands.entity(text_entity).despawn();
+    next_state.set(GameState::Running);
+}
+
+/// Turn every tile back into an empty lot for a restart.
+fn clear_tiles(
+    mut tiles: Query<(&mut Zone, &mut TileData, &mut TileTint)>,
+) {
+    for (mut zone, mut data, mut tint) in tiles.iter_mut() {
+        zone.set_if_neq(Zone::Empty);
+        data.set_if_neq(TileData::default());
+        tint.set_if_neq(TileTint::default());
+    }
+}
+
+/// The tiles themselves despawn on leaving `Playing`; drop the lookup (and
+/// any half-finished spawn) pointing at them.
+fn forget_tile_map(mut commands: Commands) {
+    commands.remove_resource::<TileMap>();
+    commands.remove_resource::<MapSpawner>();
+}
+
+/// Atlas cell of a tile's terrain: water under water and bridges, trees
+/// in the forest, and grass under everything else, zoned lots included.
+fn terrain_sprite_index(defs: &ZoneDefs, zone: Zone, data: &TileData) -> usize {
+    match zone {
+        Zone::Water | Zone::Bridge => defs.variant_sprite_index(Zone::Water, data),
+        Zone::Forest => defs.variant_sprite_index(Zone::Forest, data),
+        _ => defs.variant_sprite_index(Zone::Empty, data),
+    }
+}
+
+/// Atlas cell of the building drawn over a tile's terrain: its zone's, in
+/// the variant it was zoned with for how full it is, or its part of a
+/// multi-tile building. Bare land and a lot zoned but not built on yet
+/// have none.
+fn building_sprite_index(
+    defs: &ZoneDefs,
+    coord: IVec2,
+    zone: Zone,
+    data: &TileData,
+) -> Option<usize> {
+    match data.anchor {
+        Some(anchor) => Some(defs.part_sprite_index(zone, coord - anchor)),
+        None if data.construction > 0 => Some(CONSTRUCTION_SPRITE_INDEX),
+        None if data.zoned => None,
+        None => match zone {
+            Zone::Empty | Zone::Water | Zone::Forest => None,
+            zone => Some(defs.variant_sprite_index(zone, data)),
+        },
+    }
+}
+
+/// Point each changed tile's terrain at its atlas cell.
+/// This and `sync_building_sprites` are the only places that decide what
+/// a zone looks like; code changing a `Zone` never touches the tile's
+/// visuals itself. Every tile is looked at again when the zone
+/// definitions change.
+fn sync_terrain_visuals(
+    defs: Res<ZoneDefs>,
+    mut tiles: Query<(Ref<Zone>, Ref<TileData>, &mut TileVisual)>,
+) {
+    let all = defs.is_changed();
+    for (zone, data, mut visual) in tiles.iter_mut() {
+        if !all && !zone.is_changed() && !data.is_changed() {
+            continue;
+        }
+        let index = terrain_sprite_index(&defs, *zone, &data);
+        if visual.index != index {
+            visual.index = index;
+        }
+    }
+}
+
+/// Give each changed tile with a building a sprite for it in the buildings
+/// layer, point the sprite at the building's atlas cell, and despawn it
+/// once the tile is bulldozed. The sprite starts out in the tile's colors;
+/// `tint_buildings` keeps it in step after that.
+#[allow(clippy::too_many_arguments)]
+fn sync_building_sprites(
+    mut commands: Commands,
+    defs: Res<ZoneDefs>,
+    sprites: Res<CitySprites>,
+    map: Res<MapConfig>,
+    layers: Res<MapLayers>,
+    tiles: Query<(Entity, &TileCoord, Ref<Zone>, Ref<TileData>)>,
+    drawn: Query<(&TileVisual, Option<&BuildingSprite>), With<Zone>>,
+    mut buildings: Query<&mut TileVisual, Without<Zone>>,
+) {
+    let all = defs.is_changed();
+    for (tile, coord, zone, data) in tiles.iter() {
+        if !all && !zone.is_changed() && !data.is_changed() {
+            continue;
+        }
+        let Ok((terrain, building)) = drawn.get(tile) else {
+            continue;
+        };
+        let index = building_sprite_index(&defs, coord.coord, *zone, &data);
+        match (index, building) {
+            (Some(index), Some(building)) => {
+                if let Ok(mut visual) = buildings.get_mut(building.entity()) {
+                    if visual.index != index {
+                        visual.index = index;
+                    }
+                }
+            }
+            (Some(index), None) => {
+                let visual = TileVisual {
+                    index,
+                    color: terrain.color,
+                };
+                commands.spawn((
+                    tile_sprite(&sprites, &map, visual),
+                    visual,
+                    Transform::from_translation(tile_to_world(&map, coord.coord).extend(0.0)),
+                    BuildingOf(tile),
+                    ChildOf(layers.root(MapLayer::Buildings)),
+                ));
+            }
+            (None, Some(building)) => commands.entity(building.entity()).despawn(),
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changing_a_zone_redraws_its_tile() {
+        let mut app = App::new();
+        app.insert_resource(ZoneDefs::bundled())
+            .add_systems(Update, sync_terrain_visuals);
+        let defs = ZoneDefs::bundled();
+        let grass = defs.variant_sprite_index(Zone::Empty, &TileData::default());
+        let water = defs.variant_sprite_index(Zone::Water, &TileData::default());
+        assert_ne!(grass, water);
+        let tile = app
+            .world_mut()
+            .spawn((
+                Zone::Empty,
+                TileData::default(),
+                TileVisual {
+                    index: grass,
+                    color: Color::WHITE,
+                },
+            ))
+            .id();
+        app.update();
+
+        *app.world_mut().get_mut::<Zone>(tile).unwrap() = Zone::Water;
+        app.update();
+        assert_eq!(app.world().get::<TileVisual>(tile).unwrap().index, water);
+
+        *app.world_mut().get_mut::<Zone>(tile).unwrap() = Zone::Bridge;
+        app.update();
+        assert_eq!(app.world().get::<TileVisual>(tile).unwrap().index, water);
+
+        *app.world_mut().get_mut::<Zone>(tile).unwrap() = Zone::Road;
+        app.update();
+        assert_eq!(app.world().get::<TileVisual>(tile).unwrap().index, grass);
+    }
+
+    #[test]
+    fn buildings_are_drawn_once_built() {
+        let defs = ZoneDefs::bundled();
+        let coord = IVec2::new(3, 4);
+        let data = TileData::default();
+        assert_eq!(building_sprite_index(&defs, coord, Zone::Empty, &data), None);
+        assert_eq!(building_sprite_index(&defs, coord, Zone::Water, &data), None);
+        assert_eq!(
+            building_sprite_index(&defs, coord, Zone::Road, &data),
+            Some(defs.variant_sprite_index(Zone::Road, &data))
+        );
+
+        let zoned = TileData {
+            zoned: true,
+            ..default()
+        };
+        assert_eq!(building_sprite_index(&defs, coord, Zone::Residential, &zoned), None);
+        let site = TileData {
+            construction: 1,
+            ..zoned
+        };
+        assert_eq!(
+            building_sprite_index(&defs, coord, Zone::Residential, &site),
+            Some(CONSTRUCTION_SPRITE_INDEX)
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::elevation::MAX_LEVEL;
+use crate::map::{variant_for, TileData, Zone};
+
+/// Map area, in tiles, that gets one round of water and forest patches.
+const TERRAIN_AREA_PER_ROUND: i32 = 1024;
+/// Half the side length of the town built around the main crossing.
+const TOWN_RADIUS: i32 = 3;
+/// Mixed into the city seed for the elevation noise, so that it doesn't
+/// repeat the draws that placed the terrain.
+const ELEVATION_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+/// Tiles between the random heights of each noise layer, and how much
+/// the layer adds to the elevation: broad hills with a little roughness.
+const ELEVATION_LAYERS: [(i32, f32); 2] = [(8, 0.75), (3, 0.25)];
+/// How far the noise is spread out from its middle before it is stepped
+/// into levels; layered noise bunches up there, which would leave few
+/// valleys or hilltops.
+const ELEVATION_CONTRAST: f32 = 1.6;
+
+/// Lay out a random starter map: two main roads crossing the map, a few
+/// lakes and forests, and a small prebuilt town at the crossing. Returns
+/// every tile that isn't empty; the same inputs always give the same map.
+pub fn generate(seed: u64, width: i32, height: i32) -> Vec<(IVec2, Zone)> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut zones = vec![Zone::Empty; (width * height) as usize];
+    let mut set = |coord: IVec2, zone: Zone| {
+        if coord.x >= 0 && coord.y >= 0 && coord.x < width && coord.y < height {
+            zones[(coord.y * width + coord.x) as usize] = zone;
+        }
+    };
+
+    // Terrain first, so the roads and town can be laid over it.
+    let rounds = (width * height / TERRAIN_AREA_PER_ROUND).max(1);
+    for (zone, patches, max_radius) in [(Zone::Water, 1..=3, 4), (Zone::Forest, 2..=5, 5)] {
+        for _ in 0..rounds * rng.random_range(patches.clone()) {
+            let center = IVec2::new(rng.random_range(0..width), rng.random_range(0..height));
+            let radius = rng.random_range(2..=max_radius);
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    // Shave a random amount off the edge so patches aren't
+                    // perfect discs.
+                    let ragged = radius * radius - rng.random_range(0..=radius);
+                    if x * x + y * y <= ragged {
+                        set(center + IVec2::new(x, y), zone);
+                    }
+                }
+            }
+        }
+    }
+
+    // Main roads all the way across, bridging any water in the way.
+    let crossing = IVec2::new(
+        rng.random_range(width / 4..=width * 3 / 4),
+        rng.random_range(height / 4..=height * 3 / 4),
+    );
+    for x in 0..width {
+        set(IVec2::new(x, crossing.y), Zone::Road);
+    }
+    for y in 0..height {
+        set(IVec2::new(crossing.x, y), Zone::Road);
+    }
+
+    // Shops on the corners of the crossing and homes along the roads.
+    for y in -TOWN_RADIUS..=TOWN_RADIUS {
+        for x in -TOWN_RADIUS..=TOWN_RADIUS {
+            let zone = match (x.abs(), y.abs()) {
+                (0, _) | (_, 0) => continue,
+                (1, 1) => Zone::Commercial,
+                (1, _) | (_, 1) => Zone::Residential,
+                _ => continue,
+            };
+            set(crossing + IVec2::new(x, y), zone);
+        }
+    }
+
+    zones
+        .into_iter()
+        .enumerate()
+        .filter(|(_, zone)| *zone != Zone::Empty)
+        .map(|(i, zone)| (IVec2::new(i as i32 % width, i as i32 / width), zone))
+        .collect()
+}
+
+/// Height level of every tile of a map laid out by `generate` from the
+/// same seed, row by row: smooth noise stepped into levels, with water at
+/// level 0 and the land rising at most a level a tile away from it, so
+/// lakes sit in basins rather than under cliffs.
+pub fn elevation(seed: u64, width: i32, height: i32, tiles: &[(IVec2, Zone)]) -> Vec<u8> {
+    let mut rng = SmallRng::seed_from_u64(seed ^ ELEVATION_SALT);
+    let mut noise = vec![0.0; (width * height) as usize];
+    for (spacing, weight) in ELEVATION_LAYERS {
+        let (cols, rows) = (width / spacing + 2, height / spacing + 2);
+        let corners: Vec<f32> = (0..cols * rows).map(|_| rng.random::<f32>()).collect();
+        let corner = |x: i32, y: i32| corners[(y * cols + x) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = (x / spacing, y / spacing);
+                let tx = smoothstep((x % spacing) as f32 / spacing as f32);
+                let ty = smoothstep((y % spacing) as f32 / spacing as f32);
+                let bottom = corner(cx, cy).lerp(corner(cx + 1, cy), tx);
+                let top = corner(cx, cy + 1).lerp(corner(cx + 1, cy + 1), tx);
+                noise[(y * width + x) as usize] += bottom.lerp(top, ty) * weight;
+            }
+        }
+    }
+    let steps = f32::from(MAX_LEVEL + 1);
+    let mut levels: Vec<u8> = noise
+        .into_iter()
+        .map(|n| (n - 0.5) * ELEVATION_CONTRAST + 0.5)
+        .map(|n| ((n.max(0.0) * steps) as u8).min(MAX_LEVEL))
+        .collect();
+
+    // Walk out from the water, capping each tile at its distance from it,
+    // until the cap is above the highest level.
+    let index = |coord: IVec2| (coord.y * width + coord.x) as usize;
+    let mut queue = VecDeque::new();
+    let mut seen = vec![false; levels.len()];
+    for &(coord, zone) in tiles {
+        if zone == Zone::Water {
+            seen[index(coord)] = true;
+            queue.push_back((coord, 0));
+        }
+    }
+    while let Some((coord, distance)) = queue.pop_front() {
+        levels[index(coord)] = levels[index(coord)].min(distance);
+        if distance == MAX_LEVEL {
+            continue;
+        }
+        for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+            let next = coord + offset;
+            let on_map = next.x >= 0 && next.y >= 0 && next.x < width && next.y < height;
+            if on_map && !seen[index(next)] {
+                seen[index(next)] = true;
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    levels
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Tile data of a building the generator puts down at `coord`, so the
+/// starter town is already lived in.
+pub fn starter_data(zone: Zone, coord: IVec2, seed: u64) -> TileData {
+    let variant = variant_for(coord, seed);
+    match zone {
+        Zone::Residential => TileData {
+            population: 10,
+            variant,
+            ..default()
+        },
+        Zone::Commercial => TileData {
+            jobs: 10,
+            variant,
+            ..default()
+        },
+        _ => TileData {
+            variant,
+            ..default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_map() {
+        for seed in [0, 1, 42, u64::MAX] {
+            assert_eq!(generate(seed, 48, 32), generate(seed, 48, 32));
+            let tiles = generate(seed, 48, 32);
+            assert_eq!(elevation(seed, 48, 32, &tiles), elevation(seed, 48, 32, &tiles));
+        }
+        assert_ne!(generate(1, 48, 32), generate(2, 48, 32));
+    }
+
+    #[test]
+    fn roads_are_contiguous() {
+        for seed in 0..20 {
+            let roads: HashSet<IVec2> = generate(seed, 40, 40)
+                .into_iter()
+                .filter(|(_, zone)| *zone == Zone::Road)
+                .map(|(coord, _)| coord)
+                .collect();
+            let start = *roads.iter().next().expect("the map has roads");
+            let mut reached = HashSet::from([start]);
+            let mut queue = VecDeque::from([start]);
+            while let Some(coord) = queue.pop_front() {
+                for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                    let next = coord + offset;
+                    if roads.contains(&next) && reached.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+            assert_eq!(reached.len(), roads.len(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn the_map_stays_on_the_grid() {
+        let tiles = generate(7, 20, 12);
+        assert!(tiles
+            .iter()
+            .all(|(c, _)| c.x >= 0 && c.y >= 0 && c.x < 20 && c.y < 12));
+        assert!(tiles.iter().any(|(_, zone)| *zone == Zone::Residential));
+        assert!(tiles.iter().any(|(_, zone)| *zone == Zone::Commercial));
+    }
+
+    #[test]
+    fn lakes_sit_in_basins() {
+        let (width, height) = (40, 40);
+        for seed in 0..10 {
+            let tiles = generate(seed, width, height);
+            let levels = elevation(seed, width, height, &tiles);
+            let level = |c: IVec2| levels[(c.y * width + c.x) as usize];
+            let water: Vec<IVec2> = tiles
+                .iter()
+                .filter(|(_, zone)| *zone == Zone::Water)
+                .map(|(coord, _)| *coord)
+                .collect();
+            for &coord in &water {
+                assert_eq!(level(coord), 0);
+            }
+            for y in 0..height {
+                for x in 0..width {
+                    let coord = IVec2::new(x, y);
+                    let nearest = water
+                        .iter()
+                        .map(|w| ((w.x - x).abs() + (w.y - y).abs()) as u8)
+                        .min();
+                    if let Some(distance) = nearest {
+                        assert!(level(coord) <= distance, "seed {seed} at {coord}");
+                    }
+                }
+            }
+        }
+    }
+}
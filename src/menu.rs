@@ -0,0 +1,785 @@
+use std::collections::HashMap;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::development::Development;
+use crate::districts::Districts;
+use crate::economy::Economy;
+use crate::elevation::Elevation;
+use crate::garbage::Garbage;
+use crate::grid::Neighborhood;
+use crate::layout_image::{self, IMPORT_PATH};
+use crate::map::{TileCoord, TileData, Zone};
+use crate::loans::Loans;
+use crate::locale::{Locale, Phrase};
+use crate::mapgen;
+use crate::milestones::Milestones;
+use crate::oneway::RoadDirection;
+use crate::replay::Playback;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::calendar::MonthEnded;
+use crate::save::{self, CityState, SaveFile, AUTOSAVE_PATH, SAVE_PATH};
+use crate::scenario::{ActiveScenario, Scenario, Scenarios};
+use crate::simulation::{CityStats, Ledger, NewCity};
+use crate::trade::Trade;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::GameSet;
+use crate::state::{AppState, GameState};
+use crate::input::ctrl_held;
+use crate::ui::{disabled_menu_button, menu_button, Notification};
+use crate::water::Pipes;
+
+/// The main menu, the pause menu (Escape), and saving and loading the city
+/// from them.
+pub struct MenuPlugin {
+    /// Map size a new city starts with; loading a save may replace the
+    /// active `MapConfig`.
+    pub map: MapConfig,
+    /// Whether new cities start on a generated map instead of a blank one.
+    pub random_map: bool,
+    /// Go straight into a new city the first time the main menu opens, for
+    /// scripts driving the game remotely.
+    pub skip_menu: bool,
+    /// A city imported with `--import-map`, opened in place of the main
+    /// menu the first time it would show.
+    pub import: Option<PendingCity>,
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NewCityMap {
+            map: self.map,
+            random: self.random_map,
+        })
+            .init_resource::<ScenarioChoice>()
+            .add_message::<Notification>()
+            .add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(
+                Update,
+                (
+                    handle_main_menu_buttons,
+                    update_development_label,
+                    update_scenario_label,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                toggle_pause.run_if(
+                    in_state(GameState::Running).or(in_state(GameState::Paused)),
+                ),
+            )
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(
+                Update,
+                (handle_pause_menu_buttons, update_neighborhood_label)
+                    .chain()
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(
+                Update,
+                request_clear_map.run_if(in_state(GameState::Running)),
+            )
+            .add_systems(OnEnter(GameState::ConfirmClear), spawn_clear_dialog)
+            .add_systems(
+                Update,
+                handle_clear_dialog_buttons.run_if(in_state(GameState::ConfirmClear)),
+            )
+            .add_systems(
+                OnEnter(GameState::Running),
+                apply_pending_city.run_if(resource_exists::<PendingCity>),
+            );
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_message::<MonthEnded>().add_systems(
+            Update,
+            autosave
+                .in_set(GameSet::Simulation)
+                .run_if(not(resource_exists::<Playback>)),
+        );
+        if self.skip_menu {
+            app.add_systems(OnEnter(AppState::MainMenu), skip_main_menu);
+        }
+        if let Some(city) = &self.import {
+            app.insert_resource(ImportedCity(city.clone()))
+                .add_systems(OnEnter(AppState::MainMenu), open_imported_city);
+        }
+    }
+}
+
+/// How new cities start: the configured map, restored for every new city,
+/// and whether to generate a layout on it.
+#[derive(Resource)]
+struct NewCityMap {
+    map: MapConfig,
+    random: bool,
+}
+
+/// The scenario the main menu starts new cities in, by its place in
+/// `Scenarios`; none for a sandbox.
+#[derive(Resource, Default)]
+struct ScenarioChoice(Option<usize>);
+
+/// A loaded or generated city waiting for its map to finish spawning,
+/// and a notification to post once it is in place.
+#[derive(Resource, Clone)]
+pub struct PendingCity {
+    pub layout: SaveFile,
+    pub notice: Option<Phrase>,
+}
+
+/// The city from `--import-map`, until it has been opened.
+#[derive(Resource)]
+struct ImportedCity(PendingCity);
+
+#[derive(Component, Clone, Copy)]
+enum MainMenuButton {
+    NewCity,
+    Scenario,
+    Development,
+    LoadCity,
+    LoadAutosave,
+    ImportMap,
+    Quit,
+}
+
+/// The line under the main menu buttons saying why a city couldn't be
+/// opened.
+#[derive(Component)]
+struct MenuStatus;
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    Save,
+    Neighborhood,
+    Settings,
+    ClearMap,
+    MainMenu,
+}
+
+#[derive(Component, Clone, Copy)]
+enum ClearDialogButton {
+    Confirm,
+    Cancel,
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    new_city: Res<NewCity>,
+    choice: Res<ScenarioChoice>,
+    scenarios: Res<Scenarios>,
+    locale: Res<Locale>,
+) {
+    let can_load = save::save_exists(SAVE_PATH);
+    let can_load_autosave = save::save_exists(AUTOSAVE_PATH);
+    let can_import = layout_image::import_exists();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::MainMenu),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text("menu.title")),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
+            ));
+            let scenario = scenario_label(chosen_scenario(&choice, &scenarios));
+            for (button, label, enabled) in [
+                (MainMenuButton::NewCity, Phrase::new("menu.new_city"), true),
+                (MainMenuButton::Scenario, scenario, true),
+                (MainMenuButton::Development, development_label(new_city.development), true),
+                (MainMenuButton::LoadCity, Phrase::new("menu.load_city"), can_load),
+                (
+                    MainMenuButton::LoadAutosave,
+                    Phrase::new("menu.load_autosave"),
+                    can_load_autosave,
+                ),
+                (MainMenuButton::ImportMap, Phrase::new("menu.import_map"), can_import),
+                (MainMenuButton::Quit, Phrase::new("menu.quit"), true),
+            ] {
+                let label = locale.render(&label);
+                if enabled {
+                    parent.spawn(menu_button(&label, button));
+                } else {
+                    parent.spawn(disabled_menu_button(&label));
+                }
+            }
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.45, 0.4)),
+                MenuStatus,
+            ));
+        });
+}
+
+/// Log why `path` couldn't be opened, and say so under the menu buttons.
+fn show_open_error(
+    status: &mut Query<&mut Text, With<MenuStatus>>,
+    locale: &Locale,
+    path: &str,
+    err: &str,
+) {
+    error!("Could not open {path}: {err}");
+    if let Ok(mut text) = status.single_mut() {
+        **text = locale.render(
+            &Phrase::new("menu.load_failed")
+                .with("path", path)
+                .with("error", err),
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_main_menu_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    new_city_map: Res<NewCityMap>,
+    mut new_city: ResMut<NewCity>,
+    mut choice: ResMut<ScenarioChoice>,
+    scenarios: Res<Scenarios>,
+    mut map: ResMut<MapConfig>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: MessageWriter<AppExit>,
+    mut status: Query<&mut Text, With<MenuStatus>>,
+    locale: Res<Locale>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MainMenuButton::NewCity => {
+                let scenario = chosen_scenario(&choice, &scenarios);
+                start_new_city(&mut commands, &new_city_map, &new_city, &mut map, scenario);
+                next_state.set(AppState::Playing);
+            }
+            MainMenuButton::Scenario => {
+                choice.0 = match choice.0 {
+                    None if !scenarios.0.is_empty() => Some(0),
+                    Some(i) if i + 1 < scenarios.0.len() => Some(i + 1),
+                    _ => None,
+                };
+            }
+            MainMenuButton::Development => {
+                new_city.development = new_city.development.toggled();
+            }
+            MainMenuButton::LoadCity | MainMenuButton::LoadAutosave => {
+                let path = match button {
+                    MainMenuButton::LoadAutosave => AUTOSAVE_PATH,
+                    _ => SAVE_PATH,
+                };
+                match save::read_save(path) {
+                    Ok(save) => {
+                        let city = PendingCity {
+                            layout: save,
+                            notice: None,
+                        };
+                        open_city(&mut commands, city, &mut map);
+                        next_state.set(AppState::Playing);
+                    }
+                    Err(err) => show_open_error(&mut status, &locale, path, &err),
+                }
+            }
+            MainMenuButton::ImportMap => match layout_image::read_layout(
+                IMPORT_PATH,
+                new_city.starting_money,
+                new_city.neighborhood,
+            ) {
+                Ok(city) => {
+                    open_city(&mut commands, city, &mut map);
+                    next_state.set(AppState::Playing);
+                }
+                Err(err) => show_open_error(&mut status, &locale, IMPORT_PATH, &err),
+            },
+            MainMenuButton::Quit => {
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
+
+/// Start a new city on the configured map, with a generated layout when
+/// random maps are on, or on the scenario's own map when one is chosen.
+fn start_new_city(
+    commands: &mut Commands,
+    new_city_map: &NewCityMap,
+    new_city: &NewCity,
+    map: &mut MapConfig,
+    scenario: Option<&Scenario>,
+) {
+    *map = new_city_map.map;
+    if let Some(scenario) = scenario {
+        map.width = scenario.width;
+        map.height = scenario.height;
+        commands.insert_resource(scenario.city(new_city));
+    } else if new_city_map.random {
+        commands.insert_resource(random_city(map, new_city));
+    }
+}
+
+/// Start a new city the first time the main menu opens; later visits show
+/// the menu as usual.
+fn skip_main_menu(
+    mut commands: Commands,
+    new_city_map: Res<NewCityMap>,
+    new_city: Res<NewCity>,
+    mut map: ResMut<MapConfig>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut skipped: Local<bool>,
+) {
+    if *skipped {
+        return;
+    }
+    *skipped = true;
+    start_new_city(&mut commands, &new_city_map, &new_city, &mut map, None);
+    next_state.set(AppState::Playing);
+}
+
+/// Open the city from `--import-map` instead of the main menu, once.
+fn open_imported_city(
+    mut commands: Commands,
+    imported: Option<Res<ImportedCity>>,
+    mut map: ResMut<MapConfig>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(imported) = imported else {
+        return;
+    };
+    open_city(&mut commands, imported.0.clone(), &mut map);
+    commands.remove_resource::<ImportedCity>();
+    next_state.set(AppState::Playing);
+}
+
+/// Size the map for a loaded or imported city and queue it to be built.
+fn open_city(commands: &mut Commands, city: PendingCity, map: &mut MapConfig) {
+    map.width = city.layout.width;
+    map.height = city.layout.height;
+    commands.insert_resource(city);
+}
+
+/// Generate a starter layout from the city seed, announcing the seed so a
+/// good map can be shared.
+fn random_city(map: &MapConfig, new_city: &NewCity) -> PendingCity {
+    let mut layout =
+        generated_layout(map.width, map.height, new_city.starting_money, new_city.seed);
+    layout.neighborhood = new_city.neighborhood;
+    layout.development = new_city.development;
+    info!("Generated a random map from seed {}", new_city.seed);
+    PendingCity {
+        layout,
+        notice: Some(Phrase::new("notify.random_map").with("seed", new_city.seed)),
+    }
+}
+
+/// A map generated from `seed`, terrain, roads, starter town and all.
+pub fn generated_layout(width: i32, height: i32, money: i64, seed: u64) -> SaveFile {
+    let mut layout = SaveFile::empty(width, height, money, 0);
+    let tiles = mapgen::generate(seed, width, height);
+    layout.elevation = mapgen::elevation(seed, width, height, &tiles);
+    for (coord, zone) in tiles {
+        layout.set(coord, zone, mapgen::starter_data(zone, coord, seed));
+    }
+    layout
+}
+
+/// Pause or resume with Escape.
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    next_state.set(match state.get() {
+        GameState::Paused => GameState::Running,
+        _ => GameState::Paused,
+    });
+}
+
+/// The pause menu. The neighborhood rule can't be changed while a replay
+/// is playing back, since the recording has its own.
+fn spawn_pause_menu(
+    mut commands: Commands,
+    neighborhood: Res<Neighborhood>,
+    playback: Option<Res<Playback>>,
+    locale: Res<Locale>,
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(10),
+            DespawnOnExit(GameState::Paused),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text("pause.title")),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for (button, label) in [
+                (PauseMenuButton::Resume, "pause.resume"),
+                (PauseMenuButton::Save, "pause.save"),
+                (PauseMenuButton::Neighborhood, neighborhood_label(*neighborhood)),
+                (PauseMenuButton::Settings, "pause.settings"),
+                (PauseMenuButton::ClearMap, "pause.clear_map"),
+                (PauseMenuButton::MainMenu, "pause.main_menu"),
+            ] {
+                let label = locale.text(label);
+                if matches!(button, PauseMenuButton::Neighborhood) && playback.is_some() {
+                    parent.spawn(disabled_menu_button(&label));
+                } else {
+                    parent.spawn(menu_button(&label, button));
+                }
+            }
+        });
+}
+
+/// Pause menu actions. The neighborhood rule is read by the save, so a
+/// switch is made through `Commands`.
+fn handle_pause_menu_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    city: CityState,
+    neighborhood: Res<Neighborhood>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PauseMenuButton::Resume => next_game_state.set(GameState::Running),
+            PauseMenuButton::Save => {
+                let save = city.save();
+                notifications.write(Notification(match save::write_save(SAVE_PATH, &save) {
+                    Ok(()) => Phrase::new("notify.saved").with("path", SAVE_PATH),
+                    Err(err) => Phrase::new("notify.save_failed").with("error", err),
+                }));
+            }
+            PauseMenuButton::Neighborhood => commands.insert_resource(neighborhood.toggled()),
+            PauseMenuButton::Settings => next_game_state.set(GameState::Settings),
+            PauseMenuButton::ClearMap => next_game_state.set(GameState::ConfirmClear),
+            PauseMenuButton::MainMenu => next_app_state.set(AppState::MainMenu),
+        }
+    }
+}
+
+/// Save the city to `AUTOSAVE_PATH` at the end of every month, except
+/// while a replay is playing back. Only failures are announced.
+#[cfg(not(target_arch = "wasm32"))]
+fn autosave(
+    mut month_ended: MessageReader<MonthEnded>,
+    city: CityState,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let Some(ended) = month_ended.read().last() else {
+        return;
+    };
+    match save::write_save(AUTOSAVE_PATH, &city.save()) {
+        Ok(()) => info!(
+            "City autosaved to {AUTOSAVE_PATH} at the end of month {} of year {}",
+            ended.month.month + 1,
+            ended.month.year
+        ),
+        Err(err) => {
+            error!("Could not autosave the city: {err}");
+            notifications.write(Notification(
+                Phrase::new("notify.autosave_failed").with("error", err),
+            ));
+        }
+    }
+}
+
+fn neighborhood_label(neighborhood: Neighborhood) -> &'static str {
+    match neighborhood {
+        Neighborhood::Four => "pause.diagonal_roads_off",
+        Neighborhood::Eight => "pause.diagonal_roads_on",
+    }
+}
+
+fn development_label(development: Development) -> Phrase {
+    Phrase::new(match development {
+        Development::Instant => "menu.development_instant",
+        Development::Gradual => "menu.development_gradual",
+    })
+}
+
+fn chosen_scenario<'a>(choice: &ScenarioChoice, scenarios: &'a Scenarios) -> Option<&'a Scenario> {
+    choice.0.and_then(|i| scenarios.0.get(i))
+}
+
+fn scenario_label(scenario: Option<&Scenario>) -> Phrase {
+    match scenario {
+        Some(scenario) => Phrase::new("menu.scenario").key("name", scenario.name),
+        None => Phrase::new("menu.sandbox"),
+    }
+}
+
+/// Relabel the development button after it is pressed.
+fn update_development_label(
+    new_city: Res<NewCity>,
+    buttons: Query<(&MainMenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    locale: Res<Locale>,
+) {
+    if !new_city.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        if !matches!(button, MainMenuButton::Development) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = locale.render(&development_label(new_city.development));
+            }
+        }
+    }
+}
+
+/// Relabel the scenario button after it is pressed.
+fn update_scenario_label(
+    choice: Res<ScenarioChoice>,
+    scenarios: Res<Scenarios>,
+    buttons: Query<(&MainMenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    locale: Res<Locale>,
+) {
+    if !choice.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        if !matches!(button, MainMenuButton::Scenario) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = locale.render(&scenario_label(chosen_scenario(&choice, &scenarios)));
+            }
+        }
+    }
+}
+
+/// Relabel the neighborhood button after it is pressed.
+fn update_neighborhood_label(
+    neighborhood: Res<Neighborhood>,
+    buttons: Query<(&PauseMenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    locale: Res<Locale>,
+) {
+    if !neighborhood.is_changed() {
+        return;
+    }
+    for (button, children) in buttons.iter() {
+        if !matches!(button, PauseMenuButton::Neighborhood) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = locale.text(neighborhood_label(*neighborhood));
+            }
+        }
+    }
+}
+
+/// Ctrl+Shift+N asks to clear the map.
+fn request_clear_map(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if ctrl_held(&keys) && shift && keys.just_pressed(KeyCode::KeyN) {
+        next_state.set(GameState::ConfirmClear);
+    }
+}
+
+/// Modal dialog over a backdrop that swallows clicks meant for the map.
+fn spawn_clear_dialog(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            GlobalZIndex(10),
+            DespawnOnExit(GameState::ConfirmClear),
+        ))
+        .with_children(|backdrop| {
+            backdrop
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(16.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.1, 0.1, 0.13)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(locale.text("clear_dialog.text")),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(Justify::Center),
+                    ));
+                    panel
+                        .spawn(Node {
+                            column_gap: Val::Px(16.0),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn(menu_button(
+                                &locale.text("clear_dialog.confirm"),
+                                ClearDialogButton::Confirm,
+                            ));
+                            row.spawn(menu_button(
+                                &locale.text("clear_dialog.cancel"),
+                                ClearDialogButton::Cancel,
+                            ));
+                        });
+                });
+        });
+}
+
+/// Clearing reuses the restart path, which resets the existing tiles in
+/// place through their `Zone` rather than respawning the map.
+fn handle_clear_dialog_buttons(
+    buttons: Query<(&Interaction, &ClearDialogButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        next_state.set(match button {
+            ClearDialogButton::Confirm => GameState::Restarting,
+            ClearDialogButton::Cancel => GameState::Running,
+        });
+    }
+}
+
+/// Put the pending zones, tile data, treasury, the month's ledger, loans,
+/// economic events, milestones, elevation, pipes, one-way roads,
+/// districts, garbage, trade connections, neighborhood rule, development
+/// mode, scenario, and clock onto the freshly built map. A scenario the
+/// build doesn't know is dropped, leaving a sandbox.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_pending_city(
+    mut commands: Commands,
+    pending: Res<PendingCity>,
+    mut tiles: Query<(&TileCoord, &mut Zone, &mut TileData)>,
+    mut directions: Query<(&TileCoord, &mut RoadDirection)>,
+    mut stats: ResMut<CityStats>,
+    mut ledger: ResMut<Ledger>,
+    mut clock: ResMut<TimeOfDay>,
+    mut loans: ResMut<Loans>,
+    mut economy: ResMut<Economy>,
+    mut milestones: ResMut<Milestones>,
+    mut elevation: ResMut<Elevation>,
+    mut pipes: ResMut<Pipes>,
+    mut districts: ResMut<Districts>,
+    (mut garbage, mut trade): (ResMut<Garbage>, ResMut<Trade>),
+    (mut neighborhood, mut development, mut scenario, scenarios): (
+        ResMut<Neighborhood>,
+        ResMut<Development>,
+        ResMut<ActiveScenario>,
+        Res<Scenarios>,
+    ),
+    mut notifications: MessageWriter<Notification>,
+) {
+    let save = &pending.layout;
+    for (coord, mut zone, mut data) in tiles.iter_mut() {
+        let (saved_zone, saved_data) = save.tile(coord.coord);
+        zone.set_if_neq(saved_zone);
+        data.set_if_neq(saved_data);
+    }
+    let one_way: HashMap<IVec2, RoadDirection> = save.one_way.iter().copied().collect();
+    for (coord, mut direction) in directions.iter_mut() {
+        direction.set_if_neq(one_way.get(&coord.coord).copied().unwrap_or_default());
+    }
+    stats.money = save.money;
+    ledger.pending = save.pending_budget;
+    clock.ticks = save.ticks;
+    loans.active = save.loans.clone();
+    economy.active = save.events.clone();
+    milestones.reached = save.milestones.clone();
+    *elevation = Elevation::new(save.width, save.height, save.elevation.clone());
+    *pipes = Pipes::from_coords(save.width, save.height, &save.pipes);
+    *districts = Districts::from_saved(
+        save.width,
+        save.height,
+        save.district_ids.clone(),
+        save.districts.clone(),
+    );
+    *garbage = Garbage::from_saved(
+        save.width,
+        save.height,
+        &save.landfills,
+        save.pollution.clone(),
+        save.garbage_backlog,
+    );
+    *trade = Trade::from_saved(save.width, save.height, save.trade.clone());
+    *neighborhood = save.neighborhood;
+    *development = save.development;
+    let active = save.scenario.as_deref().and_then(|id| {
+        let found = scenarios.find(id);
+        if found.is_none() {
+            warn!("The city plays scenario {id:?}, which this build doesn't have");
+        }
+        found.cloned()
+    });
+    scenario.set_if_neq(ActiveScenario(active));
+    if let Some(notice) = &pending.notice {
+        notifications.write(Notification(notice.clone()));
+    }
+    commands.remove_resource::<PendingCity>();
+}
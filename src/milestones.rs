@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+
+use crate::game_over::track_bankruptcy;
+use crate::locale::Phrase;
+use crate::map::Zone;
+use crate::simulation::{simulation_step, CityStats, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::ui::Notification;
+
+/// Every milestone, by the population that reaches it, lowest first.
+pub const MILESTONES: [Milestone; 6] = [
+    Milestone {
+        population: 100,
+        bonus: 1_000,
+        unlocks: &[Zone::School],
+    },
+    Milestone {
+        population: 500,
+        bonus: 2_500,
+        unlocks: &[Zone::PowerPlant],
+    },
+    Milestone {
+        population: 1_000,
+        bonus: 5_000,
+        unlocks: &[],
+    },
+    Milestone {
+        population: 2_000,
+        bonus: 10_000,
+        unlocks: &[Zone::Avenue],
+    },
+    Milestone {
+        population: 5_000,
+        bonus: 20_000,
+        unlocks: &[Zone::Seaport],
+    },
+    Milestone {
+        population: 10_000,
+        bonus: 50_000,
+        unlocks: &[Zone::Airport],
+    },
+];
+
+/// Population milestones: each pays a bonus into the treasury the first
+/// time the city reaches it, and some unlock buildings that new cities
+/// start without.
+pub struct MilestonesPlugin;
+
+impl Plugin for MilestonesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Milestones>()
+            .add_message::<Notification>()
+            .add_systems(
+                Update,
+                reach_milestones
+                    .after(simulation_step)
+                    .before(track_bankruptcy)
+                    .in_set(GameSet::Simulation),
+            )
+            .add_systems(ResetCity, clear_milestones);
+    }
+}
+
+/// A population the city is rewarded for reaching.
+pub struct Milestone {
+    pub population: u32,
+    /// Paid into the treasury on reaching it.
+    pub bonus: i64,
+    /// Tools that can't be used until it is reached.
+    pub unlocks: &'static [Zone],
+}
+
+/// Populations of the milestones the city has reached, which saves keep,
+/// and the buildings the scenario being played locks or unlocks on top.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Milestones {
+    pub reached: Vec<u32>,
+    /// Available from the start, whatever the milestones say.
+    pub unlocked: Vec<Zone>,
+    /// Never available.
+    pub banned: Vec<Zone>,
+}
+
+/// Why a tool can't be used yet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lock {
+    /// It unlocks at this population.
+    Population(u32),
+    /// The scenario doesn't allow it.
+    Scenario,
+}
+
+impl Milestones {
+    /// Every milestone at or below `population`, for a city whose saved
+    /// history doesn't say which it reached.
+    pub fn reached_by(population: u32) -> Self {
+        Self {
+            reached: MILESTONES
+                .iter()
+                .map(|milestone| milestone.population)
+                .filter(|&threshold| threshold <= population)
+                .collect(),
+            ..default()
+        }
+    }
+
+    /// What is keeping `zone` locked, if it is.
+    pub fn locked(&self, zone: Zone) -> Option<Lock> {
+        if self.banned.contains(&zone) {
+            return Some(Lock::Scenario);
+        }
+        if self.unlocked.contains(&zone) {
+            return None;
+        }
+        MILESTONES
+            .iter()
+            .find(|milestone| milestone.unlocks.contains(&zone))
+            .map(|milestone| milestone.population)
+            .filter(|population| !self.reached.contains(population))
+            .map(Lock::Population)
+    }
+}
+
+/// Pay out, announce, and unlock the buildings of every milestone this
+/// tick's population passed.
+fn reach_milestones(
+    timer: Res<SimTimer>,
+    mut milestones: ResMut<Milestones>,
+    mut stats: ResMut<CityStats>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+    for milestone in &MILESTONES {
+        if stats.population < milestone.population
+            || milestones.reached.contains(&milestone.population)
+        {
+            continue;
+        }
+        milestones.reached.push(milestone.population);
+        stats.money += milestone.bonus;
+        notifications.write(Notification(
+            Phrase::new("notify.milestone")
+                .number("population", milestone.population)
+                .number("bonus", milestone.bonus),
+        ));
+        // Unless the scenario already unlocked it, or never will.
+        for &zone in milestone.unlocks {
+            if milestones.unlocked.contains(&zone) || milestones.banned.contains(&zone) {
+                continue;
+            }
+            notifications.write(Notification(
+                Phrase::new("notify.unlocked").key("zone", zone.label()),
+            ));
+        }
+    }
+}
+
+fn clear_milestones(mut milestones: ResMut<Milestones>) {
+    milestones.reached.clear();
+}
@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+use crate::grid::tile_to_world;
+use crate::input::{BuildTool, PaintCommand};
+use crate::layers::{MapLayer, MapLayers};
+use crate::map::{TileCoord, TileMap, Zone};
+use crate::replay::Playback;
+use crate::simulation::simulation_step;
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+
+/// Draw order of the arrows within the buildings layer: over the road
+/// sprites, under the cars.
+const ARROW_Z: f32 = 0.1;
+const ARROW_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.7);
+/// Length of an arrow, as a fraction of a tile.
+const ARROW_SIZE: f32 = 0.45;
+
+/// One-way roads. O switches the build tool to setting road directions:
+/// each click on a road turns it to the next of north, east, south, west,
+/// and back to two-way. Commuters, traffic, and cars never drive against
+/// a road's direction, though they may turn onto or off it from the side.
+/// An arrow over every one-way road shows which way it runs.
+pub struct OneWayPlugin;
+
+impl Plugin for OneWayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PaintCommand>()
+            .add_message::<DirectionEdit>()
+            .add_systems(Startup, create_arrow_assets)
+            .add_systems(ResetCity, clear_directions)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_direction_arrows.in_set(SpawnSet::Map),
+            )
+            .add_systems(
+                Update,
+                set_road_directions
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(
+                Update,
+                clear_off_road_directions
+                    .in_set(GameSet::Simulation)
+                    .before(simulation_step),
+            )
+            .add_systems(Update, update_direction_arrows.in_set(GameSet::Ui));
+    }
+}
+
+/// Which way traffic may drive along a road. Every tile has one; anything
+/// but a road is always two-way.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RoadDirection {
+    #[default]
+    TwoWay,
+    North,
+    East,
+    South,
+    West,
+}
+
+impl RoadDirection {
+    /// The direction a click with the direction tool turns this one to.
+    pub fn next(self) -> Self {
+        match self {
+            RoadDirection::TwoWay => RoadDirection::North,
+            RoadDirection::North => RoadDirection::East,
+            RoadDirection::East => RoadDirection::South,
+            RoadDirection::South => RoadDirection::West,
+            RoadDirection::West => RoadDirection::TwoWay,
+        }
+    }
+
+    /// The tile offset traffic moves along, or `None` both ways.
+    pub fn offset(self) -> Option<IVec2> {
+        match self {
+            RoadDirection::TwoWay => None,
+            RoadDirection::North => Some(IVec2::Y),
+            RoadDirection::East => Some(IVec2::X),
+            RoadDirection::South => Some(IVec2::NEG_Y),
+            RoadDirection::West => Some(IVec2::NEG_X),
+        }
+    }
+
+    /// Whether traffic on this road may move by `step`: any way but against
+    /// it.
+    pub fn allows(self, step: IVec2) -> bool {
+        self.offset() != Some(-step)
+    }
+}
+
+/// Whether traffic may drive from a road with direction `from` onto the
+/// next one, `step` away, with direction `to`: neither may run against it.
+pub fn can_drive(from: RoadDirection, to: RoadDirection, step: IVec2) -> bool {
+    from.allows(step) && to.allows(step)
+}
+
+/// Road directions set by the player this frame, for the replay.
+#[derive(Message, Clone)]
+pub struct DirectionEdit {
+    pub tiles: Vec<(IVec2, RoadDirection)>,
+}
+
+fn clear_directions(mut tiles: Query<&mut RoadDirection>) {
+    for mut direction in tiles.iter_mut() {
+        direction.set_if_neq(RoadDirection::TwoWay);
+    }
+}
+
+/// A road bulldozed or rezoned to something else forgets its direction,
+/// so a road built there later starts out two-way.
+fn clear_off_road_directions(mut tiles: Query<(&Zone, &mut RoadDirection), Changed<Zone>>) {
+    for (zone, mut direction) in tiles.iter_mut() {
+        if !zone.is_road() {
+            direction.set_if_neq(RoadDirection::TwoWay);
+        }
+    }
+}
+
+/// While the build tool sets directions, each press on a road turns it to
+/// the next direction. Dragging does nothing more, and other tiles are
+/// left alone.
+fn set_road_directions(
+    mut commands: MessageReader<PaintCommand>,
+    tool: Res<BuildTool>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&Zone, &mut RoadDirection)>,
+    mut edits: MessageWriter<DirectionEdit>,
+) {
+    if !tool.directions {
+        commands.clear();
+        return;
+    }
+    let mut edit = DirectionEdit { tiles: Vec::new() };
+    for command in commands.read() {
+        let PaintCommand::Paint {
+            center,
+            start: true,
+            ..
+        } = *command
+        else {
+            continue;
+        };
+        let Some((zone, mut direction)) =
+            tile_map.get(center).and_then(|entity| tiles.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        if zone.is_road() {
+            *direction = direction.next();
+            edit.tiles.push((center, *direction));
+        }
+    }
+    if !edit.tiles.is_empty() {
+        edits.write(edit);
+    }
+}
+
+/// Shared mesh and material of the arrows.
+#[derive(Resource)]
+struct ArrowAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+/// Parent of every arrow.
+#[derive(Component)]
+struct DirectionArrows;
+
+fn create_arrow_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(ArrowAssets {
+        mesh: meshes.add(Triangle2d::new(
+            Vec2::new(0.0, 0.5),
+            Vec2::new(-0.4, -0.5),
+            Vec2::new(0.4, -0.5),
+        )),
+        material: materials.add(ARROW_COLOR),
+    });
+}
+
+/// The arrows sit in the buildings layer, apart from the road sprites, so
+/// the sprite a road is drawn with never has to know its direction.
+fn spawn_direction_arrows(mut commands: Commands, layers: Res<MapLayers>) {
+    commands.spawn((
+        Transform::default(),
+        Visibility::default(),
+        DirectionArrows,
+        ChildOf(layers.root(MapLayer::Buildings)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Redraw the arrows whenever a direction changes: one over every one-way
+/// road, pointing the way it runs.
+fn update_direction_arrows(
+    mut commands: Commands,
+    map: Res<MapConfig>,
+    assets: Res<ArrowAssets>,
+    changed: Query<(), Changed<RoadDirection>>,
+    tiles: Query<(&TileCoord, &RoadDirection)>,
+    root: Query<Entity, With<DirectionArrows>>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let Ok(root) = root.single() else {
+        return;
+    };
+    commands.entity(root).despawn_related::<Children>();
+    for (coord, direction) in tiles.iter() {
+        let Some(offset) = direction.offset() else {
+            continue;
+        };
+        let center = tile_to_world(&map, coord.coord);
+        commands.entity(root).with_child((
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(assets.material.clone()),
+            Transform::from_translation(center.extend(ARROW_Z))
+                .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, offset.as_vec2()))
+                .with_scale(Vec3::splat(map.tile_size * ARROW_SIZE)),
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::commute::JobAccess;
+use crate::config::MapConfig;
+use crate::elevation::{Elevation, MAX_LEVEL};
+use crate::garbage::{Garbage, MAX_POLLUTION};
+use crate::grid::tile_to_world;
+use crate::layers::{MapLayer, MapLayers};
+use crate::locale::Locale;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::power::{carries_power, PowerGrid};
+use crate::services::{Service, ServiceCoverage};
+use crate::settings::Settings;
+use crate::sim::{MAX_CRIME, MAX_LAND_VALUE};
+use crate::simulation::SimTimer;
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+use crate::tourism::{Tourism, MAX_ATTRACTIVENESS};
+use crate::traffic::TrafficGrid;
+
+/// Ends and middle of the overlay color ramp.
+const RAMP_GOOD: Color = Color::srgb(0.2, 0.9, 0.25);
+const RAMP_MIDDLE: Color = Color::srgb(0.95, 0.85, 0.2);
+const RAMP_BAD: Color = Color::srgb(0.95, 0.2, 0.2);
+/// Color of tiles the active overlay says nothing about.
+const NOT_APPLICABLE_TINT: Color = Color::srgb(0.45, 0.45, 0.45);
+/// Opacity of the heatmap over the buildings.
+const OVERLAY_ALPHA: f32 = 0.55;
+/// Spare jobs in reach at which a home counts as well served.
+const PLENTY_OF_JOBS: u32 = 100;
+/// Color steps shown in the legend.
+const LEGEND_SWATCHES: usize = 5;
+
+/// Heatmaps of the simulation's hidden layers, picked with F1-F7, F11,
+/// and Shift+F11 and drawn as a square over each tile in the overlay map
+/// layer.
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DataOverlay>()
+            .add_systems(ResetCity, start_overlay)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_overlay_legend.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                Update,
+                (
+                    select_overlay,
+                    draw_overlay,
+                    update_overlay_legend,
+                )
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// The layer currently shown as a heatmap, if any.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum DataOverlay {
+    #[default]
+    None,
+    /// Commuters on each road against its capacity.
+    Traffic,
+    /// Crime in each home.
+    Crime,
+    /// How many services cover each tile.
+    Services,
+    /// Spare jobs within commuting range of each home.
+    Jobs,
+    /// Height of the land.
+    Elevation,
+    /// What the land under each home and shop is worth.
+    LandValue,
+    /// How attractive each tile is to visitors.
+    Tourism,
+    /// Which tiles had power in the last tick.
+    Power,
+    /// Pollution from garbage spilling over.
+    Pollution,
+}
+
+impl DataOverlay {
+    /// The key showing each layer, and whether Shift goes with it. F8 to
+    /// F10 open panels and F12 takes screenshots, so the utilities share
+    /// F11.
+    const KEYS: [(KeyCode, bool, DataOverlay); 9] = [
+        (KeyCode::F1, false, DataOverlay::Traffic),
+        (KeyCode::F2, false, DataOverlay::Crime),
+        (KeyCode::F3, false, DataOverlay::Services),
+        (KeyCode::F4, false, DataOverlay::Jobs),
+        (KeyCode::F5, false, DataOverlay::Elevation),
+        (KeyCode::F6, false, DataOverlay::LandValue),
+        (KeyCode::F7, false, DataOverlay::Tourism),
+        (KeyCode::F11, false, DataOverlay::Power),
+        (KeyCode::F11, true, DataOverlay::Pollution),
+    ];
+
+    /// Locale keys of the name and what the good and bad ends of the ramp
+    /// mean.
+    fn legend(self) -> Option<(&'static str, &'static str, &'static str)> {
+        match self {
+            DataOverlay::None => None,
+            DataOverlay::Traffic => Some((
+                "overlay.traffic",
+                "overlay.traffic.good",
+                "overlay.traffic.bad",
+            )),
+            DataOverlay::Crime => Some((
+                "overlay.crime",
+                "overlay.crime.good",
+                "overlay.crime.bad",
+            )),
+            DataOverlay::Services => Some((
+                "overlay.services",
+                "overlay.services.good",
+                "overlay.services.bad",
+            )),
+            DataOverlay::Jobs => Some(("overlay.jobs", "overlay.jobs.good", "overlay.jobs.bad")),
+            DataOverlay::Elevation => Some((
+                "overlay.elevation",
+                "overlay.elevation.good",
+                "overlay.elevation.bad",
+            )),
+            DataOverlay::LandValue => Some((
+                "overlay.land_value",
+                "overlay.land_value.good",
+                "overlay.land_value.bad",
+            )),
+            DataOverlay::Tourism => Some((
+                "overlay.tourism",
+                "overlay.tourism.good",
+                "overlay.tourism.bad",
+            )),
+            DataOverlay::Power => Some((
+                "overlay.power",
+                "overlay.power.good",
+                "overlay.power.bad",
+            )),
+            DataOverlay::Pollution => Some((
+                "overlay.pollution",
+                "overlay.pollution.good",
+                "overlay.pollution.bad",
+            )),
+        }
+    }
+
+    /// Locale key of the layer's name, or `None` for no overlay.
+    pub fn name(self) -> Option<&'static str> {
+        self.legend().map(|(name, _, _)| name)
+    }
+
+    /// How bad a tile is on this layer, from 0 (good) to 1, or `None` where
+    /// the layer doesn't apply. Elevation runs from low to high instead.
+    fn badness(self, layers: &Layers, coord: IVec2, zone: Zone, data: &TileData) -> Option<f32> {
+        match (self, zone) {
+            (DataOverlay::Traffic, _) => zone
+                .road()
+                .map(|road| layers.traffic.get(coord) as f32 / road.capacity as f32),
+            (DataOverlay::Crime, Zone::Residential | Zone::Commercial) => {
+                Some(data.crime as f32 / MAX_CRIME as f32)
+            }
+            (DataOverlay::Services, _) => {
+                let covered = Service::ALL
+                    .iter()
+                    .filter(|&&service| layers.coverage.covers(service, coord))
+                    .count();
+                Some(1.0 - covered as f32 / Service::ALL.len() as f32)
+            }
+            (DataOverlay::Jobs, Zone::Residential) => {
+                Some(1.0 - layers.jobs.unfilled(coord) as f32 / PLENTY_OF_JOBS as f32)
+            }
+            (DataOverlay::Elevation, _) => layers
+                .elevation
+                .get(coord)
+                .map(|level| f32::from(level) / f32::from(MAX_LEVEL)),
+            (DataOverlay::LandValue, Zone::Residential | Zone::Commercial) => {
+                Some(1.0 - data.land_value as f32 / MAX_LAND_VALUE as f32)
+            }
+            (DataOverlay::Tourism, _) if !zone.is_water() && zone != Zone::Forest => {
+                Some(1.0 - layers.tourism.get(coord) as f32 / MAX_ATTRACTIVENESS as f32)
+            }
+            (DataOverlay::Power, _) if carries_power(zone) => {
+                Some(if layers.power.powered(coord) { 0.0 } else { 1.0 })
+            }
+            (DataOverlay::Pollution, _) if !zone.is_water() => {
+                Some(layers.garbage.pollution(coord) as f32 / MAX_POLLUTION as f32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The grid layers overlays read from.
+struct Layers<'a> {
+    traffic: &'a TrafficGrid,
+    coverage: &'a ServiceCoverage,
+    jobs: &'a JobAccess,
+    elevation: &'a Elevation,
+    tourism: &'a Tourism,
+    power: &'a PowerGrid,
+    garbage: &'a Garbage,
+}
+
+/// Green through yellow to red as `t` goes from 0 to 1.
+pub fn ramp(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        RAMP_GOOD.mix(&RAMP_MIDDLE, t * 2.0)
+    } else {
+        RAMP_MIDDLE.mix(&RAMP_BAD, t * 2.0 - 1.0)
+    }
+}
+
+#[derive(Component)]
+struct OverlayLegend;
+
+#[derive(Component)]
+struct OverlayLegendTitle;
+
+/// Labels at the good and bad ends of the legend ramp.
+#[derive(Component)]
+struct OverlayLegendEnd {
+    bad: bool,
+}
+
+fn spawn_overlay_legend(mut commands: Commands) {
+    let label = |bad: bool| {
+        (
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            OverlayLegendEnd { bad },
+        )
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(36.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            OverlayLegend,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|legend| {
+            legend.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                OverlayLegendTitle,
+            ));
+            legend
+                .spawn(Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(label(false));
+                    for i in 0..LEGEND_SWATCHES {
+                        let t = i as f32 / (LEGEND_SWATCHES - 1) as f32;
+                        row.spawn((
+                            Node {
+                                width: Val::Px(16.0),
+                                height: Val::Px(12.0),
+                                ..default()
+                            },
+                            BackgroundColor(ramp(t)),
+                        ));
+                    }
+                    row.spawn(label(true));
+                });
+        });
+}
+
+/// Every city starts with the heatmap picked in the settings.
+fn start_overlay(settings: Res<Settings>, mut overlay: ResMut<DataOverlay>) {
+    overlay.set_if_neq(settings.overlay);
+}
+
+/// F1-F7, F11, and Shift+F11 show a layer; pressing the active layer's
+/// keys again hides it.
+fn select_overlay(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DataOverlay>) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (key, with_shift, layer) in DataOverlay::KEYS {
+        if keys.just_pressed(key) && shift == with_shift {
+            let next = if *overlay == layer {
+                DataOverlay::None
+            } else {
+                layer
+            };
+            overlay.set_if_neq(next);
+        }
+    }
+}
+
+/// One tile's square of the heatmap.
+#[derive(Component)]
+struct OverlayCell {
+    coord: IVec2,
+}
+
+/// Recolor the heatmap when the overlay changes, and every tick while one
+/// is up. Its squares are spawned when an overlay is first shown and
+/// despawned when it is turned off, so with no overlay there are none.
+#[allow(clippy::too_many_arguments)]
+fn draw_overlay(
+    mut commands: Commands,
+    map: Res<MapConfig>,
+    map_layers: Res<MapLayers>,
+    overlay: Res<DataOverlay>,
+    timer: Res<SimTimer>,
+    traffic: Res<TrafficGrid>,
+    coverage: Res<ServiceCoverage>,
+    jobs: Res<JobAccess>,
+    elevation: Res<Elevation>,
+    (tourism, power, garbage): (Res<Tourism>, Res<PowerGrid>, Res<Garbage>),
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    mut cells: Query<(Entity, &OverlayCell, &mut Sprite)>,
+) {
+    let refresh =
+        overlay.is_changed() || (*overlay != DataOverlay::None && timer.0.just_finished());
+    if !refresh {
+        return;
+    }
+    if *overlay == DataOverlay::None {
+        for (entity, ..) in cells.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let layers = Layers {
+        traffic: &traffic,
+        coverage: &coverage,
+        jobs: &jobs,
+        elevation: &elevation,
+        tourism: &tourism,
+        power: &power,
+        garbage: &garbage,
+    };
+    let mut colors = vec![NOT_APPLICABLE_TINT; (map.width * map.height).max(0) as usize];
+    for (coord, zone, data) in tiles.iter() {
+        let c = coord.coord;
+        colors[(c.y * map.width + c.x) as usize] = overlay
+            .badness(&layers, c, *zone, data)
+            .map_or(NOT_APPLICABLE_TINT, ramp)
+            .with_alpha(OVERLAY_ALPHA);
+    }
+
+    if cells.is_empty() {
+        let size = Vec2::splat(map.tile_size);
+        for (coord, ..) in tiles.iter() {
+            let c = coord.coord;
+            commands.spawn((
+                Sprite::from_color(colors[(c.y * map.width + c.x) as usize], size),
+                Transform::from_translation(tile_to_world(&map, c).extend(0.0)),
+                OverlayCell { coord: c },
+                ChildOf(map_layers.root(MapLayer::Overlay)),
+                DespawnOnExit(AppState::Playing),
+            ));
+        }
+        return;
+    }
+    for (_, cell, mut sprite) in cells.iter_mut() {
+        let color = colors[(cell.coord.y * map.width + cell.coord.x) as usize];
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+fn update_overlay_legend(
+    overlay: Res<DataOverlay>,
+    mut legend: Query<&mut Node, With<OverlayLegend>>,
+    mut title: Query<&mut Text, With<OverlayLegendTitle>>,
+    mut ends: Query<(&mut Text, &OverlayLegendEnd), Without<OverlayLegendTitle>>,
+    locale: Res<Locale>,
+) {
+    if !overlay.is_changed() {
+        return;
+    }
+    let legend_text = overlay.legend();
+    for mut node in legend.iter_mut() {
+        node.display = if legend_text.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    let Some((name, good, bad)) = legend_text else {
+        return;
+    };
+    for mut text in title.iter_mut() {
+        text.0 = locale.text(name);
+    }
+    for (mut text, end) in ends.iter_mut() {
+        text.0 = locale.text(if end.bad { bad } else { good });
+    }
+}
@@ -0,0 +1,59 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+
+use crate::grid::CARDINAL_NEIGHBORS;
+
+/// A* over the tile grid with 4-way moves of cost 1.
+///
+/// Returns the tiles from `start` to `goal` inclusive, or `None` when either
+/// end is impassable or no route exists. The search never leaves tiles for
+/// which `passable` is true, so it terminates on any finite network, and
+/// only moves from one tile to the next where `can_step` allows it.
+pub fn find_path(
+    start: IVec2,
+    goal: IVec2,
+    passable: impl Fn(IVec2) -> bool,
+    can_step: impl Fn(IVec2, IVec2) -> bool,
+) -> Option<Vec<IVec2>> {
+    if !passable(start) || !passable(goal) {
+        return None;
+    }
+
+    let heuristic = |c: IVec2| (c - goal).abs().element_sum();
+    let mut open = BinaryHeap::new();
+    let mut cost: HashMap<IVec2, i32> = HashMap::new();
+    let mut came_from: HashMap<IVec2, IVec2> = HashMap::new();
+    cost.insert(start, 0);
+    open.push(Reverse((heuristic(start), start.x, start.y)));
+
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let current = IVec2::new(x, y);
+        if current == goal {
+            let mut path = vec![goal];
+            let mut node = goal;
+            while let Some(&prev) = came_from.get(&node) {
+                path.push(prev);
+                node = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let current_cost = cost[&current];
+        for n in CARDINAL_NEIGHBORS {
+            let next = current + n;
+            if !passable(next) || !can_step(current, next) {
+                continue;
+            }
+            let next_cost = current_cost + 1;
+            if cost.get(&next).is_none_or(|&c| next_cost < c) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + heuristic(next), next.x, next.y)));
+            }
+        }
+    }
+    None
+}
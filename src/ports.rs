@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use crate::map::{TileCoord, TileData, Zone};
+use crate::simulation::{simulation_step, Demand};
+use crate::state::GameSet;
+
+/// Commercial demand an airport adds city-wide.
+const AIRPORT_COMMERCIAL_DEMAND: i32 = 30;
+/// Industrial demand a seaport adds city-wide.
+const SEAPORT_INDUSTRIAL_DEMAND: i32 = 30;
+
+/// Airports and seaports: late-game buildings a city can have one of each
+/// of, which raise demand for shops or industry everywhere while they
+/// stand.
+pub struct PortsPlugin;
+
+impl Plugin for PortsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ports>().add_systems(
+            Update,
+            track_ports
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        );
+    }
+}
+
+/// Whether a zone is a port, of which a city can have only one.
+pub fn is_port(zone: Zone) -> bool {
+    matches!(zone, Zone::Airport | Zone::Seaport)
+}
+
+/// Demand a port adds city-wide; none for anything else.
+pub fn demand_boost(zone: Zone) -> Demand {
+    match zone {
+        Zone::Airport => Demand {
+            commercial: AIRPORT_COMMERCIAL_DEMAND,
+            ..default()
+        },
+        Zone::Seaport => Demand {
+            industrial: SEAPORT_INDUSTRIAL_DEMAND,
+            ..default()
+        },
+        _ => Demand::default(),
+    }
+}
+
+/// The ports standing in the city, in `Zone::ALL` order.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Ports {
+    pub built: Vec<Zone>,
+}
+
+impl Ports {
+    /// Whether the city already has a `zone` port.
+    pub fn has(&self, zone: Zone) -> bool {
+        self.built.contains(&zone)
+    }
+
+    /// Demand every port standing adds together.
+    pub fn demand(&self) -> Demand {
+        self.built
+            .iter()
+            .fold(Demand::default(), |all, &port| all + demand_boost(port))
+    }
+}
+
+/// Look for ports again whenever zoning changes; a port counts from the
+/// tick its anchor is placed.
+fn track_ports(
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    mut ports: ResMut<Ports>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let anchored: Vec<Zone> = tiles
+        .iter()
+        .filter(|(coord, zone, data)| is_port(**zone) && data.anchor == Some(coord.coord))
+        .map(|(_, zone, _)| *zone)
+        .collect();
+    let built = Zone::ALL
+        .into_iter()
+        .filter(|zone| anchored.contains(zone))
+        .collect();
+    ports.set_if_neq(Ports { built });
+}
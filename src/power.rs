@@ -0,0 +1,260 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::grid::CARDINAL_NEIGHBORS;
+use crate::locale::Phrase;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::simulation::{simulation_step, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::ui::Notification;
+use crate::weather::Weather;
+
+/// Power a finished plant feeds into its network every tick.
+pub const PLANT_OUTPUT: u32 = 500;
+/// Power every network with buildings drawing power gets from lines out to
+/// the region, enough for a small town before it can build plants of its
+/// own.
+pub const REGIONAL_SUPPLY: u32 = 150;
+/// Residents or jobs per level of a building; each level draws its zone's
+/// rate of power.
+const LEVEL_SIZE: u32 = 25;
+
+/// Balances the power plants make against what buildings draw, per
+/// network of connected tiles. When a network draws more than it makes, the
+/// buildings farthest from its plants go dark and stop growing until
+/// supply catches up.
+pub struct PowerPlugin;
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ResetCity, init_power_grid).add_systems(
+            Update,
+            (update_power_networks, balance_power)
+                .chain()
+                .in_set(GameSet::Simulation)
+                .after(simulation_step),
+        );
+    }
+}
+
+/// Whether a zone passes power on to the tiles next to it. Buildings and
+/// roads carry it; open land, water, and woods don't.
+pub fn carries_power(zone: Zone) -> bool {
+    !matches!(zone, Zone::Empty | Zone::Water | Zone::Forest)
+}
+
+/// Power a tile draws per tick: its zone's rate for every level of
+/// residents or jobs it holds.
+pub fn consumption(zone: Zone, data: &TileData) -> u32 {
+    let rate = match zone {
+        Zone::Residential => 2,
+        Zone::Commercial => 3,
+        Zone::Industrial => 4,
+        _ => return 0,
+    };
+    rate * (data.population + data.jobs).div_ceil(LEVEL_SIZE)
+}
+
+/// What one network made and was asked for in a tick.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PowerBalance {
+    pub supply: u32,
+    pub demand: u32,
+}
+
+/// Power networks and who had power in the last tick, laid out row by
+/// row. The networks are rebuilt only when zoning changes.
+#[derive(Resource)]
+pub struct PowerGrid {
+    width: i32,
+    height: i32,
+    /// Network of each tile, or `None` for tiles that don't carry power.
+    network: Vec<Option<usize>>,
+    /// Tiles that carry power, in the order brownouts cut them off:
+    /// farthest from a plant first, then latest in row order.
+    shed_order: Vec<usize>,
+    /// Indexed by network.
+    balances: Vec<PowerBalance>,
+    powered: Vec<bool>,
+    /// Buildings cut off in the last tick.
+    pub dark: u32,
+}
+
+impl PowerGrid {
+    fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            network: vec![None; size],
+            shed_order: Vec::new(),
+            balances: Vec::new(),
+            powered: vec![true; size],
+            dark: 0,
+        }
+    }
+
+    /// Whether the tile at `coord` had power in the last tick.
+    pub fn powered(&self, coord: IVec2) -> bool {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return false;
+        }
+        self.powered[(coord.y * self.width + coord.x) as usize]
+    }
+
+    /// Supply and demand of every network added up.
+    pub fn total(&self) -> PowerBalance {
+        self.balances.iter().fold(PowerBalance::default(), |total, b| PowerBalance {
+            supply: total.supply + b.supply,
+            demand: total.demand + b.demand,
+        })
+    }
+}
+
+/// Label the connected networks of power-carrying tiles, and order their
+/// tiles by how far power travels from the nearest plant. Tiles of a
+/// network without a plant come first.
+fn networks(width: i32, height: i32, zones: &[Zone]) -> (Vec<Option<usize>>, usize, Vec<usize>) {
+    let size = (width * height) as usize;
+    let neighbors = |i: usize| {
+        let coord = IVec2::new(i as i32 % width, i as i32 / width);
+        CARDINAL_NEIGHBORS.iter().filter_map(move |&n| {
+            let c = coord + n;
+            (c.x >= 0 && c.y >= 0 && c.x < width && c.y < height)
+                .then(|| (c.y * width + c.x) as usize)
+                .filter(|&j| carries_power(zones[j]))
+        })
+    };
+
+    let mut network = vec![None; size];
+    let mut count = 0;
+    let mut queue = VecDeque::new();
+    for start in 0..size {
+        if network[start].is_some() || !carries_power(zones[start]) {
+            continue;
+        }
+        network[start] = Some(count);
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            for j in neighbors(i) {
+                if network[j].is_none() {
+                    network[j] = Some(count);
+                    queue.push_back(j);
+                }
+            }
+        }
+        count += 1;
+    }
+
+    let mut distance = vec![u32::MAX; size];
+    for i in 0..size {
+        if zones[i] == Zone::PowerPlant {
+            distance[i] = 0;
+            queue.push_back(i);
+        }
+    }
+    while let Some(i) = queue.pop_front() {
+        for j in neighbors(i) {
+            if distance[j] == u32::MAX {
+                distance[j] = distance[i] + 1;
+                queue.push_back(j);
+            }
+        }
+    }
+
+    let mut shed_order: Vec<usize> = (0..size).filter(|&i| network[i].is_some()).collect();
+    shed_order.sort_by_key(|&i| (Reverse(distance[i]), Reverse(i)));
+    (network, count, shed_order)
+}
+
+fn init_power_grid(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(PowerGrid::new(map.width, map.height));
+}
+
+/// Relabel the networks, but only when some zone changed.
+fn update_power_networks(
+    mut grid: ResMut<PowerGrid>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+
+    let (width, height) = (grid.width, grid.height);
+    let mut zones = vec![Zone::Empty; (width * height) as usize];
+    for (coord, zone) in tiles.iter() {
+        zones[(coord.coord.y * width + coord.coord.x) as usize] = *zone;
+    }
+    let (network, count, shed_order) = networks(width, height, &zones);
+    grid.network = network;
+    grid.shed_order = shed_order;
+    grid.balances = vec![PowerBalance::default(); count];
+}
+
+/// Once a tick, add up what each network makes and draws (more in a
+/// heatwave), and cut off buildings in shedding order until every network
+/// draws no more than it makes. Warns when a brownout starts.
+fn balance_power(
+    timer: Res<SimTimer>,
+    mut grid: ResMut<PowerGrid>,
+    weather: Res<Weather>,
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+
+    let grid = &mut *grid;
+    let width = grid.width;
+    let mut draw = vec![0; grid.powered.len()];
+    grid.balances.fill(PowerBalance::default());
+    for (coord, zone, data) in tiles.iter() {
+        let i = (coord.coord.y * width + coord.coord.x) as usize;
+        let Some(network) = grid.network[i] else {
+            continue;
+        };
+        let balance = &mut grid.balances[network];
+        draw[i] = consumption(*zone, data) * weather.power_percent() / 100;
+        balance.demand += draw[i];
+        // A plant makes power once, on its anchor, after it is built.
+        if *zone == Zone::PowerPlant && data.anchor == Some(coord.coord) && data.construction == 0
+        {
+            balance.supply += PLANT_OUTPUT;
+        }
+    }
+
+    for balance in &mut grid.balances {
+        if balance.demand > 0 {
+            balance.supply += REGIONAL_SUPPLY;
+        }
+    }
+
+    let mut load: Vec<u32> = grid.balances.iter().map(|b| b.demand).collect();
+    let was_dark = grid.dark > 0;
+    grid.dark = 0;
+    grid.powered.fill(true);
+    for &i in &grid.shed_order {
+        let Some(network) = grid.network[i] else {
+            continue;
+        };
+        if draw[i] > 0 && load[network] > grid.balances[network].supply {
+            grid.powered[i] = false;
+            load[network] -= draw[i];
+            grid.dark += 1;
+        }
+    }
+
+    if grid.dark > 0 && !was_dark {
+        let total = grid.total();
+        notifications.write(Notification(
+            Phrase::new("notify.brownout")
+                .number("demand", total.demand)
+                .number("supply", total.supply),
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+
+use crate::commute::JobAccess;
+use crate::config::MapConfig;
+use crate::fire::FireGrid;
+use crate::garbage::Garbage;
+use crate::grid::{tile_to_world, Neighborhood};
+use crate::layers::{MapLayer, MapLayers};
+use crate::map::{TileCoord, TileData, Zone};
+use crate::power::PowerGrid;
+use crate::sim::{commercial_capacity, ZoneGrid};
+use crate::simulation::{PopulationGrid, SimTimer, CUSTOMER_RADIUS};
+use crate::state::{AppState, GameSet, SpawnSet};
+use crate::traffic::TrafficGrid;
+use crate::water::WaterSupply;
+
+/// Most problem icons shown at once; the worst problems get them.
+const MAX_ICONS: usize = 48;
+/// Crime at which a home or shop counts as struggling.
+const HIGH_CRIME: u32 = 60;
+/// Pollution at which a home or shop counts as struggling.
+const HIGH_POLLUTION: u32 = 50;
+/// Draw order of the icons within the markers layer, above the brush and
+/// paste previews.
+const ICON_Z: f32 = 0.5;
+/// Size of an icon, and how far above its tile's center it floats, as
+/// fractions of a tile.
+const ICON_RADIUS: f32 = 0.22;
+const ICON_RISE: f32 = 0.3;
+
+/// Badges floating over the buildings with the most pressing problems,
+/// updated every tick and gone once a problem is fixed. I hides them, for
+/// screenshots.
+pub struct ProblemsPlugin;
+
+impl Plugin for ProblemsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_icon_assets)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_problem_icons.in_set(SpawnSet::Map),
+            )
+            .add_systems(
+                Update,
+                (toggle_problem_icons, update_problem_icons)
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// Something wrong with a building, worst first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Problem {
+    OnFire,
+    /// No road next to it, so it can't grow.
+    NoRoad,
+    /// Cut off by a brownout, so it can't grow.
+    NoPower,
+    /// No water in reach, so it holds half as much.
+    NoWater,
+    /// Finished but standing empty, with nobody to move in: a home without
+    /// spare jobs in reach, or a shop without enough residents nearby to
+    /// keep anyone busy.
+    Abandoned,
+    HighCrime,
+    /// Garbage spilling over around it drives residents out and land
+    /// value down.
+    Polluted,
+    /// A road next to it is over capacity.
+    Jammed,
+}
+
+impl Problem {
+    const ALL: [Problem; 8] = [
+        Problem::OnFire,
+        Problem::NoRoad,
+        Problem::NoPower,
+        Problem::NoWater,
+        Problem::Abandoned,
+        Problem::HighCrime,
+        Problem::Polluted,
+        Problem::Jammed,
+    ];
+
+    fn color(self) -> Color {
+        match self {
+            Problem::OnFire => Color::srgb(1.0, 0.45, 0.1),
+            Problem::NoRoad => Color::srgb(0.55, 0.55, 0.6),
+            Problem::NoPower => Color::srgb(0.7, 0.5, 1.0),
+            Problem::NoWater => Color::srgb(0.35, 0.65, 1.0),
+            Problem::Abandoned => Color::srgb(0.55, 0.4, 0.3),
+            Problem::HighCrime => Color::srgb(0.85, 0.15, 0.2),
+            Problem::Polluted => Color::srgb(0.5, 0.65, 0.2),
+            Problem::Jammed => Color::srgb(0.95, 0.8, 0.15),
+        }
+    }
+
+    /// Character drawn on the badge.
+    fn glyph(self) -> &'static str {
+        match self {
+            Problem::OnFire => "^",
+            Problem::NoRoad => "=",
+            Problem::NoPower => "*",
+            Problem::NoWater => "o",
+            Problem::Abandoned => "x",
+            Problem::HighCrime => "!",
+            Problem::Polluted => "%",
+            Problem::Jammed => "~",
+        }
+    }
+}
+
+/// Shared mesh and per-problem materials of the badges.
+#[derive(Resource)]
+struct IconAssets {
+    mesh: Handle<Mesh>,
+    /// Indexed by `Problem`.
+    materials: [Handle<ColorMaterial>; Problem::ALL.len()],
+}
+
+/// Parent of every icon, so hiding it hides them all.
+#[derive(Component)]
+struct ProblemIcons;
+
+/// The icon over the tile at `coord`.
+#[derive(Component)]
+struct ProblemIcon {
+    coord: IVec2,
+    problem: Problem,
+}
+
+fn create_icon_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(IconAssets {
+        mesh: meshes.add(Circle::new(1.0)),
+        materials: Problem::ALL.map(|problem| materials.add(problem.color())),
+    });
+}
+
+fn spawn_problem_icons(mut commands: Commands, layers: Res<MapLayers>) {
+    commands.spawn((
+        ProblemIcons,
+        Transform::default(),
+        Visibility::Inherited,
+        ChildOf(layers.root(MapLayer::Markers)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+fn toggle_problem_icons(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut root: Query<&mut Visibility, With<ProblemIcons>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyI) {
+        return;
+    }
+    for mut visibility in root.iter_mut() {
+        visibility.toggle_inherited_hidden();
+    }
+}
+
+/// The grid layers problems are read from.
+struct Layers<'a> {
+    zones: &'a ZoneGrid,
+    neighborhood: Neighborhood,
+    fire: &'a FireGrid,
+    traffic: &'a TrafficGrid,
+    water: &'a WaterSupply,
+    power: &'a PowerGrid,
+    garbage: &'a Garbage,
+    jobs: &'a JobAccess,
+    population: &'a PopulationGrid,
+}
+
+/// Whether a finished building at `coord` stands empty with nobody to move
+/// in.
+fn abandoned(coord: IVec2, zone: Zone, data: &TileData, layers: &Layers) -> bool {
+    if data.zoned || data.construction > 0 || data.population + data.jobs > 0 {
+        return false;
+    }
+    match zone {
+        Zone::Residential => layers.jobs.unfilled(coord) == 0,
+        Zone::Commercial => {
+            let customers = layers.population.within(coord, CUSTOMER_RADIUS);
+            commercial_capacity(customers, u32::MAX) == 0
+        }
+        _ => false,
+    }
+}
+
+/// The worst problem of the building at `coord`, if it has one, with how
+/// bad it is for ranking tiles with the same problem.
+fn problem_at(
+    coord: IVec2,
+    zone: Zone,
+    data: &TileData,
+    layers: &Layers,
+) -> Option<(Problem, u32)> {
+    if !matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial) {
+        return None;
+    }
+    let pollution = layers.garbage.pollution(coord);
+    if layers.fire.burning(coord) {
+        Some((Problem::OnFire, 0))
+    } else if !layers.zones.next_to_road(coord, layers.neighborhood) {
+        Some((Problem::NoRoad, 0))
+    } else if !layers.power.powered(coord) {
+        Some((Problem::NoPower, 0))
+    } else if !layers.water.watered(coord) {
+        Some((Problem::NoWater, 0))
+    } else if abandoned(coord, zone, data, layers) {
+        Some((Problem::Abandoned, 0))
+    } else if zone != Zone::Industrial && data.crime >= HIGH_CRIME {
+        Some((Problem::HighCrime, data.crime))
+    } else if zone != Zone::Industrial && pollution >= HIGH_POLLUTION {
+        Some((Problem::Polluted, pollution))
+    } else if layers.traffic.congested_near(coord, layers.neighborhood) {
+        Some((Problem::Jammed, 0))
+    } else {
+        None
+    }
+}
+
+/// Once a tick, put icons over the `MAX_ICONS` worst problems, keeping the
+/// icons that are still right and replacing the rest.
+#[allow(clippy::too_many_arguments)]
+fn update_problem_icons(
+    mut commands: Commands,
+    timer: Res<SimTimer>,
+    assets: Res<IconAssets>,
+    map: Res<MapConfig>,
+    neighborhood: Res<Neighborhood>,
+    (fire, traffic, water): (Res<FireGrid>, Res<TrafficGrid>, Res<WaterSupply>),
+    (power, garbage): (Res<PowerGrid>, Res<Garbage>),
+    (jobs, population): (Res<JobAccess>, Res<PopulationGrid>),
+    tiles: Query<(&TileCoord, &Zone, &TileData)>,
+    root: Query<(Entity, &Visibility), With<ProblemIcons>>,
+    icons: Query<(Entity, &ProblemIcon)>,
+) {
+    let Ok((root, visibility)) = root.single() else {
+        return;
+    };
+    if !timer.0.just_finished() || *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let mut zones = ZoneGrid::new(map.width, map.height);
+    for (coord, zone, _) in tiles.iter() {
+        zones.set(coord.coord, *zone);
+    }
+    let layers = Layers {
+        zones: &zones,
+        neighborhood: *neighborhood,
+        fire: &fire,
+        traffic: &traffic,
+        water: &water,
+        power: &power,
+        garbage: &garbage,
+        jobs: &jobs,
+        population: &population,
+    };
+    let mut problems: Vec<(IVec2, Problem, u32)> = tiles
+        .iter()
+        .filter_map(|(coord, zone, data)| {
+            let (problem, severity) = problem_at(coord.coord, *zone, data, &layers)?;
+            Some((coord.coord, problem, severity))
+        })
+        .collect();
+    // Ties go to the lower row, then column, so the same city always shows
+    // the same icons.
+    problems.sort_by_key(|&(coord, problem, severity)| {
+        (problem, Reverse(severity), coord.y, coord.x)
+    });
+    problems.truncate(MAX_ICONS);
+
+    for (entity, icon) in icons.iter() {
+        let keep = problems
+            .iter()
+            .position(|&(coord, problem, _)| coord == icon.coord && problem == icon.problem);
+        match keep {
+            Some(i) => {
+                problems.swap_remove(i);
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for (coord, problem, _) in problems {
+        let center = tile_to_world(&map, coord) + Vec2::Y * map.tile_size * ICON_RISE;
+        let radius = map.tile_size * ICON_RADIUS;
+        commands.entity(root).with_child((
+            Transform::from_translation(center.extend(ICON_Z)),
+            Visibility::default(),
+            ProblemIcon { coord, problem },
+            children![
+                (
+                    Mesh2d(assets.mesh.clone()),
+                    MeshMaterial2d(assets.materials[problem as usize].clone()),
+                    Transform::from_scale(Vec3::splat(radius)),
+                ),
+                (
+                    Text2d::new(problem.glyph()),
+                    TextFont {
+                        font_size: radius * 1.4,
+                        ..default()
+                    },
+                    TextColor(Color::BLACK),
+                    Transform::from_xyz(0.0, 0.0, 0.01),
+                ),
+            ],
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::development::Development;
+use crate::elevation::Elevation;
+use crate::input::{rezone, TileEdit, UndoStack};
+use crate::locale::Locale;
+use crate::map::{TileData, TileMap, Zone};
+use crate::milestones::Milestones;
+use crate::replay::Playback;
+use crate::simulation::{CityStats, NewCity};
+use crate::state::{GameSet, GameState};
+use crate::zone_defs::ZoneDefs;
+
+/// Where the endpoint listens. Only local tools can reach it.
+pub const REMOTE_ADDRESS: &str = "127.0.0.1:15702";
+/// Longest a connection waits for the game to answer, e.g. while a frame
+/// is stalled loading a save.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest request body accepted.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// A small JSON-over-HTTP endpoint for scripts and tests (`--remote`):
+///
+/// - `GET /stats`: population, jobs, money, and the tick count.
+/// - `GET /tiles`: the zone and data of every tile, row by row.
+/// - `POST /zone` with `{"x": 3, "y": 4, "zone": "Road"}`: zone a tile
+///   exactly as a click with the brush would, paying for it and going into
+///   undo and the replay recording. A multi-tile zone builds the whole
+///   building anchored there.
+///
+/// Requests are answered by `serve_remote` ahead of each frame's tick, so
+/// they always see a consistent city, and edits are recorded on the tick
+/// they land on.
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        match TcpListener::bind(REMOTE_ADDRESS) {
+            Ok(listener) => {
+                info!("Serving the city on http://{REMOTE_ADDRESS}");
+                thread::spawn(move || accept_requests(listener, sender));
+            }
+            Err(err) => error!("Could not listen on {REMOTE_ADDRESS}: {err}"),
+        }
+        app.insert_resource(RemoteRequests(Mutex::new(receiver)))
+            .add_message::<TileEdit>()
+            .add_systems(Update, serve_remote.before(GameSet::Simulation));
+    }
+}
+
+/// A parsed HTTP request and where to send its response.
+struct RemoteRequest {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+    reply: Sender<RemoteResponse>,
+}
+
+struct RemoteResponse {
+    status: u16,
+    body: serde_json::Value,
+}
+
+impl RemoteResponse {
+    fn ok(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Requests waiting for the next frame. The receiver is only ever used by
+/// `serve_remote`; the lock just makes it shareable as a resource.
+#[derive(Resource)]
+struct RemoteRequests(Mutex<Receiver<RemoteRequest>>);
+
+/// Body of `POST /zone`.
+#[derive(Deserialize)]
+struct ZoneCommand {
+    x: i32,
+    y: i32,
+    zone: Zone,
+}
+
+/// One entry of `GET /tiles`.
+#[derive(Serialize)]
+struct TileJson {
+    x: i32,
+    y: i32,
+    zone: Zone,
+    #[serde(flatten)]
+    data: TileData,
+}
+
+/// Network thread: read each connection's request, hand it to the game,
+/// and write back whatever the game answers.
+fn accept_requests(listener: TcpListener, requests: Sender<RemoteRequest>) {
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let response = match read_request(&stream) {
+            Ok((method, path, body)) => {
+                let (reply, answer) = mpsc::channel();
+                let request = RemoteRequest {
+                    method,
+                    path,
+                    body,
+                    reply,
+                };
+                if requests.send(request).is_err() {
+                    return;
+                }
+                answer
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| RemoteResponse::error(503, "the game did not answer"))
+            }
+            Err(err) => RemoteResponse::error(400, err),
+        };
+        if let Err(err) = write_response(&mut stream, &response) {
+            warn!("Could not answer a remote request: {err}");
+        }
+    }
+}
+
+/// Method, path, and body of an HTTP/1.1 request.
+fn read_request(stream: &TcpStream) -> Result<(String, String, Vec<u8>), String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".to_string());
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("bad Content-Length {}", value.trim()))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(format!("body over {MAX_BODY_BYTES} bytes"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    Ok((method, path, body))
+}
+
+fn write_response(stream: &mut TcpStream, response: &RemoteResponse) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {reason}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        response.status,
+        body.len()
+    )
+}
+
+/// Answer every request that arrived since the last frame.
+#[allow(clippy::too_many_arguments)]
+fn serve_remote(
+    requests: Res<RemoteRequests>,
+    state: Option<Res<State<GameState>>>,
+    playback: Option<Res<Playback>>,
+    map: Res<MapConfig>,
+    clock: Res<TimeOfDay>,
+    tile_map: Option<Res<TileMap>>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut stats: ResMut<CityStats>,
+    milestones: Res<Milestones>,
+    elevation: Res<Elevation>,
+    (defs, development): (Res<ZoneDefs>, Res<Development>),
+    new_city: Res<NewCity>,
+    mut undo: ResMut<UndoStack>,
+    mut edits: MessageWriter<TileEdit>,
+    locale: Res<Locale>,
+) {
+    let Ok(receiver) = requests.0.lock() else {
+        return;
+    };
+    let running = state.is_some_and(|state| *state.get() == GameState::Running);
+    for request in receiver.try_iter() {
+        let response = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/stats") => RemoteResponse::ok(json!({
+                "tick": clock.ticks,
+                "population": stats.population,
+                "jobs": stats.jobs,
+                "money": stats.money,
+            })),
+            ("GET", "/tiles") => {
+                let grid: Vec<TileJson> = (0..map.height)
+                    .flat_map(|y| (0..map.width).map(move |x| IVec2::new(x, y)))
+                    .filter_map(|coord| {
+                        let entity = tile_map.as_ref()?.get(coord)?;
+                        let (zone, data) = tiles.get(entity).ok()?;
+                        Some(TileJson {
+                            x: coord.x,
+                            y: coord.y,
+                            zone: *zone,
+                            data: *data,
+                        })
+                    })
+                    .collect();
+                RemoteResponse::ok(json!({
+                    "width": map.width,
+                    "height": map.height,
+                    "tiles": grid,
+                }))
+            }
+            ("POST", "/zone") => match (&tile_map, serde_json::from_slice(&request.body)) {
+                _ if !running => RemoteResponse::error(409, "the city is not running"),
+                _ if playback.is_some() => {
+                    RemoteResponse::error(409, "a replay is playing back")
+                }
+                (_, Err(err)) => RemoteResponse::error(400, err.to_string()),
+                (None, _) => RemoteResponse::error(409, "the map is not built yet"),
+                (Some(tile_map), Ok(ZoneCommand { x, y, zone })) => {
+                    let coord = IVec2::new(x, y);
+                    let rezoned = rezone(
+                        coord,
+                        zone,
+                        tile_map,
+                        &mut tiles,
+                        &mut stats,
+                        &milestones,
+                        &elevation,
+                        &defs,
+                        *development,
+                        new_city.seed,
+                    );
+                    match rezoned {
+                        Ok(action) => {
+                            let cost = action.cost;
+                            edits.write(TileEdit {
+                                tiles: action.changes.iter().map(|c| (c.coord, c.after)).collect(),
+                                cost,
+                            });
+                            undo.push(action);
+                            RemoteResponse::ok(json!({ "cost": cost, "money": stats.money }))
+                        }
+                        Err(reason) => RemoteResponse::error(409, locale.english(reason)),
+                    }
+                }
+            },
+            _ => RemoteResponse::error(404, "no such endpoint"),
+        };
+        // The client may have given up waiting.
+        let _ = request.reply.send(response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+    use crate::bench::{advance, bench_app};
+
+    const SIZE: i32 = 16;
+    /// Ticks to run after zoning: enough for the buildings to go up and the
+    /// first residents to move in.
+    const TICKS: u64 = 40;
+
+    /// The simulation with the endpoint served on a free port, and where.
+    fn remote_app() -> (App, SocketAddr) {
+        let layout: Vec<_> = (0..SIZE * SIZE)
+            .map(|i| (IVec2::new(i % SIZE, i / SIZE), Zone::Empty, TileData::default()))
+            .collect();
+        let mut app = bench_app(SIZE, &layout);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || accept_requests(listener, sender));
+        app.insert_resource(RemoteRequests(Mutex::new(receiver)))
+            .insert_resource(State::new(GameState::Running))
+            .insert_resource(Locale::load("en"))
+            .init_resource::<Milestones>()
+            .init_resource::<UndoStack>()
+            .add_message::<TileEdit>()
+            .add_systems(Update, serve_remote.before(GameSet::Simulation));
+        (app, address)
+    }
+
+    /// Send one request, running frames until it is answered, and return
+    /// the status and JSON body of the response. Time stands still in
+    /// those frames, so the simulation doesn't tick.
+    fn call(app: &mut App, address: SocketAddr, request: &str) -> (u16, serde_json::Value) {
+        let (method, rest) = request.split_once(' ').unwrap();
+        let (path, body) = rest.split_once(' ').unwrap_or((rest, ""));
+        let text = format!(
+            "{method} {path} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(text.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::ZERO);
+        while !client.is_finished() {
+            app.update();
+            thread::sleep(Duration::from_millis(1));
+        }
+        let response = client.join().unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn a_home_zoned_remotely_grows() {
+        let (mut app, address) = remote_app();
+        let (status, stats) = call(&mut app, address, "GET /stats");
+        assert_eq!(status, 200);
+        assert_eq!(stats["population"], 0);
+        let money = stats["money"].as_i64().unwrap();
+
+        let (status, road) = call(&mut app, address, r#"POST /zone {"x":5,"y":5,"zone":"Road"}"#);
+        assert_eq!(status, 200);
+        let (status, home) =
+            call(&mut app, address, r#"POST /zone {"x":5,"y":6,"zone":"Residential"}"#);
+        assert_eq!(status, 200);
+        let cost = road["cost"].as_i64().unwrap() + home["cost"].as_i64().unwrap();
+        assert!(cost > 0);
+        assert_eq!(home["money"].as_i64().unwrap(), money - cost);
+        // Homes only grow with jobs in reach.
+        let work = r#"POST /zone {"x":5,"y":4,"zone":"Industrial"}"#;
+        assert_eq!(call(&mut app, address, work).0, 200);
+
+        for _ in 0..TICKS {
+            advance(app.world_mut());
+            app.update();
+        }
+        let (_, stats) = call(&mut app, address, "GET /stats");
+        assert_eq!(stats["tick"], TICKS);
+        assert!(stats["population"].as_u64().unwrap() > 0);
+
+        let (status, grid) = call(&mut app, address, "GET /tiles");
+        assert_eq!(status, 200);
+        let tile = &grid["tiles"][(6 * SIZE + 5) as usize];
+        assert_eq!((&tile["x"], &tile["y"]), (&json!(5), &json!(6)));
+        assert_eq!(tile["zone"], "Residential");
+        assert!(tile["population"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn bad_requests_are_turned_away() {
+        let (mut app, address) = remote_app();
+        assert_eq!(call(&mut app, address, "GET /nowhere").0, 404);
+        assert_eq!(call(&mut app, address, "POST /zone {\"x\":1}").0, 400);
+        let road = r#"POST /zone {"x":1,"y":1,"zone":"Road"}"#;
+        assert_eq!(call(&mut app, address, road).0, 200);
+        assert_eq!(call(&mut app, address, road).0, 409);
+        let off_map = r#"POST /zone {"x":-1,"y":1,"zone":"Road"}"#;
+        assert_eq!(call(&mut app, address, off_map).0, 409);
+
+        app.insert_resource(State::new(GameState::Paused));
+        let elsewhere = r#"POST /zone {"x":2,"y":2,"zone":"Road"}"#;
+        assert_eq!(call(&mut app, address, elsewhere).0, 409);
+    }
+}
@@ -0,0 +1,642 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::grid::Neighborhood;
+use crate::input::{BrushSize, BuildTool, TileEdit, TileSnapshot};
+use crate::loans::TakeLoan;
+use crate::locale::Phrase;
+use crate::map::{TileData, TileMap, Zone};
+use crate::menu::{apply_pending_city, PendingCity};
+use crate::oneway::{DirectionEdit, RoadDirection};
+use crate::save::{
+    CityState, SaveFile, SaveFileV0, SaveFileV1, SaveFileV2, SaveFileV3, SaveFileV4, SaveFileV5,
+    SaveFileV6, SaveFileV7, SaveFileV8, SaveFileV9, SaveFileV10, SaveFileV11, SaveHeader,
+};
+use crate::simulation::{simulation_step, CityStats, NewCity};
+use crate::state::{AppState, GameSet, GameState, ResetCity};
+use crate::ui::Notification;
+use crate::water::{PipeEdit, Pipes};
+
+/// Where the current session is recorded, relative to the working
+/// directory.
+pub const RECORDING_PATH: &str = "replay.ron";
+
+/// Records every player action with the tick it happened on, and plays a
+/// recording back (`--replay`). The simulation is deterministic given the
+/// seed, so playing back a recording rebuilds the same city.
+pub struct ReplayPlugin {
+    /// Whether to write the session to `RECORDING_PATH`.
+    pub record: bool,
+    /// A recording to play back instead of letting the player build.
+    pub playback: Option<(String, Replay)>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some((path, replay)) = &self.playback {
+            app.insert_resource(Playback {
+                path: path.clone(),
+                replay: replay.clone(),
+                next: 0,
+                started: false,
+            });
+        }
+        app.insert_resource(Recorder {
+            enabled: self.record,
+            ..default()
+        })
+        .add_message::<TileEdit>()
+        .add_message::<PipeEdit>()
+        .add_message::<DirectionEdit>()
+        .add_message::<TakeLoan>()
+        .add_message::<Notification>()
+        .add_systems(OnEnter(AppState::MainMenu), start_playback)
+        .add_systems(
+            OnEnter(GameState::Running),
+            begin_recording.after(apply_pending_city),
+        )
+        .add_systems(
+            Update,
+            play_back
+                .in_set(GameSet::Input)
+                .run_if(resource_exists::<Playback>),
+        )
+        .add_systems(
+            Update,
+            record_actions
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        )
+        .add_systems(ResetCity, stop_recording)
+        .add_systems(OnEnter(GameState::Restarting), stop_playback)
+        .add_systems(OnExit(AppState::Playing), (save_recording, stop_playback))
+        .add_systems(Last, save_recording_on_exit);
+    }
+}
+
+/// A recorded session: the city it started from, and everything the player
+/// did to it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub start: SaveFile,
+    pub actions: Vec<ReplayEvent>,
+    /// The city when recording stopped, to check playback against.
+    pub end: Option<ReplayEnd>,
+}
+
+/// The format version of a recording's starting city, which decides how
+/// the rest of it is read.
+#[derive(Deserialize)]
+struct ReplayHeader {
+    start: SaveHeader,
+}
+
+/// A recording whose starting city is saved in an older format.
+#[derive(Deserialize)]
+struct OldReplay<Start> {
+    seed: u64,
+    start: Start,
+    actions: Vec<ReplayEvent>,
+    end: Option<ReplayEnd>,
+}
+
+impl<Start: Into<SaveFile>> From<OldReplay<Start>> for Replay {
+    fn from(old: OldReplay<Start>) -> Self {
+        Self {
+            seed: old.seed,
+            start: old.start.into(),
+            actions: old.actions,
+            end: old.end,
+        }
+    }
+}
+
+impl Replay {
+    /// Parse and sanity-check a recording, migrating a starting city saved
+    /// in an older format.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let header: ReplayHeader = ron::from_str(text).map_err(|e| e.to_string())?;
+        let replay = match header.start.version()? {
+            0 => ron::from_str::<OldReplay<SaveFileV0>>(text).map(Replay::from),
+            1 => ron::from_str::<OldReplay<SaveFileV1>>(text).map(Replay::from),
+            2 => ron::from_str::<OldReplay<SaveFileV2>>(text).map(Replay::from),
+            3 => ron::from_str::<OldReplay<SaveFileV3>>(text).map(Replay::from),
+            4 => ron::from_str::<OldReplay<SaveFileV4>>(text).map(Replay::from),
+            5 => ron::from_str::<OldReplay<SaveFileV5>>(text).map(Replay::from),
+            6 => ron::from_str::<OldReplay<SaveFileV6>>(text).map(Replay::from),
+            7 => ron::from_str::<OldReplay<SaveFileV7>>(text).map(Replay::from),
+            8 => ron::from_str::<OldReplay<SaveFileV8>>(text).map(Replay::from),
+            9 => ron::from_str::<OldReplay<SaveFileV9>>(text).map(Replay::from),
+            10 => ron::from_str::<OldReplay<SaveFileV10>>(text).map(Replay::from),
+            11 => ron::from_str::<OldReplay<SaveFileV11>>(text).map(Replay::from),
+            _ => ron::from_str::<Replay>(text),
+        }
+        .map_err(|e| e.to_string())?;
+        replay.start.validate()?;
+        if replay.actions.windows(2).any(|w| w[0].tick > w[1].tick) {
+            return Err("actions must be in tick order".to_string());
+        }
+        Ok(replay)
+    }
+}
+
+/// A player action, applied after `tick` simulation ticks and before the
+/// next one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub action: ReplayAction,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ReplayAction {
+    SelectTool { zone: Zone, brush: BrushSize },
+    /// Tiles set by painting, pasting, undo, or redo, and what it cost.
+    Edit {
+        tiles: Vec<(IVec2, TileSnapshot)>,
+        cost: i64,
+    },
+    /// Pipe laid (`true`) or dug up under each tile, and what it cost.
+    Pipes { tiles: Vec<(IVec2, bool)>, cost: i64 },
+    /// Roads turned to a new direction with the direction tool.
+    Directions { tiles: Vec<(IVec2, RoadDirection)> },
+    /// A loan requested from the budget panel, granted or not.
+    TakeLoan { principal: i64 },
+    /// The diagonal roads setting switched in the pause menu.
+    SetNeighborhood { neighborhood: Neighborhood },
+}
+
+/// City totals at the end of a recording.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ReplayEnd {
+    pub tick: u64,
+    pub population: u32,
+    pub jobs: u32,
+    pub money: i64,
+}
+
+impl ReplayEnd {
+    fn of(stats: &CityStats, clock: &TimeOfDay) -> Self {
+        Self {
+            tick: clock.ticks,
+            population: stats.population,
+            jobs: stats.jobs,
+            money: stats.money,
+        }
+    }
+}
+
+/// The session being recorded, once the city has been built.
+#[derive(Resource, Default)]
+struct Recorder {
+    enabled: bool,
+    replay: Option<Replay>,
+    /// Tool and brush as of the last recorded `SelectTool`.
+    tool: Option<(Zone, BrushSize)>,
+    /// Neighborhood rule as of the start or the last `SetNeighborhood`.
+    neighborhood: Neighborhood,
+}
+
+/// A recording being played back. Player edits are disabled while this
+/// exists; it is removed once playback finishes or the city is left.
+#[derive(Resource)]
+pub struct Playback {
+    path: String,
+    replay: Replay,
+    /// Index of the next action to apply.
+    next: usize,
+    started: bool,
+}
+
+/// Skip the main menu and build the recorded city.
+fn start_playback(
+    mut commands: Commands,
+    playback: Option<ResMut<Playback>>,
+    mut map: ResMut<MapConfig>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    if playback.started {
+        return;
+    }
+    playback.started = true;
+    let start = &playback.replay.start;
+    map.width = start.width;
+    map.height = start.height;
+    commands.insert_resource(PendingCity {
+        layout: start.clone(),
+        notice: Some(Phrase::new("notify.replaying").with("path", &playback.path)),
+    });
+    next_state.set(AppState::Playing);
+}
+
+/// Start recording from the city as it is once it first runs.
+fn begin_recording(
+    mut recorder: ResMut<Recorder>,
+    playback: Option<Res<Playback>>,
+    city: CityState,
+    new_city: Res<NewCity>,
+) {
+    if !recorder.enabled || recorder.replay.is_some() || playback.is_some() {
+        return;
+    }
+    let start = city.save();
+    recorder.neighborhood = start.neighborhood;
+    recorder.replay = Some(Replay {
+        seed: new_city.seed,
+        start,
+        actions: Vec::new(),
+        end: None,
+    });
+}
+
+/// Log tool changes, map edits, pipe laid, road directions set, loan
+/// requests, and neighborhood switches made this frame against the clock,
+/// which has not yet advanced for this frame's tick.
+#[allow(clippy::too_many_arguments)]
+fn record_actions(
+    mut recorder: ResMut<Recorder>,
+    tool: Res<BuildTool>,
+    neighborhood: Res<Neighborhood>,
+    clock: Res<TimeOfDay>,
+    mut edits: MessageReader<TileEdit>,
+    mut pipe_edits: MessageReader<PipeEdit>,
+    mut direction_edits: MessageReader<DirectionEdit>,
+    mut loan_requests: MessageReader<TakeLoan>,
+) {
+    let recorder = &mut *recorder;
+    let Some(replay) = &mut recorder.replay else {
+        edits.clear();
+        pipe_edits.clear();
+        direction_edits.clear();
+        loan_requests.clear();
+        return;
+    };
+    let tick = clock.ticks;
+    if recorder.tool != Some((tool.zone, tool.brush)) {
+        recorder.tool = Some((tool.zone, tool.brush));
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::SelectTool {
+                zone: tool.zone,
+                brush: tool.brush,
+            },
+        });
+    }
+    if recorder.neighborhood != *neighborhood {
+        recorder.neighborhood = *neighborhood;
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::SetNeighborhood {
+                neighborhood: *neighborhood,
+            },
+        });
+    }
+    for edit in edits.read() {
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::Edit {
+                tiles: edit.tiles.clone(),
+                cost: edit.cost,
+            },
+        });
+    }
+    for edit in pipe_edits.read() {
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::Pipes {
+                tiles: edit.tiles.clone(),
+                cost: edit.cost,
+            },
+        });
+    }
+    for edit in direction_edits.read() {
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::Directions {
+                tiles: edit.tiles.clone(),
+            },
+        });
+    }
+    for request in loan_requests.read() {
+        replay.actions.push(ReplayEvent {
+            tick,
+            action: ReplayAction::TakeLoan {
+                principal: request.principal,
+            },
+        });
+    }
+}
+
+/// Apply every recorded action due by now, then compare the city with the
+/// recording once its last tick has been simulated.
+#[allow(clippy::too_many_arguments)]
+fn play_back(
+    mut commands: Commands,
+    mut playback: ResMut<Playback>,
+    clock: Res<TimeOfDay>,
+    tile_map: Res<TileMap>,
+    mut tiles: Query<(&mut Zone, &mut TileData)>,
+    mut directions: Query<&mut RoadDirection>,
+    mut pipes: ResMut<Pipes>,
+    mut tool: ResMut<BuildTool>,
+    mut stats: ResMut<CityStats>,
+    mut neighborhood: ResMut<Neighborhood>,
+    mut loan_requests: MessageWriter<TakeLoan>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    while let Some(event) = playback.replay.actions.get(playback.next) {
+        if event.tick > clock.ticks {
+            break;
+        }
+        match &event.action {
+            ReplayAction::SelectTool { zone, brush } => {
+                tool.zone = *zone;
+                tool.brush = *brush;
+            }
+            ReplayAction::Edit { tiles: edited, cost } => {
+                for (coord, snapshot) in edited {
+                    let Some(entity) = tile_map.get(*coord) else {
+                        continue;
+                    };
+                    if let Ok((mut zone, mut data)) = tiles.get_mut(entity) {
+                        *zone = snapshot.zone;
+                        *data = snapshot.data;
+                    }
+                }
+                stats.money -= cost;
+            }
+            ReplayAction::Pipes { tiles: laid, cost } => {
+                for &(coord, pipe) in laid {
+                    pipes.set(coord, pipe);
+                }
+                stats.money -= cost;
+            }
+            ReplayAction::Directions { tiles: turned } => {
+                for &(coord, turned_to) in turned {
+                    let entity = tile_map.get(coord);
+                    if let Some(mut direction) = entity.and_then(|e| directions.get_mut(e).ok()) {
+                        *direction = turned_to;
+                    }
+                }
+            }
+            ReplayAction::TakeLoan { principal } => {
+                loan_requests.write(TakeLoan {
+                    principal: *principal,
+                });
+            }
+            ReplayAction::SetNeighborhood {
+                neighborhood: recorded,
+            } => *neighborhood = *recorded,
+        }
+        playback.next += 1;
+    }
+
+    let done = playback.next == playback.replay.actions.len();
+    let end = playback.replay.end;
+    let result = match end {
+        Some(end) if done && clock.ticks >= end.tick => {
+            let actual = ReplayEnd::of(&stats, &clock);
+            if actual == end {
+                info!("Replay finished at tick {}, matching the recording", end.tick);
+                Phrase::new("notify.replay_matched").with("tick", end.tick)
+            } else {
+                warn!("Replay diverged: recorded {end:?}, got {actual:?}");
+                Phrase::new("notify.replay_diverged").with("tick", end.tick)
+            }
+        }
+        None if done => {
+            info!("Replay finished");
+            Phrase::new("notify.replay_finished")
+        }
+        _ => return,
+    };
+    notifications.write(Notification(result));
+    commands.remove_resource::<Playback>();
+}
+
+/// A reset city starts a new recording once it runs again.
+fn stop_recording(mut recorder: ResMut<Recorder>) {
+    recorder.replay = None;
+    recorder.tool = None;
+}
+
+/// Anything after a restart or leaving the city was never recorded.
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+fn save_recording(
+    mut recorder: ResMut<Recorder>,
+    stats: Res<CityStats>,
+    clock: Res<TimeOfDay>,
+) {
+    let Some(mut replay) = recorder.replay.take() else {
+        return;
+    };
+    replay.end = Some(ReplayEnd::of(&stats, &clock));
+    match write_replay(RECORDING_PATH, &replay) {
+        Ok(()) => info!("Recorded this session to {RECORDING_PATH}"),
+        Err(err) => error!("Could not write {RECORDING_PATH}: {err}"),
+    }
+}
+
+/// Quitting from inside a city skips `OnExit(AppState::Playing)`.
+fn save_recording_on_exit(
+    recorder: ResMut<Recorder>,
+    stats: Res<CityStats>,
+    clock: Res<TimeOfDay>,
+    mut exits: MessageReader<AppExit>,
+) {
+    if exits.read().next().is_some() {
+        save_recording(recorder, stats, clock);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay(path: &str, replay: &Replay) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(replay, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_replay(path: &str) -> Result<Replay, String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| Replay::parse(&text))
+}
+
+/// Recording is switched off on the web, where there is no filesystem.
+#[cfg(target_arch = "wasm32")]
+fn write_replay(_path: &str, _replay: &Replay) -> Result<(), String> {
+    Err("recording is not available in the web build".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_replay(_path: &str) -> Result<Replay, String> {
+    Err("replays are not available in the web build".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::{advance, bench_app};
+    use crate::map::TileCoord;
+
+    const SIZE: i32 = 16;
+    /// Ticks the session runs for.
+    const TICKS: u64 = 60;
+    const COST_PER_TILE: i64 = 10;
+
+    /// A road across the middle and down the left edge, with empty land
+    /// everywhere else.
+    fn layout() -> Vec<(IVec2, Zone, TileData)> {
+        (0..SIZE * SIZE)
+            .map(|i| IVec2::new(i % SIZE, i / SIZE))
+            .map(|coord| {
+                let zone = if coord.y == 8 || coord.x == 0 {
+                    Zone::Road
+                } else {
+                    Zone::Empty
+                };
+                (coord, zone, TileData::default())
+            })
+            .collect()
+    }
+
+    /// What the player zones, and on which tick.
+    fn session() -> Vec<(u64, Zone, Vec<IVec2>)> {
+        let row = |y, xs: std::ops::Range<i32>| xs.map(|x| IVec2::new(x, y)).collect();
+        vec![
+            (1, Zone::Residential, row(9, 1..8)),
+            (4, Zone::Commercial, row(7, 1..4)),
+            (9, Zone::Industrial, row(7, 10..15)),
+            (15, Zone::Residential, row(9, 8..15)),
+            (30, Zone::Empty, row(9, 5..7)),
+        ]
+    }
+
+    fn tiles(app: &mut App) -> Vec<(IVec2, Zone, TileData)> {
+        let world = app.world_mut();
+        let mut tiles: Vec<_> = world
+            .query::<(&TileCoord, &Zone, &TileData)>()
+            .iter(world)
+            .map(|(coord, zone, data)| (coord.coord, *zone, *data))
+            .collect();
+        tiles.sort_by_key(|(coord, _, _)| (coord.y, coord.x));
+        tiles
+    }
+
+    fn end(app: &App) -> ReplayEnd {
+        ReplayEnd::of(app.world().resource::<CityStats>(), app.world().resource())
+    }
+
+    fn replay_app() -> App {
+        let mut app = bench_app(SIZE, &layout());
+        app.init_resource::<BuildTool>()
+            .add_message::<TileEdit>()
+            .add_message::<PipeEdit>()
+            .add_message::<DirectionEdit>()
+            .add_message::<TakeLoan>()
+            .add_message::<Notification>();
+        app
+    }
+
+    /// Play the session as the player would: paint, pay, and report the
+    /// edit, with the recorder listening.
+    fn record() -> (Replay, App) {
+        let mut app = replay_app();
+        let mut start = SaveFile::empty(SIZE, SIZE, 0, 0);
+        for (coord, zone, data) in layout() {
+            start.set(coord, zone, data);
+        }
+        app.insert_resource(Recorder {
+            enabled: true,
+            replay: Some(Replay {
+                seed: 0,
+                start,
+                actions: Vec::new(),
+                end: None,
+            }),
+            ..default()
+        })
+        .add_systems(Update, record_actions.before(simulation_step));
+
+        let session = session();
+        for tick in 0..TICKS {
+            for (_, zone, coords) in session.iter().filter(|(at, _, _)| *at == tick) {
+                let world = app.world_mut();
+                let snapshot = TileSnapshot {
+                    zone: *zone,
+                    data: TileData::default(),
+                };
+                for &coord in coords {
+                    let tile = world.resource::<TileMap>().get(coord).unwrap();
+                    let mut entity = world.entity_mut(tile);
+                    *entity.get_mut::<Zone>().unwrap() = snapshot.zone;
+                    *entity.get_mut::<TileData>().unwrap() = snapshot.data;
+                }
+                let cost = COST_PER_TILE * coords.len() as i64;
+                world.resource_mut::<CityStats>().money -= cost;
+                world.write_message(TileEdit {
+                    tiles: coords.iter().map(|&coord| (coord, snapshot)).collect(),
+                    cost,
+                });
+            }
+            advance(app.world_mut());
+            app.update();
+        }
+
+        let ended = end(&app);
+        let mut replay = app.world_mut().resource_mut::<Recorder>().replay.take().unwrap();
+        replay.end = Some(ended);
+        (replay, app)
+    }
+
+    #[test]
+    fn a_recorded_session_replays_to_the_same_city() {
+        let (replay, mut recorded) = record();
+        let edits = replay
+            .actions
+            .iter()
+            .filter(|event| matches!(event.action, ReplayAction::Edit { .. }))
+            .count();
+        assert_eq!(edits, session().len());
+        let text = ron::ser::to_string(&replay).unwrap();
+        let replay = Replay::parse(&text).unwrap();
+
+        let mut played = replay_app();
+        played
+            .insert_resource(Playback {
+                path: "test".to_string(),
+                replay: replay.clone(),
+                next: 0,
+                started: true,
+            })
+            .add_systems(Update, play_back.before(simulation_step));
+        for _ in 0..TICKS {
+            advance(played.world_mut());
+            played.update();
+        }
+
+        let ended = end(&played);
+        assert!(ended.population > 0 && ended.jobs > 0);
+        assert_eq!(Some(ended), replay.end);
+        let (recorded_stats, played_stats) = (
+            recorded.world().resource::<CityStats>(),
+            played.world().resource::<CityStats>(),
+        );
+        assert_eq!(recorded_stats.attractiveness, played_stats.attractiveness);
+        assert!(tiles(&mut recorded) == tiles(&mut played));
+        assert_eq!(played.world().resource::<Playback>().next, replay.actions.len());
+    }
+}
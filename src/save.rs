@@ -0,0 +1,1107 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::development::Development;
+use crate::districts::{District, Districts, MAX_DISTRICTS};
+use crate::economy::{ActiveEvent, Economy};
+use crate::elevation::{Elevation, MAX_LEVEL};
+use crate::garbage::{Garbage, LANDFILL_CAPACITY, MAX_POLLUTION};
+use crate::grid::Neighborhood;
+use crate::loans::{Loan, Loans};
+use crate::map::{TileCoord, TileData, Zone};
+use crate::milestones::Milestones;
+use crate::oneway::RoadDirection;
+use crate::ports::is_port;
+use crate::scenario::ActiveScenario;
+use crate::simulation::{CityStats, Ledger};
+use crate::trade::{Connection, Trade};
+use crate::water::Pipes;
+
+/// Where the city is saved, relative to the working directory.
+pub const SAVE_PATH: &str = "city.ron";
+/// Where the city is saved at the end of every month.
+pub const AUTOSAVE_PATH: &str = "autosave.ron";
+
+/// Format version written into every save. Changing `SaveFile` means
+/// bumping this, keeping the old layout as a `SaveFileV*` struct, and
+/// migrating it forward in `SaveFile::parse`.
+pub const SAVE_VERSION: u32 = 12;
+
+/// Everything needed to rebuild a city. Derived state (traffic, coverage,
+/// totals) is recomputed by the simulation after loading.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveFile {
+    /// Always `SAVE_VERSION` once loaded.
+    pub version: u32,
+    pub width: i32,
+    pub height: i32,
+    pub money: i64,
+    /// Simulation ticks since the city was founded.
+    pub ticks: u64,
+    /// Zone and data of every tile, row by row.
+    pub tiles: Vec<(Zone, TileData)>,
+    /// Loans still being repaid.
+    pub loans: Vec<Loan>,
+    /// Which tiles count as next to a road.
+    pub neighborhood: Neighborhood,
+    /// Budget accumulated this month, settled into `money` when it ends.
+    pub pending_budget: i64,
+    /// Economic events under way.
+    pub events: Vec<ActiveEvent>,
+    /// Populations of the milestones reached.
+    pub milestones: Vec<u32>,
+    /// Height level of every tile, row by row.
+    pub elevation: Vec<u8>,
+    /// Tiles with pipe laid under them, row by row.
+    pub pipes: Vec<IVec2>,
+    /// The named districts.
+    pub districts: Vec<District>,
+    /// District id of every tile, row by row, 0 for none.
+    pub district_ids: Vec<u16>,
+    /// Garbage buried in every landfill with any, row by row.
+    pub landfills: Vec<(IVec2, u32)>,
+    /// Pollution of every tile, row by row.
+    pub pollution: Vec<u8>,
+    /// Garbage made that nothing could take yet.
+    pub garbage_backlog: u32,
+    /// Whether zoned buildings go up at once or as demand calls for them.
+    pub development: Development,
+    /// Id of the scenario the city is playing, none in a sandbox.
+    pub scenario: Option<String>,
+    /// Roads out to neighboring cities.
+    pub trade: Vec<Connection>,
+    /// Every one-way road and the way it runs, row by row.
+    pub one_way: Vec<(IVec2, RoadDirection)>,
+}
+
+/// Just the format version of a save, read ahead of the rest so that a
+/// file from a newer build is turned away before its fields are parsed.
+#[derive(Deserialize)]
+pub struct SaveHeader {
+    /// Saves from before versioning have none, and count as version 0.
+    #[serde(default)]
+    version: u32,
+}
+
+impl SaveHeader {
+    /// The version, if this build knows how to read it.
+    pub fn version(&self) -> Result<u32, String> {
+        if self.version > SAVE_VERSION {
+            return Err(format!(
+                "the save is format version {}, but this build only reads up to version \
+                 {SAVE_VERSION}; it was made by a newer version of the game",
+                self.version
+            ));
+        }
+        Ok(self.version)
+    }
+}
+
+/// Version 0: saves from before the format was versioned. Loans and the
+/// road neighborhood were added along the way, so not every one has them.
+#[derive(Deserialize)]
+pub struct SaveFileV0 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    #[serde(default)]
+    loans: Vec<Loan>,
+    #[serde(default)]
+    neighborhood: Neighborhood,
+}
+
+impl From<SaveFileV0> for SaveFileV1 {
+    /// Missing loans mean none were taken, and a missing neighborhood the
+    /// four sides every city used before it could be changed.
+    fn from(old: SaveFileV0) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+        }
+    }
+}
+
+/// Version 1: from before the budget was settled monthly.
+#[derive(Deserialize)]
+pub struct SaveFileV1 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+}
+
+impl From<SaveFileV1> for SaveFileV2 {
+    /// The budget went straight into the treasury every tick, so nothing
+    /// is owed yet.
+    fn from(old: SaveFileV1) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: 0,
+        }
+    }
+}
+
+/// Version 2: from before economic events.
+#[derive(Deserialize)]
+pub struct SaveFileV2 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+}
+
+/// Version 3: from before population milestones.
+#[derive(Deserialize)]
+pub struct SaveFileV3 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+}
+
+impl From<SaveFileV2> for SaveFileV3 {
+    /// No events had happened yet.
+    fn from(old: SaveFileV2) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: Vec::new(),
+        }
+    }
+}
+
+/// Version 4: from before terrain elevation.
+#[derive(Deserialize)]
+pub struct SaveFileV4 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+}
+
+/// Version 5: from before water pipes.
+#[derive(Deserialize)]
+pub struct SaveFileV5 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+}
+
+/// Version 6: from before districts.
+#[derive(Deserialize)]
+pub struct SaveFileV6 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+}
+
+/// Version 7: from before garbage collection.
+#[derive(Deserialize)]
+pub struct SaveFileV7 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+    districts: Vec<District>,
+    district_ids: Vec<u16>,
+}
+
+/// Version 8: from before gradual development.
+#[derive(Deserialize)]
+pub struct SaveFileV8 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+    districts: Vec<District>,
+    district_ids: Vec<u16>,
+    landfills: Vec<(IVec2, u32)>,
+    pollution: Vec<u8>,
+    garbage_backlog: u32,
+}
+
+/// Version 9: from before scenarios.
+#[derive(Deserialize)]
+pub struct SaveFileV9 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+    districts: Vec<District>,
+    district_ids: Vec<u16>,
+    landfills: Vec<(IVec2, u32)>,
+    pollution: Vec<u8>,
+    garbage_backlog: u32,
+    development: Development,
+}
+
+/// Version 10: from before trade with neighboring cities.
+#[derive(Deserialize)]
+pub struct SaveFileV10 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+    districts: Vec<District>,
+    district_ids: Vec<u16>,
+    landfills: Vec<(IVec2, u32)>,
+    pollution: Vec<u8>,
+    garbage_backlog: u32,
+    development: Development,
+    scenario: Option<String>,
+}
+
+/// Version 11: from before one-way roads.
+#[derive(Deserialize)]
+pub struct SaveFileV11 {
+    width: i32,
+    height: i32,
+    money: i64,
+    ticks: u64,
+    tiles: Vec<(Zone, TileData)>,
+    loans: Vec<Loan>,
+    neighborhood: Neighborhood,
+    pending_budget: i64,
+    events: Vec<ActiveEvent>,
+    milestones: Vec<u32>,
+    elevation: Vec<u8>,
+    pipes: Vec<IVec2>,
+    districts: Vec<District>,
+    district_ids: Vec<u16>,
+    landfills: Vec<(IVec2, u32)>,
+    pollution: Vec<u8>,
+    garbage_backlog: u32,
+    development: Development,
+    scenario: Option<String>,
+    trade: Vec<Connection>,
+}
+
+impl From<SaveFileV0> for SaveFile {
+    fn from(old: SaveFileV0) -> Self {
+        SaveFileV1::from(old).into()
+    }
+}
+
+impl From<SaveFileV1> for SaveFile {
+    fn from(old: SaveFileV1) -> Self {
+        SaveFileV2::from(old).into()
+    }
+}
+
+impl From<SaveFileV2> for SaveFile {
+    fn from(old: SaveFileV2) -> Self {
+        SaveFileV3::from(old).into()
+    }
+}
+
+impl From<SaveFileV3> for SaveFile {
+    fn from(old: SaveFileV3) -> Self {
+        SaveFileV4::from(old).into()
+    }
+}
+
+impl From<SaveFileV3> for SaveFileV4 {
+    /// The save doesn't say which milestones were reached, so the city
+    /// counts as having reached every one up to the population it was
+    /// saved with, without being paid their bonuses again.
+    fn from(old: SaveFileV3) -> Self {
+        let population = old.tiles.iter().map(|(_, data)| data.population).sum();
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: Milestones::reached_by(population).reached,
+        }
+    }
+}
+
+impl From<SaveFileV4> for SaveFile {
+    fn from(old: SaveFileV4) -> Self {
+        SaveFileV5::from(old).into()
+    }
+}
+
+impl From<SaveFileV4> for SaveFileV5 {
+    /// Every map was flat.
+    fn from(old: SaveFileV4) -> Self {
+        Self {
+            elevation: vec![0; old.tiles.len()],
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+        }
+    }
+}
+
+impl From<SaveFileV5> for SaveFile {
+    fn from(old: SaveFileV5) -> Self {
+        SaveFileV6::from(old).into()
+    }
+}
+
+impl From<SaveFileV5> for SaveFileV6 {
+    /// No pipe had been laid.
+    fn from(old: SaveFileV5) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: Vec::new(),
+        }
+    }
+}
+
+impl From<SaveFileV6> for SaveFile {
+    fn from(old: SaveFileV6) -> Self {
+        SaveFileV7::from(old).into()
+    }
+}
+
+impl From<SaveFileV6> for SaveFileV7 {
+    /// No districts had been drawn.
+    fn from(old: SaveFileV6) -> Self {
+        Self {
+            district_ids: vec![0; old.tiles.len()],
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: Vec::new(),
+        }
+    }
+}
+
+impl From<SaveFileV7> for SaveFile {
+    fn from(old: SaveFileV7) -> Self {
+        SaveFileV8::from(old).into()
+    }
+}
+
+impl From<SaveFileV7> for SaveFileV8 {
+    /// No garbage had been made.
+    fn from(old: SaveFileV7) -> Self {
+        Self {
+            pollution: vec![0; old.tiles.len()],
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: old.districts,
+            district_ids: old.district_ids,
+            landfills: Vec::new(),
+            garbage_backlog: 0,
+        }
+    }
+}
+
+impl From<SaveFileV8> for SaveFile {
+    fn from(old: SaveFileV8) -> Self {
+        SaveFileV9::from(old).into()
+    }
+}
+
+impl From<SaveFileV8> for SaveFileV9 {
+    /// Every building went up the moment it was zoned.
+    fn from(old: SaveFileV8) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: old.districts,
+            district_ids: old.district_ids,
+            landfills: old.landfills,
+            pollution: old.pollution,
+            garbage_backlog: old.garbage_backlog,
+            development: Development::Instant,
+        }
+    }
+}
+
+impl From<SaveFileV9> for SaveFile {
+    fn from(old: SaveFileV9) -> Self {
+        SaveFileV10::from(old).into()
+    }
+}
+
+impl From<SaveFileV9> for SaveFileV10 {
+    /// Every city was a sandbox.
+    fn from(old: SaveFileV9) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: old.districts,
+            district_ids: old.district_ids,
+            landfills: old.landfills,
+            pollution: old.pollution,
+            garbage_backlog: old.garbage_backlog,
+            development: old.development,
+            scenario: None,
+        }
+    }
+}
+
+impl From<SaveFileV10> for SaveFile {
+    fn from(old: SaveFileV10) -> Self {
+        SaveFileV11::from(old).into()
+    }
+}
+
+impl From<SaveFileV10> for SaveFileV11 {
+    /// Roads already at the edge open their connections as the city
+    /// loads, so trade builds up from there.
+    fn from(old: SaveFileV10) -> Self {
+        Self {
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: old.districts,
+            district_ids: old.district_ids,
+            landfills: old.landfills,
+            pollution: old.pollution,
+            garbage_backlog: old.garbage_backlog,
+            development: old.development,
+            scenario: old.scenario,
+            trade: Vec::new(),
+        }
+    }
+}
+
+impl From<SaveFileV11> for SaveFile {
+    /// Every road ran both ways.
+    fn from(old: SaveFileV11) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            width: old.width,
+            height: old.height,
+            money: old.money,
+            ticks: old.ticks,
+            tiles: old.tiles,
+            loans: old.loans,
+            neighborhood: old.neighborhood,
+            pending_budget: old.pending_budget,
+            events: old.events,
+            milestones: old.milestones,
+            elevation: old.elevation,
+            pipes: old.pipes,
+            districts: old.districts,
+            district_ids: old.district_ids,
+            landfills: old.landfills,
+            pollution: old.pollution,
+            garbage_backlog: old.garbage_backlog,
+            development: old.development,
+            scenario: old.scenario,
+            trade: old.trade,
+            one_way: Vec::new(),
+        }
+    }
+}
+
+/// The parts of the world a save is taken from.
+#[derive(SystemParam)]
+pub struct CityState<'w, 's> {
+    tiles: Query<
+        'w,
+        's,
+        (
+            &'static TileCoord,
+            &'static Zone,
+            &'static TileData,
+            &'static RoadDirection,
+        ),
+    >,
+    map: Res<'w, MapConfig>,
+    stats: Res<'w, CityStats>,
+    clock: Res<'w, TimeOfDay>,
+    loans: Res<'w, Loans>,
+    neighborhood: Res<'w, Neighborhood>,
+    ledger: Res<'w, Ledger>,
+    economy: Res<'w, Economy>,
+    milestones: Res<'w, Milestones>,
+    elevation: Res<'w, Elevation>,
+    pipes: Res<'w, Pipes>,
+    districts: Res<'w, Districts>,
+    garbage: Res<'w, Garbage>,
+    development: Res<'w, Development>,
+    scenario: Res<'w, ActiveScenario>,
+    trade: Res<'w, Trade>,
+}
+
+impl CityState<'_, '_> {
+    /// The city as it stands.
+    pub fn save(&self) -> SaveFile {
+        let mut save =
+            SaveFile::empty(self.map.width, self.map.height, self.stats.money, self.clock.ticks);
+        for (coord, zone, data, direction) in self.tiles.iter() {
+            save.set(coord.coord, *zone, *data);
+            if *direction != RoadDirection::TwoWay {
+                save.one_way.push((coord.coord, *direction));
+            }
+        }
+        save.one_way.sort_by_key(|(coord, _)| (coord.y, coord.x));
+        save.loans = self.loans.active.clone();
+        save.neighborhood = *self.neighborhood;
+        save.pending_budget = self.ledger.pending;
+        save.events = self.economy.active.clone();
+        save.milestones = self.milestones.reached.clone();
+        save.elevation = self.elevation.levels().to_vec();
+        save.pipes = self.pipes.coords();
+        save.districts = self.districts.list().to_vec();
+        save.district_ids = self.districts.tile_ids().to_vec();
+        save.landfills = self.garbage.landfills();
+        save.pollution = self.garbage.pollution_levels().to_vec();
+        save.garbage_backlog = self.garbage.backlog;
+        save.development = *self.development;
+        save.scenario = self.scenario.0.as_ref().map(|scenario| scenario.id.to_string());
+        save.trade = self.trade.connections().to_vec();
+        save
+    }
+}
+
+impl SaveFile {
+    /// An all-empty map to fill in with `set`.
+    pub fn empty(width: i32, height: i32, money: i64, ticks: u64) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            width,
+            height,
+            money,
+            ticks,
+            tiles: vec![(Zone::Empty, TileData::default()); (width * height) as usize],
+            loans: Vec::new(),
+            neighborhood: Neighborhood::default(),
+            pending_budget: 0,
+            events: Vec::new(),
+            milestones: Vec::new(),
+            elevation: vec![0; (width * height) as usize],
+            pipes: Vec::new(),
+            districts: Vec::new(),
+            district_ids: vec![0; (width * height) as usize],
+            landfills: Vec::new(),
+            pollution: vec![0; (width * height) as usize],
+            garbage_backlog: 0,
+            development: Development::Instant,
+            scenario: None,
+            trade: Vec::new(),
+            one_way: Vec::new(),
+        }
+    }
+
+    /// Parse save file contents of any version this build understands,
+    /// migrating older ones forward, and sanity-check them.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let header: SaveHeader = ron::from_str(text).map_err(|e| e.to_string())?;
+        let save = match header.version()? {
+            0 => ron::from_str::<SaveFileV0>(text).map(SaveFile::from),
+            1 => ron::from_str::<SaveFileV1>(text).map(SaveFile::from),
+            2 => ron::from_str::<SaveFileV2>(text).map(SaveFile::from),
+            3 => ron::from_str::<SaveFileV3>(text).map(SaveFile::from),
+            4 => ron::from_str::<SaveFileV4>(text).map(SaveFile::from),
+            5 => ron::from_str::<SaveFileV5>(text).map(SaveFile::from),
+            6 => ron::from_str::<SaveFileV6>(text).map(SaveFile::from),
+            7 => ron::from_str::<SaveFileV7>(text).map(SaveFile::from),
+            8 => ron::from_str::<SaveFileV8>(text).map(SaveFile::from),
+            9 => ron::from_str::<SaveFileV9>(text).map(SaveFile::from),
+            10 => ron::from_str::<SaveFileV10>(text).map(SaveFile::from),
+            11 => ron::from_str::<SaveFileV11>(text).map(SaveFile::from),
+            _ => ron::from_str::<SaveFile>(text),
+        }
+        .map_err(|e| e.to_string())?;
+        save.validate()?;
+        Ok(save)
+    }
+
+    /// Check that the tiles, elevation, district ids, and pollution match
+    /// the map size, that every pipe is on the map, that every trade
+    /// connection is a road at its edge, that every one-way tile is a road,
+    /// that every tile's district is
+    /// listed once, that garbage is only buried in landfills
+    /// with room for it, that there is at most one of each port, and that
+    /// every multi-tile building is whole and its tiles agree on where its
+    /// anchor is.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width <= 0 || self.height <= 0 {
+            return Err(format!(
+                "map size must be positive, got {}x{}",
+                self.width, self.height
+            ));
+        }
+        if self.tiles.len() != (self.width * self.height) as usize {
+            return Err(format!(
+                "expected {} tiles for a {}x{} map, found {}",
+                self.width * self.height,
+                self.width,
+                self.height,
+                self.tiles.len()
+            ));
+        }
+        if self.elevation.len() != self.tiles.len() {
+            return Err(format!(
+                "expected {} elevation levels, found {}",
+                self.tiles.len(),
+                self.elevation.len()
+            ));
+        }
+        if let Some(level) = self.elevation.iter().find(|&&level| level > MAX_LEVEL) {
+            return Err(format!("elevation level {level} is above the highest, {MAX_LEVEL}"));
+        }
+        if let Some(pipe) = self.pipes.iter().find(|pipe| {
+            pipe.x < 0 || pipe.y < 0 || pipe.x >= self.width || pipe.y >= self.height
+        }) {
+            return Err(format!("the pipe at {pipe} is off the map"));
+        }
+        for connection in &self.trade {
+            let c = connection.coord;
+            let on_map = c.x >= 0 && c.y >= 0 && c.x < self.width && c.y < self.height;
+            let on_edge = c.x == 0 || c.y == 0 || c.x == self.width - 1 || c.y == self.height - 1;
+            if !on_map || !on_edge || !self.tile(c).0.is_road() {
+                return Err(format!("the trade connection at {c} isn't a road at the map edge"));
+            }
+        }
+        for &(c, _) in &self.one_way {
+            let on_map = c.x >= 0 && c.y >= 0 && c.x < self.width && c.y < self.height;
+            if !on_map || !self.tile(c).0.is_road() {
+                return Err(format!("the one-way road at {c} isn't a road"));
+            }
+        }
+        self.validate_districts()?;
+        self.validate_garbage()?;
+        for port in Zone::ALL.into_iter().filter(|&zone| is_port(zone)) {
+            let tiles = self.tiles.iter().filter(|(zone, _)| *zone == port).count();
+            if tiles > port.footprint().element_product() as usize {
+                return Err(format!("there is more than one {port:?}"));
+            }
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.validate_building(IVec2::new(x, y))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_districts(&self) -> Result<(), String> {
+        if self.district_ids.len() != self.tiles.len() {
+            return Err(format!(
+                "expected {} district ids, found {}",
+                self.tiles.len(),
+                self.district_ids.len()
+            ));
+        }
+        if self.districts.len() > MAX_DISTRICTS {
+            return Err(format!(
+                "{} districts, but a city has at most {MAX_DISTRICTS}",
+                self.districts.len()
+            ));
+        }
+        for (i, district) in self.districts.iter().enumerate() {
+            if district.id == 0 || self.districts[..i].iter().any(|d| d.id == district.id) {
+                return Err(format!("district id {} is reserved or used twice", district.id));
+            }
+        }
+        if let Some(id) = self
+            .district_ids
+            .iter()
+            .find(|&&id| id != 0 && self.districts.iter().all(|d| d.id != id))
+        {
+            return Err(format!("tiles are in district {id}, which isn't listed"));
+        }
+        Ok(())
+    }
+
+    fn validate_garbage(&self) -> Result<(), String> {
+        if self.pollution.len() != self.tiles.len() {
+            return Err(format!(
+                "expected {} pollution levels, found {}",
+                self.tiles.len(),
+                self.pollution.len()
+            ));
+        }
+        let worst = self.pollution.iter().find(|&&level| u32::from(level) > MAX_POLLUTION);
+        if let Some(level) = worst {
+            return Err(format!("pollution {level} is above the worst, {MAX_POLLUTION}"));
+        }
+        for &(coord, fill) in &self.landfills {
+            let on_map =
+                coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+            if !on_map || self.tile(coord).0 != Zone::Landfill {
+                return Err(format!("garbage is buried at {coord}, which isn't a landfill"));
+            }
+            if fill > LANDFILL_CAPACITY {
+                return Err(format!(
+                    "the landfill at {coord} holds {fill}, more than {LANDFILL_CAPACITY}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_building(&self, coord: IVec2) -> Result<(), String> {
+        let (zone, data) = self.tile(coord);
+        let Some(anchor) = data.anchor else {
+            if zone.is_multi_tile() {
+                return Err(format!("{zone:?} at {coord} has no anchor"));
+            }
+            return Ok(());
+        };
+        if !zone.covered_tiles(anchor).any(|tile| tile == coord) {
+            return Err(format!("{zone:?} at {coord} is not part of a building at {anchor}"));
+        }
+        for tile in zone.covered_tiles(anchor) {
+            let on_map = tile.x >= 0 && tile.y >= 0 && tile.x < self.width && tile.y < self.height;
+            if !on_map || self.tile(tile).0 != zone || self.tile(tile).1.anchor != Some(anchor) {
+                return Err(format!("the {zone:?} anchored at {anchor} is missing {tile}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tile at `coord`, which must be on the map.
+    pub fn tile(&self, coord: IVec2) -> (Zone, TileData) {
+        self.tiles[self.index(coord)]
+    }
+
+    pub fn set(&mut self, coord: IVec2, zone: Zone, data: TileData) {
+        let index = self.index(coord);
+        self.tiles[index] = (zone, data);
+    }
+
+    fn index(&self, coord: IVec2) -> usize {
+        (coord.y * self.width + coord.x) as usize
+    }
+}
+
+/// Whether there is a save to load at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_save(path: &str, save: &SaveFile) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_save(path: &str) -> Result<SaveFile, String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| SaveFile::parse(&text))
+}
+
+/// There is no filesystem on the web, so there is never a save.
+#[cfg(target_arch = "wasm32")]
+pub fn save_exists(_path: &str) -> bool {
+    false
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_save(_path: &str, _save: &SaveFile) -> Result<(), String> {
+    Err("saving is not available in the web build".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_save(_path: &str) -> Result<SaveFile, String> {
+    Err("loading is not available in the web build".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fields of a 2x1 map with a home next to a road, each with the format
+    /// version it joined the save in. A fixture of a version has every field
+    /// up to it.
+    const FIXTURE_FIELDS: [(u32, &str); 17] = [
+        (0, "width: 2, height: 1, money: 500, ticks: 40"),
+        (
+            0,
+            "tiles: [(Residential, (population: 12, jobs: 0, crime: 0)), \
+             (Road, (population: 0, jobs: 0, crime: 0))]",
+        ),
+        (1, "loans: [(principal: 100, balance: 60, ticks_left: 3)], neighborhood: Four"),
+        (2, "pending_budget: 7"),
+        (3, "events: []"),
+        (4, "milestones: []"),
+        (5, "elevation: [0, 1]"),
+        (6, "pipes: [(1, 0)]"),
+        (7, "districts: [(id: 1, name: \"Old Town\")]"),
+        (7, "district_ids: [1, 0]"),
+        (8, "landfills: []"),
+        (8, "pollution: [3, 0]"),
+        (8, "garbage_backlog: 2"),
+        (9, "development: Instant"),
+        (10, "scenario: None"),
+        (11, "trade: [(coord: (1, 0), opened: 10)]"),
+        (12, "one_way: [((1, 0), North)]"),
+    ];
+
+    fn fixture(version: u32) -> String {
+        let mut fields: Vec<String> = FIXTURE_FIELDS
+            .iter()
+            .filter(|(since, _)| *since <= version)
+            .map(|(_, field)| field.to_string())
+            .collect();
+        if version > 0 {
+            fields.insert(0, format!("version: {version}"));
+        }
+        format!("({})", fields.join(", "))
+    }
+
+    #[test]
+    fn every_version_loads() {
+        for version in 0..=SAVE_VERSION {
+            let save = SaveFile::parse(&fixture(version))
+                .unwrap_or_else(|e| panic!("version {version}: {e}"));
+            assert_eq!(save.version, SAVE_VERSION);
+            assert_eq!((save.width, save.height), (2, 1));
+            assert_eq!(save.money, 500);
+            assert_eq!(save.tile(IVec2::ZERO).0, Zone::Residential);
+            assert_eq!(save.tile(IVec2::ZERO).1.population, 12);
+            assert_eq!(save.tile(IVec2::X).0, Zone::Road);
+            let loans = if version >= 1 { 1 } else { 0 };
+            assert_eq!(save.loans.len(), loans, "version {version}");
+        }
+    }
+
+    #[test]
+    fn migrations_fill_in_what_old_saves_lack() {
+        let old = SaveFile::parse(&fixture(0)).unwrap();
+        assert!(old.loans.is_empty());
+        assert_eq!(old.pending_budget, 0);
+        assert_eq!(old.elevation, vec![0, 0]);
+        assert!(old.pipes.is_empty());
+        assert_eq!(old.district_ids, vec![0, 0]);
+        assert_eq!(old.pollution, vec![0, 0]);
+        assert_eq!(old.development, Development::Instant);
+        assert!(old.trade.is_empty());
+        assert!(old.one_way.is_empty());
+
+        let v11 = SaveFile::parse(&fixture(11)).unwrap();
+        assert_eq!(v11.pending_budget, 7);
+        assert_eq!(v11.elevation, vec![0, 1]);
+        assert_eq!(v11.pipes, vec![IVec2::X]);
+        assert_eq!(v11.district_ids, vec![1, 0]);
+        assert_eq!(v11.pollution, vec![3, 0]);
+        assert_eq!(v11.garbage_backlog, 2);
+        assert_eq!(v11.trade.len(), 1);
+        assert!(v11.one_way.is_empty());
+
+        let current = SaveFile::parse(&fixture(SAVE_VERSION)).unwrap();
+        assert_eq!(current.one_way, vec![(IVec2::X, RoadDirection::North)]);
+    }
+
+    #[test]
+    fn milestones_are_reached_by_the_population_saved() {
+        let old = SaveFile::parse(&fixture(3)).unwrap();
+        assert_eq!(old.milestones, Milestones::reached_by(12).reached);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let newer = fixture(SAVE_VERSION).replacen(
+            &format!("version: {SAVE_VERSION}"),
+            &format!("version: {}", SAVE_VERSION + 1),
+            1,
+        );
+        let error = SaveFile::parse(&newer).err().expect("a newer save should be refused");
+        assert!(error.contains("newer version"));
+    }
+
+    #[test]
+    fn round_trips() {
+        let save = SaveFile::parse(&fixture(SAVE_VERSION)).unwrap();
+        let text = ron::ser::to_string(&save).unwrap();
+        let again = SaveFile::parse(&text).unwrap();
+        assert!(again.tiles == save.tiles);
+        assert_eq!(again.one_way, save.one_way);
+    }
+
+    #[test]
+    fn rejects_buildings_hanging_off_the_map() {
+        let mut save = SaveFile::empty(2, 2, 0, 0);
+        let data = TileData {
+            anchor: Some(IVec2::NEG_ONE),
+            ..default()
+        };
+        save.set(IVec2::ZERO, Zone::PowerPlant, data);
+        assert!(save.validate().unwrap_err().contains("missing"));
+
+        let mut save = SaveFile::empty(2, 2, 0, 0);
+        let data = TileData {
+            anchor: Some(IVec2::new(1, 1)),
+            ..default()
+        };
+        save.set(IVec2::new(1, 1), Zone::PowerPlant, data);
+        assert!(save.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_one_way_tiles_off_the_road() {
+        let mut save = SaveFile::parse(&fixture(SAVE_VERSION)).unwrap();
+        save.one_way = vec![(IVec2::ZERO, RoadDirection::East)];
+        assert!(save.validate().is_err());
+        save.one_way = vec![(IVec2::new(-1, 0), RoadDirection::East)];
+        assert!(save.validate().is_err());
+    }
+}
@@ -0,0 +1,536 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::calendar::{GameClock, TICKS_PER_YEAR};
+use crate::day_night::TimeOfDay;
+use crate::game_over::{track_bankruptcy, Ending};
+use crate::garbage::Garbage;
+use crate::locale::{Locale, Phrase};
+use crate::map::Zone;
+use crate::menu::{generated_layout, PendingCity};
+use crate::milestones::Milestones;
+use crate::save::SaveFile;
+use crate::simulation::{simulation_step, CityStats, NewCity, SimTimer};
+use crate::state::{AppState, GameSet, GameState, ResetCity, SpawnSet};
+
+/// The scenarios that ship with the game, in the order the main menu
+/// offers them.
+const BUNDLED: [&str; 3] = [
+    include_str!("../assets/scenarios/river_town.ron"),
+    include_str!("../assets/scenarios/green_valley.ron"),
+    include_str!("../assets/scenarios/shoestring.ron"),
+];
+
+/// Scenarios: cities that start from a given map and treasury, with some
+/// buildings locked or unlocked, and objectives that end the game in a win
+/// or a loss once they are met or the deadline passes.
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scenarios::bundled())
+            .init_resource::<ActiveScenario>()
+            .add_systems(ResetCity, restart_scenario)
+            .add_systems(OnExit(AppState::Playing), leave_scenario)
+            .add_systems(
+                Update,
+                apply_scenario_rules.run_if(resource_changed::<ActiveScenario>),
+            )
+            .add_systems(
+                Update,
+                judge_scenario
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step)
+                    .before(track_bankruptcy),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_objectives_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(Update, update_objectives_panel.in_set(GameSet::Ui));
+    }
+}
+
+/// A check on the city, written in scenario files.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub enum Condition {
+    /// At least this many residents.
+    Population(u32),
+    /// At least this many jobs.
+    Jobs(u32),
+    /// At least this much in the treasury.
+    Money(i64),
+    /// Pollution across the map no worse than this on average.
+    Pollution(u32),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+/// What conditions are checked against, taken from the city each tick.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Facts {
+    pub population: u32,
+    pub jobs: u32,
+    pub money: i64,
+    /// Average pollution of every tile, up to `MAX_POLLUTION`.
+    pub pollution: u32,
+}
+
+impl Facts {
+    pub fn of(stats: &CityStats, garbage: &Garbage) -> Self {
+        let levels = garbage.pollution_levels();
+        let total: u32 = levels.iter().map(|&level| u32::from(level)).sum();
+        Self {
+            population: stats.population,
+            jobs: stats.jobs,
+            money: stats.money,
+            pollution: total.checked_div(levels.len() as u32).unwrap_or(0),
+        }
+    }
+}
+
+/// One line of the objectives panel: how deep it is nested, whether it
+/// holds, and what it says.
+type ObjectiveLine = (usize, bool, Phrase);
+
+impl Condition {
+    pub fn holds(&self, facts: &Facts) -> bool {
+        match self {
+            Condition::Population(target) => facts.population >= *target,
+            Condition::Jobs(target) => facts.jobs >= *target,
+            Condition::Money(target) => facts.money >= *target,
+            Condition::Pollution(limit) => facts.pollution <= *limit,
+            Condition::Not(condition) => !condition.holds(facts),
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(facts)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(facts)),
+        }
+    }
+
+    /// The panel lines for this condition and everything it combines, the
+    /// latter indented under it.
+    fn describe(&self, facts: &Facts, depth: usize, lines: &mut Vec<ObjectiveLine>) {
+        let phrase = match self {
+            Condition::Population(target) => Phrase::new("scenario.population")
+                .number("current", facts.population)
+                .number("target", *target),
+            Condition::Jobs(target) => Phrase::new("scenario.jobs")
+                .number("current", facts.jobs)
+                .number("target", *target),
+            Condition::Money(target) => Phrase::new("scenario.money")
+                .number("current", facts.money)
+                .number("target", *target),
+            Condition::Pollution(limit) => Phrase::new("scenario.pollution")
+                .number("current", facts.pollution)
+                .number("target", *limit),
+            Condition::Not(_) => Phrase::new("scenario.not"),
+            Condition::All(_) => Phrase::new("scenario.all"),
+            Condition::Any(_) => Phrase::new("scenario.any"),
+        };
+        lines.push((depth, self.holds(facts), phrase));
+        match self {
+            Condition::Not(condition) => condition.describe(facts, depth + 1, lines),
+            Condition::All(conditions) | Condition::Any(conditions) => {
+                for condition in conditions {
+                    condition.describe(facts, depth + 1, lines);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The panel lines for this condition, leaving out the "all of" a
+    /// list of objectives usually comes in.
+    fn lines(&self, facts: &Facts) -> Vec<ObjectiveLine> {
+        let mut lines = Vec::new();
+        match self {
+            Condition::All(conditions) => {
+                for condition in conditions {
+                    condition.describe(facts, 0, &mut lines);
+                }
+            }
+            _ => self.describe(facts, 0, &mut lines),
+        }
+        lines
+    }
+}
+
+/// How a scenario turned out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Won,
+    Lost,
+}
+
+/// A city to start from and the objectives to meet with it, read from a
+/// file in `assets/scenarios`.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct Scenario {
+    /// What saves record the scenario by.
+    pub id: &'static str,
+    /// Locale key of the name.
+    pub name: &'static str,
+    pub width: i32,
+    pub height: i32,
+    /// The map is generated from this when given, and blank otherwise.
+    pub seed: Option<u64>,
+    pub money: i64,
+    /// Buildings milestones would lock that are available from the start.
+    pub unlocked: Vec<Zone>,
+    /// Buildings that can't be used at all.
+    pub locked: Vec<Zone>,
+    pub win: Condition,
+    /// Fails the scenario as soon as it holds.
+    pub lose: Option<Condition>,
+    /// Years the city has to win in.
+    pub years: Option<u64>,
+}
+
+impl Scenario {
+    /// Parse a scenario file and check that its map has a size.
+    pub fn parse(text: &'static str) -> Result<Self, String> {
+        let scenario: Scenario = ron::from_str(text).map_err(|e| e.to_string())?;
+        if scenario.width <= 0 || scenario.height <= 0 {
+            return Err(format!(
+                "map size must be positive, got {}x{}",
+                scenario.width, scenario.height
+            ));
+        }
+        Ok(scenario)
+    }
+
+    /// The tick the city has until, if there is a deadline.
+    pub fn deadline(&self) -> Option<u64> {
+        self.years.map(|years| years * TICKS_PER_YEAR)
+    }
+
+    /// How the scenario has turned out `ticks` into the city, if it has.
+    /// Meeting the losing condition beats meeting the winning one on the
+    /// same tick, and reaching the deadline without either is a loss.
+    pub fn judge(&self, facts: &Facts, ticks: u64) -> Option<Outcome> {
+        if self.lose.as_ref().is_some_and(|lose| lose.holds(facts)) {
+            return Some(Outcome::Lost);
+        }
+        if self.win.holds(facts) {
+            return Some(Outcome::Won);
+        }
+        if self.deadline().is_some_and(|deadline| ticks >= deadline) {
+            return Some(Outcome::Lost);
+        }
+        None
+    }
+
+    /// The city the scenario starts as, built like any new one in the
+    /// ways the scenario leaves open.
+    pub fn city(&self, new_city: &NewCity) -> PendingCity {
+        let mut layout = match self.seed {
+            Some(seed) => generated_layout(self.width, self.height, self.money, seed),
+            None => SaveFile::empty(self.width, self.height, self.money, 0),
+        };
+        layout.neighborhood = new_city.neighborhood;
+        layout.development = new_city.development;
+        layout.scenario = Some(self.id.to_string());
+        PendingCity {
+            layout,
+            notice: Some(Phrase::new("notify.scenario").key("name", self.name)),
+        }
+    }
+}
+
+/// Every scenario there is to play.
+#[derive(Resource)]
+pub struct Scenarios(pub Vec<Scenario>);
+
+impl Scenarios {
+    fn bundled() -> Self {
+        Self(
+            BUNDLED
+                .iter()
+                .map(|text| Scenario::parse(text).expect("the built-in scenarios are valid"))
+                .collect(),
+        )
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Scenario> {
+        self.0.iter().find(|scenario| scenario.id == id)
+    }
+}
+
+/// The scenario the city is playing, none in a sandbox city. Saves keep
+/// which one it is.
+#[derive(Resource, Default, PartialEq)]
+pub struct ActiveScenario(pub Option<Scenario>);
+
+/// Restarting or clearing the map in a scenario starts the scenario over,
+/// unless another city is already on its way in.
+fn restart_scenario(
+    mut commands: Commands,
+    active: Res<ActiveScenario>,
+    pending: Option<Res<PendingCity>>,
+    new_city: Res<NewCity>,
+) {
+    if let (Some(scenario), None) = (&active.0, pending) {
+        commands.insert_resource(scenario.city(&new_city));
+    }
+}
+
+/// Cities opened from the main menu are sandboxes unless they say
+/// otherwise.
+fn leave_scenario(mut active: ResMut<ActiveScenario>) {
+    active.0 = None;
+}
+
+/// Lock and unlock the buildings the scenario says to.
+fn apply_scenario_rules(active: Res<ActiveScenario>, mut milestones: ResMut<Milestones>) {
+    let (unlocked, banned) = match &active.0 {
+        Some(scenario) => (scenario.unlocked.clone(), scenario.locked.clone()),
+        None => (Vec::new(), Vec::new()),
+    };
+    milestones.unlocked = unlocked;
+    milestones.banned = banned;
+}
+
+/// End the game once the scenario is won or lost.
+fn judge_scenario(
+    timer: Res<SimTimer>,
+    clock: Res<TimeOfDay>,
+    stats: Res<CityStats>,
+    garbage: Res<Garbage>,
+    active: Res<ActiveScenario>,
+    mut ending: ResMut<Ending>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+    let Some(scenario) = &active.0 else {
+        return;
+    };
+    let Some(outcome) = scenario.judge(&Facts::of(&stats, &garbage), clock.ticks) else {
+        return;
+    };
+    *ending = match outcome {
+        Outcome::Won => Ending::ScenarioWon,
+        Outcome::Lost => Ending::ScenarioLost,
+    };
+    next_state.set(GameState::GameOver);
+}
+
+#[derive(Component)]
+struct ObjectivesPanel;
+
+#[derive(Component)]
+struct ObjectivesTitle;
+
+#[derive(Component)]
+struct ObjectivesText;
+
+/// Objectives of the scenario under the demand bars, hidden in a sandbox.
+fn spawn_objectives_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(110.0),
+                right: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            ObjectivesPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                ObjectivesTitle,
+            ));
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                ObjectivesText,
+            ));
+        });
+}
+
+/// Show how far the city is with each objective, which ones it is
+/// meeting, what would lose the scenario, and the deadline.
+fn update_objectives_panel(
+    active: Res<ActiveScenario>,
+    stats: Res<CityStats>,
+    garbage: Res<Garbage>,
+    locale: Res<Locale>,
+    mut panel: Query<&mut Node, With<ObjectivesPanel>>,
+    mut title: Query<&mut Text, (With<ObjectivesTitle>, Without<ObjectivesText>)>,
+    mut body: Query<&mut Text, (With<ObjectivesText>, Without<ObjectivesTitle>)>,
+) {
+    if !active.is_changed() && !stats.is_changed() {
+        return;
+    }
+    let Ok(mut node) = panel.single_mut() else {
+        return;
+    };
+    let Some(scenario) = &active.0 else {
+        node.display = Display::None;
+        return;
+    };
+    node.display = Display::Flex;
+    if let Ok(mut text) = title.single_mut() {
+        **text = locale.text(scenario.name);
+    }
+
+    let facts = Facts::of(&stats, &garbage);
+    let mut lines = vec![locale.text("scenario.objectives")];
+    let add = |lines: &mut Vec<String>, objectives: Vec<ObjectiveLine>| {
+        for (depth, met, phrase) in objectives {
+            let check = if met { "[x]" } else { "[ ]" };
+            let indent = "  ".repeat(depth + 1);
+            lines.push(format!("{indent}{check} {}", locale.render(&phrase)));
+        }
+    };
+    add(&mut lines, scenario.win.lines(&facts));
+    if let Some(lose) = &scenario.lose {
+        lines.push(locale.text("scenario.lose_if"));
+        add(&mut lines, lose.lines(&facts));
+    }
+    if let Some(deadline) = scenario.deadline() {
+        let date = locale.render(&GameClock::at(deadline).phrase());
+        lines.push(locale.render(&Phrase::new("scenario.deadline").with("date", date)));
+    }
+    let text = lines.join("\n");
+    if let Ok(mut body) = body.single_mut() {
+        if **body != text {
+            **body = text;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled(id: &str) -> Scenario {
+        Scenarios::bundled().find(id).unwrap().clone()
+    }
+
+    /// The first outcome of a city whose facts go `facts` one tick after
+    /// another, and the tick it came on.
+    fn play(scenario: &Scenario, facts: impl IntoIterator<Item = Facts>) -> Option<(u64, Outcome)> {
+        facts
+            .into_iter()
+            .zip(1..)
+            .find_map(|(facts, tick)| scenario.judge(&facts, tick).map(|outcome| (tick, outcome)))
+    }
+
+    #[test]
+    fn every_bundled_scenario_parses() {
+        let ids: Vec<&str> = Scenarios::bundled().0.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["river_town", "green_valley", "shoestring"]);
+        assert_eq!(bundled("river_town").deadline(), Some(20 * TICKS_PER_YEAR));
+    }
+
+    #[test]
+    fn reaching_the_target_wins() {
+        let scenario = bundled("river_town");
+        let growing = (0..).map(|tick| Facts {
+            population: tick * 10,
+            ..Facts::default()
+        });
+        assert_eq!(play(&scenario, growing), Some((201, Outcome::Won)));
+    }
+
+    #[test]
+    fn the_deadline_passing_loses() {
+        let scenario = bundled("river_town");
+        let stalled = std::iter::repeat(Facts {
+            population: 1999,
+            ..Facts::default()
+        });
+        assert_eq!(play(&scenario, stalled), Some((20 * TICKS_PER_YEAR, Outcome::Lost)));
+    }
+
+    #[test]
+    fn the_losing_condition_ends_it_early() {
+        let scenario = bundled("green_valley");
+        // Jobs grow in step with pollution, which crosses the limit first.
+        let dirty = (0..).map(|tick| Facts {
+            jobs: tick * 50,
+            pollution: tick,
+            ..Facts::default()
+        });
+        assert_eq!(play(&scenario, dirty), Some((17, Outcome::Lost)));
+        let clean = (0..).map(|tick| Facts {
+            jobs: tick * 50,
+            pollution: 15,
+            ..Facts::default()
+        });
+        assert_eq!(play(&scenario, clean), Some((31, Outcome::Won)));
+
+        // Winning and losing on the same tick is a loss.
+        let both = Facts {
+            jobs: 1500,
+            pollution: 16,
+            ..Facts::default()
+        };
+        assert_eq!(scenario.judge(&both, 1), Some(Outcome::Lost));
+    }
+
+    #[test]
+    fn every_objective_must_hold_at_once() {
+        let scenario = bundled("shoestring");
+        let facts = [(1000, 0), (0, 25_000), (999, 30_000), (1000, 25_000)];
+        let sequence = facts.map(|(population, money)| Facts {
+            population,
+            money,
+            ..Facts::default()
+        });
+        assert_eq!(play(&scenario, sequence), Some((4, Outcome::Won)));
+        assert_eq!(play(&scenario, sequence[..3].to_vec()), None);
+    }
+
+    #[test]
+    fn conditions_combine() {
+        let facts = Facts {
+            population: 500,
+            jobs: 200,
+            money: -10,
+            pollution: 30,
+        };
+        let holds = |text: &'static str| ron::from_str::<Condition>(text).unwrap().holds(&facts);
+        assert!(holds("Population(500)"));
+        assert!(!holds("Population(501)"));
+        assert!(holds("Money(-10)"));
+        assert!(holds("Not(Pollution(29))"));
+        assert!(holds("All([Jobs(200), Pollution(30)])"));
+        assert!(!holds("All([Jobs(200), Money(0)])"));
+        assert!(holds("Any([Money(0), Not(Jobs(201))])"));
+        assert!(holds("All([])"));
+        assert!(!holds("Any([])"));
+
+        let lines = ron::from_str::<Condition>("All([Jobs(100), Any([Money(0), Jobs(900)])])")
+            .unwrap()
+            .lines(&facts);
+        let shape: Vec<(usize, bool)> = lines.iter().map(|&(depth, met, _)| (depth, met)).collect();
+        assert_eq!(shape, vec![(0, true), (0, false), (1, false), (1, false)]);
+    }
+
+    #[test]
+    fn maps_need_a_size() {
+        let text = r#"(id: "x", name: "x", width: 0, height: 10, seed: None, money: 0,
+            unlocked: [], locked: [], win: Money(1), lose: None, years: None)"#;
+        assert!(Scenario::parse(text).is_err());
+        assert!(Scenario::parse("(id: \"x\")").is_err());
+    }
+}
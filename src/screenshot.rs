@@ -0,0 +1,170 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::camera::{RenderTarget, ScalingMode};
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::render::render_resource::{TextureFormat, TextureUsages};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::MapConfig;
+use crate::locale::Phrase;
+use crate::state::GameSet;
+use crate::ui::Notification;
+
+/// F12 / Shift+F12 screenshots of the view or the whole map.
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, take_screenshot.in_set(GameSet::Input));
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, screenshots_unavailable.in_set(GameSet::Input));
+    }
+}
+
+/// Directory screenshots are written to, relative to the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+const SCREENSHOT_DIR: &str = "screenshots";
+/// Largest edge of a whole-map capture, in pixels.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_MAP_CAPTURE_PX: f32 = 4096.0;
+
+
+/// F12 saves the current view; Shift+F12 saves the entire map regardless of
+/// where the camera is.
+#[cfg(not(target_arch = "wasm32"))]
+fn take_screenshot(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut images: ResMut<Assets<Image>>,
+    map: Res<MapConfig>,
+) {
+    if !keys.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    let path = timestamped_path(SCREENSHOT_DIR);
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        let map_size = Vec2::new(
+            map.width as f32 * map.tile_size,
+            map.height as f32 * map.tile_size,
+        );
+        let scale = (MAX_MAP_CAPTURE_PX / map_size.max_element()).min(1.0);
+        let size = (map_size * scale).as_uvec2().max(UVec2::ONE);
+
+        let mut target =
+            Image::new_target_texture(size.x, size.y, TextureFormat::bevy_default());
+        target.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+        let target = images.add(target);
+
+        let camera = commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    target: RenderTarget::Image(target.clone().into()),
+                    order: -1,
+                    ..default()
+                },
+                Projection::Orthographic(OrthographicProjection {
+                    scaling_mode: ScalingMode::Fixed {
+                        width: map_size.x,
+                        height: map_size.y,
+                    },
+                    ..OrthographicProjection::default_2d()
+                }),
+            ))
+            .id();
+        commands
+            .spawn(Screenshot::image(target))
+            .observe(save_capture(path, Some(camera)));
+    } else {
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(save_capture(path, None));
+    }
+}
+
+/// `<dir>/city_YYYYMMDD_HHMMSS.png` for the current UTC time.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn timestamped_path(dir: &str) -> PathBuf {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    Path::new(dir).join(format!(
+        "city_{year:04}{month:02}{day:02}_{:02}{:02}{:02}.png",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60,
+    ))
+}
+
+/// Observer that writes a captured frame to `path`, reporting the outcome
+/// as a notification and cleaning up the capture camera if there was one.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_capture(
+    path: PathBuf,
+    camera: Option<Entity>,
+) -> impl FnMut(On<ScreenshotCaptured>, Commands, MessageWriter<Notification>) {
+    move |captured, mut commands, mut notifications| {
+        if let Some(camera) = camera {
+            commands.entity(camera).despawn();
+        }
+
+        let result = std::fs::create_dir_all(SCREENSHOT_DIR)
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                captured
+                    .image
+                    .clone()
+                    .try_into_dynamic()
+                    .map_err(|e| e.to_string())
+            })
+            .and_then(|image| {
+                image.to_rgb8().save(&path).map_err(|e| e.to_string())
+            });
+
+        let message = match result {
+            Ok(()) => {
+                info!("Screenshot saved to {}", path.display());
+                Phrase::new("notify.screenshot_saved").with("path", path.display())
+            }
+            Err(err) => {
+                error!("Could not save screenshot: {err}");
+                Phrase::new("notify.screenshot_failed").with("error", err)
+            }
+        };
+        notifications.write(Notification(message));
+    }
+}
+
+/// Screenshots are written to disk, which the web build can't do.
+#[cfg(target_arch = "wasm32")]
+fn screenshots_unavailable(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if keys.just_pressed(KeyCode::F12) {
+        notifications.write(Notification(
+            Phrase::new("notify.screenshots_unavailable"),
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::map::{TileCoord, Zone};
+use crate::simulation::simulation_step;
+use crate::state::{GameSet, ResetCity};
+
+/// Keeps the coverage layers of all service buildings up to date.
+pub struct ServicesPlugin;
+
+impl Plugin for ServicesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ResetCity, init_service_coverage).add_systems(
+            Update,
+            update_service_coverage
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        );
+    }
+}
+
+/// A city service provided by a service building zone.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Service {
+    Fire,
+    Police,
+    School,
+}
+
+impl Service {
+    pub const ALL: [Service; 3] = [Service::Fire, Service::Police, Service::School];
+
+    /// The zone whose tiles provide this service.
+    pub fn zone(self) -> Zone {
+        match self {
+            Service::Fire => Zone::FireStation,
+            Service::Police => Zone::PoliceStation,
+            Service::School => Zone::School,
+        }
+    }
+
+    /// The service a zone provides, if any.
+    pub fn provided_by(zone: Zone) -> Option<Self> {
+        Service::ALL.into_iter().find(|service| service.zone() == zone)
+    }
+
+    /// Tiles within this distance of a building are covered.
+    pub fn radius(self) -> i32 {
+        match self {
+            Service::Fire => 8,
+            Service::Police => 10,
+            Service::School => 7,
+        }
+    }
+}
+
+/// One coverage layer per service, laid out row by row. Rebuilt only when
+/// zoning changes, so systems can read it every tick for free.
+#[derive(Resource)]
+pub struct ServiceCoverage {
+    width: i32,
+    height: i32,
+    layers: [Vec<bool>; 3],
+}
+
+impl ServiceCoverage {
+    fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            layers: std::array::from_fn(|_| vec![false; size]),
+        }
+    }
+
+    /// Whether `coord` is in range of a building providing `service`.
+    pub fn covers(&self, service: Service, coord: IVec2) -> bool {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return false;
+        }
+        self.layers[service as usize][(coord.y * self.width + coord.x) as usize]
+    }
+}
+
+/// Mark every tile within `radius` of any source.
+pub fn coverage(width: i32, height: i32, sources: &[IVec2], radius: i32) -> Vec<bool> {
+    let mut covered = vec![false; (width * height) as usize];
+    for source in sources {
+        for y in (source.y - radius).max(0)..=(source.y + radius).min(height - 1) {
+            for x in (source.x - radius).max(0)..=(source.x + radius).min(width - 1) {
+                if (IVec2::new(x, y) - *source).length_squared() <= radius * radius {
+                    covered[(y * width + x) as usize] = true;
+                }
+            }
+        }
+    }
+    covered
+}
+
+fn init_service_coverage(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(ServiceCoverage::new(map.width, map.height));
+}
+
+/// Rebuild the coverage layers, but only when some zone changed.
+fn update_service_coverage(
+    mut coverage_res: ResMut<ServiceCoverage>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+) {
+    if changed.is_empty() {
+        return;
+    }
+
+    let (width, height) = (coverage_res.width, coverage_res.height);
+    for service in Service::ALL {
+        let buildings: Vec<IVec2> = tiles
+            .iter()
+            .filter(|(_, zone)| **zone == service.zone())
+            .map(|(coord, _)| coord.coord)
+            .collect();
+        coverage_res.layers[service as usize] =
+            coverage(width, height, &buildings, service.radius());
+    }
+}
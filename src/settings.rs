@@ -0,0 +1,438 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::WindowResized;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::locale::{self, Locale, Phrase};
+use crate::overlay::DataOverlay;
+use crate::state::GameState;
+use crate::ui::menu_button;
+
+/// File name of the settings, in the platform's per-user config directory.
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_FILE: &str = "settings.toml";
+/// Seconds to wait after a change before writing the settings, so a burst
+/// of changes, like dragging the window's edge, is written once.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DELAY_SECONDS: f32 = 0.5;
+/// How much each press of the volume buttons changes it.
+const VOLUME_STEP: f32 = 0.1;
+/// Simulation speeds the settings panel steps through.
+const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+/// Heatmaps a city can start with, in the order the panel steps through.
+const OVERLAYS: [DataOverlay; 10] = [
+    DataOverlay::None,
+    DataOverlay::Traffic,
+    DataOverlay::Crime,
+    DataOverlay::Services,
+    DataOverlay::Jobs,
+    DataOverlay::Elevation,
+    DataOverlay::LandValue,
+    DataOverlay::Tourism,
+    DataOverlay::Power,
+    DataOverlay::Pollution,
+];
+
+/// The player's preferences, kept between sessions in a settings file of
+/// their own, and the settings panel in the pause menu for changing them.
+pub struct SettingsPlugin {
+    pub settings: Settings,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .add_systems(Update, remember_window_size)
+            .add_systems(OnEnter(GameState::Settings), spawn_settings_panel)
+            .add_systems(
+                Update,
+                (handle_settings_buttons, close_settings, update_settings_panel)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            );
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, save_settings.after(remember_window_size));
+    }
+}
+
+/// Everything remembered between sessions. Starts from the config file,
+/// with the saved settings over it; `--lang` and `--windowed` override
+/// both.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize)]
+pub struct Settings {
+    /// From 0.0 (silent) to 1.0.
+    pub volume: f32,
+    /// Toggled with M.
+    pub muted: bool,
+    /// Locale the interface is shown in; read at startup.
+    pub language: String,
+    /// Multiplier on the simulation's tick rate.
+    pub speed: f32,
+    pub hints: bool,
+    /// Logical size the window was last left at.
+    pub window_width: u32,
+    pub window_height: u32,
+    /// Heatmap a city starts with.
+    pub overlay: DataOverlay,
+    /// Whether the tile grid lines are showing (G).
+    pub grid_lines: bool,
+}
+
+/// On-disk layout of the settings file; anything missing or out of range
+/// keeps the value from the config file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SettingsFile {
+    volume: Option<f32>,
+    muted: Option<bool>,
+    language: Option<String>,
+    speed: Option<f32>,
+    hints: Option<bool>,
+    window_width: Option<u32>,
+    window_height: Option<u32>,
+    overlay: Option<DataOverlay>,
+    grid_lines: Option<bool>,
+}
+
+impl Settings {
+    /// The settings from `config` alone.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            volume: config.volume,
+            muted: config.muted,
+            language: config.language.clone(),
+            speed: 1.0,
+            hints: config.hints,
+            window_width: config.window_width,
+            window_height: config.window_height,
+            overlay: DataOverlay::None,
+            grid_lines: false,
+        }
+    }
+
+    /// The saved settings over `config`. A missing or unreadable settings
+    /// file leaves the config's values as they are.
+    pub fn load(config: &Config) -> Self {
+        let mut settings = Self::from_config(config);
+        if let Some(file) = read() {
+            settings.apply(file);
+        }
+        settings
+    }
+
+    fn apply(&mut self, file: SettingsFile) {
+        if let Some(volume) = file.volume.filter(|v| (0.0..=1.0).contains(v)) {
+            self.volume = volume;
+        }
+        if let Some(muted) = file.muted {
+            self.muted = muted;
+        }
+        if let Some(language) = file.language.filter(|l| !l.is_empty()) {
+            self.language = language;
+        }
+        if let Some(speed) = file.speed.filter(|s| s.is_finite() && *s > 0.0) {
+            self.speed = speed;
+        }
+        if let Some(hints) = file.hints {
+            self.hints = hints;
+        }
+        if let (Some(width), Some(height)) = (file.window_width, file.window_height) {
+            if width > 0 && height > 0 {
+                self.window_width = width;
+                self.window_height = height;
+            }
+        }
+        if let Some(overlay) = file.overlay {
+            self.overlay = overlay;
+        }
+        if let Some(grid_lines) = file.grid_lines {
+            self.grid_lines = grid_lines;
+        }
+    }
+
+    /// Linear volume sounds should play at right now.
+    pub fn sound_level(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+/// Where the settings live: the per-user config directory of each
+/// platform, in a folder for the game.
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> Option<PathBuf> {
+    let var = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let dir = if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    }?;
+    Some(dir.join("bevy_city_sim").join(SETTINGS_FILE))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read() -> Option<SettingsFile> {
+    let text = fs::read_to_string(settings_path()?).ok()?;
+    toml::from_str(&text).ok()
+}
+
+/// The web build has nowhere to keep settings, so every session starts
+/// from the defaults.
+#[cfg(target_arch = "wasm32")]
+fn read() -> Option<SettingsFile> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(settings: &Settings) -> Result<(), String> {
+    let path = settings_path().ok_or("no config directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = toml::to_string(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Write the settings once they have stopped changing for a moment.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_settings(time: Res<Time>, settings: Res<Settings>, mut changed_at: Local<Option<f32>>) {
+    let now = time.elapsed_secs();
+    if settings.is_changed() && !settings.is_added() {
+        *changed_at = Some(now);
+    }
+    if changed_at.is_some_and(|at| now - at >= SAVE_DELAY_SECONDS) {
+        *changed_at = None;
+        if let Err(err) = write(&settings) {
+            warn!("Could not save the settings: {err}");
+        }
+    }
+}
+
+/// Remember the window's size for the next session.
+fn remember_window_size(mut resized: MessageReader<WindowResized>, mut settings: ResMut<Settings>) {
+    let Some(last) = resized.read().last() else {
+        return;
+    };
+    let (width, height) = (last.width.round() as u32, last.height.round() as u32);
+    if width > 0 && height > 0 && (width, height) != (settings.window_width, settings.window_height)
+    {
+        settings.window_width = width;
+        settings.window_height = height;
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    Quieter,
+    Louder,
+    Sound,
+    Slower,
+    Faster,
+    Language,
+    Hints,
+    GridLines,
+    Overlay,
+    Back,
+}
+
+/// Text showing a setting's current value, rewritten as it changes.
+#[derive(Component, Clone, Copy)]
+enum SettingsText {
+    Volume,
+    Speed,
+}
+
+fn spawn_settings_panel(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(10),
+            DespawnOnExit(GameState::Settings),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text("settings.title")),
+                TextFont {
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for (down, value, up) in [
+                (SettingsButton::Quieter, SettingsText::Volume, SettingsButton::Louder),
+                (SettingsButton::Slower, SettingsText::Speed, SettingsButton::Faster),
+            ] {
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(12.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(menu_button("-", down));
+                        row.spawn((
+                            Text::new(""),
+                            TextFont {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            value,
+                        ));
+                        row.spawn(menu_button("+", up));
+                    });
+            }
+            // Labelled properly once `update_settings_panel` runs.
+            for button in [
+                SettingsButton::Sound,
+                SettingsButton::Language,
+                SettingsButton::Hints,
+                SettingsButton::GridLines,
+                SettingsButton::Overlay,
+            ] {
+                parent.spawn(menu_button("", button));
+            }
+            parent.spawn((
+                Text::new(locale.text("settings.restart_note")),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+            parent.spawn(menu_button(&locale.text("settings.back"), SettingsButton::Back));
+        });
+}
+
+/// The entry after `current` in `all`, wrapping around, or the first if
+/// `current` isn't there.
+fn next_of<T: PartialEq + Clone>(all: &[T], current: &T) -> T {
+    let i = all.iter().position(|item| item == current).map_or(0, |i| i + 1);
+    all[i % all.len()].clone()
+}
+
+fn handle_settings_buttons(
+    buttons: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        // Volume steps are kept to whole percents so they add up evenly.
+        let volume = |v: f32| ((v * 100.0).round() / 100.0).clamp(0.0, 1.0);
+        match button {
+            SettingsButton::Quieter => settings.volume = volume(settings.volume - VOLUME_STEP),
+            SettingsButton::Louder => settings.volume = volume(settings.volume + VOLUME_STEP),
+            SettingsButton::Sound => settings.muted = !settings.muted,
+            SettingsButton::Slower => {
+                let slower = SPEEDS.iter().rev().find(|&&s| s < settings.speed);
+                settings.speed = *slower.unwrap_or(&SPEEDS[0]);
+            }
+            SettingsButton::Faster => {
+                let faster = SPEEDS.iter().find(|&&s| s > settings.speed);
+                settings.speed = *faster.unwrap_or(&SPEEDS[SPEEDS.len() - 1]);
+            }
+            SettingsButton::Language => {
+                settings.language = next_of(&locale::languages(), &settings.language);
+            }
+            SettingsButton::Hints => settings.hints = !settings.hints,
+            SettingsButton::GridLines => settings.grid_lines = !settings.grid_lines,
+            SettingsButton::Overlay => settings.overlay = next_of(&OVERLAYS, &settings.overlay),
+            SettingsButton::Back => next_state.set(GameState::Paused),
+        }
+    }
+}
+
+/// Escape goes back to the pause menu.
+fn close_settings(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Show every setting's current value, on the panel's texts and buttons.
+fn update_settings_panel(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    values: Query<(Entity, &SettingsText)>,
+    buttons: Query<(&SettingsButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    mut shown: Local<bool>,
+) {
+    // The panel is spawned fresh each time it opens, so it is filled in
+    // on its first frame whether or not anything changed.
+    if settings.is_changed() {
+        *shown = false;
+    }
+    if *shown && !values.is_empty() {
+        return;
+    }
+    *shown = true;
+
+    let on_off = |on: bool, on_key: &'static str, off_key: &'static str| {
+        Phrase::new(if on { on_key } else { off_key })
+    };
+    for (entity, value) in values.iter() {
+        let phrase = match value {
+            SettingsText::Volume => Phrase::new("settings.volume")
+                .with("percent", (settings.volume * 100.0).round()),
+            SettingsText::Speed => Phrase::new("settings.speed").with("speed", settings.speed),
+        };
+        if let Ok(mut text) = texts.get_mut(entity) {
+            **text = locale.render(&phrase);
+        }
+    }
+    for (button, children) in buttons.iter() {
+        let phrase = match button {
+            SettingsButton::Sound => {
+                on_off(!settings.muted, "settings.sound_on", "settings.sound_off")
+            }
+            SettingsButton::Language => {
+                Phrase::new("settings.language").with("language", &settings.language)
+            }
+            SettingsButton::Hints => {
+                on_off(settings.hints, "settings.hints_on", "settings.hints_off")
+            }
+            SettingsButton::GridLines => {
+                on_off(settings.grid_lines, "settings.grid_lines_on", "settings.grid_lines_off")
+            }
+            SettingsButton::Overlay => Phrase::new("settings.overlay")
+                .key("overlay", settings.overlay.name().unwrap_or("overlay.none")),
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = locale.render(&phrase);
+            }
+        }
+    }
+}
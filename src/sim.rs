@@ -0,0 +1,620 @@
+//! The growth and money rules of the simulation, on plain data. The
+//! `simulation_step` system gathers the inputs from the world, runs these,
+//! and writes the results back.
+
+use bevy::math::IVec2;
+use bevy::tasks::ComputeTaskPool;
+
+use crate::commute::commute_growth;
+use crate::garbage::MAX_POLLUTION;
+use crate::grid::Neighborhood;
+use crate::map::{TileData, Zone};
+use crate::services::Service;
+use crate::simulation::{Budget, Demand};
+use crate::zone_defs::{ZoneDef, ZoneDefs};
+
+/// Most crime a full tile on worthless land gains per tick outside police
+/// coverage, and what every tile loses per tick inside it.
+const MAX_CRIME_GROWTH: u32 = 3;
+const CRIME_DECAY: u32 = 5;
+pub const MAX_CRIME: u32 = 100;
+/// Land value of a plain tile, and the most any tile can have.
+const BASE_LAND_VALUE: i32 = 50;
+pub const MAX_LAND_VALUE: u32 = 100;
+/// Land value a view over the city, and a school nearby, add.
+const VIEW_LAND_VALUE: i32 = 15;
+const SCHOOL_LAND_VALUE: i32 = 10;
+/// Share of a shop's jobs still paying tax at the worst crime, in percent.
+const LAWLESS_TAX_PERCENT: u32 = 50;
+/// Extra residents a home with a view can hold, people paying more to
+/// live up on the hills.
+const VIEW_POPULATION: u32 = 10;
+/// Residents nearby needed to support each shop job.
+const CUSTOMERS_PER_JOB: u32 = 5;
+/// Share of its residents a home loses at the worst pollution, in percent.
+const POLLUTED_HOME_PERCENT: u32 = 40;
+
+/// Zone of every tile, laid out row by row.
+pub struct ZoneGrid {
+    width: i32,
+    height: i32,
+    zones: Vec<Zone>,
+}
+
+impl ZoneGrid {
+    /// An all-empty grid to fill in with `set`.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            zones: vec![Zone::Empty; (width * height) as usize],
+        }
+    }
+
+    /// Zone at `coord`, or `None` off the map.
+    pub fn get(&self, coord: IVec2) -> Option<Zone> {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return None;
+        }
+        Some(self.zones[(coord.y * self.width + coord.x) as usize])
+    }
+
+    /// Set the zone at `coord`, which must be on the map.
+    pub fn set(&mut self, coord: IVec2, zone: Zone) {
+        self.zones[(coord.y * self.width + coord.x) as usize] = zone;
+    }
+
+    /// Whether a road is next to `coord`.
+    pub fn next_to_road(&self, coord: IVec2, neighborhood: Neighborhood) -> bool {
+        neighborhood
+            .offsets()
+            .iter()
+            .any(|&n| self.get(coord + n).is_some_and(Zone::is_road))
+    }
+
+    /// Road tiles weighted by how much their tier costs to maintain.
+    pub fn road_maintenance(&self) -> f32 {
+        self.zones
+            .iter()
+            .filter_map(|zone| zone.road())
+            .map(|road| road.maintenance)
+            .sum()
+    }
+}
+
+/// What a tile's growth depends on besides its own state, looked up in the
+/// other simulation layers.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Surroundings {
+    pub next_to_road: bool,
+    pub police: bool,
+    pub school: bool,
+    /// A road next to the tile is over capacity.
+    pub congested: bool,
+    /// The tile's power network had power for it in the last tick.
+    pub powered: bool,
+    /// A water source or a pipe carrying water is in reach.
+    pub watered: bool,
+    /// The tile is high enough up to look out over the city.
+    pub view: bool,
+    /// Pollution from garbage spilling over, 0 to `MAX_POLLUTION`.
+    pub pollution: u32,
+    /// Change to a home's population cap from what is next to it, in
+    /// percent.
+    pub neighbors_percent: i32,
+    /// Spare jobs within commuting range of a home.
+    pub unfilled_jobs: u32,
+    /// Residents within shopping range of a shop.
+    pub customers: u32,
+    /// Jobs an industrial tile can hold above its capacity, during
+    /// factory orders.
+    pub extra_jobs: u32,
+}
+
+/// Jobs a shop of at most `capacity` jobs can offer with `customers`
+/// residents nearby.
+pub fn commercial_capacity(customers: u32, capacity: u32) -> u32 {
+    (customers / CUSTOMERS_PER_JOB).min(capacity)
+}
+
+/// What a tile can hold of `capacity`: all of it with water, half without.
+fn water_capacity(capacity: u32, watered: bool) -> u32 {
+    if watered {
+        capacity
+    } else {
+        capacity / 2
+    }
+}
+
+/// What a home of `capacity` holds with `pollution` around it: residents
+/// unhappy living next to spilled garbage move out, up to
+/// `POLLUTED_HOME_PERCENT` of them at the worst.
+fn polluted_capacity(capacity: u32, pollution: u32) -> u32 {
+    let lost = POLLUTED_HOME_PERCENT * pollution.min(MAX_POLLUTION) / MAX_POLLUTION;
+    capacity * (100 - lost) / 100
+}
+
+/// What the land under a tile is worth, from 0 to `MAX_LAND_VALUE`: what
+/// is next to it, a view, and a school nearby raise it, and crime and
+/// pollution bring it down.
+pub fn land_value(around: &Surroundings, crime: u32) -> u32 {
+    let view = if around.view { VIEW_LAND_VALUE } else { 0 };
+    let school = if around.school { SCHOOL_LAND_VALUE } else { 0 };
+    let bad = (crime + around.pollution) as i32 / 2;
+    let value = BASE_LAND_VALUE + around.neighbors_percent + view + school - bad;
+    value.clamp(0, MAX_LAND_VALUE as i32) as u32
+}
+
+/// Crime on a tile after a tick. Inside police coverage it falls by
+/// `CRIME_DECAY`; outside, it rises the fuller the tile is (`occupants` of
+/// `capacity`) and the less its land is worth.
+pub fn next_crime(crime: u32, occupants: u32, capacity: u32, land_value: u32, police: bool) -> u32 {
+    if police {
+        return crime.saturating_sub(CRIME_DECAY);
+    }
+    let density = occupants.min(capacity) * 100 / capacity.max(1);
+    let cheapness = MAX_LAND_VALUE - land_value.min(MAX_LAND_VALUE);
+    let growth = (density * cheapness * MAX_CRIME_GROWTH).div_ceil(100 * MAX_LAND_VALUE);
+    (crime + growth).min(MAX_CRIME)
+}
+
+/// Jobs of a shop that pay tax: all of them without crime, down to
+/// `LAWLESS_TAX_PERCENT` at the worst, as theft eats into takings.
+pub fn taxed_shop_jobs(jobs: u32, crime: u32) -> u32 {
+    let lost = (100 - LAWLESS_TAX_PERCENT) * crime.min(MAX_CRIME) / MAX_CRIME;
+    jobs * (100 - lost) / 100
+}
+
+/// Advance one tile by a tick, at the capacity and growth rate `def` gives
+/// its zone:
+/// - Homes and shops gather crime by `next_crime`, which their land value
+///   feeds and is lowered by in turn. The worse the crime in a home, the
+///   fewer residents it holds, and pollution drives residents out too.
+///   Next to a road,
+///   they gain residents while spare jobs are within commuting range,
+///   twice as fast near a school. Homes with a view hold a few more, and
+///   what is next to a home scales how many it holds.
+/// - Shops gain jobs up to what the residents around them can support,
+///   except next to a congested road, where they stall.
+/// - Industry gains jobs while it has a road to ship goods on, past the
+///   usual maximum while factory orders are in.
+/// - Homes, shops, and industry cut off by a brownout don't grow, and
+///   without water they hold half as many residents or jobs.
+/// - Everything else holds no residents or jobs; building tiles only keep
+///   track of their anchor.
+/// - A building still under construction only counts down to being
+///   finished, and a lot zoned but not built on yet only has its land
+///   valued, for development to pick the best lots first.
+pub fn grow_tile(zone: Zone, def: &ZoneDef, data: &mut TileData, around: &Surroundings) {
+    if data.zoned {
+        if matches!(zone, Zone::Residential | Zone::Commercial) {
+            data.land_value = land_value(around, 0);
+        }
+        return;
+    }
+    if data.construction > 0 {
+        data.construction -= 1;
+        return;
+    }
+    match zone {
+        Zone::Residential => {
+            let (crime, value) = (data.crime, land_value(around, data.crime));
+            data.crime = next_crime(crime, data.population, def.capacity, value, around.police);
+            data.land_value = value;
+
+            let view = if around.view { VIEW_POPULATION } else { 0 };
+            let scaled = (def.capacity + view) as i32 * (100 + around.neighbors_percent) / 100;
+            let capacity = water_capacity(scaled.max(0) as u32, around.watered);
+            let capacity = polluted_capacity(capacity, around.pollution)
+                .saturating_sub(data.crime / 2);
+            if around.next_to_road && around.powered {
+                let base = if around.school { def.growth * 2 } else { def.growth };
+                data.population += commute_growth(base, around.unfilled_jobs, data.population);
+            }
+            data.population = data.population.min(capacity);
+        }
+        Zone::Commercial => {
+            let (crime, value) = (data.crime, land_value(around, data.crime));
+            data.crime = next_crime(crime, data.jobs, def.capacity, value, around.police);
+            data.land_value = value;
+            if !around.congested && around.powered {
+                data.jobs += def.growth;
+            }
+            let capacity = commercial_capacity(around.customers, def.capacity);
+            data.jobs = data.jobs.min(water_capacity(capacity, around.watered));
+        }
+        Zone::Industrial => {
+            let capacity = water_capacity(def.capacity + around.extra_jobs, around.watered);
+            if around.next_to_road && around.powered {
+                data.jobs = (data.jobs + def.growth).min(capacity);
+            } else if !around.watered {
+                // Losing water empties half a factory even while it stalls.
+                data.jobs = data.jobs.min(capacity);
+            }
+        }
+        Zone::PowerPlant | Zone::Seaport | Zone::Airport => {
+            *data = TileData {
+                anchor: data.anchor,
+                ..TileData::default()
+            };
+        }
+        Zone::Road
+        | Zone::DirtRoad
+        | Zone::Avenue
+        | Zone::Bridge
+        | Zone::Empty
+        | Zone::FireStation
+        | Zone::PoliceStation
+        | Zone::School
+        | Zone::WaterTower
+        | Zone::PumpingStation
+        | Zone::Landfill
+        | Zone::RecyclingCenter
+        | Zone::Water
+        | Zone::Forest => {
+            *data = TileData::default();
+        }
+    }
+}
+
+/// A tile as a tick sees it, copied out of the world.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TileState {
+    pub coord: IVec2,
+    pub zone: Zone,
+    pub data: TileData,
+}
+
+/// Grow every tile one tick by `grow_tile`, at the rates `defs` gives and
+/// with what `around` says surrounds it. While `construction_paused`,
+/// buildings under construction sit the tick out.
+///
+/// A tile's growth only reads its own state and what `around` looks up in
+/// layers built before the tick, so with `parallel` the tiles are split
+/// over the compute task pool, to the same result as growing them in turn.
+pub fn tick(
+    tiles: &mut [TileState],
+    defs: &ZoneDefs,
+    construction_paused: bool,
+    parallel: bool,
+    around: impl Fn(IVec2, Zone) -> Surroundings + Sync,
+) {
+    let grow = |tile: &mut TileState| {
+        if construction_paused && tile.data.construction > 0 {
+            return;
+        }
+        let surroundings = around(tile.coord, tile.zone);
+        grow_tile(tile.zone, defs.get(tile.zone), &mut tile.data, &surroundings);
+    };
+    if parallel {
+        let pool = ComputeTaskPool::get();
+        let size = tiles.len().div_ceil(pool.thread_num()).max(1);
+        let grow = &grow;
+        pool.scope(|scope| {
+            for chunk in tiles.chunks_mut(size) {
+                scope.spawn(async move { chunk.iter_mut().for_each(grow) });
+            }
+        });
+    } else {
+        tiles.iter_mut().for_each(&grow);
+    }
+}
+
+/// Demand for each zone from the city totals: people move in when there
+/// are spare jobs, shops want customers, and industry wants workers. The
+/// base terms keep an empty map asking for houses and factories first.
+pub fn demand(population: u32, commercial_jobs: u32, industrial_jobs: u32) -> Demand {
+    let population = population as i32;
+    let jobs = (commercial_jobs + industrial_jobs) as i32;
+    Demand {
+        residential: jobs - population + 10,
+        commercial: population / 2 - commercial_jobs as i32,
+        industrial: population / 2 - industrial_jobs as i32 + 10,
+    }
+}
+
+/// One tick's budget: taxes on the totals (`jobs` being the ones paying
+/// tax), less road maintenance (`roads`
+/// being the tier-weighted tile count) and the upkeep of each service's
+/// buildings (indexed by `Service`).
+pub fn budget(
+    population: u32,
+    jobs: u32,
+    roads: f32,
+    road_upkeep_per_tile: f32,
+    service_upkeep: [i64; Service::ALL.len()],
+) -> Budget {
+    Budget {
+        road_maintenance: (roads * road_upkeep_per_tile).round() as i64,
+        fire_upkeep: service_upkeep[Service::Fire as usize],
+        police_upkeep: service_upkeep[Service::Police as usize],
+        school_upkeep: service_upkeep[Service::School as usize],
+        ..Budget::compute(population, jobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::TaskPool;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// What a home or factory needs to grow, short of a road.
+    fn served() -> Surroundings {
+        Surroundings {
+            powered: true,
+            watered: true,
+            unfilled_jobs: 1000,
+            ..Surroundings::default()
+        }
+    }
+
+    fn grown(zone: Zone, data: TileData, around: Surroundings) -> TileData {
+        let mut data = data;
+        grow_tile(zone, ZoneDefs::bundled().get(zone), &mut data, &around);
+        data
+    }
+
+    #[test]
+    fn homes_only_grow_next_to_a_road() {
+        let by_road = Surroundings {
+            next_to_road: true,
+            ..served()
+        };
+        assert_eq!(grown(Zone::Residential, TileData::default(), by_road).population, 1);
+        assert_eq!(grown(Zone::Residential, TileData::default(), served()).population, 0);
+    }
+
+    #[test]
+    fn homes_stop_at_their_capacity() {
+        let defs = ZoneDefs::bundled();
+        let capacity = defs.get(Zone::Residential).capacity;
+        let full = TileData {
+            population: capacity,
+            ..TileData::default()
+        };
+        let around = Surroundings {
+            next_to_road: true,
+            police: true,
+            ..served()
+        };
+        assert_eq!(grown(Zone::Residential, full, around).population, capacity);
+        let overfull = TileData {
+            population: capacity + 20,
+            ..full
+        };
+        assert_eq!(grown(Zone::Residential, overfull, around).population, capacity);
+    }
+
+    #[test]
+    fn roads_and_empty_tiles_hold_nothing() {
+        let busy = TileData {
+            population: 30,
+            jobs: 12,
+            crime: 40,
+            land_value: 70,
+            ..TileData::default()
+        };
+        for zone in [Zone::Road, Zone::Avenue, Zone::Empty] {
+            assert!(grown(zone, busy, served()) == TileData::default(), "{zone:?}");
+        }
+    }
+
+    #[test]
+    fn tick_grows_homes_by_the_road_only() {
+        let mut tiles: Vec<TileState> = (0..4)
+            .map(|x| TileState {
+                coord: IVec2::new(x, 0),
+                zone: if x == 0 { Zone::Road } else { Zone::Residential },
+                data: TileData::default(),
+            })
+            .collect();
+        let mut zones = ZoneGrid::new(4, 1);
+        for tile in &tiles {
+            zones.set(tile.coord, tile.zone);
+        }
+        let around = |coord: IVec2, _| Surroundings {
+            next_to_road: zones.next_to_road(coord, Neighborhood::Four),
+            ..served()
+        };
+        let defs = ZoneDefs::bundled();
+        for _ in 0..3 {
+            tick(&mut tiles, &defs, false, false, around);
+        }
+        let population: Vec<u32> = tiles.iter().map(|tile| tile.data.population).collect();
+        assert_eq!(population, vec![0, 3, 0, 0]);
+    }
+
+    #[test]
+    fn diagonal_roads_count_only_in_the_eight_neighborhood() {
+        // A home at the bottom left with a road touching its corner only.
+        let layout = [(IVec2::ZERO, Zone::Residential), (IVec2::ONE, Zone::Road)];
+        let mut zones = ZoneGrid::new(2, 2);
+        for (coord, zone) in layout {
+            zones.set(coord, zone);
+        }
+        assert!(!zones.next_to_road(IVec2::ZERO, Neighborhood::Four));
+        assert!(zones.next_to_road(IVec2::ZERO, Neighborhood::Eight));
+
+        let defs = ZoneDefs::bundled();
+        for (neighborhood, population) in [(Neighborhood::Four, 0), (Neighborhood::Eight, 1)] {
+            let mut tiles: Vec<TileState> = layout
+                .iter()
+                .map(|&(coord, zone)| TileState {
+                    coord,
+                    zone,
+                    data: TileData::default(),
+                })
+                .collect();
+            let around = |coord: IVec2, _| Surroundings {
+                next_to_road: zones.next_to_road(coord, neighborhood),
+                ..served()
+            };
+            tick(&mut tiles, &defs, false, false, around);
+            assert_eq!(tiles[0].data.population, population, "{neighborhood:?}");
+        }
+    }
+
+    #[test]
+    fn tick_holds_back_construction_while_paused() {
+        let mut tiles = vec![TileState {
+            coord: IVec2::ZERO,
+            zone: Zone::Residential,
+            data: TileData {
+                construction: 2,
+                ..TileData::default()
+            },
+        }];
+        let defs = ZoneDefs::bundled();
+        tick(&mut tiles, &defs, true, false, |_, _| served());
+        assert_eq!(tiles[0].data.construction, 2);
+        tick(&mut tiles, &defs, false, false, |_, _| served());
+        assert_eq!(tiles[0].data.construction, 1);
+    }
+
+    #[test]
+    fn shops_need_customers_nearby() {
+        assert_eq!(commercial_capacity(0, 40), 0);
+        assert_eq!(commercial_capacity(CUSTOMERS_PER_JOB - 1, 40), 0);
+        assert_eq!(commercial_capacity(CUSTOMERS_PER_JOB * 7 + 2, 40), 7);
+        assert_eq!(commercial_capacity(CUSTOMERS_PER_JOB * 40, 40), 40);
+        assert_eq!(commercial_capacity(u32::MAX, 40), 40);
+
+        let defs = ZoneDefs::bundled();
+        let def = defs.get(Zone::Commercial);
+        let open = TileData {
+            jobs: def.capacity,
+            ..TileData::default()
+        };
+        let wilderness = Surroundings {
+            customers: 0,
+            ..served()
+        };
+        assert_eq!(grown(Zone::Commercial, open, wilderness).jobs, 0);
+        let town = Surroundings {
+            customers: CUSTOMERS_PER_JOB * 3,
+            ..served()
+        };
+        assert_eq!(grown(Zone::Commercial, open, town).jobs, 3);
+        let city = Surroundings {
+            customers: u32::MAX,
+            ..served()
+        };
+        assert_eq!(grown(Zone::Commercial, TileData::default(), city).jobs, def.growth);
+    }
+
+    #[test]
+    fn parallel_ticks_match_serial_ones() {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        let zones = [
+            Zone::Empty,
+            Zone::Road,
+            Zone::Residential,
+            Zone::Commercial,
+            Zone::Industrial,
+            Zone::School,
+            Zone::Forest,
+        ];
+        let (width, height) = (96, 64);
+        let mut rng = SmallRng::seed_from_u64(348);
+        let mut tiles = Vec::new();
+        let mut surroundings = Vec::new();
+        for i in 0..width * height {
+            tiles.push(TileState {
+                coord: IVec2::new(i % width, i / width),
+                zone: zones[rng.random_range(0..zones.len())],
+                data: TileData {
+                    population: rng.random_range(0..60),
+                    jobs: rng.random_range(0..60),
+                    crime: rng.random_range(0..100),
+                    construction: rng.random_range(0..3),
+                    ..TileData::default()
+                },
+            });
+            surroundings.push(Surroundings {
+                next_to_road: rng.random(),
+                police: rng.random(),
+                school: rng.random(),
+                congested: rng.random(),
+                view: rng.random(),
+                powered: rng.random(),
+                watered: rng.random(),
+                pollution: rng.random_range(0..100),
+                neighbors_percent: rng.random_range(-20..=15),
+                unfilled_jobs: rng.random_range(0..200),
+                customers: rng.random_range(0..2000),
+                extra_jobs: rng.random_range(0..20),
+            });
+        }
+        let around = |coord: IVec2, _| surroundings[(coord.y * width + coord.x) as usize];
+
+        let defs = ZoneDefs::bundled();
+        let mut serial = tiles.clone();
+        let mut parallel = tiles;
+        for tick_number in 0..5 {
+            let paused = tick_number == 2;
+            tick(&mut serial, &defs, paused, false, around);
+            tick(&mut parallel, &defs, paused, true, around);
+        }
+        assert!(serial == parallel);
+        assert!(serial.iter().any(|tile| tile.data.population > 0));
+    }
+
+    #[test]
+    fn police_bring_crime_down() {
+        assert_eq!(next_crime(50, 40, 40, 0, true), 50 - CRIME_DECAY);
+        assert_eq!(next_crime(CRIME_DECAY - 1, 40, 40, 0, true), 0);
+    }
+
+    #[test]
+    fn crime_grows_with_crowding_and_cheap_land() {
+        // Empty buildings breed no crime, whatever the land is worth.
+        assert_eq!(next_crime(10, 0, 40, 0, false), 10);
+        assert_eq!(next_crime(10, 0, 0, 0, false), 10);
+        // Full and worthless: the fastest it grows.
+        assert_eq!(next_crime(10, 40, 40, 0, false), 10 + MAX_CRIME_GROWTH);
+        assert_eq!(next_crime(10, 80, 40, 0, false), 10 + MAX_CRIME_GROWTH);
+        // The dearest land stays safe even when full.
+        assert_eq!(next_crime(10, 40, 40, MAX_LAND_VALUE, false), 10);
+        // Any crowding on cheap land adds at least a little.
+        assert_eq!(next_crime(10, 20, 40, MAX_LAND_VALUE / 2, false), 11);
+        assert_eq!(next_crime(MAX_CRIME - 1, 40, 40, 0, false), MAX_CRIME);
+    }
+
+    #[test]
+    fn crime_lowers_land_value_and_shop_taxes() {
+        let nothing = Surroundings::default();
+        assert_eq!(land_value(&nothing, 0), BASE_LAND_VALUE as u32);
+        assert_eq!(land_value(&nothing, 40), BASE_LAND_VALUE as u32 - 20);
+        assert_eq!(land_value(&nothing, 2 * MAX_CRIME + 1), 0);
+        let fine = Surroundings {
+            view: true,
+            school: true,
+            neighbors_percent: 10,
+            ..nothing
+        };
+        assert_eq!(land_value(&fine, 0), 85);
+        assert_eq!(land_value(&fine, MAX_CRIME), 35);
+
+        assert_eq!(taxed_shop_jobs(40, 0), 40);
+        assert_eq!(taxed_shop_jobs(40, MAX_CRIME / 2), 30);
+        assert_eq!(taxed_shop_jobs(40, MAX_CRIME), 40 * LAWLESS_TAX_PERCENT / 100);
+        assert_eq!(taxed_shop_jobs(40, 10 * MAX_CRIME), 40 * LAWLESS_TAX_PERCENT / 100);
+    }
+
+    #[test]
+    fn money_is_taxes_less_upkeep() {
+        let mut upkeep = [0; Service::ALL.len()];
+        upkeep[Service::Fire as usize] = 5;
+        upkeep[Service::School as usize] = 8;
+        let budget = budget(100, 50, 20.0, 0.5, upkeep);
+        assert_eq!(budget.residential_tax, 10);
+        assert_eq!(budget.commercial_tax, 10);
+        assert_eq!(budget.road_maintenance, 10);
+        assert_eq!(budget.fire_upkeep, 5);
+        assert_eq!(budget.school_upkeep, 8);
+        assert_eq!(budget.net(), 10 + 10 - 10 - 5 - 8 - budget.service_costs);
+    }
+}
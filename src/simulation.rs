@@ -0,0 +1,710 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use crate::adjacency::Adjacency;
+use crate::calendar::{ends_month, TICKS_PER_MONTH};
+use crate::commute::JobAccess;
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::development::Development;
+use crate::diagnostics::{SimDiagnostics, SimPhase};
+use crate::districts::{DistrictStats, DistrictTally, Districts};
+use crate::economy::Economy;
+use crate::elevation::{Elevation, VIEW_LEVEL};
+use crate::garbage::{is_garbage_site, Garbage};
+use crate::grid::Neighborhood;
+use crate::history::{StatsHistory, StatsSnapshot};
+use crate::loans::Loans;
+use crate::locale::Phrase;
+use crate::map::{TileCoord, TileData, Zone};
+use crate::ports::{is_port, Ports};
+use crate::power::PowerGrid;
+use crate::services::{Service, ServiceCoverage};
+use crate::settings::Settings;
+use crate::sim::{self, Surroundings, TileState, ZoneGrid};
+use crate::state::{GameSet, ResetCity};
+use crate::statistics::CityBreakdown;
+use crate::tourism::{tourism_income, Tourism};
+use crate::trade::Trade;
+use crate::traffic::TrafficGrid;
+use crate::ui::Notification;
+use crate::water::{is_water_source, Pipes, WaterSupply, PIPE_UPKEEP_PER_TILE};
+use crate::weather::Weather;
+use crate::zone_defs::ZoneDefs;
+
+/// Shops draw customers from homes within this many tiles (a square).
+pub const CUSTOMER_RADIUS: i32 = 8;
+/// Maps with at least this many tiles grow them on every core; on smaller
+/// ones, handing out the work costs more than it saves.
+const PARALLEL_TILES: usize = 16_384;
+/// Share of the gap to each tick's net budget the income trend closes;
+/// lower is smoother.
+const TREND_SMOOTHING: f32 = 0.25;
+
+/// Ticks tile growth, city totals, demand, and the budget.
+pub struct SimulationPlugin {
+    pub tick_seconds: f32,
+    pub starting_money: i64,
+    pub road_upkeep: f32,
+    pub seed: u64,
+    pub neighborhood: Neighborhood,
+    pub development: Development,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NewCity {
+            starting_money: self.starting_money,
+            seed: self.seed,
+            neighborhood: self.neighborhood,
+            development: self.development,
+        })
+        .insert_resource(self.neighborhood)
+        .insert_resource(self.development)
+        .insert_resource(CityStats {
+            money: self.starting_money,
+            ..default()
+        })
+        .insert_resource(SimTimer(Timer::from_seconds(
+            self.tick_seconds,
+            TimerMode::Repeating,
+        )))
+        .insert_resource(TickSeconds(self.tick_seconds))
+        .insert_resource(SimRng(SmallRng::seed_from_u64(self.seed)))
+        .insert_resource(Maintenance {
+            road_per_tile: self.road_upkeep,
+        })
+        .init_resource::<Demand>()
+        .init_resource::<Budget>()
+        .init_resource::<Ledger>()
+        .init_resource::<IncomeTrend>()
+        .init_resource::<StatsHistory>()
+        .init_resource::<SimDiagnostics>()
+        .add_message::<Notification>()
+        .add_systems(Update, simulation_step.in_set(GameSet::Simulation))
+        .add_systems(
+            Update,
+            apply_sim_speed
+                .before(GameSet::Simulation)
+                .run_if(resource_exists_and_changed::<Settings>),
+        )
+        .add_systems(ResetCity, reset_simulation);
+    }
+}
+
+/// How a new city starts, kept around for restarts.
+#[derive(Resource)]
+pub struct NewCity {
+    pub starting_money: i64,
+    pub seed: u64,
+    pub neighborhood: Neighborhood,
+    pub development: Development,
+}
+
+/// Aggregate city statistics.
+#[derive(Resource, Default)]
+pub struct CityStats {
+    pub population: u32,
+    pub jobs: u32,
+    pub money: i64,
+    /// How attractive the city's shops are to visitors on average, up to
+    /// `MAX_ATTRACTIVENESS`.
+    pub attractiveness: u32,
+}
+
+/// How much the city wants more of each zone type, recomputed every tick.
+/// Positive values mean "build more of this".
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Demand {
+    pub residential: i32,
+    pub commercial: i32,
+    pub industrial: i32,
+}
+
+impl Demand {
+    /// Demand for a zone type; non-buildable zones have no demand.
+    pub fn get(&self, zone: Zone) -> i32 {
+        match zone {
+            Zone::Residential => self.residential,
+            Zone::Commercial => self.commercial,
+            Zone::Industrial => self.industrial,
+            Zone::Road
+            | Zone::DirtRoad
+            | Zone::Avenue
+            | Zone::Bridge
+            | Zone::Empty
+            | Zone::FireStation
+            | Zone::PoliceStation
+            | Zone::School
+            | Zone::PowerPlant
+            | Zone::WaterTower
+            | Zone::PumpingStation
+            | Zone::Landfill
+            | Zone::Seaport
+            | Zone::Airport
+            | Zone::RecyclingCenter
+            | Zone::Water
+            | Zone::Forest => 0,
+        }
+    }
+}
+
+impl std::ops::Add for Demand {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            residential: self.residential + other.residential,
+            commercial: self.commercial + other.commercial,
+            industrial: self.industrial + other.industrial,
+        }
+    }
+}
+
+/// Income and expense breakdown of the most recent simulation tick.
+/// `simulation_step` computes this first and then applies `net()` to the
+/// treasury, so the budget panel always shows the numbers actually used.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub residential_tax: i64,
+    pub commercial_tax: i64,
+    pub tourism: i64,
+    /// Freight exported to neighboring cities.
+    pub trade: i64,
+    pub road_maintenance: i64,
+    pub power_upkeep: i64,
+    pub water_upkeep: i64,
+    pub pipe_maintenance: i64,
+    pub garbage_upkeep: i64,
+    pub port_upkeep: i64,
+    pub service_costs: i64,
+    pub fire_upkeep: i64,
+    pub police_upkeep: i64,
+    pub school_upkeep: i64,
+    pub loan_repayments: i64,
+}
+
+impl Budget {
+    /// Compute the tax and general service line items for a tick from the
+    /// city's totals; building upkeep is filled in by the caller. Services
+    /// cost twice what residents pay, rounded along with their tax, so the
+    /// two come to the `population / 10` the city lost to its residents
+    /// before the budget was broken down.
+    pub fn compute(population: u32, jobs: u32) -> Self {
+        let residential_tax = population as i64 / 10;
+        Self {
+            residential_tax,
+            commercial_tax: jobs as i64 / 5,
+            service_costs: 2 * residential_tax,
+            ..default()
+        }
+    }
+
+    pub fn income(&self) -> i64 {
+        self.residential_tax + self.commercial_tax + self.tourism + self.trade
+    }
+
+    pub fn expenses(&self) -> i64 {
+        self.road_maintenance
+            + self.power_upkeep
+            + self.water_upkeep
+            + self.pipe_maintenance
+            + self.garbage_upkeep
+            + self.port_upkeep
+            + self.service_costs
+            + self.fire_upkeep
+            + self.police_upkeep
+            + self.school_upkeep
+            + self.loan_repayments
+    }
+
+    /// Change in money this budget applies.
+    pub fn net(&self) -> i64 {
+        self.income() - self.expenses()
+    }
+}
+
+/// Budget accumulated tick by tick over the current month, which is paid
+/// into (or out of) the treasury when the month ends.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ledger {
+    pub pending: i64,
+}
+
+impl Ledger {
+    /// Book a tick's budget, paying the month's total into `money` on the
+    /// tick that ends it.
+    pub fn book(&mut self, budget: &Budget, money: &mut i64, ends_month: bool) {
+        self.pending += budget.net();
+        if ends_month {
+            *money += self.pending;
+            self.pending = 0;
+        }
+    }
+}
+
+/// Net budget per tick, averaged over the last several ticks, so the HUD
+/// can show where the treasury is heading without flickering.
+#[derive(Resource, Default, Clone, Copy, PartialEq)]
+pub struct IncomeTrend {
+    pub per_tick: f32,
+}
+
+impl IncomeTrend {
+    /// What a month at the current rate comes to.
+    pub fn per_month(&self) -> i64 {
+        (self.per_tick * TICKS_PER_MONTH as f32).round() as i64
+    }
+}
+
+/// The city's random number generator, seeded from the city seed so a run
+/// can be reproduced. Every random decision that affects the simulation
+/// draws from it, from systems ordered after `simulation_step`; purely
+/// cosmetic randomness keeps its own stream so frame timing cannot shift it.
+#[derive(Resource)]
+pub struct SimRng(pub SmallRng);
+
+/// Per-tick running costs of infrastructure, from the config file.
+#[derive(Resource)]
+pub struct Maintenance {
+    pub road_per_tile: f32,
+}
+
+/// Population of every tile as of the last tick, stored as a summed-area
+/// table so the residents in any square can be counted in constant time.
+#[derive(Resource)]
+pub struct PopulationGrid {
+    width: i32,
+    height: i32,
+    /// `sums[y * (width + 1) + x]` holds the population of all tiles left
+    /// of `x` and below `y`.
+    sums: Vec<u32>,
+}
+
+impl PopulationGrid {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            sums: vec![0; ((width + 1) * (height + 1)) as usize],
+        }
+    }
+
+    /// Rebuild from per-tile populations laid out row by row.
+    pub fn rebuild(&mut self, population: &[u32]) {
+        let stride = (self.width + 1) as usize;
+        for y in 0..self.height as usize {
+            let mut row = 0;
+            for x in 0..self.width as usize {
+                row += population[y * self.width as usize + x];
+                self.sums[(y + 1) * stride + x + 1] = self.sums[y * stride + x + 1] + row;
+            }
+        }
+    }
+
+    /// Residents living within `radius` tiles of `coord` on either axis,
+    /// clipped to the map.
+    pub fn within(&self, coord: IVec2, radius: i32) -> u32 {
+        let min = (coord - radius).max(IVec2::ZERO);
+        let max = (coord + radius + 1).min(IVec2::new(self.width, self.height));
+        if min.x >= max.x || min.y >= max.y {
+            return 0;
+        }
+        let at = |c: IVec2| self.sums[(c.y * (self.width + 1) + c.x) as usize];
+        at(max) + at(min) - at(IVec2::new(min.x, max.y)) - at(IVec2::new(max.x, min.y))
+    }
+}
+
+/// Timer that ticks the simulation.
+#[derive(Resource)]
+pub struct SimTimer(pub Timer);
+
+/// Seconds per tick at normal speed, from the config and `--speed`, before
+/// the speed in `Settings`.
+#[derive(Resource)]
+struct TickSeconds(f32);
+
+/// Run the simulation at the speed picked in the settings.
+fn apply_sim_speed(
+    settings: Res<Settings>,
+    tick_seconds: Res<TickSeconds>,
+    mut timer: ResMut<SimTimer>,
+) {
+    let duration = Duration::from_secs_f32(tick_seconds.0 / settings.speed);
+    if timer.0.duration() != duration {
+        timer.0.set_duration(duration);
+    }
+}
+
+/// Put the economy and the RNG back where a new city starts.
+fn reset_simulation(
+    mut commands: Commands,
+    new_city: Res<NewCity>,
+    map: Res<MapConfig>,
+    mut timer: ResMut<SimTimer>,
+    mut history: ResMut<StatsHistory>,
+) {
+    history.clear();
+    commands.insert_resource(PopulationGrid::new(map.width, map.height));
+    commands.insert_resource(CityStats {
+        money: new_city.starting_money,
+        ..default()
+    });
+    commands.insert_resource(Demand::default());
+    commands.insert_resource(CityBreakdown::default());
+    commands.insert_resource(Budget::default());
+    commands.insert_resource(Ledger::default());
+    commands.insert_resource(IncomeTrend::default());
+    commands.insert_resource(SimRng(SmallRng::seed_from_u64(new_city.seed)));
+    commands.insert_resource(new_city.neighborhood);
+    commands.insert_resource(new_city.development);
+    timer.0.reset();
+}
+
+/// Simple, very toy simulation step. Every tick, each tile grows by the
+/// rules in `sim::grow_tile`, then demand and the budget are worked out
+/// from the new totals, shifted by any economic events under way and the
+/// weather, and the budget is added to the month's ledger, which is
+/// settled into the treasury on the last tick of the month, and folded
+/// into the income trend. The resulting totals are recorded in
+/// `StatsHistory`.
+///
+/// A tile's growth only reads its own data and layers built before the
+/// tick (the zone snapshot, last tick's population, coverage, traffic),
+/// so on large maps the tiles are grown in parallel. The totals are summed
+/// in a serial pass afterwards, which comes out the same either way.
+#[allow(clippy::too_many_arguments)]
+pub fn simulation_step(
+    time: Res<Time>,
+    mut timer: ResMut<SimTimer>,
+    mut tiles: Query<(
+        &TileCoord,
+        &Zone,
+        &mut TileData,
+    )>,
+    mut stats: ResMut<CityStats>,
+    mut demand: ResMut<Demand>,
+    (mut budget, mut ledger, mut trend): (ResMut<Budget>, ResMut<Ledger>, ResMut<IncomeTrend>),
+    mut notifications: MessageWriter<Notification>,
+    (traffic, tourism, trade): (Res<TrafficGrid>, Res<Tourism>, Res<Trade>),
+    (coverage, adjacency, power, water): (
+        Res<ServiceCoverage>,
+        Res<Adjacency>,
+        Res<PowerGrid>,
+        Res<WaterSupply>,
+    ),
+    defs: Res<ZoneDefs>,
+    (maintenance, job_access, pipes, diagnostics): (
+        Res<Maintenance>,
+        Res<JobAccess>,
+        Res<Pipes>,
+        Res<SimDiagnostics>,
+    ),
+    mut population_grid: ResMut<PopulationGrid>,
+    clock: Res<TimeOfDay>,
+    (mut history, mut district_stats, mut breakdown): (
+        ResMut<StatsHistory>,
+        ResMut<DistrictStats>,
+        ResMut<CityBreakdown>,
+    ),
+    (loans, economy, districts, ports): (Res<Loans>, Res<Economy>, Res<Districts>, Res<Ports>),
+    (neighborhood, elevation, garbage, weather): (
+        Res<Neighborhood>,
+        Res<Elevation>,
+        Res<Garbage>,
+        Res<Weather>,
+    ),
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let tick_span = diagnostics.start();
+
+    let previous_money = stats.money;
+
+    // Reset stats and re-compute from tiles.
+    stats.population = 0;
+    stats.jobs = 0;
+    let mut commercial_jobs = 0;
+    let mut industrial_jobs = 0;
+    // Jobs paying tax, after what crime takes from shops.
+    let mut taxed_jobs = 0;
+    let mut upkeep = [0; Service::ALL.len()];
+    let mut power_upkeep = 0;
+    let mut water_upkeep = 0;
+    let mut garbage_upkeep = 0;
+    let mut port_upkeep = 0;
+    let mut tourism_total = 0;
+    // Industrial jobs working on each road network out of the city.
+    let mut exports = vec![0; trade.networks()];
+    // Attractiveness of every shop with jobs, added up.
+    let mut attraction = 0;
+    let mut shops = 0;
+    let modifiers = economy.modifiers();
+    let commercial_tax = |tax: i64| modifiers.tax(tax) * weather.commercial_tax_percent() / 100;
+    // In the snow, buildings only get on with construction every other tick.
+    let building_paused = weather.slows_construction() && clock.ticks % 2 == 1;
+
+    // Snapshot the zones first so neighbors can be looked up.
+    let (width, height) = (population_grid.width, population_grid.height);
+    let mut zones = ZoneGrid::new(width, height);
+    let mut snapshot = StatsSnapshot::default();
+    let mut states = Vec::with_capacity((width * height) as usize);
+    for (coord, zone, data) in tiles.iter() {
+        zones.set(coord.coord, *zone);
+        snapshot.count_zone(*zone);
+        states.push(TileState {
+            coord: coord.coord,
+            zone: *zone,
+            data: *data,
+        });
+    }
+
+    let around = |coord: IVec2, zone: Zone| match zone {
+        Zone::Residential => Surroundings {
+            next_to_road: zones.next_to_road(coord, *neighborhood),
+            police: coverage.covers(Service::Police, coord),
+            school: coverage.covers(Service::School, coord),
+            unfilled_jobs: job_access.unfilled(coord),
+            view: elevation.get(coord).is_some_and(|level| level >= VIEW_LEVEL),
+            neighbors_percent: adjacency.get(coord).percent(),
+            powered: power.powered(coord),
+            watered: water.watered(coord),
+            pollution: garbage.pollution(coord),
+            ..default()
+        },
+        Zone::Commercial => Surroundings {
+            congested: traffic.congested_near(coord, *neighborhood),
+            customers: population_grid.within(coord, CUSTOMER_RADIUS),
+            police: coverage.covers(Service::Police, coord),
+            school: coverage.covers(Service::School, coord),
+            view: elevation.get(coord).is_some_and(|level| level >= VIEW_LEVEL),
+            powered: power.powered(coord),
+            watered: water.watered(coord),
+            pollution: garbage.pollution(coord),
+            ..default()
+        },
+        Zone::Industrial => Surroundings {
+            next_to_road: zones.next_to_road(coord, *neighborhood),
+            extra_jobs: modifiers.industrial_jobs,
+            powered: power.powered(coord),
+            watered: water.watered(coord),
+            ..default()
+        },
+        _ => Surroundings::default(),
+    };
+    let growth_span = diagnostics.start();
+    let parallel = (width * height) as usize >= PARALLEL_TILES;
+    sim::tick(&mut states, &defs, building_paused, parallel, around);
+    // Only the tiles that changed are written back, so change detection
+    // only sees those. The query visits the tiles in the same order as
+    // when they were copied out.
+    for ((_, _, mut data), state) in tiles.iter_mut().zip(&states) {
+        data.set_if_neq(state.data);
+    }
+    diagnostics.finish(SimPhase::Growth, growth_span);
+
+    // Districts and the breakdown are added up in the same pass as the
+    // city.
+    let mut tallies = vec![DistrictTally::default(); districts.list().len()];
+    let mut tally = CityBreakdown::default();
+    let mut population = vec![0; (width * height) as usize];
+    for (coord, zone, data) in tiles.iter() {
+        let coord = coord.coord;
+        let mut charged = 0;
+        if let Some(service) = Service::provided_by(*zone) {
+            upkeep[service as usize] += defs.get(*zone).upkeep;
+            charged += defs.get(*zone).upkeep;
+        }
+        // A building's upkeep is charged once, on its anchor.
+        if *zone == Zone::PowerPlant && data.anchor == Some(coord) {
+            power_upkeep += defs.get(*zone).upkeep;
+            charged += defs.get(*zone).upkeep;
+        }
+        if is_port(*zone) && data.anchor == Some(coord) {
+            port_upkeep += defs.get(*zone).upkeep;
+            charged += defs.get(*zone).upkeep;
+        }
+        if is_water_source(*zone) {
+            water_upkeep += defs.get(*zone).upkeep;
+            charged += defs.get(*zone).upkeep;
+        }
+        if is_garbage_site(*zone) {
+            garbage_upkeep += defs.get(*zone).upkeep;
+            charged += defs.get(*zone).upkeep;
+        }
+
+        population[(coord.y * width + coord.x) as usize] = data.population;
+        stats.population += data.population;
+        stats.jobs += data.jobs;
+        let taxed = match zone {
+            Zone::Commercial => {
+                commercial_jobs += data.jobs;
+                if data.jobs > 0 {
+                    let score = tourism.get(coord);
+                    tourism_total += tourism_income(data.jobs, score);
+                    attraction += score;
+                    shops += 1;
+                }
+                sim::taxed_shop_jobs(data.jobs, data.crime)
+            }
+            Zone::Industrial => {
+                industrial_jobs += data.jobs;
+                if let Some(network) = trade.network(coord) {
+                    exports[network] += data.jobs;
+                }
+                data.jobs
+            }
+            _ => 0,
+        };
+        taxed_jobs += taxed;
+        if let Some(slot) = districts.slot(coord) {
+            tallies[slot].add(*zone, data, taxed, charged);
+        }
+        let on_road = *zone == Zone::Residential && zones.next_to_road(coord, *neighborhood);
+        tally.add(*zone, data, defs.get(*zone).capacity, power.powered(coord), on_road);
+    }
+    breakdown.set_if_neq(tally);
+    let summaries = districts.list().iter().zip(&tallies).map(|(district, tally)| {
+        let (residents, taxed_jobs) = tally.taxpayers();
+        let base = Budget::compute(residents, taxed_jobs);
+        let taxes = modifiers.tax(base.residential_tax) + commercial_tax(base.commercial_tax);
+        tally.summary(district.id, taxes)
+    });
+    district_stats.set_if_neq(DistrictStats(summaries.collect()));
+    stats.attractiveness = attraction.checked_div(shops).unwrap_or(0);
+
+    population_grid.rebuild(&population);
+
+    demand.set_if_neq(
+        sim::demand(stats.population, commercial_jobs, industrial_jobs)
+            + modifiers.demand
+            + ports.demand()
+            + trade.demand(clock.ticks),
+    );
+
+    // Money: compute the budget first, then book it, settling the month's
+    // total once it is over. The clock only advances after this system.
+    let base = sim::budget(
+        stats.population,
+        taxed_jobs,
+        zones.road_maintenance(),
+        maintenance.road_per_tile,
+        upkeep,
+    );
+    budget.set_if_neq(Budget {
+        residential_tax: modifiers.tax(base.residential_tax),
+        commercial_tax: commercial_tax(base.commercial_tax),
+        tourism: tourism_total,
+        trade: trade.income(&exports, clock.ticks),
+        road_maintenance: base.road_maintenance * weather.road_percent() / 100,
+        power_upkeep,
+        water_upkeep,
+        garbage_upkeep,
+        port_upkeep,
+        pipe_maintenance: (pipes.count() as f32 * PIPE_UPKEEP_PER_TILE).round() as i64,
+        loan_repayments: loans.payment_due(),
+        ..base
+    });
+    ledger.book(&budget, &mut stats.money, ends_month(clock.ticks + 1));
+    trend.per_tick += (budget.net() as f32 - trend.per_tick) * TREND_SMOOTHING;
+
+    if previous_money >= 0 && stats.money < 0 {
+        notifications.write(Notification(Phrase::new("notify.in_the_red")));
+    }
+
+    // The clock only advances after this system, so count this tick here.
+    history.push(StatsSnapshot {
+        tick: clock.ticks + 1,
+        population: stats.population,
+        jobs: stats.jobs,
+        money: stats.money,
+        ..snapshot
+    });
+    diagnostics.finish(SimPhase::Tick, tick_span);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn customers_are_counted_in_a_square_clipped_to_the_map() {
+        // A home of 1 on every tile, and 100 at (2, 2).
+        let mut population = vec![1; 25];
+        population[2 * 5 + 2] = 100;
+        let mut grid = PopulationGrid::new(5, 5);
+        grid.rebuild(&population);
+
+        assert_eq!(grid.within(IVec2::new(2, 2), 0), 100);
+        assert_eq!(grid.within(IVec2::new(2, 2), 1), 108);
+        assert_eq!(grid.within(IVec2::new(2, 2), CUSTOMER_RADIUS), 124);
+        assert_eq!(grid.within(IVec2::ZERO, 1), 4);
+        assert_eq!(grid.within(IVec2::new(4, 0), 2), 108);
+        assert_eq!(grid.within(IVec2::new(4, 0), 1), 4);
+        assert_eq!(grid.within(IVec2::new(-3, 2), 2), 0);
+        assert_eq!(grid.within(IVec2::new(-1, 2), 1), 3);
+        assert_eq!(grid.within(IVec2::new(9, 9), 1), 0);
+
+        grid.rebuild(&[0; 25]);
+        assert_eq!(grid.within(IVec2::new(2, 2), CUSTOMER_RADIUS), 0);
+    }
+
+    /// Every line item a different bit, so one missed or counted twice
+    /// shows in the totals.
+    fn itemized() -> Budget {
+        Budget {
+            residential_tax: 1,
+            commercial_tax: 2,
+            tourism: 4,
+            trade: 8,
+            road_maintenance: 16,
+            power_upkeep: 32,
+            water_upkeep: 64,
+            pipe_maintenance: 128,
+            garbage_upkeep: 256,
+            port_upkeep: 512,
+            service_costs: 1024,
+            fire_upkeep: 2048,
+            police_upkeep: 4096,
+            school_upkeep: 8192,
+            loan_repayments: 16384,
+        }
+    }
+
+    #[test]
+    fn net_is_every_line_item() {
+        let budget = itemized();
+        assert_eq!(budget.income(), 1 + 2 + 4 + 8);
+        assert_eq!(budget.expenses(), 32768 - 16);
+        assert_eq!(budget.net(), budget.income() - budget.expenses());
+    }
+
+    #[test]
+    fn residents_are_rounded_once() {
+        for population in 0..200 {
+            for jobs in 0..50 {
+                let net = Budget::compute(population, jobs).net();
+                assert_eq!(net, jobs as i64 / 5 - population as i64 / 10, "{population}, {jobs}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_ledger_applies_the_line_items() {
+        let budget = itemized();
+        let mut ledger = Ledger::default();
+        let mut money = 1000;
+        for tick in 1..=TICKS_PER_MONTH {
+            ledger.book(&budget, &mut money, ends_month(tick));
+            if tick < TICKS_PER_MONTH {
+                assert_eq!(money, 1000);
+            }
+        }
+        assert_eq!(money, 1000 + TICKS_PER_MONTH as i64 * budget.net());
+        assert_eq!(ledger.pending, 0);
+    }
+}
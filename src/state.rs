@@ -0,0 +1,99 @@
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+
+/// Top-level game flow: fetch assets first, then pick a city from the main
+/// menu and play it. Everything spawned while playing is despawned again on
+/// leaving `Playing`.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AppState {
+    #[default]
+    Loading,
+    MainMenu,
+    Playing,
+    DownloadFailed,
+}
+
+/// What the city is doing while `AppState::Playing`. Only `Running`
+/// advances the game; once built, the world stays spawned in every variant.
+#[derive(SubStates, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[source(AppState = AppState::Playing)]
+pub enum GameState {
+    /// Tiles are still being spawned and `TileMap` does not exist yet.
+    #[default]
+    Building,
+    Running,
+    /// The pause menu is open (Escape).
+    Paused,
+    /// The settings panel is open, from the pause menu.
+    Settings,
+    /// Asking the player to confirm clearing the map.
+    ConfirmClear,
+    /// The city went bankrupt; the summary screen is up.
+    GameOver,
+    /// `ResetCity` runs on entering this state, after which the game starts
+    /// running again from scratch.
+    Restarting,
+}
+
+/// Every plugin adds the systems that put its part of the city back to a
+/// fresh start here. Runs on entering `AppState::Playing`, sizing per-tile
+/// grids for the map about to be built, and on restarting in place.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResetCity;
+
+/// Per-frame ordering while playing: player input is applied before the
+/// simulation ticks, and the UI reflects the result last.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    Input,
+    Simulation,
+    Ui,
+}
+
+/// Ordering of the systems that build the world on entering `Playing`:
+/// the atlas must exist before tiles use it, and tiles before the HUD.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpawnSet {
+    Assets,
+    Map,
+    Ui,
+}
+
+/// Registers `AppState`, `GameState`, `ResetCity`, and the ordering
+/// between the plugin system sets.
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .add_sub_state::<GameState>()
+            .init_schedule(ResetCity)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                run_reset_city.before(SpawnSet::Assets),
+            )
+            .add_systems(
+                OnEnter(GameState::Restarting),
+                (run_reset_city, finish_restart).chain(),
+            )
+            .configure_sets(
+                OnEnter(AppState::Playing),
+                (SpawnSet::Assets, SpawnSet::Map, SpawnSet::Ui).chain(),
+            )
+            .configure_sets(
+                Update,
+                (GameSet::Input, GameSet::Simulation, GameSet::Ui)
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
+fn run_reset_city(world: &mut World) {
+    world.run_schedule(ResetCity);
+}
+
+/// The reset systems have all run by the time this transition applies.
+fn finish_restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Running);
+}
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+
+use crate::calendar::TICKS_PER_MONTH;
+use crate::history::{StatsField, StatsHistory};
+use crate::locale::{Locale, Phrase};
+use crate::map::{TileData, Zone};
+use crate::sim::MAX_LAND_VALUE;
+use crate::state::{AppState, GameSet, SpawnSet};
+
+/// How full the homes have to be before the panel calls housing short, in
+/// percent.
+const FULL_HOUSING_PERCENT: u32 = 90;
+
+/// The statistics panel, toggled with F8: what the city is made of and
+/// how well it is used, with the reasons growth is stalling, and how the
+/// population, jobs, and money moved over the last month. Its numbers are
+/// added up by `simulation_step` in the pass it already makes over every
+/// tile, and the month's come from `StatsHistory`.
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CityBreakdown>()
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_statistics_panel.in_set(SpawnSet::Ui),
+            )
+            .add_systems(
+                Update,
+                (toggle_statistics_panel, update_statistics_panel)
+                    .chain()
+                    .in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// The city tile by tile, as of the last tick.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct CityBreakdown {
+    /// Tiles of each zone, in `Zone::ALL` order.
+    pub tiles: [u32; Zone::ALL.len()],
+    pub population: u32,
+    /// Residents the homes would hold if every one were full.
+    pub housing: u32,
+    pub jobs: u32,
+    /// Land value of every home and shop, added up, and how many there are.
+    land_value: u32,
+    lots: u32,
+    /// Homes, shops, and industry with and without power.
+    pub powered: u32,
+    pub unpowered: u32,
+    /// Residential tiles, and those of them next to a road.
+    pub homes: u32,
+    pub homes_on_road: u32,
+}
+
+impl CityBreakdown {
+    /// Count one tile in. `capacity` is the most its zone holds, and
+    /// `on_road` whether it is next to a road by the neighborhood rule.
+    pub fn add(
+        &mut self,
+        zone: Zone,
+        data: &TileData,
+        capacity: u32,
+        powered: bool,
+        on_road: bool,
+    ) {
+        if let Some(i) = Zone::ALL.iter().position(|&z| z == zone) {
+            self.tiles[i] += 1;
+        }
+        self.population += data.population;
+        self.jobs += data.jobs;
+        if zone == Zone::Residential {
+            self.housing += capacity;
+            self.homes += 1;
+            self.homes_on_road += u32::from(on_road);
+        }
+        if matches!(zone, Zone::Residential | Zone::Commercial) {
+            self.land_value += data.land_value;
+            self.lots += 1;
+        }
+        if matches!(zone, Zone::Residential | Zone::Commercial | Zone::Industrial) {
+            if powered {
+                self.powered += 1;
+            } else {
+                self.unpowered += 1;
+            }
+        }
+    }
+
+    /// Tiles zoned `zone`.
+    pub fn count(&self, zone: Zone) -> u32 {
+        Zone::ALL
+            .iter()
+            .position(|&z| z == zone)
+            .map_or(0, |i| self.tiles[i])
+    }
+
+    /// Jobs with a resident to work them.
+    pub fn filled_jobs(&self) -> u32 {
+        self.jobs.min(self.population)
+    }
+
+    /// Average land value of the homes and shops.
+    pub fn land_value(&self) -> u32 {
+        self.land_value.checked_div(self.lots).unwrap_or(0)
+    }
+
+    /// Share of the residential tiles next to a road, in percent; all of
+    /// them when there are none.
+    pub fn road_access_percent(&self) -> u32 {
+        (self.homes_on_road * 100).checked_div(self.homes).unwrap_or(100)
+    }
+
+    /// What is keeping the city from growing, most pressing first.
+    pub fn hints(&self) -> Vec<Phrase> {
+        let mut hints = Vec::new();
+        let cut_off = self.homes - self.homes_on_road;
+        if cut_off > 0 {
+            hints.push(Phrase::new("stats.hint.no_road").number("count", cut_off));
+        }
+        if self.unpowered > 0 {
+            hints.push(Phrase::new("stats.hint.no_power").number("count", self.unpowered));
+        }
+        if self.housing == 0 {
+            hints.push(Phrase::new("stats.hint.no_homes"));
+        } else if self.population * 100 >= self.housing * FULL_HOUSING_PERCENT {
+            let percent = self.population * 100 / self.housing;
+            hints.push(Phrase::new("stats.hint.homes_full").number("percent", percent));
+        }
+        if self.jobs < self.population {
+            let jobless = self.population - self.jobs;
+            hints.push(Phrase::new("stats.hint.jobless").number("count", jobless));
+        }
+        if self.filled_jobs() < self.jobs {
+            let empty = self.jobs - self.filled_jobs();
+            hints.push(Phrase::new("stats.hint.empty_jobs").number("count", empty));
+        }
+        hints
+    }
+}
+
+/// How `field` moved over the last month of ticks: its lowest and highest,
+/// and how far it got from where the month started. `None` before the
+/// first tick.
+fn month_trend(history: &StatsHistory, field: StatsField) -> Option<Phrase> {
+    let month = TICKS_PER_MONTH as usize;
+    let (min, max) = history.min_max(field, history.len().saturating_sub(month)..history.len())?;
+    let mut snapshots = history.last_n(month);
+    let first = snapshots.next()?.get(field);
+    let latest = snapshots.next_back().map_or(first, |snapshot| snapshot.get(field));
+    Some(
+        Phrase::new("stats.trend")
+            .key("field", field.label())
+            .number("min", min)
+            .number("max", max)
+            .signed("change", latest - first),
+    )
+}
+
+#[derive(Component)]
+struct StatisticsPanel;
+
+#[derive(Component)]
+struct StatisticsText;
+
+fn spawn_statistics_panel(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                left: Val::Percent(35.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            StatisticsPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(locale.text("stats.title")),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            panel.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                StatisticsText,
+            ));
+        });
+}
+
+/// Show or hide the statistics panel with F8.
+fn toggle_statistics_panel(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut panel: Query<&mut Node, With<StatisticsPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::F8) {
+        return;
+    }
+
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Rewrite the panel while it is open, when it is opened and whenever the
+/// numbers change.
+fn update_statistics_panel(
+    breakdown: Res<CityBreakdown>,
+    history: Res<StatsHistory>,
+    locale: Res<Locale>,
+    panel: Query<Ref<Node>, With<StatisticsPanel>>,
+    mut text: Query<&mut Text, With<StatisticsText>>,
+) {
+    let Ok(node) = panel.single() else {
+        return;
+    };
+    let stale = node.is_changed() || breakdown.is_changed() || history.is_changed();
+    if node.display == Display::None || !stale {
+        return;
+    }
+
+    let mut lines = vec![locale.text("stats.zones")];
+    for zone in Zone::ALL {
+        let count = breakdown.count(zone);
+        if count > 0 {
+            let line = Phrase::new("stats.zone")
+                .key("zone", zone.tile_label())
+                .number("count", count);
+            lines.push(format!("  {}", locale.render(&line)));
+        }
+    }
+    lines.extend([
+        locale.render(
+            &Phrase::new("stats.population")
+                .number("population", breakdown.population)
+                .number("housing", breakdown.housing),
+        ),
+        locale.render(
+            &Phrase::new("stats.jobs")
+                .number("filled", breakdown.filled_jobs())
+                .number("jobs", breakdown.jobs),
+        ),
+        locale.render(
+            &Phrase::new("stats.land_value")
+                .number("value", breakdown.land_value())
+                .number("max", MAX_LAND_VALUE),
+        ),
+        locale.render(
+            &Phrase::new("stats.power")
+                .number("powered", breakdown.powered)
+                .number("unpowered", breakdown.unpowered),
+        ),
+        locale.render(
+            &Phrase::new("stats.road_access").number("percent", breakdown.road_access_percent()),
+        ),
+        locale.text("stats.holding_back"),
+    ]);
+    let hints = breakdown.hints();
+    if hints.is_empty() {
+        lines.push(format!("  {}", locale.text("stats.hint.none")));
+    }
+    for hint in &hints {
+        lines.push(format!("  {}", locale.render(hint)));
+    }
+    if !history.is_empty() {
+        lines.push(locale.text("stats.last_month"));
+    }
+    for field in [StatsField::Population, StatsField::Jobs, StatsField::Money] {
+        if let Some(trend) = month_trend(&history, field) {
+            lines.push(format!("  {}", locale.render(&trend)));
+        }
+    }
+
+    let body = lines.join("\n");
+    if let Ok(mut text) = text.single_mut() {
+        if **text != body {
+            **text = body;
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Drawing of the terrain layer, and of the sprites in the layers above.
+//!
+//! Gameplay code only ever writes `TileVisual`; the backend chosen at
+//! compile time turns that into pixels. The default backend gives every
+//! tile its own `Sprite`. With the `chunk-mesh` feature, each map chunk is
+//! instead a single mesh whose UVs and vertex colors are patched in place,
+//! which avoids per-tile transforms and extraction on very large maps.
+//! Buildings are few enough to be sprites under either backend.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::assets::CitySprites;
+use crate::config::MapConfig;
+use crate::grid::tile_to_world;
+use crate::layers::{MapLayer, MapLayers};
+#[cfg(feature = "chunk-mesh")]
+use crate::map::{TileCoord, CHUNK_SIZE};
+use crate::map::MapChunk;
+use crate::state::AppState;
+
+/// Applies `TileVisual` changes through the active rendering backend.
+pub struct TileRenderPlugin;
+
+impl Plugin for TileRenderPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "chunk-mesh")]
+        app.init_resource::<ChunkMeshes>()
+            .add_systems(OnExit(AppState::Playing), forget_chunk_meshes);
+        // PostUpdate, so every Update system that edits visuals is done.
+        app.add_systems(
+            PostUpdate,
+            apply_sprite_visuals.run_if(in_state(AppState::Playing)),
+        );
+        #[cfg(feature = "chunk-mesh")]
+        app.add_systems(
+            PostUpdate,
+            apply_chunk_visuals.run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// What a tile looks like: the atlas cell to draw and the color to
+/// multiply it by.
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct TileVisual {
+    pub index: usize,
+    pub color: Color,
+}
+
+/// A tile-sized sprite of the atlas, looking like `visual`.
+pub fn tile_sprite(sprites: &CitySprites, map: &MapConfig, visual: TileVisual) -> Sprite {
+    Sprite {
+        image: sprites.texture.clone(),
+        custom_size: Some(Vec2::splat(map.tile_size)),
+        color: visual.color,
+        texture_atlas: Some(TextureAtlas {
+            layout: sprites.layout.clone(),
+            index: visual.index,
+        }),
+        ..default()
+    }
+}
+
+/// Everything needed to spawn the visual side of map chunks.
+#[cfg(not(feature = "chunk-mesh"))]
+#[derive(SystemParam)]
+pub struct TileRenderer<'w> {
+    sprites: Res<'w, CitySprites>,
+    map: Res<'w, MapConfig>,
+    layers: Res<'w, MapLayers>,
+}
+
+#[cfg(not(feature = "chunk-mesh"))]
+impl TileRenderer<'_> {
+    /// Spawn the parent entity of one chunk.
+    pub fn spawn_chunk(&mut self, commands: &mut Commands, _chunk: IVec2) -> Entity {
+        commands
+            .spawn((
+                MapChunk,
+                Transform::default(),
+                Visibility::default(),
+                ChildOf(self.layers.root(MapLayer::Terrain)),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .id()
+    }
+
+    /// Components that draw the tile at `coord` as part of `chunk`.
+    pub fn tile(&self, chunk: Entity, coord: IVec2, visual: TileVisual) -> impl Bundle {
+        let center = tile_to_world(&self.map, coord);
+        (
+            tile_sprite(&self.sprites, &self.map, visual),
+            Transform::from_translation(center.extend(0.0)),
+            ChildOf(chunk),
+        )
+    }
+}
+
+/// Copy changed visuals onto the sprites drawing them: every tile's under
+/// the default backend, and the buildings' under either.
+fn apply_sprite_visuals(
+    mut tiles: Query<(&TileVisual, &mut Sprite), Changed<TileVisual>>,
+) {
+    for (visual, mut sprite) in tiles.iter_mut() {
+        sprite.color = visual.color;
+        if let Some(ref mut atlas) = sprite.texture_atlas {
+            atlas.index = visual.index;
+        }
+    }
+}
+
+/// Mesh and material of every spawned chunk.
+#[cfg(feature = "chunk-mesh")]
+#[derive(Resource, Default)]
+struct ChunkMeshes {
+    meshes: std::collections::HashMap<IVec2, Handle<Mesh>>,
+    material: Option<Handle<ColorMaterial>>,
+}
+
+#[cfg(feature = "chunk-mesh")]
+#[derive(SystemParam)]
+pub struct TileRenderer<'w> {
+    sprites: Res<'w, CitySprites>,
+    map: Res<'w, MapConfig>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    chunk_meshes: ResMut<'w, ChunkMeshes>,
+    layers: Res<'w, MapLayers>,
+}
+
+#[cfg(feature = "chunk-mesh")]
+impl TileRenderer<'_> {
+    /// Spawn one chunk as a mesh with a quad per tile slot; slots past the
+    /// map edge are collapsed to nothing.
+    pub fn spawn_chunk(&mut self, commands: &mut Commands, chunk: IVec2) -> Entity {
+        use bevy::asset::RenderAssetUsages;
+        use bevy::mesh::{Indices, PrimitiveTopology};
+
+        let slots = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        let half = self.map.tile_size / 2.0;
+        let mut positions = Vec::with_capacity(slots * 4);
+        let mut indices = Vec::with_capacity(slots * 6);
+        for slot in 0..slots as i32 {
+            let coord = chunk * CHUNK_SIZE + IVec2::new(slot % CHUNK_SIZE, slot / CHUNK_SIZE);
+            let inside = coord.x < self.map.width && coord.y < self.map.height;
+            let center = tile_to_world(&self.map, coord);
+            let h = if inside { half } else { 0.0 };
+            positions.extend([
+                [center.x - h, center.y - h, 0.0],
+                [center.x + h, center.y - h, 0.0],
+                [center.x + h, center.y + h, 0.0],
+                [center.x - h, center.y + h, 0.0],
+            ]);
+            let base = slot as u32 * 4;
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0f32, 0.0]; slots * 4])
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![[1.0f32; 4]; slots * 4])
+            .with_inserted_indices(Indices::U32(indices));
+        let mesh = self.meshes.add(mesh);
+        self.chunk_meshes.meshes.insert(chunk, mesh.clone());
+
+        let texture = self.sprites.texture.clone();
+        let materials = &mut self.materials;
+        let material = self
+            .chunk_meshes
+            .material
+            .get_or_insert_with(|| materials.add(ColorMaterial::from(texture)))
+            .clone();
+
+        commands
+            .spawn((
+                MapChunk,
+                Mesh2d(mesh),
+                MeshMaterial2d(material),
+                Transform::default(),
+                ChildOf(self.layers.root(MapLayer::Terrain)),
+                DespawnOnExit(AppState::Playing),
+            ))
+            .id()
+    }
+
+    /// Tiles are drawn by their chunk's mesh and are not its children, so
+    /// they only need to be cleaned up with the rest of the city.
+    pub fn tile(&self, _chunk: Entity, _coord: IVec2, _visual: TileVisual) -> impl Bundle {
+        DespawnOnExit(AppState::Playing)
+    }
+}
+
+/// The chunks are gone once play ends; drop their meshes with them.
+#[cfg(feature = "chunk-mesh")]
+fn forget_chunk_meshes(mut chunk_meshes: ResMut<ChunkMeshes>) {
+    chunk_meshes.meshes.clear();
+}
+
+/// Patch the UVs and colors of changed tiles into their chunk meshes.
+#[cfg(feature = "chunk-mesh")]
+fn apply_chunk_visuals(
+    tiles: Query<(&TileCoord, &TileVisual), Changed<TileVisual>>,
+    chunk_meshes: Res<ChunkMeshes>,
+    sprites: Res<CitySprites>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    use bevy::mesh::VertexAttributeValues;
+
+    let Some(layout) = layouts.get(&sprites.layout) else {
+        return;
+    };
+    let size = layout.size.as_vec2();
+
+    for (coord, visual) in tiles.iter() {
+        let chunk = coord.coord.div_euclid(IVec2::splat(CHUNK_SIZE));
+        let Some(mesh) = chunk_meshes.meshes.get(&chunk).and_then(|h| meshes.get_mut(h)) else {
+            continue;
+        };
+        let local = coord.coord - chunk * CHUNK_SIZE;
+        let first = ((local.y * CHUNK_SIZE + local.x) * 4) as usize;
+
+        if let (Some(rect), Some(VertexAttributeValues::Float32x2(uvs))) = (
+            layout.textures.get(visual.index),
+            mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0),
+        ) {
+            let min = rect.min.as_vec2() / size;
+            let max = rect.max.as_vec2() / size;
+            uvs[first..first + 4].copy_from_slice(&[
+                [min.x, max.y],
+                [max.x, max.y],
+                [max.x, min.y],
+                [min.x, min.y],
+            ]);
+        }
+        if let Some(VertexAttributeValues::Float32x4(colors)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+        {
+            let color = visual.color.to_linear().to_f32_array();
+            colors[first..first + 4].fill(color);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::day_night::Daylight;
+use crate::map::{BuildingSprite, Zone};
+use crate::state::GameSet;
+use crate::tile_render::TileVisual;
+use crate::weather::Weather;
+
+/// Multiplier for unlit tiles at the darkest point of the night.
+const NIGHT_TINT: Color = Color::srgb(0.35, 0.38, 0.55);
+/// Multiplier for tiles with their lights on at night.
+const LIGHTS_ON_TINT: Color = Color::srgb(1.0, 0.85, 0.55);
+
+/// Composes every tile tint into the colors of the tile and its building.
+pub struct TintPlugin;
+
+impl Plugin for TintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (apply_tile_tints, tint_buildings).chain().in_set(GameSet::Ui),
+        );
+    }
+}
+
+/// Color multipliers tinting one tile, one field per feature. Features
+/// only write their own field; `apply_tile_tints` is the single place that
+/// combines them (with the time of day) into `TileVisual::color`. The data
+/// overlay is drawn in a layer of its own instead.
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct TileTint {
+    /// Darkening of busy roads.
+    pub traffic: Color,
+    /// Glow of a burning building.
+    pub fire: Color,
+    /// Highlight of the roads the commute debug view reached.
+    pub route: Color,
+    /// Dimming and pipe highlights while laying pipe.
+    pub pipes: Color,
+    /// District colors while the district panel is open.
+    pub districts: Color,
+    /// Wash of a zoned lot's zone color until it is built on.
+    pub zoning: Color,
+}
+
+impl Default for TileTint {
+    fn default() -> Self {
+        Self {
+            traffic: Color::WHITE,
+            fire: Color::WHITE,
+            route: Color::WHITE,
+            pipes: Color::WHITE,
+            districts: Color::WHITE,
+            zoning: Color::WHITE,
+        }
+    }
+}
+
+impl TileTint {
+    /// Product of all feature tints.
+    fn combined(&self) -> Color {
+        let features = multiply(self.traffic, self.fire);
+        let layers = multiply(multiply(features, self.route), self.pipes);
+        multiply(multiply(layers, self.districts), self.zoning)
+    }
+}
+
+/// Component-wise product of two colors.
+fn multiply(a: Color, b: Color) -> Color {
+    let (a, b) = (a.to_srgba(), b.to_srgba());
+    Color::srgba(a.red * b.red, a.green * b.green, a.blue * b.blue, a.alpha * b.alpha)
+}
+
+/// Whether a zone has lights on at night.
+fn has_lights(zone: Zone) -> bool {
+    matches!(zone, Zone::Residential | Zone::Commercial)
+}
+
+/// Write the composed tint to every tile whose inputs changed, or to all
+/// of them when the light level or the weather moves.
+pub fn apply_tile_tints(
+    daylight: Res<Daylight>,
+    weather: Res<Weather>,
+    mut tiles: Query<(Ref<Zone>, Ref<TileTint>, &mut TileVisual)>,
+) {
+    let all = daylight.is_changed() || weather.is_changed();
+    let darkness = daylight.darkness;
+    let night = multiply(Color::WHITE.mix(&NIGHT_TINT, darkness), weather.tint());
+    let lights = multiply(Color::WHITE.mix(&LIGHTS_ON_TINT, darkness), weather.tint());
+
+    for (zone, tint, mut visual) in tiles.iter_mut() {
+        if !all && !zone.is_changed() && !tint.is_changed() {
+            continue;
+        }
+        // Fires glow through the night like lit windows do.
+        let lit = has_lights(*zone) || tint.fire != Color::WHITE;
+        let time_of_day = if lit { lights } else { night };
+        let color = multiply(tint.combined(), time_of_day);
+        if visual.color != color {
+            visual.color = color;
+        }
+    }
+}
+
+/// Color each building like the tile it stands on, whenever the tile's
+/// color changes.
+pub fn tint_buildings(
+    tiles: Query<(&TileVisual, &BuildingSprite), Changed<TileVisual>>,
+    mut buildings: Query<&mut TileVisual, Without<BuildingSprite>>,
+) {
+    for (tile, building) in tiles.iter() {
+        if let Ok(mut visual) = buildings.get_mut(building.entity()) {
+            if visual.color != tile.color {
+                visual.color = tile.color;
+            }
+        }
+    }
+}
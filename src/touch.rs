@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use bevy::ui::UiSystems;
+
+use crate::config::MapConfig;
+use crate::input::{paint_zones, screen_coord, BuildTool, PaintCommand};
+use crate::map::MainCamera;
+use crate::replay::Playback;
+use crate::state::{AppState, GameSet};
+
+/// A touch that moves further than this, in screen pixels, is a drag
+/// rather than a tap.
+const TAP_PIXELS: f32 = 12.0;
+/// A touch held longer than this isn't a tap, even if it never moved.
+const TAP_SECONDS: f32 = 0.4;
+
+/// Touchscreen controls: tap a tile to place the selected zone there, drag
+/// one finger to pan the camera, and pinch to zoom. Touches that start on
+/// a button are left to the UI, as mouse clicks are.
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchGestures>()
+            .add_message::<PaintCommand>()
+            .add_systems(
+                PreUpdate,
+                read_touches
+                    .after(UiSystems::Focus)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                tap_to_build
+                    .before(paint_zones)
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            );
+    }
+}
+
+/// What the fingers on the screen did this frame, worked out before any
+/// system acts on it so a drag never also counts as a tap.
+#[derive(Resource)]
+pub struct TouchGestures {
+    /// Screen pixels a single finger dragged the view.
+    pub pan: Vec2,
+    /// Factor a two-finger pinch scales the view by: below 1 zooms in.
+    pub pinch: f32,
+    /// Screen positions tapped.
+    taps: Vec<Vec2>,
+    /// When each touch still down started, in seconds since startup.
+    started: HashMap<u64, f32>,
+    /// Touches that landed on the UI, which the map ignores.
+    on_ui: HashSet<u64>,
+    /// Touches that have become drags or pinches, so can't be taps.
+    dragging: HashSet<u64>,
+}
+
+impl Default for TouchGestures {
+    fn default() -> Self {
+        Self {
+            pan: Vec2::ZERO,
+            pinch: 1.0,
+            taps: Vec::new(),
+            started: HashMap::new(),
+            on_ui: HashSet::new(),
+            dragging: HashSet::new(),
+        }
+    }
+}
+
+/// Sort this frame's touches into taps, a one-finger pan, or a pinch.
+/// Runs after the UI has seen the touches, so one on a button is known.
+fn read_touches(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    ui_buttons: Query<&Interaction, With<Button>>,
+    mut gestures: ResMut<TouchGestures>,
+) {
+    gestures.pan = Vec2::ZERO;
+    gestures.pinch = 1.0;
+    gestures.taps.clear();
+
+    let now = time.elapsed_secs();
+    let on_button = ui_buttons.iter().any(|i| *i != Interaction::None);
+    for touch in touches.iter_just_pressed() {
+        gestures.started.insert(touch.id(), now);
+        if on_button {
+            gestures.on_ui.insert(touch.id());
+        }
+    }
+
+    let on_map: Vec<&Touch> = touches
+        .iter()
+        .filter(|touch| !gestures.on_ui.contains(&touch.id()))
+        .collect();
+    match on_map.as_slice() {
+        [] => {}
+        [touch] => {
+            if touch.distance().length() > TAP_PIXELS {
+                gestures.dragging.insert(touch.id());
+            }
+            if gestures.dragging.contains(&touch.id()) {
+                let delta = touch.delta();
+                gestures.pan = Vec2::new(-delta.x, delta.y);
+            }
+        }
+        [a, b, ..] => {
+            let was_apart = a.previous_position().distance(b.previous_position());
+            let apart = a.position().distance(b.position());
+            if was_apart > 0.0 && apart > 0.0 {
+                gestures.pinch = was_apart / apart;
+            }
+            gestures.dragging.insert(a.id());
+            gestures.dragging.insert(b.id());
+        }
+    }
+
+    for touch in touches.iter_just_released() {
+        let id = touch.id();
+        let quick = gestures.started.get(&id).is_some_and(|&at| now - at <= TAP_SECONDS);
+        if quick && !gestures.on_ui.contains(&id) && !gestures.dragging.contains(&id) {
+            gestures.taps.push(touch.position());
+        }
+    }
+    for touch in touches.iter_just_released().chain(touches.iter_just_canceled()) {
+        let id = touch.id();
+        gestures.started.remove(&id);
+        gestures.on_ui.remove(&id);
+        gestures.dragging.remove(&id);
+    }
+}
+
+/// A tap places the selected zone under the brush at the tapped tile,
+/// each one undone on its own.
+fn tap_to_build(
+    gestures: Res<TouchGestures>,
+    tool: Res<BuildTool>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    mut commands: MessageWriter<PaintCommand>,
+) {
+    for &tap in &gestures.taps {
+        let Some(center) = screen_coord(&camera_q, &map, tap) else {
+            continue;
+        };
+        commands.write(PaintCommand::Paint {
+            center,
+            zone: tool.zone,
+            start: true,
+        });
+        commands.write(PaintCommand::Finish);
+    }
+}
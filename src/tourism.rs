@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::garbage::Garbage;
+use crate::map::{TileCoord, Zone};
+use crate::simulation::simulation_step;
+use crate::state::GameSet;
+
+/// Most attractive a tile can be.
+pub const MAX_ATTRACTIVENESS: u32 = 100;
+/// How far from a tile the water and woods it looks out on can be.
+const SCENERY_RADIUS: i32 = 2;
+/// Attractiveness each tile of water or woods in reach adds.
+const SCENERY_POINTS: u32 = 10;
+/// Attractiveness lost for each point of pollution on the tile.
+const POLLUTION_PENALTY: u32 = 2;
+/// Attractiveness a shop needs before tourists come to it.
+const TOURIST_THRESHOLD: u32 = 30;
+/// Jobs times attractiveness that earn one unit of tourism income a tick.
+const TOURISM_DIVISOR: u32 = 500;
+
+/// Tourism: shops by the water or the woods, with little pollution around,
+/// draw visitors, who bring in income of their own on top of taxes.
+pub struct TourismPlugin;
+
+impl Plugin for TourismPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tourism>().add_systems(
+            Update,
+            update_attractiveness
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        );
+    }
+}
+
+/// Whether a zone is scenery visitors come to see.
+fn is_scenery(zone: Zone) -> bool {
+    zone.is_water() || zone == Zone::Forest
+}
+
+/// How attractive every tile is to visitors, row by row, from
+/// `MAX_ATTRACTIVENESS` for a tile among water and woods down to 0: each
+/// tile of scenery within `SCENERY_RADIUS` adds to it, and pollution on the
+/// tile takes away from it. Scenery itself counts for nothing.
+pub fn attractiveness(width: i32, height: i32, zones: &[Zone], pollution: &[u8]) -> Vec<u8> {
+    let mut scores = vec![0; zones.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) as usize;
+            if is_scenery(zones[i]) {
+                continue;
+            }
+            let mut scenery = 0;
+            for ny in (y - SCENERY_RADIUS).max(0)..=(y + SCENERY_RADIUS).min(height - 1) {
+                for nx in (x - SCENERY_RADIUS).max(0)..=(x + SCENERY_RADIUS).min(width - 1) {
+                    if is_scenery(zones[(ny * width + nx) as usize]) {
+                        scenery += 1;
+                    }
+                }
+            }
+            let score = (scenery * SCENERY_POINTS).min(MAX_ATTRACTIVENESS);
+            scores[i] = score.saturating_sub(u32::from(pollution[i]) * POLLUTION_PENALTY) as u8;
+        }
+    }
+    scores
+}
+
+/// Tourism income a shop with `jobs` jobs earns per tick on a tile of
+/// this attractiveness.
+pub fn tourism_income(jobs: u32, attractiveness: u32) -> i64 {
+    if attractiveness < TOURIST_THRESHOLD {
+        return 0;
+    }
+    i64::from(jobs * attractiveness / TOURISM_DIVISOR)
+}
+
+/// How attractive every tile is, laid out row by row. Worked out again
+/// from the zones and pollution, so never saved.
+#[derive(Resource, Default)]
+pub struct Tourism {
+    width: i32,
+    height: i32,
+    scores: Vec<u8>,
+}
+
+impl Tourism {
+    /// Attractiveness of the tile at `coord`, 0 off the map.
+    pub fn get(&self, coord: IVec2) -> u32 {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        if !on_map {
+            return 0;
+        }
+        u32::from(self.scores[(coord.y * self.width + coord.x) as usize])
+    }
+}
+
+/// Work attractiveness out again whenever zoning or pollution changes,
+/// ahead of the tick that pays for it.
+fn update_attractiveness(
+    map: Res<MapConfig>,
+    garbage: Res<Garbage>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+    mut tourism: ResMut<Tourism>,
+) {
+    if changed.is_empty() && !garbage.is_changed() {
+        return;
+    }
+    let (width, height) = (map.width, map.height);
+    let size = (width * height).max(0) as usize;
+    let mut zones = vec![Zone::Empty; size];
+    let mut pollution = vec![0; size];
+    for (coord, zone) in tiles.iter() {
+        let c = coord.coord;
+        let i = (c.y * width + c.x) as usize;
+        zones[i] = *zone;
+        pollution[i] = garbage.pollution(c) as u8;
+    }
+    *tourism = Tourism {
+        width,
+        height,
+        scores: attractiveness(width, height, &zones, &pollution),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Zones of a map drawn top row first: `~` water, `F` forest, `C`
+    /// shops, `I` industry, anything else empty.
+    fn zones(rows: &[&str]) -> Vec<Zone> {
+        rows.iter()
+            .rev()
+            .flat_map(|row| row.chars())
+            .map(|c| match c {
+                '~' => Zone::Water,
+                'F' => Zone::Forest,
+                'C' => Zone::Commercial,
+                'I' => Zone::Industrial,
+                _ => Zone::Empty,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lakeside_shops_beat_inland_ones_by_industry() {
+        let rows = [
+            "~~~~~~~", //
+            "CCCCCCC",
+            ".......",
+            ".......",
+            "FFFFFFF",
+            "CCCIIII",
+        ];
+        let zones = zones(&rows);
+        // The factories foul the air at their end of the inland strip.
+        let mut pollution = vec![0; zones.len()];
+        pollution[..7].copy_from_slice(&[0, 5, 15, 20, 20, 20, 20]);
+        let scores = attractiveness(7, 6, &zones, &pollution);
+        let at = |x: i32, y: i32| u32::from(scores[(y * 7 + x) as usize]);
+
+        // Three to five tiles of lake in reach along the shore.
+        let lakeside: Vec<u32> = (0..7).map(|x| at(x, 4)).collect();
+        assert_eq!(lakeside, vec![30, 40, 50, 50, 50, 40, 30]);
+        // As many trees inland, but the smoke drives the visitors off.
+        let inland: Vec<u32> = (0..3).map(|x| at(x, 0)).collect();
+        assert_eq!(inland, vec![30, 30, 20]);
+        // The lake and the woods themselves draw no one.
+        assert_eq!((at(3, 5), at(3, 1)), (0, 0));
+
+        let income = |scores: &[u32]| scores.iter().map(|&s| tourism_income(40, s)).sum::<i64>();
+        assert_eq!(income(&lakeside[..3]), 2 + 3 + 4);
+        assert_eq!(income(&inland), 2 + 2);
+    }
+
+    #[test]
+    fn pollution_spoils_the_view() {
+        let zones = zones(&["F~F", ".C.", "F~F"]);
+        assert_eq!(attractiveness(3, 3, &zones, &[0; 9])[4], 60);
+        let mut pollution = [0; 9];
+        pollution[4] = 10;
+        assert_eq!(attractiveness(3, 3, &zones, &pollution)[4], 40);
+        pollution[4] = 200;
+        assert_eq!(attractiveness(3, 3, &zones, &pollution)[4], 0);
+        // Pollution next door doesn't count against the tile.
+        let mut next_door = [0; 9];
+        next_door[3] = 50;
+        assert_eq!(attractiveness(3, 3, &zones, &next_door)[4], 60);
+    }
+
+    #[test]
+    fn attractiveness_is_capped() {
+        let mut zones = vec![Zone::Water; 25];
+        zones[12] = Zone::Commercial;
+        assert_eq!(u32::from(attractiveness(5, 5, &zones, &[0; 25])[12]), MAX_ATTRACTIVENESS);
+    }
+
+    #[test]
+    fn tourists_need_an_attractive_shop() {
+        assert_eq!(tourism_income(100, TOURIST_THRESHOLD - 1), 0);
+        assert_eq!(tourism_income(100, TOURIST_THRESHOLD), 6);
+        assert_eq!(tourism_income(100, MAX_ATTRACTIVENESS), 20);
+        assert_eq!(tourism_income(0, MAX_ATTRACTIVENESS), 0);
+        assert_eq!(Tourism::default().get(IVec2::ZERO), 0);
+    }
+}
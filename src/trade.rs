@@ -0,0 +1,336 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::TICKS_PER_MONTH;
+use crate::config::MapConfig;
+use crate::day_night::TimeOfDay;
+use crate::grid::{tile_to_world, Neighborhood, CARDINAL_NEIGHBORS};
+use crate::layers::{MapLayer, MapLayers};
+use crate::map::{TileCoord, Zone};
+use crate::sim::ZoneGrid;
+use crate::simulation::{simulation_step, Demand};
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+
+/// Income a road network out of the city earns per tick while any of its
+/// industry is working, and the industrial jobs that each earn one more.
+const TRADE_BASE_INCOME: i64 = 2;
+const JOBS_PER_TRADE_INCOME: u32 = 40;
+/// Industrial demand a connection adds, before diminishing returns.
+const TRADE_DEMAND: i32 = 10;
+/// Ticks a new connection takes to carry its full trade: three months.
+const TRADE_RAMP_TICKS: u64 = 3 * TICKS_PER_MONTH;
+/// Draw order of the arrows within the markers layer: level with the
+/// problem icons.
+const ARROW_Z: f32 = 0.5;
+const ARROW_COLOR: Color = Color::srgb(0.95, 0.75, 0.2);
+/// Length of an arrow, as a fraction of a tile.
+const ARROW_SIZE: f32 = 0.8;
+
+/// Trade with neighboring cities: every road tile at the edge of the map
+/// is a connection, which raises industrial demand for freight to export
+/// and earns income with the industry on its road network. Each further
+/// connection adds half as much as the one before, so one good road out
+/// is worth more than many.
+pub struct TradePlugin;
+
+impl Plugin for TradePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Trade>()
+            .add_systems(Startup, create_arrow_assets)
+            .add_systems(ResetCity, reset_trade)
+            .add_systems(
+                OnEnter(AppState::Playing),
+                spawn_trade_arrows.in_set(SpawnSet::Map),
+            )
+            .add_systems(
+                Update,
+                update_connections
+                    .in_set(GameSet::Simulation)
+                    .before(simulation_step),
+            )
+            .add_systems(Update, update_trade_arrows.in_set(GameSet::Ui));
+    }
+}
+
+/// A road leaving the map, into a neighboring city.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Connection {
+    pub coord: IVec2,
+    /// Tick the road reached the edge, from which trade builds up.
+    pub opened: u64,
+}
+
+impl Connection {
+    /// How much of its full trade the connection carries after `ticks`,
+    /// in percent.
+    fn ramp(&self, ticks: u64) -> u64 {
+        (ticks.saturating_sub(self.opened) * 100 / TRADE_RAMP_TICKS).min(100)
+    }
+}
+
+/// How much a group of connections is worth together, in percent of a
+/// single one at full trade: the busiest counts fully, the next half, the
+/// one after a quarter, and so on.
+fn combined_percent(mut ramps: Vec<u64>) -> u64 {
+    ramps.sort_unstable_by(|a, b| b.cmp(a));
+    ramps
+        .iter()
+        .enumerate()
+        .map(|(rank, ramp)| ramp * (100 >> rank.min(7)) / 100)
+        .sum()
+}
+
+/// The city's connections, and which road network out of the city each
+/// industrial tile ships its freight on. Rebuilt when zoning or the
+/// neighborhood rule changes; saves keep the connections.
+#[derive(Resource, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Trade {
+    width: i32,
+    height: i32,
+    /// Every connection, in row order.
+    connections: Vec<Connection>,
+    /// Indices into `connections` of the ones on each road network.
+    networks: Vec<Vec<usize>>,
+    /// For every tile, row by row, the network its industry exports on.
+    exporters: Vec<Option<u16>>,
+}
+
+impl Trade {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            exporters: vec![None; (width * height).max(0) as usize],
+            ..default()
+        }
+    }
+
+    /// The connections of a loaded city. Which networks they are on is
+    /// worked out again from the map.
+    pub fn from_saved(width: i32, height: i32, connections: Vec<Connection>) -> Self {
+        Self {
+            connections,
+            ..Self::new(width, height)
+        }
+    }
+
+    /// Find the connections and the networks behind them. Connections
+    /// already in `previous` keep the tick they opened; new ones open at
+    /// `ticks`. Roads join up side by side, and industry exports on a
+    /// network it is next to by the neighborhood rule.
+    pub fn detect(
+        zones: &ZoneGrid,
+        width: i32,
+        height: i32,
+        neighborhood: Neighborhood,
+        previous: &[Connection],
+        ticks: u64,
+    ) -> Self {
+        let mut trade = Self::new(width, height);
+        let index = |c: IVec2| (c.y * width + c.x) as usize;
+        let on_edge = |c: IVec2| c.x == 0 || c.y == 0 || c.x == width - 1 || c.y == height - 1;
+        let mut roads: Vec<Option<u16>> = vec![None; trade.exporters.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let coord = IVec2::new(x, y);
+                if !on_edge(coord) || !zones.get(coord).is_some_and(Zone::is_road) {
+                    continue;
+                }
+                let opened = previous
+                    .iter()
+                    .find(|old| old.coord == coord)
+                    .map_or(ticks, |old| old.opened);
+                let network = match roads[index(coord)] {
+                    Some(network) => network,
+                    None => {
+                        // A network not reached yet: label every road on it.
+                        let network = trade.networks.len() as u16;
+                        trade.networks.push(Vec::new());
+                        roads[index(coord)] = Some(network);
+                        let mut stack = vec![coord];
+                        while let Some(road) = stack.pop() {
+                            for offset in CARDINAL_NEIGHBORS {
+                                let next = road + offset;
+                                if zones.get(next).is_some_and(Zone::is_road)
+                                    && roads[index(next)].is_none()
+                                {
+                                    roads[index(next)] = Some(network);
+                                    stack.push(next);
+                                }
+                            }
+                        }
+                        network
+                    }
+                };
+                trade.networks[network as usize].push(trade.connections.len());
+                trade.connections.push(Connection { coord, opened });
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let coord = IVec2::new(x, y);
+                if zones.get(coord) != Some(Zone::Industrial) {
+                    continue;
+                }
+                // Next to two networks, the first one found carries it.
+                trade.exporters[index(coord)] = neighborhood
+                    .offsets()
+                    .iter()
+                    .map(|&offset| coord + offset)
+                    .filter(|&next| zones.get(next).is_some())
+                    .find_map(|next| roads[index(next)]);
+            }
+        }
+        trade
+    }
+
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    /// How many road networks lead out of the city.
+    pub fn networks(&self) -> usize {
+        self.networks.len()
+    }
+
+    /// The network industry at `coord` exports on, if any.
+    pub fn network(&self, coord: IVec2) -> Option<usize> {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        if !on_map {
+            return None;
+        }
+        self.exporters[(coord.y * self.width + coord.x) as usize].map(usize::from)
+    }
+
+    /// Trade income per tick `ticks` into the city, with `exports[i]`
+    /// industrial jobs working on network `i`. A network earns nothing
+    /// without industry working on it.
+    pub fn income(&self, exports: &[u32], ticks: u64) -> i64 {
+        self.networks
+            .iter()
+            .zip(exports)
+            .filter(|(_, &jobs)| jobs > 0)
+            .map(|(network, &jobs)| {
+                let ramps = network.iter().map(|&i| self.connections[i].ramp(ticks));
+                let earned = TRADE_BASE_INCOME + i64::from(jobs / JOBS_PER_TRADE_INCOME);
+                earned * combined_percent(ramps.collect()) as i64 / 100
+            })
+            .sum()
+    }
+
+    /// Industrial demand for freight to export, from every connection
+    /// together.
+    pub fn demand(&self, ticks: u64) -> Demand {
+        let ramps = self.connections.iter().map(|c| c.ramp(ticks)).collect();
+        Demand {
+            industrial: TRADE_DEMAND * combined_percent(ramps) as i32 / 100,
+            ..default()
+        }
+    }
+}
+
+fn reset_trade(map: Res<MapConfig>, mut trade: ResMut<Trade>) {
+    *trade = Trade::new(map.width, map.height);
+}
+
+/// Find the connections again whenever zoning or the neighborhood rule
+/// changes, ahead of the tick that trades on them.
+fn update_connections(
+    map: Res<MapConfig>,
+    neighborhood: Res<Neighborhood>,
+    clock: Res<TimeOfDay>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+    mut trade: ResMut<Trade>,
+) {
+    if changed.is_empty() && !neighborhood.is_changed() {
+        return;
+    }
+    let mut zones = ZoneGrid::new(map.width, map.height);
+    for (coord, zone) in tiles.iter() {
+        zones.set(coord.coord, *zone);
+    }
+    let detected = Trade::detect(
+        &zones,
+        map.width,
+        map.height,
+        *neighborhood,
+        &trade.connections,
+        clock.ticks,
+    );
+    trade.set_if_neq(detected);
+}
+
+/// Shared mesh and material of the arrows.
+#[derive(Resource)]
+struct ArrowAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+/// Parent of every arrow.
+#[derive(Component)]
+struct TradeArrows;
+
+fn create_arrow_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(ArrowAssets {
+        mesh: meshes.add(Triangle2d::new(
+            Vec2::new(0.0, 0.5),
+            Vec2::new(-0.4, -0.5),
+            Vec2::new(0.4, -0.5),
+        )),
+        material: materials.add(ARROW_COLOR),
+    });
+}
+
+fn spawn_trade_arrows(mut commands: Commands, layers: Res<MapLayers>) {
+    commands.spawn((
+        Transform::default(),
+        Visibility::default(),
+        TradeArrows,
+        ChildOf(layers.root(MapLayer::Markers)),
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// An arrow just off the map beyond every connection, pointing out of it.
+fn update_trade_arrows(
+    mut commands: Commands,
+    trade: Res<Trade>,
+    map: Res<MapConfig>,
+    assets: Res<ArrowAssets>,
+    root: Query<Entity, With<TradeArrows>>,
+) {
+    if !trade.is_changed() {
+        return;
+    }
+    let Ok(root) = root.single() else {
+        return;
+    };
+    commands.entity(root).despawn_related::<Children>();
+    for connection in trade.connections() {
+        let coord = connection.coord;
+        let outward = if coord.y == map.height - 1 {
+            IVec2::Y
+        } else if coord.y == 0 {
+            IVec2::NEG_Y
+        } else if coord.x == 0 {
+            IVec2::NEG_X
+        } else {
+            IVec2::X
+        };
+        let center = tile_to_world(&map, coord + outward);
+        commands.entity(root).with_child((
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(assets.material.clone()),
+            Transform::from_translation(center.extend(ARROW_Z))
+                .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, outward.as_vec2()))
+                .with_scale(Vec3::splat(map.tile_size * ARROW_SIZE)),
+        ));
+    }
+}
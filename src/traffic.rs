@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::diagnostics::{SimDiagnostics, SimPhase};
+use crate::grid::{Neighborhood, CARDINAL_NEIGHBORS};
+use crate::map::{TileCoord, TileData, Zone};
+use crate::oneway::{can_drive, RoadDirection};
+use crate::simulation::{simulation_step, SimTimer};
+use crate::state::{GameSet, ResetCity};
+use crate::tint::TileTint;
+
+/// Recompute commuter routes every this many simulation ticks.
+const TRAFFIC_INTERVAL_TICKS: u32 = 5;
+/// Darkest tint applied to a fully congested road.
+const BUSY_ROAD_TINT: f32 = 0.45;
+
+/// Routes residents to the nearest jobs over the road network and tracks
+/// the resulting traffic on each road tile.
+pub struct TrafficPlugin;
+
+impl Plugin for TrafficPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(ResetCity, init_traffic_grid)
+            .add_systems(
+                Update,
+                (update_traffic, tint_roads_by_traffic)
+                    .chain()
+                    .in_set(GameSet::Simulation)
+                    .after(simulation_step),
+            );
+    }
+}
+
+/// Commuters passing through each road tile, laid out row by row.
+#[derive(Resource)]
+pub struct TrafficGrid {
+    width: i32,
+    height: i32,
+    traffic: Vec<u32>,
+    /// Capacity of each road tile as of the last update, zero off-road.
+    capacity: Vec<u32>,
+    ticks_until_update: u32,
+}
+
+impl TrafficGrid {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            traffic: vec![0; (width * height) as usize],
+            capacity: vec![0; (width * height) as usize],
+            ticks_until_update: 0,
+        }
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        if coord.x < 0 || coord.y < 0 || coord.x >= self.width || coord.y >= self.height {
+            return None;
+        }
+        Some((coord.y * self.width + coord.x) as usize)
+    }
+
+    /// Commuters using the road at `coord` (zero off-road or off-map).
+    pub fn get(&self, coord: IVec2) -> u32 {
+        self.index(coord).map_or(0, |i| self.traffic[i])
+    }
+
+    /// Whether any road next to `coord` is over capacity.
+    pub fn congested_near(&self, coord: IVec2, neighborhood: Neighborhood) -> bool {
+        neighborhood
+            .offsets()
+            .iter()
+            .filter_map(|&n| self.index(coord + n))
+            .any(|i| self.traffic[i] > self.capacity[i])
+    }
+}
+
+fn init_traffic_grid(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(TrafficGrid::new(map.width, map.height));
+}
+
+/// Assign commuter traffic to roads.
+///
+/// One multi-source BFS from every road touching a job tile, searching
+/// backwards against each road's direction, gives each road the direction
+/// of its nearest job; each populated residential tile then
+/// walks that chain from its best adjacent road. That is linear in the
+/// number of roads plus total route length, and runs only every
+/// `TRAFFIC_INTERVAL_TICKS` ticks. A home with no allowed way to a job
+/// adds no traffic.
+pub fn compute_traffic(
+    width: i32,
+    height: i32,
+    zones: &[Zone],
+    directions: &[RoadDirection],
+    population: &[u32],
+) -> Vec<u32> {
+    let size = (width * height) as usize;
+    let index = |c: IVec2| {
+        (c.x >= 0 && c.y >= 0 && c.x < width && c.y < height)
+            .then(|| (c.y * width + c.x) as usize)
+    };
+    let coord = |i: usize| IVec2::new(i as i32 % width, i as i32 / width);
+
+    let mut distance = vec![u32::MAX; size];
+    let mut next = vec![usize::MAX; size];
+    let mut queue = VecDeque::new();
+    for i in 0..size {
+        if !zones[i].is_road() {
+            continue;
+        }
+        let near_jobs = CARDINAL_NEIGHBORS.iter().any(|&n| {
+            index(coord(i) + n).is_some_and(|j| {
+                matches!(zones[j], Zone::Commercial | Zone::Industrial)
+            })
+        });
+        if near_jobs {
+            distance[i] = 0;
+            queue.push_back(i);
+        }
+    }
+    while let Some(i) = queue.pop_front() {
+        for &n in &CARDINAL_NEIGHBORS {
+            if let Some(j) = index(coord(i) + n) {
+                // Commuters would drive from `j` to `i`.
+                let allowed = can_drive(directions[j], directions[i], -n);
+                if zones[j].is_road() && allowed && distance[j] == u32::MAX {
+                    distance[j] = distance[i] + 1;
+                    next[j] = i;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+
+    let mut traffic = vec![0; size];
+    for i in 0..size {
+        if zones[i] != Zone::Residential || population[i] == 0 {
+            continue;
+        }
+        let start = CARDINAL_NEIGHBORS
+            .iter()
+            .filter_map(|&n| index(coord(i) + n))
+            .filter(|&j| distance[j] != u32::MAX)
+            .min_by_key(|&j| distance[j]);
+        let mut road = start;
+        while let Some(r) = road {
+            traffic[r] += population[i];
+            road = (next[r] != usize::MAX).then_some(next[r]);
+        }
+    }
+    traffic
+}
+
+/// Every few ticks, rebuild the traffic grid from the current tiles. A
+/// road changing direction reroutes everyone on the next tick instead.
+fn update_traffic(
+    timer: Res<SimTimer>,
+    mut grid: ResMut<TrafficGrid>,
+    diagnostics: Res<SimDiagnostics>,
+    redirected: Query<(), Changed<RoadDirection>>,
+    tiles: Query<(&TileCoord, &Zone, &TileData, &RoadDirection)>,
+) {
+    if !redirected.is_empty() {
+        grid.bypass_change_detection().ticks_until_update = 0;
+    }
+    if !timer.0.just_finished() {
+        return;
+    }
+    if grid.ticks_until_update > 0 {
+        grid.bypass_change_detection().ticks_until_update -= 1;
+        return;
+    }
+    let span = diagnostics.start();
+
+    let size = (grid.width * grid.height) as usize;
+    let mut zones = vec![Zone::Empty; size];
+    let mut directions = vec![RoadDirection::TwoWay; size];
+    let mut population = vec![0; size];
+    for (coord, zone, data, direction) in tiles.iter() {
+        if let Some(i) = grid.index(coord.coord) {
+            zones[i] = *zone;
+            directions[i] = *direction;
+            population[i] = data.population;
+        }
+    }
+
+    let traffic = compute_traffic(grid.width, grid.height, &zones, &directions, &population);
+    grid.traffic = traffic;
+    grid.capacity = zones
+        .iter()
+        .map(|zone| zone.road().map_or(0, |road| road.capacity))
+        .collect();
+    grid.ticks_until_update = TRAFFIC_INTERVAL_TICKS - 1;
+    diagnostics.finish(SimPhase::Traffic, span);
+}
+
+/// Darken roads as they fill up, and clear the tint from everything else.
+fn tint_roads_by_traffic(
+    grid: Res<TrafficGrid>,
+    mut tiles: Query<(&TileCoord, &Zone, &mut TileTint)>,
+) {
+    if !grid.is_changed() {
+        return;
+    }
+
+    for (coord, zone, mut tint) in tiles.iter_mut() {
+        let load = zone.road().map_or(0.0, |road| {
+            (grid.get(coord.coord) as f32 / road.capacity as f32).min(1.0)
+        });
+        let shade = 1.0 - (1.0 - BUSY_ROAD_TINT) * load;
+        let traffic = Color::srgb(shade, shade, shade);
+        if tint.traffic != traffic {
+            tint.traffic = traffic;
+        }
+    }
+}
@@ -0,0 +1,1094 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::assets::PlaceholderGraphics;
+use crate::camera::TileLink;
+use crate::calendar::GameClock;
+use crate::config::MapConfig;
+use crate::districts::Districts;
+use crate::input::{ctrl_held, cursor_tile, BuildTool};
+use crate::map::{MainCamera, TileData, TileMap, Zone};
+use crate::economy::Economy;
+use crate::garbage::Garbage;
+use crate::loans::{Loans, TakeLoan, LOAN_SIZES, MAX_LOANS};
+use crate::locale::{Locale, Phrase};
+use crate::milestones::{Lock, Milestones};
+use crate::ports::{demand_boost, Ports};
+use crate::power::PowerGrid;
+use crate::replay::Playback;
+use crate::simulation::{Budget, CityStats, Demand, IncomeTrend, Ledger};
+use crate::tourism::MAX_ATTRACTIVENESS;
+use crate::state::{AppState, GameSet, ResetCity, SpawnSet};
+use crate::weather::Weather;
+
+/// Demand value at which an RCI bar is drawn completely full.
+const DEMAND_BAR_MAX: i32 = 100;
+/// Pixel size of a single RCI demand bar.
+const DEMAND_BAR_WIDTH: f32 = 12.0;
+const DEMAND_BAR_HEIGHT: f32 = 60.0;
+
+/// Number of notifications shown in the corner log at once.
+const NOTIFICATION_VISIBLE: usize = 5;
+/// Number of notifications kept for the history view.
+const NOTIFICATION_HISTORY: usize = 100;
+/// Seconds a notification stays in the corner log; the last two fade out.
+const NOTIFICATION_LIFETIME: f32 = 6.0;
+const NOTIFICATION_FADE: f32 = 2.0;
+
+/// Background of menu buttons, and while the pointer is over one.
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.25);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.3, 0.3, 0.38);
+const DISABLED_BUTTON_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+/// Color of the monthly income next to the money, by its sign.
+const INCOME_UP_COLOR: Color = Color::srgb(0.4, 0.9, 0.4);
+const INCOME_DOWN_COLOR: Color = Color::srgb(0.95, 0.35, 0.3);
+const INCOME_FLAT_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+
+/// HUD: stats line, RCI demand bars, budget panel, notification log, and
+/// the tile under the cursor.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NotificationLog>()
+            .add_message::<Notification>()
+            .add_message::<TileNotification>()
+            .add_systems(OnEnter(AppState::Playing), setup_ui.in_set(SpawnSet::Ui))
+            .add_systems(
+                Update,
+                (
+                    update_stats_ui,
+                    update_clock_ui,
+                    update_weather_ui,
+                    update_tool_ui,
+                    update_hover_ui,
+                    update_demand_ui,
+                    toggle_budget_panel,
+                    update_budget_ui,
+                    update_power_ui,
+                    update_garbage_ui,
+                    handle_loan_buttons.run_if(not(resource_exists::<Playback>)),
+                    update_economy_ui,
+                    update_loan_ui,
+                    (log_notifications, fade_notifications).chain(),
+                    toggle_notification_history,
+                )
+                    .in_set(GameSet::Ui),
+            )
+            .add_systems(Update, highlight_buttons)
+            .add_systems(ResetCity, clear_notifications);
+    }
+}
+
+/// A message for the player, shown in the notification log.
+#[derive(Message, Clone)]
+pub struct Notification(pub Phrase);
+
+/// A notification about one tile, logged like any other; clicking it in
+/// the corner log focuses the camera on the tile.
+#[derive(Message, Clone)]
+pub struct TileNotification {
+    pub tile: IVec2,
+    pub text: Phrase,
+}
+
+/// Retained notification history, newest last.
+#[derive(Resource, Default)]
+pub struct NotificationLog {
+    history: VecDeque<String>,
+}
+
+impl NotificationLog {
+    fn push(&mut self, message: String) {
+        if self.history.len() == NOTIFICATION_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(message);
+    }
+}
+
+/// Marker on the UI text that shows stats.
+#[derive(Component)]
+struct StatsText;
+
+/// Marker on the span after the stats giving the month's income at the
+/// current rate.
+#[derive(Component)]
+struct IncomeText;
+
+/// Marker on the UI text that shows the day and time.
+#[derive(Component)]
+struct ClockText;
+
+/// Marker on the swatch and the UI text that show today's weather.
+#[derive(Component)]
+struct WeatherIcon;
+
+#[derive(Component)]
+struct WeatherText;
+
+/// Marker on the UI text that shows the selected zone and brush size.
+#[derive(Component)]
+struct ToolText;
+
+/// The UI text describing the tile under the cursor, with the coordinate,
+/// zone, residents, and jobs it shows, or `None` off the map.
+#[derive(Component, Default)]
+struct HoverText(Option<(IVec2, Zone, u32, u32)>);
+
+/// Root node of the budget panel (toggled with B).
+#[derive(Component)]
+struct BudgetPanel;
+
+/// Text listing the budget line items.
+#[derive(Component)]
+struct BudgetText;
+
+/// Text showing the net income, colored by sign.
+#[derive(Component)]
+struct BudgetNetText;
+
+/// Text comparing the power made with the power drawn, red in a brownout.
+#[derive(Component)]
+struct PowerText;
+
+/// Text comparing the garbage made with what can be collected, red while
+/// it piles up.
+#[derive(Component)]
+struct GarbageText;
+
+/// HUD warning shown while garbage piles up.
+#[derive(Component)]
+struct GarbageWarningText;
+
+/// Text listing economic events under way and what they change.
+#[derive(Component)]
+struct EconomyText;
+
+/// Text listing outstanding loans.
+#[derive(Component)]
+struct LoanText;
+
+/// Budget panel button borrowing its amount.
+#[derive(Component, Clone, Copy)]
+struct LoanButton(i64);
+
+/// Container for the fading notification entries.
+#[derive(Component)]
+struct NotificationPanel;
+
+/// One fading line in the notification log.
+#[derive(Component)]
+struct NotificationEntry {
+    age: Timer,
+}
+
+/// Full notification history panel (toggled with N).
+#[derive(Component)]
+struct NotificationHistoryText;
+
+/// Fill node of one RCI demand bar in the HUD.
+#[derive(Component)]
+struct DemandBar(Zone);
+
+/// A labelled menu button carrying `action`, which the owning screen
+/// reacts to when it is pressed.
+pub fn menu_button(label: &str, action: impl Component) -> impl Bundle {
+    (
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        action,
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    )
+}
+
+/// A greyed-out menu button that cannot be pressed.
+pub fn disabled_menu_button(label: &str) -> impl Bundle {
+    (
+        Node {
+            padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+            TextColor(DISABLED_BUTTON_TEXT_COLOR),
+        )],
+    )
+}
+
+/// Hover highlight shared by every menu button.
+fn highlight_buttons(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in buttons.iter_mut() {
+        color.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+    }
+}
+
+fn setup_ui(
+    mut commands: Commands,
+    placeholder: Option<Res<PlaceholderGraphics>>,
+    locale: Res<Locale>,
+) {
+    // The hovered tile in the bottom-left corner, and a warning next to it
+    // when the sprites are placeholders.
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                column_gap: Val::Px(24.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(locale.text("hud.hover_off_map")),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                HoverText::default(),
+            ));
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                GarbageWarningText,
+            ));
+            if placeholder.is_some() {
+                parent.spawn((
+                    Text::new(locale.text("hud.placeholder_graphics")),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.9, 0.6, 0.3)),
+                ));
+            }
+        });
+
+    // Stats line with the clock and the build tool next to it.
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                column_gap: Val::Px(24.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(stats_line(&locale, &CityStats::default())),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                StatsText,
+                children![(
+                    TextSpan::default(),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(INCOME_FLAT_COLOR),
+                    IncomeText,
+                )],
+            ));
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.9)),
+                ClockText,
+            ));
+            parent
+                .spawn(Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|weather| {
+                    weather.spawn((
+                        Node {
+                            width: Val::Px(14.0),
+                            height: Val::Px(14.0),
+                            ..default()
+                        },
+                        BorderRadius::MAX,
+                        BackgroundColor(Weather::default().icon_color()),
+                        WeatherIcon,
+                    ));
+                    weather.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.9)),
+                        WeatherText,
+                    ));
+                });
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.9)),
+                ToolText,
+            ));
+        });
+
+    // RCI demand bars in the top-right corner.
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                right: Val::Px(10.0),
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|parent| {
+            for (zone, label, color) in [
+                (Zone::Residential, "hud.demand.residential", Color::srgb(0.2, 0.8, 0.2)),
+                (Zone::Commercial, "hud.demand.commercial", Color::srgb(0.2, 0.4, 0.9)),
+                (Zone::Industrial, "hud.demand.industrial", Color::srgb(0.9, 0.8, 0.2)),
+            ] {
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        column
+                            .spawn((
+                                Node {
+                                    width: Val::Px(DEMAND_BAR_WIDTH),
+                                    height: Val::Px(DEMAND_BAR_HEIGHT),
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::FlexEnd,
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.1)),
+                            ))
+                            .with_children(|bar| {
+                                bar.spawn((
+                                    Node {
+                                        width: Val::Percent(100.0),
+                                        height: Val::Percent(0.0),
+                                        ..default()
+                                    },
+                                    BackgroundColor(color),
+                                    DemandBar(zone),
+                                ));
+                            });
+                        column.spawn((
+                            Text::new(locale.text(label)),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                    });
+            }
+        });
+
+    // Budget panel, hidden until toggled.
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            BudgetPanel,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BudgetText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BudgetNetText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+                PowerText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                GarbageText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+                EconomyText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::top(Val::Px(8.0)),
+                    ..default()
+                },
+                LoanText,
+            ));
+            panel
+                .spawn(Node {
+                    column_gap: Val::Px(8.0),
+                    margin: UiRect::top(Val::Px(4.0)),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for principal in LOAN_SIZES {
+                        let borrow = Phrase::new("budget.borrow").number("amount", principal);
+                        let label = locale.render(&borrow);
+                        row.spawn(menu_button(&label, LoanButton(principal)));
+                    }
+                });
+        });
+
+    // Notification log in the bottom-right corner, newest at the bottom.
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            ..default()
+        },
+        NotificationPanel,
+        DespawnOnExit(AppState::Playing),
+    ));
+
+    // Full history, hidden until toggled.
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(50.0),
+            right: Val::Px(10.0),
+            max_height: Val::Percent(80.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            overflow: Overflow::clip(),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        NotificationHistoryText,
+        DespawnOnExit(AppState::Playing),
+    ));
+}
+
+/// Show the totals, and after the money the month's income at the rate of
+/// the last few ticks, green or red by which way it goes.
+fn update_stats_ui(
+    stats: Res<CityStats>,
+    trend: Res<IncomeTrend>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<StatsText>>,
+    mut income: Query<(&mut TextSpan, &mut TextColor), With<IncomeText>>,
+) {
+    if stats.is_changed() {
+        if let Ok(mut text) = query.single_mut() {
+            **text = stats_line(&locale, &stats);
+        }
+    }
+    if !trend.is_changed() {
+        return;
+    }
+
+    if let Ok((mut span, mut color)) = income.single_mut() {
+        let per_month = trend.per_month();
+        let text = locale.render(&Phrase::new("hud.income").signed("income", per_month));
+        if **span != text {
+            **span = text;
+        }
+        color.set_if_neq(TextColor(match per_month.signum() {
+            1 => INCOME_UP_COLOR,
+            -1 => INCOME_DOWN_COLOR,
+            _ => INCOME_FLAT_COLOR,
+        }));
+    }
+}
+
+fn stats_line(locale: &Locale, stats: &CityStats) -> String {
+    locale.render(
+        &Phrase::new("hud.stats")
+            .number("population", stats.population)
+            .number("jobs", stats.jobs)
+            .number("money", stats.money),
+    )
+}
+
+fn update_clock_ui(
+    calendar: Res<GameClock>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ClockText>>,
+) {
+    if !calendar.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = query.single_mut() {
+        **text = locale.render(&calendar.phrase());
+    }
+}
+
+/// Show today's weather as a colored dot and its name.
+fn update_weather_ui(
+    weather: Res<Weather>,
+    locale: Res<Locale>,
+    mut icon: Query<&mut BackgroundColor, With<WeatherIcon>>,
+    mut text: Query<&mut Text, With<WeatherText>>,
+) {
+    if !weather.is_changed() {
+        return;
+    }
+
+    if let Ok(mut color) = icon.single_mut() {
+        color.0 = weather.icon_color();
+    }
+    if let Ok(mut text) = text.single_mut() {
+        **text = locale.text(weather.label());
+    }
+}
+
+/// Show the selected tool and brush, and the population that unlocks the
+/// tool if it is still locked. While laying pipe, setting road directions,
+/// or painting a district, say that instead, or that the bulldozer digs
+/// pipe up or takes tiles out of districts.
+fn update_tool_ui(
+    tool: Res<BuildTool>,
+    milestones: Res<Milestones>,
+    districts: Res<Districts>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ToolText>>,
+) {
+    if !tool.is_changed() && !milestones.is_changed() && !districts.is_changed() {
+        return;
+    }
+
+    let district = tool.district.and_then(|id| districts.name(id));
+    let phrase = match (district, milestones.locked(tool.zone)) {
+        (Some(_), _) if tool.zone == Zone::Empty => Phrase::new("hud.tool.unassign_district"),
+        (Some(name), _) => Phrase::new("hud.tool.district").with("district", name),
+        _ if tool.pipes && tool.zone == Zone::Empty => Phrase::new("hud.tool.dig_pipe"),
+        _ if tool.pipes => Phrase::new("hud.tool.pipe"),
+        _ if tool.directions => Phrase::new("hud.tool.direction"),
+        (None, Some(Lock::Population(population))) => {
+            Phrase::new("hud.tool_locked").number("population", population)
+        }
+        (None, Some(Lock::Scenario)) => Phrase::new("hud.tool_banned"),
+        (None, None) => Phrase::new("hud.tool"),
+    };
+    if let Ok(mut text) = query.single_mut() {
+        **text = locale.render(
+            &phrase
+                .key("zone", tool.zone.label())
+                .with("brush", tool.brush.label()),
+        );
+    }
+}
+
+/// Describe the tile under the cursor, rewriting the text only when what
+/// it says changes.
+fn update_hover_ui(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    map: Res<MapConfig>,
+    tile_map: Option<Res<TileMap>>,
+    tiles: Query<(&Zone, &TileData)>,
+    locale: Res<Locale>,
+    mut query: Query<(&mut Text, &mut HoverText)>,
+) {
+    let Ok((mut text, mut shown)) = query.single_mut() else {
+        return;
+    };
+    let hovered = cursor_tile(&windows, &camera_q, &map).and_then(|coord| {
+        let (zone, data) = tiles.get(tile_map.as_ref()?.get(coord)?).ok()?;
+        Some((coord, *zone, data.population, data.jobs))
+    });
+    if shown.0 == hovered {
+        return;
+    }
+
+    shown.0 = hovered;
+    **text = match hovered {
+        Some((coord, zone, population, jobs)) => locale.render(
+            &Phrase::new("hud.hover")
+                .number("x", coord.x)
+                .number("y", coord.y)
+                .key("zone", zone.tile_label())
+                .number("population", population)
+                .number("jobs", jobs),
+        ),
+        None => locale.text("hud.hover_off_map"),
+    };
+}
+
+/// Resize the RCI bars whenever demand changes.
+fn update_demand_ui(
+    demand: Res<Demand>,
+    mut bars: Query<(&DemandBar, &mut Node)>,
+) {
+    if !demand.is_changed() {
+        return;
+    }
+
+    for (bar, mut node) in bars.iter_mut() {
+        let value = demand.get(bar.0).clamp(0, DEMAND_BAR_MAX);
+        node.height =
+            Val::Percent(value as f32 / DEMAND_BAR_MAX as f32 * 100.0);
+    }
+}
+
+/// Show or hide the budget panel with B.
+fn toggle_budget_panel(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut panel: Query<&mut Node, With<BudgetPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    if let Ok(mut node) = panel.single_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Rewrite the budget panel whenever the budget changes, with what the
+/// month will settle into the treasury so far.
+fn update_budget_ui(
+    budget: Res<Budget>,
+    ledger: Res<Ledger>,
+    stats: Res<CityStats>,
+    locale: Res<Locale>,
+    mut lines: Query<&mut Text, (With<BudgetText>, Without<BudgetNetText>)>,
+    mut net: Query<(&mut Text, &mut TextColor), With<BudgetNetText>>,
+) {
+    if !budget.is_changed() && !ledger.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = lines.single_mut() {
+        let items = locale.render(
+            &Phrase::new("budget.lines")
+                .number("residential_tax", budget.residential_tax)
+                .number("commercial_tax", budget.commercial_tax)
+                .number("tourism", budget.tourism)
+                .number("trade", budget.trade)
+                .number("road_maintenance", budget.road_maintenance)
+                .number("power_upkeep", budget.power_upkeep)
+                .number("water_upkeep", budget.water_upkeep)
+                .number("pipe_maintenance", budget.pipe_maintenance)
+                .number("garbage_upkeep", budget.garbage_upkeep)
+                .number("port_upkeep", budget.port_upkeep)
+                .number("service_costs", budget.service_costs)
+                .number("fire_upkeep", budget.fire_upkeep)
+                .number("police_upkeep", budget.police_upkeep)
+                .number("school_upkeep", budget.school_upkeep)
+                .number("loan_repayments", budget.loan_repayments),
+        );
+        let attractiveness = locale.render(
+            &Phrase::new("budget.attractiveness")
+                .with("score", stats.attractiveness)
+                .with("max", MAX_ATTRACTIVENESS),
+        );
+        **text = format!("{items}\n{attractiveness}");
+    }
+
+    if let Ok((mut text, mut color)) = net.single_mut() {
+        let net = budget.net();
+        **text = locale.render(
+            &Phrase::new("budget.net")
+                .signed("net", net)
+                .signed("month", ledger.pending),
+        );
+        color.0 = if net < 0 {
+            Color::srgb(0.9, 0.2, 0.2)
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+/// Rewrite the power line whenever the balance changes, so players can see
+/// when the next plant is due.
+fn update_power_ui(
+    power: Res<PowerGrid>,
+    locale: Res<Locale>,
+    mut query: Query<(&mut Text, &mut TextColor), With<PowerText>>,
+) {
+    if !power.is_changed() {
+        return;
+    }
+
+    let Ok((mut text, mut color)) = query.single_mut() else {
+        return;
+    };
+    let total = power.total();
+    let (phrase, tint) = if power.dark > 0 {
+        let phrase = Phrase::new("budget.power.brownout").number("dark", power.dark);
+        (phrase, Color::srgb(0.9, 0.2, 0.2))
+    } else {
+        (Phrase::new("budget.power"), Color::WHITE)
+    };
+    **text = locale.render(
+        &phrase
+            .number("demand", total.demand)
+            .number("supply", total.supply),
+    );
+    color.0 = tint;
+}
+
+/// Rewrite the garbage line and the HUD warning whenever collection
+/// changes, so players see a landfill filling up before garbage spills.
+fn update_garbage_ui(
+    garbage: Res<Garbage>,
+    locale: Res<Locale>,
+    mut line: Query<(&mut Text, &mut TextColor), With<GarbageText>>,
+    mut warning: Query<&mut Text, (With<GarbageWarningText>, Without<GarbageText>)>,
+) {
+    if !garbage.is_changed() {
+        return;
+    }
+
+    if let Ok((mut text, mut color)) = line.single_mut() {
+        let (phrase, tint) = if garbage.overflowing() {
+            let phrase = Phrase::new("budget.garbage.overflow").number("backlog", garbage.backlog);
+            (phrase, Color::srgb(0.9, 0.2, 0.2))
+        } else {
+            let phrase = Phrase::new("budget.garbage").number("space", garbage.landfill_space);
+            (phrase, Color::WHITE)
+        };
+        **text = locale.render(
+            &phrase
+                .number("produced", garbage.produced)
+                .number("capacity", garbage.capacity),
+        );
+        color.0 = tint;
+    }
+    if let Ok(mut text) = warning.single_mut() {
+        let content = if garbage.overflowing() {
+            locale.render(&Phrase::new("hud.garbage_overflow").number("backlog", garbage.backlog))
+        } else {
+            String::new()
+        };
+        if **text != content {
+            **text = content;
+        }
+    }
+}
+
+fn handle_loan_buttons(
+    buttons: Query<(&Interaction, &LoanButton), Changed<Interaction>>,
+    mut requests: MessageWriter<TakeLoan>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            requests.write(TakeLoan {
+                principal: button.0,
+            });
+        }
+    }
+}
+
+/// List the economic events under way, and the combined change they make
+/// to taxes, demand, and industry, followed by the demand each port adds
+/// and what the weather is changing.
+fn update_economy_ui(
+    economy: Res<Economy>,
+    ports: Res<Ports>,
+    weather: Res<Weather>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<EconomyText>>,
+) {
+    if !economy.is_changed() && !ports.is_changed() && !weather.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = query.single_mut() {
+        let mut lines = economy_lines(&economy, &locale);
+        for &port in &ports.built {
+            let boost = demand_boost(port);
+            lines.push(locale.render(
+                &Phrase::new("economy.port")
+                    .key("port", port.label())
+                    .signed("residential", boost.residential.into())
+                    .signed("commercial", boost.commercial.into())
+                    .signed("industrial", boost.industrial.into()),
+            ));
+        }
+        if let Some(effects) = weather.effects() {
+            lines.push(locale.render(&effects));
+        }
+        **text = lines.join("\n");
+    }
+}
+
+/// The economic events under way and what they change together, or that
+/// the economy is steady.
+fn economy_lines(economy: &Economy, locale: &Locale) -> Vec<String> {
+    if economy.active.is_empty() {
+        return vec![locale.text("economy.steady")];
+    }
+    let mut lines = vec![locale.text("economy.title")];
+    for active in &economy.active {
+        lines.push(locale.render(
+            &Phrase::new("economy.event")
+                .key("event", active.event.name())
+                .with("ticks", active.ticks_left),
+        ));
+    }
+    let modifiers = economy.modifiers();
+    let demand = modifiers.demand;
+    lines.push(locale.render(
+        &Phrase::new("economy.effects")
+            .with("tax", modifiers.tax_percent)
+            .signed("residential", demand.residential.into())
+            .signed("commercial", demand.commercial.into())
+            .signed("industrial", demand.industrial.into()),
+    ));
+    if modifiers.industrial_jobs > 0 {
+        lines.push(locale.render(
+            &Phrase::new("economy.industry").with("jobs", modifiers.industrial_jobs),
+        ));
+    }
+    lines
+}
+
+/// List outstanding loans whenever one is taken, repaid, or paid off.
+fn update_loan_ui(
+    loans: Res<Loans>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<LoanText>>,
+) {
+    if !loans.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = query.single_mut() {
+        let mut lines = vec![locale.render(
+            &Phrase::new("loans.title")
+                .with("count", loans.active.len())
+                .with("max", MAX_LOANS),
+        )];
+        for loan in &loans.active {
+            lines.push(locale.render(
+                &Phrase::new("loans.loan")
+                    .number("principal", loan.principal)
+                    .number("balance", loan.balance)
+                    .with("ticks", loan.ticks_left),
+            ));
+        }
+        **text = lines.join("\n");
+    }
+}
+
+/// Record new notifications and add them to the corner log, dropping the
+/// oldest entries so at most `NOTIFICATION_VISIBLE` are on screen. Entries
+/// about a tile are buttons linking to it.
+fn log_notifications(
+    mut commands: Commands,
+    mut messages: MessageReader<Notification>,
+    mut tile_messages: MessageReader<TileNotification>,
+    mut log: ResMut<NotificationLog>,
+    locale: Res<Locale>,
+    panel: Query<(Entity, Option<&Children>), With<NotificationPanel>>,
+) {
+    let Ok((panel, children)) = panel.single() else {
+        return;
+    };
+
+    let new: Vec<(String, Option<IVec2>)> = messages
+        .read()
+        .map(|n| (locale.render(&n.0), None))
+        .chain(tile_messages.read().map(|n| (locale.render(&n.text), Some(n.tile))))
+        .collect();
+    if new.is_empty() {
+        return;
+    }
+
+    // Only the newest few of a burst are worth spawning at all.
+    let spawn_from = new.len().saturating_sub(NOTIFICATION_VISIBLE);
+    let existing: Vec<Entity> =
+        children.map(|c| c.to_vec()).unwrap_or_default();
+    let keep = NOTIFICATION_VISIBLE - (new.len() - spawn_from);
+    for entity in existing.iter().take(existing.len().saturating_sub(keep)) {
+        commands.entity(*entity).despawn();
+    }
+
+    for (i, (message, tile)) in new.into_iter().enumerate() {
+        if i >= spawn_from {
+            let mut entry = commands.spawn((
+                Text::new(message.clone()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                NotificationEntry {
+                    age: Timer::from_seconds(
+                        NOTIFICATION_LIFETIME,
+                        TimerMode::Once,
+                    ),
+                },
+                ChildOf(panel),
+            ));
+            if let Some(tile) = tile {
+                entry.insert((Button, TileLink(tile)));
+            }
+        }
+        log.push(message);
+    }
+}
+
+/// Fade notifications out over their last seconds and then remove them.
+fn fade_notifications(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut entries: Query<(Entity, &mut NotificationEntry, &mut TextColor)>,
+) {
+    for (entity, mut entry, mut color) in entries.iter_mut() {
+        entry.age.tick(time.delta());
+        if entry.age.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let remaining = entry.age.remaining_secs();
+        color.0.set_alpha((remaining / NOTIFICATION_FADE).min(1.0));
+    }
+}
+
+/// Show or hide the full notification history with N.
+fn toggle_notification_history(
+    keys: Res<ButtonInput<KeyCode>>,
+    log: Res<NotificationLog>,
+    locale: Res<Locale>,
+    mut history: Query<(&mut Text, &mut Node), With<NotificationHistoryText>>,
+) {
+    let Ok((mut text, mut node)) = history.single_mut() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyN) && !ctrl_held(&keys) {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+
+    if node.display != Display::None && (log.is_changed() || node.is_changed()) {
+        let title = locale.text("notifications.title");
+        let lines: Vec<&str> = std::iter::once(title.as_str())
+            .chain(log.history.iter().rev().map(String::as_str))
+            .collect();
+        **text = lines.join("\n");
+    }
+}
+
+/// Forget every notification for a restart.
+fn clear_notifications(
+    mut commands: Commands,
+    mut log: ResMut<NotificationLog>,
+    entries: Query<Entity, With<NotificationEntry>>,
+) {
+    log.history.clear();
+    for entity in entries.iter() {
+        commands.entity(entity).despawn();
+    }
+}
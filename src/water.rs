@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::config::MapConfig;
+use crate::grid::CARDINAL_NEIGHBORS;
+use crate::input::{BuildTool, PaintCommand};
+use crate::map::{TileCoord, TileMap, Zone};
+use crate::replay::Playback;
+use crate::services::coverage;
+use crate::simulation::{simulation_step, CityStats};
+use crate::state::{GameSet, ResetCity};
+use crate::tint::{apply_tile_tints, TileTint};
+
+/// Cost of laying one tile of pipe.
+pub const PIPE_COST: i64 = 5;
+/// Running cost per tick of every tile of pipe.
+pub const PIPE_UPKEEP_PER_TILE: f32 = 0.1;
+/// Tiles around a water tower or pumping station that it waters itself.
+const SOURCE_REACH: i32 = 4;
+/// Tiles around a pipe carrying water that it waters.
+const PIPE_REACH: i32 = 2;
+/// Tints of the pipe view: tiles without pipe are dimmed, and pipes show
+/// whether water reaches them.
+const PIPE_VIEW_DIM: Color = Color::srgb(0.35, 0.35, 0.4);
+const WET_PIPE_TINT: Color = Color::srgb(0.4, 0.85, 1.0);
+const DRY_PIPE_TINT: Color = Color::srgb(0.6, 0.35, 0.3);
+
+/// Water service: towers and pumping stations water the tiles around
+/// them, and feed a network of buried pipes that waters the tiles along
+/// it. Homes, shops, and industry without water hold half as many
+/// residents or jobs. P switches the build tool to laying pipe.
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Pipes::new(0, 0))
+            .add_message::<PaintCommand>()
+            .add_message::<PipeEdit>()
+            .add_systems(ResetCity, (clear_pipes, init_water_supply))
+            .add_systems(
+                Update,
+                lay_pipes
+                    .in_set(GameSet::Input)
+                    .run_if(not(resource_exists::<Playback>)),
+            )
+            .add_systems(
+                Update,
+                update_water_supply
+                    .in_set(GameSet::Simulation)
+                    .before(simulation_step),
+            )
+            .add_systems(
+                Update,
+                tint_pipe_view.before(apply_tile_tints).in_set(GameSet::Ui),
+            );
+    }
+}
+
+/// Whether a zone is a source of water.
+pub fn is_water_source(zone: Zone) -> bool {
+    matches!(zone, Zone::WaterTower | Zone::PumpingStation)
+}
+
+/// Pipe laid or dug up by the player this frame, and what it cost, for
+/// the replay.
+#[derive(Message, Clone)]
+pub struct PipeEdit {
+    /// Each tile changed, and whether it has pipe now.
+    pub tiles: Vec<(IVec2, bool)>,
+    pub cost: i64,
+}
+
+/// The buried pipe layer, laid out row by row. Kept in saves; water only
+/// flows through it from a source.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct Pipes {
+    width: i32,
+    height: i32,
+    laid: Vec<bool>,
+}
+
+impl Pipes {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            laid: vec![false; (width * height).max(0) as usize],
+        }
+    }
+
+    /// A layer with pipe on every tile of `coords` that is on the map.
+    pub fn from_coords(width: i32, height: i32, coords: &[IVec2]) -> Self {
+        let mut pipes = Self::new(width, height);
+        for &coord in coords {
+            pipes.set(coord, true);
+        }
+        pipes
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        on_map.then(|| (coord.y * self.width + coord.x) as usize)
+    }
+
+    /// Whether there is pipe under `coord`; none off the map.
+    pub fn get(&self, coord: IVec2) -> bool {
+        self.index(coord).is_some_and(|i| self.laid[i])
+    }
+
+    /// Lay or dig up the pipe under `coord`, if it is on the map.
+    pub fn set(&mut self, coord: IVec2, laid: bool) {
+        if let Some(i) = self.index(coord) {
+            self.laid[i] = laid;
+        }
+    }
+
+    /// Tiles of pipe laid.
+    pub fn count(&self) -> usize {
+        self.laid.iter().filter(|&&laid| laid).count()
+    }
+
+    /// Every tile with pipe, row by row, for saving.
+    pub fn coords(&self) -> Vec<IVec2> {
+        (0..self.laid.len())
+            .filter(|&i| self.laid[i])
+            .map(|i| IVec2::new(i as i32 % self.width, i as i32 / self.width))
+            .collect()
+    }
+}
+
+/// Which tiles have water and which pipes carry it, laid out row by row.
+/// Rebuilt only when a water source or a pipe changes.
+#[derive(Resource)]
+pub struct WaterSupply {
+    width: i32,
+    height: i32,
+    /// Water sources as of the last rebuild, row by row.
+    sources: Vec<IVec2>,
+    wet_pipes: Vec<bool>,
+    watered: Vec<bool>,
+}
+
+impl WaterSupply {
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            sources: Vec::new(),
+            wet_pipes: vec![false; size],
+            watered: vec![false; size],
+        }
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        let on_map = coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height;
+        on_map.then(|| (coord.y * self.width + coord.x) as usize)
+    }
+
+    /// Whether the tile at `coord` has water.
+    pub fn watered(&self, coord: IVec2) -> bool {
+        self.index(coord).is_some_and(|i| self.watered[i])
+    }
+
+    /// Whether the pipe under `coord` carries water from a source.
+    pub fn wet_pipe(&self, coord: IVec2) -> bool {
+        self.index(coord).is_some_and(|i| self.wet_pipes[i])
+    }
+}
+
+/// Pipes water reaches, following connected pipe out from every pipe on or
+/// next to a source, and the tiles within reach of a source or those
+/// pipes.
+fn supply(width: i32, height: i32, sources: &[IVec2], pipes: &Pipes) -> (Vec<bool>, Vec<bool>) {
+    let mut wet = vec![false; (width * height) as usize];
+    let mut queue: VecDeque<IVec2> = VecDeque::new();
+    let fed = sources
+        .iter()
+        .flat_map(|&source| std::iter::once(source).chain(CARDINAL_NEIGHBORS.map(|n| source + n)));
+    for coord in fed {
+        if let Some(i) = pipes.index(coord).filter(|&i| pipes.laid[i] && !wet[i]) {
+            wet[i] = true;
+            queue.push_back(coord);
+        }
+    }
+    while let Some(coord) = queue.pop_front() {
+        for n in CARDINAL_NEIGHBORS {
+            if let Some(i) = pipes.index(coord + n).filter(|&i| pipes.laid[i] && !wet[i]) {
+                wet[i] = true;
+                queue.push_back(coord + n);
+            }
+        }
+    }
+
+    let wet_coords: Vec<IVec2> = (0..wet.len())
+        .filter(|&i| wet[i])
+        .map(|i| IVec2::new(i as i32 % width, i as i32 / width))
+        .collect();
+    let by_source = coverage(width, height, sources, SOURCE_REACH);
+    let by_pipe = coverage(width, height, &wet_coords, PIPE_REACH);
+    let watered = by_source.iter().zip(&by_pipe).map(|(a, b)| *a || *b).collect();
+    (wet, watered)
+}
+
+/// New and cleared cities start without pipe; a loaded one brings its
+/// own.
+fn clear_pipes(map: Res<MapConfig>, mut pipes: ResMut<Pipes>) {
+    *pipes = Pipes::new(map.width, map.height);
+}
+
+fn init_water_supply(mut commands: Commands, map: Res<MapConfig>) {
+    commands.insert_resource(WaterSupply::new(map.width, map.height));
+}
+
+/// Rebuild the water supply when pipe was laid or dug up, or a water
+/// source was built or removed; other zoning leaves it alone.
+fn update_water_supply(
+    mut supply_res: ResMut<WaterSupply>,
+    pipes: Res<Pipes>,
+    changed: Query<(), Changed<Zone>>,
+    tiles: Query<(&TileCoord, &Zone)>,
+) {
+    if changed.is_empty() && !pipes.is_changed() {
+        return;
+    }
+    let mut sources: Vec<IVec2> = tiles
+        .iter()
+        .filter(|(_, zone)| is_water_source(**zone))
+        .map(|(coord, _)| coord.coord)
+        .collect();
+    sources.sort_by_key(|coord| (coord.y, coord.x));
+    if sources == supply_res.sources && !pipes.is_changed() {
+        return;
+    }
+
+    let (width, height) = (supply_res.width, supply_res.height);
+    let (wet_pipes, watered) = supply(width, height, &sources, &pipes);
+    supply_res.sources = sources;
+    supply_res.wet_pipes = wet_pipes;
+    supply_res.watered = watered;
+}
+
+/// While the build tool lays pipe, paint strokes lay it across the brush,
+/// or dig it up with the bulldozer, paying `PIPE_COST` for every tile
+/// laid. Pipe can't go under water.
+fn lay_pipes(
+    mut commands: MessageReader<PaintCommand>,
+    tool: Res<BuildTool>,
+    tile_map: Res<TileMap>,
+    tiles: Query<&Zone>,
+    mut pipes: ResMut<Pipes>,
+    mut stats: ResMut<CityStats>,
+    mut edits: MessageWriter<PipeEdit>,
+) {
+    if !tool.pipes {
+        commands.clear();
+        return;
+    }
+    let mut edit = PipeEdit {
+        tiles: Vec::new(),
+        cost: 0,
+    };
+    for command in commands.read() {
+        let PaintCommand::Paint { center, zone, .. } = *command else {
+            continue;
+        };
+        let laying = zone != Zone::Empty;
+        for coord in tool.brush.footprint(center) {
+            let on_land = tile_map
+                .get(coord)
+                .and_then(|entity| tiles.get(entity).ok())
+                .is_some_and(|zone| !zone.is_water());
+            if !on_land || pipes.get(coord) == laying {
+                continue;
+            }
+            pipes.set(coord, laying);
+            if laying {
+                edit.cost += PIPE_COST;
+            }
+            edit.tiles.push((coord, laying));
+        }
+    }
+    if !edit.tiles.is_empty() {
+        stats.money -= edit.cost;
+        edits.write(edit);
+    }
+}
+
+/// While laying pipe, dim the map and pick out the pipes, bright where
+/// water reaches them; otherwise touch nothing.
+fn tint_pipe_view(
+    tool: Res<BuildTool>,
+    pipes: Res<Pipes>,
+    supply: Res<WaterSupply>,
+    mut tiles: Query<(&TileCoord, &mut TileTint)>,
+) {
+    let refresh = tool.is_changed() || (tool.pipes && (pipes.is_changed() || supply.is_changed()));
+    if !refresh {
+        return;
+    }
+    for (coord, mut tint) in tiles.iter_mut() {
+        let coord = coord.coord;
+        let color = match (tool.pipes, pipes.get(coord)) {
+            (false, _) => Color::WHITE,
+            (true, false) => PIPE_VIEW_DIM,
+            (true, true) if supply.wet_pipe(coord) => WET_PIPE_TINT,
+            (true, true) => DRY_PIPE_TINT,
+        };
+        if tint.pipes != color {
+            tint.pipes = color;
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::calendar::{GameClock, TICKS_PER_DAY};
+use crate::day_night::TimeOfDay;
+use crate::locale::Phrase;
+use crate::simulation::{simulation_step, NewCity};
+use crate::state::GameSet;
+
+/// Share of the usual commercial tax collected in the rain.
+const RAIN_COMMERCIAL_TAX_PERCENT: i64 = 90;
+/// Pollution rain washes away per tick on top of the usual.
+const RAIN_POLLUTION_DECAY: u8 = 1;
+/// Power buildings draw in a heatwave, as a share of the usual.
+const HEATWAVE_POWER_PERCENT: u32 = 125;
+/// How much more likely fires are in a heatwave.
+const HEATWAVE_FIRE_FACTOR: f64 = 2.0;
+/// Road maintenance in the snow, as a share of the usual.
+const SNOW_ROAD_PERCENT: i64 = 150;
+/// Mixed into the city seed so the weather doesn't follow `SimRng`.
+const WEATHER_SALT: u64 = 0x5EA5_0115;
+
+/// Weather that changes every day or two: rain in any season, heatwaves
+/// in summer, and snow in winter, each shifting the simulation a little
+/// while it lasts. The weather on any day follows from the city seed and
+/// the date alone, so it is the same on every run and after loading.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>().add_systems(
+            Update,
+            update_weather
+                .in_set(GameSet::Simulation)
+                .before(simulation_step),
+        );
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Shops earn a little less, and pollution clears faster.
+    Rain,
+    /// Buildings draw more power, and fires break out more often.
+    Heatwave,
+    /// Construction is slowed, and roads cost more to keep clear.
+    Snow,
+}
+
+impl Weather {
+    /// The weather on the day `ticks` falls in.
+    pub fn on(seed: u64, ticks: u64) -> Self {
+        let day = ticks / TICKS_PER_DAY;
+        // Days go in pairs, each pair one spell of weather or two.
+        let pair = day / 2;
+        let one_spell = day_rng(seed, pair).random_bool(0.5);
+        let spell = if one_spell { pair * 2 } else { day };
+        let spell_start = spell * TICKS_PER_DAY;
+
+        let roll = day_rng(seed, spell ^ (1 << 63)).random_range(0..100);
+        let table: &[(Weather, u32)] = match GameClock::at(spell_start).month {
+            // December to February.
+            11 | 0 | 1 => &[(Weather::Snow, 35), (Weather::Rain, 15)],
+            // June to August.
+            5..=7 => &[(Weather::Heatwave, 25), (Weather::Rain, 20)],
+            _ => &[(Weather::Rain, 35)],
+        };
+        let mut rolled = 0;
+        for &(weather, chance) in table {
+            rolled += chance;
+            if roll < rolled {
+                return weather;
+            }
+        }
+        Weather::Clear
+    }
+
+    /// Locale key of the name shown to the player.
+    pub fn label(self) -> &'static str {
+        match self {
+            Weather::Clear => "weather.clear",
+            Weather::Rain => "weather.rain",
+            Weather::Heatwave => "weather.heatwave",
+            Weather::Snow => "weather.snow",
+        }
+    }
+
+    /// Color of the HUD icon.
+    pub fn icon_color(self) -> Color {
+        match self {
+            Weather::Clear => Color::srgb(1.0, 0.85, 0.3),
+            Weather::Rain => Color::srgb(0.35, 0.55, 0.95),
+            Weather::Heatwave => Color::srgb(1.0, 0.45, 0.2),
+            Weather::Snow => Color::srgb(0.95, 0.95, 1.0),
+        }
+    }
+
+    /// Multiplier for every tile's color: rain darkens the map and snow
+    /// lightens it.
+    pub fn tint(self) -> Color {
+        match self {
+            Weather::Clear => Color::WHITE,
+            Weather::Rain => Color::srgb(0.82, 0.85, 0.92),
+            Weather::Heatwave => Color::srgb(1.05, 0.98, 0.9),
+            Weather::Snow => Color::srgb(1.15, 1.15, 1.2),
+        }
+    }
+
+    /// Share of the usual commercial tax collected.
+    pub fn commercial_tax_percent(self) -> i64 {
+        match self {
+            Weather::Rain => RAIN_COMMERCIAL_TAX_PERCENT,
+            _ => 100,
+        }
+    }
+
+    /// Pollution washed away per tick on top of the usual.
+    pub fn pollution_decay(self) -> u8 {
+        match self {
+            Weather::Rain => RAIN_POLLUTION_DECAY,
+            _ => 0,
+        }
+    }
+
+    /// Power buildings draw, as a share of the usual.
+    pub fn power_percent(self) -> u32 {
+        match self {
+            Weather::Heatwave => HEATWAVE_POWER_PERCENT,
+            _ => 100,
+        }
+    }
+
+    /// How much more likely fires are than usual.
+    pub fn fire_factor(self) -> f64 {
+        match self {
+            Weather::Heatwave => HEATWAVE_FIRE_FACTOR,
+            _ => 1.0,
+        }
+    }
+
+    /// Whether buildings only get on with construction every other tick.
+    pub fn slows_construction(self) -> bool {
+        self == Weather::Snow
+    }
+
+    /// Road maintenance, as a share of the usual.
+    pub fn road_percent(self) -> i64 {
+        match self {
+            Weather::Snow => SNOW_ROAD_PERCENT,
+            _ => 100,
+        }
+    }
+
+    /// What the weather is changing, for the budget panel; nothing when
+    /// clear.
+    pub fn effects(self) -> Option<Phrase> {
+        match self {
+            Weather::Clear => None,
+            Weather::Rain => Some(
+                Phrase::new("weather.rain_effects").with("tax", RAIN_COMMERCIAL_TAX_PERCENT),
+            ),
+            Weather::Heatwave => Some(
+                Phrase::new("weather.heatwave_effects")
+                    .with("power", HEATWAVE_POWER_PERCENT)
+                    .with("fire", HEATWAVE_FIRE_FACTOR),
+            ),
+            Weather::Snow => {
+                Some(Phrase::new("weather.snow_effects").with("roads", SNOW_ROAD_PERCENT))
+            }
+        }
+    }
+}
+
+/// A generator for one day (or spell) of weather in the city.
+fn day_rng(seed: u64, day: u64) -> SmallRng {
+    SmallRng::seed_from_u64(seed ^ WEATHER_SALT ^ day.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Follow the clock, which also catches it jumping when a city is loaded.
+/// Runs ahead of the tick so the whole tick sees the day's weather.
+fn update_weather(clock: Res<TimeOfDay>, new_city: Res<NewCity>, mut weather: ResMut<Weather>) {
+    weather.set_if_neq(Weather::on(new_city.seed, clock.ticks));
+}
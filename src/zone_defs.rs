@@ -0,0 +1,251 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::assets::SHEET_CELLS;
+use crate::map::{TileData, Zone};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::locale::Phrase;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ui::Notification;
+
+/// Where the zone definitions are read from, relative to the working
+/// directory, so the balance can be changed without rebuilding.
+#[cfg(not(target_arch = "wasm32"))]
+const ZONES_PATH: &str = "assets/zones.ron";
+/// The definitions shipped with the game, built in for the web and for
+/// when the file is missing or broken.
+const BUNDLED: &str = include_str!("../assets/zones.ron");
+/// Seconds between checks of the file for changes.
+#[cfg(not(target_arch = "wasm32"))]
+const RELOAD_CHECK_SECONDS: f32 = 1.0;
+
+/// Loads what every zone looks like, costs, and how it grows from
+/// `assets/zones.ron`, and reloads it whenever the file changes.
+pub struct ZoneDefsPlugin;
+
+impl Plugin for ZoneDefsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ZoneDefs::load());
+        #[cfg(not(target_arch = "wasm32"))]
+        app.insert_resource(ZonesFile {
+            modified: modified(),
+            checked: 0.0,
+        })
+        .add_systems(Update, reload_zone_defs);
+    }
+}
+
+/// One zone's entry in the definitions file.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct ZoneDef {
+    pub zone: Zone,
+    /// Atlas cell the zone is drawn with.
+    pub sprite: usize,
+    /// Atlas cells of each tile of a multi-tile building, row by row from
+    /// its anchor; empty for zones one tile big.
+    #[serde(default)]
+    pub parts: Vec<usize>,
+    /// Pools of atlas cells to pick a tile's look from, one per density
+    /// level from emptiest to fullest; empty to always use `sprite`.
+    #[serde(default)]
+    pub variants: Vec<Vec<usize>>,
+    /// Flat color standing in for the sprite.
+    pub color: (f32, f32, f32),
+    /// One-off cost of placing this zone.
+    #[serde(default)]
+    pub cost: i64,
+    /// Running cost per tick of a building in this zone.
+    #[serde(default)]
+    pub upkeep: i64,
+    /// Ticks a newly zoned building takes to build; zero for zones that are
+    /// finished the moment they are placed.
+    #[serde(default)]
+    pub construction: u32,
+    /// Most residents or jobs one tile holds.
+    #[serde(default)]
+    pub capacity: u32,
+    /// Residents or jobs a growing tile gains per tick.
+    #[serde(default)]
+    pub growth: u32,
+}
+
+impl ZoneDef {
+    pub fn color(&self) -> Color {
+        let (red, green, blue) = self.color;
+        Color::srgb(red, green, blue)
+    }
+}
+
+/// The definition of every zone, indexed by `Zone`.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct ZoneDefs {
+    defs: Vec<ZoneDef>,
+}
+
+impl ZoneDefs {
+    /// The definitions on disk, or the built-in ones if the file is
+    /// missing or broken.
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match fs::read_to_string(ZONES_PATH) {
+            Ok(text) => match parse(&text) {
+                Ok(defs) => return defs,
+                Err(err) => error!("{ZONES_PATH}: {err}; using the built-in zones"),
+            },
+            Err(err) => warn!("Could not read {ZONES_PATH}: {err}; using the built-in zones"),
+        }
+        Self::bundled()
+    }
+
+    /// The definitions built into the game.
+    pub fn bundled() -> Self {
+        parse(BUNDLED).expect("the built-in zones.ron is valid")
+    }
+
+    pub fn get(&self, zone: Zone) -> &ZoneDef {
+        &self.defs[zone as usize]
+    }
+
+    /// Sprite for the part of a building `offset` tiles from its anchor, so
+    /// a multi-tile building is drawn as one picture spread over its tiles.
+    pub fn part_sprite_index(&self, zone: Zone, offset: IVec2) -> usize {
+        let def = self.get(zone);
+        let part = (offset.y * zone.footprint().x + offset.x) as usize;
+        def.parts.get(part).copied().unwrap_or(def.sprite)
+    }
+
+    /// Sprite for a one-tile building: from the pool for how full it is,
+    /// picked by the tile's variant.
+    pub fn variant_sprite_index(&self, zone: Zone, data: &TileData) -> usize {
+        let def = self.get(zone);
+        let levels = def.variants.len() as u32;
+        if levels == 0 {
+            return def.sprite;
+        }
+        let occupants = data.population + data.jobs;
+        let level = (occupants * levels / def.capacity.max(1)).min(levels - 1);
+        let pool = &def.variants[level as usize];
+        match pool.len() {
+            0 => def.sprite,
+            len => pool[data.variant as usize % len],
+        }
+    }
+
+    /// Every atlas cell `zone` is drawn with.
+    pub fn sprite_indices(&self, zone: Zone) -> impl Iterator<Item = usize> + '_ {
+        zone.covered_tiles(IVec2::ZERO)
+            .map(move |offset| self.part_sprite_index(zone, offset))
+            .chain(self.get(zone).variants.iter().flatten().copied())
+    }
+}
+
+/// Read and check a definitions file: every zone must be listed exactly
+/// once, with sprites on the sheet, one part per tile of a multi-tile
+/// building and no variants for one, and no negative costs.
+pub fn parse(text: &str) -> Result<ZoneDefs, String> {
+    let entries: Vec<ZoneDef> = ron::from_str(text).map_err(|e| e.to_string())?;
+    let mut slots: Vec<Option<ZoneDef>> = vec![None; Zone::ALL.len()];
+    for def in entries {
+        let zone = def.zone;
+        let tiles = zone.footprint().element_product() as usize;
+        if slots[zone as usize].is_some() {
+            return Err(format!("{zone:?} is defined more than once"));
+        }
+        if let Some(&sprite) = std::iter::once(&def.sprite)
+            .chain(&def.parts)
+            .chain(def.variants.iter().flatten())
+            .find(|&&sprite| sprite >= SHEET_CELLS)
+        {
+            return Err(format!(
+                "{zone:?} uses sprite {sprite}, but the sheet only has {SHEET_CELLS}"
+            ));
+        }
+        if zone.is_multi_tile() && def.parts.len() != tiles {
+            return Err(format!(
+                "{zone:?} covers {tiles} tiles, so it needs {tiles} parts, not {}",
+                def.parts.len()
+            ));
+        }
+        if zone.is_multi_tile() && !def.variants.is_empty() {
+            return Err(format!("{zone:?} is drawn from its parts, so it has no variants"));
+        }
+        if !zone.is_multi_tile() && !def.parts.is_empty() {
+            return Err(format!("{zone:?} is one tile big, so it has no parts"));
+        }
+        if def.cost < 0 || def.upkeep < 0 {
+            return Err(format!("{zone:?} has a negative cost or upkeep"));
+        }
+        slots[zone as usize] = Some(def);
+    }
+
+    let missing: Vec<String> = Zone::ALL
+        .iter()
+        .filter(|&&zone| slots[zone as usize].is_none())
+        .map(|zone| format!("{zone:?}"))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("no definition for {}", missing.join(", ")));
+    }
+    Ok(ZoneDefs {
+        defs: slots.into_iter().flatten().collect(),
+    })
+}
+
+/// When the definitions file last changed, and when that was last looked
+/// at.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource)]
+struct ZonesFile {
+    modified: Option<SystemTime>,
+    checked: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn modified() -> Option<SystemTime> {
+    fs::metadata(ZONES_PATH).and_then(|meta| meta.modified()).ok()
+}
+
+/// Every second, reload the definitions if the file changed since it was
+/// last read. A broken file is reported and the current definitions kept.
+/// The placeholder sheet keeps the colors it was drawn with until the next
+/// city is opened.
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_zone_defs(
+    time: Res<Time>,
+    mut file: ResMut<ZonesFile>,
+    mut defs: ResMut<ZoneDefs>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let now = time.elapsed_secs();
+    if now - file.checked < RELOAD_CHECK_SECONDS {
+        return;
+    }
+    file.checked = now;
+    let modified = modified();
+    if modified.is_none() || modified == file.modified {
+        return;
+    }
+    file.modified = modified;
+
+    let reloaded = fs::read_to_string(ZONES_PATH)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse(&text));
+    match reloaded {
+        Ok(reloaded) => {
+            info!("Reloaded {ZONES_PATH}");
+            defs.set_if_neq(reloaded);
+            notifications.write(Notification(Phrase::new("notify.zones_reloaded")));
+        }
+        Err(err) => {
+            error!("{ZONES_PATH}: {err}; keeping the zones as they were");
+            notifications.write(Notification(
+                Phrase::new("notify.zones_invalid").with("error", err),
+            ));
+        }
+    }
+}